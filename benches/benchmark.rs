@@ -1,18 +1,267 @@
 #[macro_use]
 extern crate criterion;
 
-use criterion::Criterion;
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput};
 
 extern crate hk;
-use hk::HegselmannKrause;
+use hk::{Aggregator, ConvergenceCriterion, Float, HegselmannKrause, Kernel};
 
+/// numbers of agents of the parameterized benchmarks
+const SIZES: [u32; 4] = [100, 1000, 10000, 100000];
+/// (min, max) confidences of the parameterized benchmarks: small and large homogeneous
+/// confidences and heterogeneous ones
+const CONFIDENCES: [(Float, Float); 3] = [(0.05, 0.05), (0.25, 0.25), (0., 1.)];
+
+/// a sweep method of the model
+type Sweep = fn(&mut HegselmannKrause);
+
+/// the synchronous sweeps to compare for `n` agents, the quadratic naive sweep is only
+/// included up to `naive_limit` agents, since it is too slow for larger systems
+fn sweeps(n: u32, naive_limit: u32) -> Vec<(&'static str, Sweep)> {
+    let mut sweeps: Vec<(&'static str, Sweep)> = vec![
+        ("btree", HegselmannKrause::sweep_tree),
+        ("sorted", HegselmannKrause::sweep_sorted),
+    ];
+    if n <= naive_limit {
+        sweeps.insert(0, ("naive", HegselmannKrause::sweep_naive));
+    }
+    sweeps
+}
+
+/// convergence threshold of the total change of a sweep, like in the main loop
+const THRESHOLD: Float = 1e-4;
+/// maximum number of sweeps of a run to convergence
+const MAX_SWEEPS: usize = 10000;
+
+/// sweep with the given method like `run_until_converged`
+fn converge(hk: &mut HegselmannKrause, sweep: Sweep) {
+    for _ in 0..MAX_SWEEPS {
+        sweep(hk);
+        if hk.has_converged(THRESHOLD) {
+            break;
+        }
+    }
+}
+
+/// the benchmarks of the large systems take too long for the default number of samples
+fn sample_size(n: u32) -> usize {
+    if n >= 10000 {
+        10
+    } else {
+        100
+    }
+}
+
+// the first sweep from random initial opinions, where every agent has many neighbors,
+// every iteration starts from a fresh state, such that no converged system is measured
+// by accident, the throughput is given in agents per second
+// with the rayon feature, the new opinions are calculated in parallel
+fn sweep_benchmark(c: &mut Criterion) {
+    for &n in SIZES.iter() {
+        let mut group = c.benchmark_group(format!("first sync sweep N={}", n));
+        group.throughput(Throughput::Elements(n as u64));
+        group.sample_size(sample_size(n));
+        for &(min, max) in CONFIDENCES.iter() {
+            for (name, sweep) in sweeps(n, 10000) {
+                let id = BenchmarkId::new(name, format!("eps=[{},{}]", min, max));
+                group.bench_function(id, |b| {
+                    b.iter_batched(
+                        || HegselmannKrause::new(n, min, max, 13),
+                        // return the system, such that it is not dropped within the measurement
+                        |mut hk| {
+                            sweep(&mut hk);
+                            hk
+                        },
+                        BatchSize::LargeInput,
+                    )
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+// full runs from random initial opinions until convergence, which may take hundreds of
+// sweeps
+fn convergence_benchmark(c: &mut Criterion) {
+    for &n in SIZES.iter() {
+        let mut group = c.benchmark_group(format!("convergence N={}", n));
+        group.throughput(Throughput::Elements(n as u64));
+        group.sample_size(sample_size(n));
+        for &(min, max) in CONFIDENCES.iter() {
+            for (name, sweep) in sweeps(n, 1000) {
+                let id = BenchmarkId::new(name, format!("eps=[{},{}]", min, max));
+                group.bench_function(id, |b| {
+                    b.iter_batched(
+                        || HegselmannKrause::new(n, min, max, 13),
+                        |mut hk| {
+                            converge(&mut hk, sweep);
+                            hk
+                        },
+                        BatchSize::LargeInput,
+                    )
+                });
+            }
+
+            // the sweeps chosen by `sweep`, as in the main loop
+            let id = BenchmarkId::new("auto", format!("eps=[{},{}]", min, max));
+            group.bench_function(id, |b| {
+                b.iter_batched(
+                    || {
+                        let mut hk = HegselmannKrause::new(n, min, max, 13);
+                        hk.reset();
+                        hk
+                    },
+                    |mut hk| {
+                        let criterion = ConvergenceCriterion::SumChange {
+                            threshold: THRESHOLD,
+                        };
+                        let _ = hk.run_until_converged(criterion, Some(MAX_SWEEPS));
+                        hk
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+        group.finish();
+    }
+}
+
+// run once with and once without the f64 feature, criterion reports the change in the
+// cost of the sweeps between the two precisions
 fn criterion_benchmark(c: &mut Criterion) {
-    let mut hk = HegselmannKrause::new(1000, 0., 1., 13);
-    c.bench_function("hk N=1000 sync sweep", |b| b.iter(|| hk.sweep_naive()));
+    // the sweeps of a large system, which reuse their buffers, mostly after convergence
+    let mut hk = HegselmannKrause::new(100000, 0., 1., 13);
+    c.bench_function("hk N=100000 sync btree sweep", |b| {
+        b.iter(|| hk.sweep_tree())
+    });
+
+    // converged systems, in which all agents of a cluster share their query, which is only
+    // looked up for kernels without cumulative sums
+    let mut hk = HegselmannKrause::new(10000, 0.1, 0.1, 13);
+    for _ in 0..100 {
+        hk.sweep_tree();
+    }
+    c.bench_function("hk N=10000 converged sync btree sweep", |b| {
+        b.iter(|| hk.sweep_tree())
+    });
+
+    let mut hk = HegselmannKrause::new(10000, 0.1, 0.1, 13);
+    hk.set_skip_unchanged(true);
+    for _ in 0..100 {
+        hk.sweep_tree();
+    }
+    c.bench_function(
+        "hk N=10000 converged sync btree sweep skipping unchanged",
+        |b| b.iter(|| hk.sweep_tree()),
+    );
+
+    let mut hk = HegselmannKrause::new(10000, 0.1, 0.1, 13);
+    hk.set_kernel(Kernel::Gaussian);
+    for _ in 0..100 {
+        hk.sweep_tree();
+    }
+    c.bench_function("hk N=10000 converged gaussian sync btree sweep", |b| {
+        b.iter(|| hk.sweep_tree())
+    });
+
+    let mut hk = HegselmannKrause::new(10000, 0.1, 0.1, 13);
+    hk.set_kernel(Kernel::Gaussian);
+    hk.set_skip_unchanged(true);
+    for _ in 0..100 {
+        hk.sweep_tree();
+    }
+    c.bench_function(
+        "hk N=10000 converged gaussian sync btree sweep skipping unchanged",
+        |b| b.iter(|| hk.sweep_tree()),
+    );
+
+    // late sweeps of a large system with small heterogeneous confidences, in which the
+    // members of a cluster share their new opinion, the mean is answered by cumulative sums
+    // anyway, while the median walks through all neighbors
+    for &(aggregator, name) in &[(Aggregator::Mean, "mean"), (Aggregator::Median, "median")] {
+        for &coarsening in &[false, true] {
+            let mut hk = HegselmannKrause::new(100000, 0.01, 0.05, 13);
+            hk.set_aggregator(aggregator).unwrap();
+            hk.set_coarsening(coarsening);
+            for _ in 0..100 {
+                hk.sweep();
+            }
+            let suffix = if coarsening { " coarsening" } else { "" };
+            c.bench_function(
+                &format!("hk N=100000 converged {} sync sweep{}", name, suffix),
+                |b| b.iter(|| hk.sweep()),
+            );
+        }
+    }
+
+    // asynchronous updates query and update the tree for every single agent
+    let mut hk = HegselmannKrause::new(10000, 0., 1., 13);
+    c.bench_function("hk N=10000 async btree sweep", |b| {
+        b.iter(|| hk.sweep_async_tree())
+    });
+
+    // cluster detection of many agents with distinct opinions
+    let hk = HegselmannKrause::new(100000, 0., 1., 13);
+    c.bench_function("hk N=100000 cluster sizes", |b| {
+        b.iter(|| hk.cluster_sizes())
+    });
+}
+
+// the first ten sweeps from random initial opinions, visiting the agents in the order of
+// their indices or of their opinions, which are sorted again every ten sweeps
+fn order_benchmark(c: &mut Criterion) {
+    let n = 100000;
+    let mut group = c.benchmark_group(format!("ten sync sweeps N={}", n));
+    group.throughput(Throughput::Elements(10 * n as u64));
+    group.sample_size(10);
+    for (name, sweep) in sweeps(n, 0) {
+        for &(order, sorted) in &[("indices", false), ("opinions", true)] {
+            group.bench_function(BenchmarkId::new(name, order), |b| {
+                b.iter_batched(
+                    || {
+                        let mut hk = HegselmannKrause::new(n, 0.01, 0.05, 13);
+                        hk.set_sorted_iteration(sorted);
+                        hk
+                    },
+                    |mut hk| {
+                        for _ in 0..10 {
+                            sweep(&mut hk);
+                        }
+                        hk
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
 
-    let mut hk = HegselmannKrause::new(1000, 0., 1., 13);
-    c.bench_function("hk N=1000 sync btree sweep", |b| b.iter(|| hk.sweep_tree()));
+// consecutive sweeps of a very large system with small confidences, whose speed depends
+// mostly on the memory traffic of walking through the agents, the tree would walk through
+// millions of distinct opinions per agent in the first sweeps
+fn large_system_benchmark(c: &mut Criterion) {
+    let n = 10_000_000;
+    let mut group = c.benchmark_group(format!("large system N={}", n));
+    group.throughput(Throughput::Elements(n as u64));
+    group.sample_size(10);
+    for &(order, sorted) in &[("indices", false), ("opinions", true)] {
+        let mut hk = HegselmannKrause::new(n, 0.01, 0.05, 13);
+        hk.set_sorted_iteration(sorted);
+        group.bench_function(BenchmarkId::new("sorted", order), |b| {
+            b.iter(|| hk.sweep_sorted())
+        });
+    }
+    group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    sweep_benchmark,
+    convergence_benchmark,
+    order_benchmark,
+    large_system_benchmark
+);
 criterion_main!(benches);
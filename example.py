@@ -5,6 +5,15 @@ def parse_file_generator(filename):
         for line in f:
             if line.startswith("# sweeps:"):
                 speed = float(line[9:])
+            elif line.startswith("# NOT CONVERGED"):
+                # the sample reached the maximum number of sweeps, its clusters follow anyway
+                pass
+            elif line.startswith("# seed:"):
+                # the seed of the sample, to reproduce it on its own
+                pass
+            elif line.startswith("# truth distances:"):
+                # only present for simulations with truth seekers
+                pass
             elif line.startswith("#"):
                 positions = list(map(float, line[2:].split()))
             else:
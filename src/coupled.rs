@@ -0,0 +1,223 @@
+/// This file implements the Hegselmann-Krause bounded confidence model with two coupled
+/// opinion topics A and B, heterogeneous confidences and synchronous update.
+/// An agent only takes another agent into account, if their opinions are close on both
+/// topics, i.e., closer than the confidence of the updating agent for the respective topic.
+/// Like the multidimensional model, only the naive method of iterating all agents is implemented.
+use std::io::prelude::*;
+
+use itertools::Itertools;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::float::Float;
+use crate::hegselmannkrause::EPS;
+use crate::hegselmannkrause_nd::list_clusters;
+
+/// structure representing an agent with opinions on two topics
+#[derive(Clone, Debug)]
+struct CoupledAgent {
+    /// current opinion of the agent on topic A
+    opinion_a: Float,
+    /// current opinion of the agent on topic B
+    opinion_b: Float,
+    /// idiosyncratic confidence of the agent for topic A
+    confidence_a: Float,
+    /// idiosyncratic confidence of the agent for topic B
+    confidence_b: Float,
+}
+
+impl CoupledAgent {
+    fn new(opinion: (Float, Float), confidence: (Float, Float)) -> CoupledAgent {
+        CoupledAgent {
+            opinion_a: opinion.0,
+            opinion_b: opinion.1,
+            confidence_a: confidence.0,
+            confidence_b: confidence.1,
+        }
+    }
+
+    /// whether the agent takes `other` into account, which needs to be close on both topics
+    fn accepts(&self, other: &CoupledAgent) -> bool {
+        (self.opinion_a - other.opinion_a).abs() < self.confidence_a
+            && (self.opinion_b - other.opinion_b).abs() < self.confidence_b
+    }
+}
+
+/// used for testing purposes
+impl PartialEq for CoupledAgent {
+    fn eq(&self, other: &CoupledAgent) -> bool {
+        (self.opinion_a - other.opinion_a).abs() < EPS
+            && (self.opinion_b - other.opinion_b).abs() < EPS
+            && (self.confidence_a - other.confidence_a).abs() < EPS
+            && (self.confidence_b - other.confidence_b).abs() < EPS
+    }
+}
+
+/// structure representing a realization of the HK model with two coupled topics
+pub struct CoupledHegselmannKrause {
+    /// number of agents in the system
+    num_agents: u32,
+    /// vector of all agents constituting the system
+    agents: Vec<CoupledAgent>,
+    /// lower bound of the confidences of all agents
+    min_confidence: Float,
+    /// upper bound of the confidences of all agents
+    max_confidence: Float,
+
+    /// total change of agents opinions on both topics during the last sweep
+    pub accumulated_change: Float,
+
+    /// we need many, good (but not crypto) random numbers
+    /// we will use here the pcg generator
+    rng: Pcg64,
+}
+
+/// used for testing purposes
+impl PartialEq for CoupledHegselmannKrause {
+    fn eq(&self, other: &CoupledHegselmannKrause) -> bool {
+        self.agents == other.agents
+    }
+}
+
+impl CoupledHegselmannKrause {
+    pub fn new(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+    ) -> CoupledHegselmannKrause {
+        let rng = Pcg64::seed_from_u64(seed);
+        let agents: Vec<CoupledAgent> = Vec::new();
+
+        let mut hk = CoupledHegselmannKrause {
+            num_agents: n,
+            agents,
+            min_confidence,
+            max_confidence,
+            accumulated_change: 0.,
+            rng,
+        };
+
+        hk.reset();
+        hk
+    }
+
+    /// reset the state of an CoupledHegselmannKrause struct
+    /// initialize the agents with opinions uniformly distributed in the unit square
+    /// and uniformly distributed confidences, which are the same for both topics
+    pub fn reset(&mut self) {
+        /// helper function to scale a uniform[0,1] random number to a uniform[low, high]
+        fn scale(x: Float, low: Float, high: Float) -> Float {
+            x * (high - low) + low
+        }
+
+        let mut agents = Vec::with_capacity(self.num_agents as usize);
+        for _ in 0..self.num_agents {
+            let opinion = (self.rng.gen(), self.rng.gen());
+            let confidence = scale(self.rng.gen(), self.min_confidence, self.max_confidence);
+            agents.push(CoupledAgent::new(opinion, (confidence, confidence)));
+        }
+        self.agents = agents;
+    }
+
+    /// overwrite the state of all agents with the given opinions and confidences,
+    /// both as pairs for topic A and B
+    /// this is mainly useful to study specific configurations
+    pub fn set_state(&mut self, opinions: &[(Float, Float)], confidences: &[(Float, Float)]) {
+        assert_eq!(opinions.len(), confidences.len());
+        self.num_agents = opinions.len() as u32;
+        self.agents = opinions
+            .iter()
+            .zip(confidences.iter())
+            .map(|(&x, &c)| CoupledAgent::new(x, c))
+            .collect();
+        self.accumulated_change = 0.;
+    }
+
+    /// snapshot of the current opinions of all agents on topic A and B
+    pub fn opinions(&self) -> Vec<(Float, Float)> {
+        self.agents
+            .iter()
+            .map(|i| (i.opinion_a, i.opinion_b))
+            .collect()
+    }
+
+    /// calculate all new opinions using the naive method of iterating all agents
+    fn sync_new_opinions_naive(&self) -> Vec<(Float, Float)> {
+        self.agents
+            .iter()
+            .map(|i| {
+                let mut sum_a = 0.;
+                let mut sum_b = 0.;
+                let mut count = 0;
+                for j in self.agents.iter().filter(|j| i.accepts(j)) {
+                    sum_a += j.opinion_a;
+                    sum_b += j.opinion_b;
+                    count += 1;
+                }
+
+                (sum_a / count as Float, sum_b / count as Float)
+            })
+            .collect()
+    }
+
+    // perform a sweep (update every agent) with the naive method
+    pub fn sweep_naive(&mut self) {
+        let new_opinions = self.sync_new_opinions_naive();
+        self.accumulated_change = 0.;
+
+        for (i, (a, b)) in new_opinions.into_iter().enumerate() {
+            let agent = &mut self.agents[i];
+            self.accumulated_change += (agent.opinion_a - a).abs() + (agent.opinion_b - b).abs();
+
+            agent.opinion_a = a;
+            agent.opinion_b = b;
+        }
+    }
+
+    pub fn sweep(&mut self) {
+        self.sweep_naive();
+    }
+
+    /// A cluster are agents whose opinions are chained by distances of less than EPS on both
+    /// topics, ordered by their opinions on topic A and then B
+    fn list_clusters(&self) -> Vec<Vec<usize>> {
+        let opinions: Vec<[Float; 2]> = self
+            .agents
+            .iter()
+            .map(|i| [i.opinion_a, i.opinion_b])
+            .collect();
+        list_clusters(&opinions, EPS)
+    }
+
+    pub fn cluster_sizes(&self) -> Vec<usize> {
+        self.list_clusters().iter().map(|c| c.len()).collect()
+    }
+
+    /// the centroids (a, b) of all clusters, in the same order as `cluster_sizes`
+    pub fn cluster_centroids(&self) -> Vec<(Float, Float)> {
+        self.list_clusters()
+            .iter()
+            .map(|c| {
+                let a = c.iter().map(|&i| self.agents[i].opinion_a).sum::<Float>();
+                let b = c.iter().map(|&i| self.agents[i].opinion_b).sum::<Float>();
+                (a / c.len() as Float, b / c.len() as Float)
+            })
+            .collect()
+    }
+
+    pub fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        // write centroids of the clusters, topics separated by commas
+        let string_list = self
+            .cluster_centroids()
+            .iter()
+            .map(|(a, b)| format!("{},{}", a, b))
+            .join(" ");
+        writeln!(file, "# {}", string_list)?;
+
+        // write sizes of the clusters
+        let string_list = self.cluster_sizes().iter().map(|c| c.to_string()).join(" ");
+        writeln!(file, "{}", string_list)?;
+        Ok(())
+    }
+}
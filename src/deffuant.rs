@@ -0,0 +1,300 @@
+/// This file implements the Deffuant-Weisbuch bounded confidence model with heterogeneous
+/// confidences. In contrast to the Hegselmann-Krause model, agents interact pairwise:
+/// in every step two random agents are chosen and, if their opinions are close enough,
+/// both move towards each other by a fraction `mu` of their distance.
+use std::io::Write;
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::error::{HkError, NotConverged};
+use crate::float::Float;
+use crate::hegselmannkrause::{
+    list_clusters, sees_only_own_opinion, validate_cluster_eps, validate_model_parameters,
+    write_clusters, ConvergenceCheck, ConvergenceCriterion, ConvergenceResult, HKAgent, Kernel,
+    OpinionSpace, EPS,
+};
+
+/// structure representing a realization of the Deffuant model
+pub struct Deffuant {
+    /// number of agents in the system
+    num_agents: u32,
+    /// vector of all agents constituting the system
+    agents: Vec<HKAgent>,
+    /// lower bound of the confidences of all agents
+    min_confidence: Float,
+    /// upper bound of the confidences of all agents
+    max_confidence: Float,
+    /// convergence parameter, i.e., the fraction of the distance the agents move
+    mu: Float,
+    /// opinions closer than this tolerance belong to the same cluster
+    cluster_eps: Float,
+
+    /// total change of agents opinion during the last sweep
+    accumulated_change: Float,
+    /// largest change of a single agents opinion during the last sweep
+    max_change: Float,
+
+    /// we need many, good (but not crypto) random numbers
+    /// we will use here the pcg generator
+    rng: Pcg64,
+}
+
+impl Deffuant {
+    /// panics on invalid parameters, see `try_new`
+    pub fn new(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        mu: Float,
+        seed: u64,
+    ) -> Deffuant {
+        Deffuant::try_new(n, min_confidence, max_confidence, mu, seed)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// construct a model of `n >= 1` agents, whose confidences are uniformly distributed
+    /// in [min_confidence, max_confidence] with finite bounds 0 <= min <= max, and
+    /// whose agents move by the fraction `mu` in (0, 1] of their distance
+    pub fn try_new(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        mu: Float,
+        seed: u64,
+    ) -> Result<Deffuant, HkError> {
+        validate_model_parameters(n, min_confidence, max_confidence)?;
+        if !(mu > 0. && mu <= 1.) {
+            return Err(HkError::InvalidParameter(format!(
+                "mu {} needs to be in (0, 1]",
+                mu
+            )));
+        }
+
+        let rng = Pcg64::seed_from_u64(seed);
+        let agents: Vec<HKAgent> = Vec::new();
+
+        let mut dw = Deffuant {
+            num_agents: n,
+            agents,
+            min_confidence,
+            max_confidence,
+            mu,
+            cluster_eps: EPS,
+            accumulated_change: 0.,
+            max_change: 0.,
+            rng,
+        };
+
+        dw.reset();
+        Ok(dw)
+    }
+
+    /// reseed the random number generator and `reset()`, such that the sample depends
+    /// only on `seed` and the parameters, but not on the samples simulated before
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.rng = Pcg64::seed_from_u64(seed);
+        self.reset();
+    }
+
+    /// reset the state of a Deffuant struct
+    /// initialize the agents with random initial conditions, exactly like the
+    /// Hegselmann-Krause model does for the same seed
+    pub fn reset(&mut self) {
+        /// helper function to scale a uniform[0,1] random number to a uniform[low, high]
+        fn scale(x: Float, low: Float, high: Float) -> Float {
+            x * (high - low) + low
+        }
+
+        // initialize a vector of n agents with uniformly distributed opinions and confidences
+        self.agents = (0..self.num_agents)
+            .map(|_| {
+                HKAgent::new(
+                    self.rng.gen(),
+                    scale(self.rng.gen(), self.min_confidence, self.max_confidence),
+                )
+            })
+            .collect();
+    }
+
+    /// let opinions closer than `eps` belong to the same cluster (default 1e-5)
+    pub fn set_cluster_eps(&mut self, eps: Float) -> Result<(), HkError> {
+        validate_cluster_eps(eps)?;
+        self.cluster_eps = eps;
+        Ok(())
+    }
+
+    /// perform a single pairwise interaction of two distinct random agents
+    /// every agent moves towards the other if the other is within its own confidence
+    pub fn step(&mut self) {
+        // a single agent has nobody to interact with
+        if self.num_agents < 2 {
+            return;
+        }
+
+        let i = self.rng.gen_range(0, self.num_agents) as usize;
+        let mut j = self.rng.gen_range(0, self.num_agents - 1) as usize;
+        // skip `i` such that `j` is uniformly drawn from all other agents
+        if j >= i {
+            j += 1;
+        }
+
+        let xi = self.agents[i].opinion;
+        let xj = self.agents[j].opinion;
+
+        if self.agents[i].accepts(OpinionSpace::Line, xj) {
+            self.agents[i].opinion = xi + self.mu * (xj - xi);
+            self.add_change((self.agents[i].opinion - xi).abs());
+        }
+        if self.agents[j].accepts(OpinionSpace::Line, xi) {
+            self.agents[j].opinion = xj + self.mu * (xi - xj);
+            self.add_change((self.agents[j].opinion - xj).abs());
+        }
+    }
+
+    /// add the change of one opinion to `accumulated_change` and `max_change`
+    fn add_change(&mut self, change: Float) {
+        self.accumulated_change += change;
+        if change > self.max_change {
+            self.max_change = change;
+        }
+    }
+
+    /// perform a sweep, i.e., `num_agents` pairwise interactions
+    pub fn sweep(&mut self) {
+        self.accumulated_change = 0.;
+        self.max_change = 0.;
+
+        for _ in 0..self.num_agents {
+            self.step();
+        }
+    }
+
+    /// total change of all opinions during the last sweep
+    pub fn last_change(&self) -> Float {
+        self.accumulated_change
+    }
+
+    /// largest change of a single opinion during the last sweep
+    pub fn last_max_change(&self) -> Float {
+        self.max_change
+    }
+
+    /// whether the total change of all opinions during the last sweep is below `threshold`
+    pub fn has_converged(&self, threshold: Float) -> bool {
+        self.accumulated_change < threshold
+    }
+
+    /// sweep until the `criterion` is met and return the number of sweeps, or `NotConverged`
+    /// if it is not met within `max_sweeps` sweeps
+    /// a system, which no interaction can change, e.g., a single agent, is converged from
+    /// the start and takes 0 sweeps, see `is_static`
+    pub fn run_until_converged(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<usize, NotConverged> {
+        let (sweeps, converged) = self.sweep_until_converged(criterion, max_sweeps);
+        if converged {
+            Ok(sweeps)
+        } else {
+            Err(NotConverged {
+                sweeps,
+                last_change: self.accumulated_change,
+            })
+        }
+    }
+
+    /// sweep until the total change of all opinions drops below `threshold` or `max_sweeps`
+    /// sweeps are done and summarize the run, like `run_until_converged`
+    pub fn run_to_convergence(&mut self, threshold: Float, max_sweeps: usize) -> ConvergenceResult {
+        self.run_to_convergence_with(
+            ConvergenceCriterion::SumChange { threshold },
+            Some(max_sweeps),
+        )
+    }
+
+    /// like `run_to_convergence`, but for any `criterion`
+    pub fn run_to_convergence_with(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> ConvergenceResult {
+        let (sweeps, converged) = self.sweep_until_converged(criterion, max_sweeps);
+        ConvergenceResult::new(
+            sweeps,
+            converged,
+            &self.agents,
+            &list_clusters(&self.agents, OpinionSpace::Line, self.cluster_eps),
+        )
+    }
+
+    /// the number of sweeps until the `criterion` is met or `max_sweeps` sweeps are done,
+    /// and whether it is met
+    fn sweep_until_converged(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> (usize, bool) {
+        // decided before sweeping, such that 0 sweeps did not touch the state or the rng
+        if criterion.accepts_static() && self.is_static() {
+            return (0, true);
+        }
+        let mut check = ConvergenceCheck::new(criterion);
+        let mut sweeps = 0;
+        loop {
+            self.sweep();
+            sweeps += 1;
+
+            let converged = check.converged(self.accumulated_change, self.max_change, || {
+                self.cluster_sizes()
+            });
+            let capped = max_sweeps.is_some_and(|max| sweeps >= max);
+            if converged || capped {
+                return (sweeps, converged);
+            }
+        }
+    }
+
+    /// whether no interaction can change any opinion, since every agent only accepts agents
+    /// sharing its opinion, e.g., for a single agent
+    pub fn is_static(&self) -> bool {
+        self.agents.len() <= 1 || sees_only_own_opinion(&self.agents, Kernel::Hard)
+    }
+
+    /// snapshot of the current opinions of all agents
+    pub fn opinions(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.opinion).collect()
+    }
+
+    /// snapshot of the current confidences of all agents
+    pub fn confidences(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.confidence_left).collect()
+    }
+
+    /// number of agents
+    pub fn num_agents(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// snapshot of the current opinion and confidence of the agent `idx`, or `None` if there
+    /// is no such agent
+    pub fn agent(&self, idx: usize) -> Option<(Float, Float)> {
+        self.agents.get(idx).map(|i| (i.opinion, i.confidence_left))
+    }
+
+    pub fn cluster_sizes(&self) -> Vec<usize> {
+        list_clusters(&self.agents, OpinionSpace::Line, self.cluster_eps)
+            .iter()
+            .map(|c| c.len())
+            .collect()
+    }
+
+    pub fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        write_clusters(
+            &self.agents,
+            &list_clusters(&self.agents, OpinionSpace::Line, self.cluster_eps),
+            file,
+        )
+    }
+}
@@ -0,0 +1,172 @@
+/// This file implements the DeGroot model of opinion dynamics as a baseline for the bounded
+/// confidence models. Every sweep, all opinions are replaced synchronously by the weighted
+/// averages `x(t+1) = W x(t)` with a fixed row-stochastic matrix `W`, regardless of the
+/// distances of the opinions.
+use std::io::Write;
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::error::HkError;
+use crate::float::Float;
+use crate::hegselmannkrause::{list_clusters, write_clusters, HKAgent, OpinionSpace, EPS};
+
+/// tolerance for the deviation of the sum of a row of the weight matrix from 1
+const STOCHASTIC_TOLERANCE: Float = 1e-4;
+
+/// structure representing a realization of the DeGroot model
+pub struct DeGroot {
+    /// number of agents in the system
+    num_agents: u32,
+    /// vector of all agents constituting the system, their confidences are irrelevant
+    agents: Vec<HKAgent>,
+    /// non-zero entries `(j, w_ij)` of every row `i` of the weight matrix
+    rows: Vec<Vec<(usize, Float)>>,
+
+    /// total change of agents opinion during the last sweep
+    pub accumulated_change: Float,
+
+    /// we need many, good (but not crypto) random numbers
+    /// we will use here the pcg generator
+    rng: Pcg64,
+}
+
+impl DeGroot {
+    /// DeGroot model with the dense weight matrix `weights`, whose entry `[i][j]` is the
+    /// weight agent `i` gives to the opinion of agent `j`
+    pub fn new(weights: &[Vec<Float>], seed: u64) -> Result<DeGroot, HkError> {
+        let n = weights.len();
+        if let Some((i, row)) = weights.iter().enumerate().find(|(_, row)| row.len() != n) {
+            return Err(HkError::InvalidWeights(format!(
+                "row {} of the weight matrix has {} entries, but there are {} rows",
+                i,
+                row.len(),
+                n
+            )));
+        }
+
+        let triplets: Vec<(usize, usize, Float)> = weights
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, &w)| w != 0.)
+                    .map(move |(j, &w)| (i, j, w))
+            })
+            .collect();
+        DeGroot::from_triplets(n as u32, &triplets, seed)
+    }
+
+    /// DeGroot model of `n` agents with a sparse weight matrix given as triplets `(i, j, w_ij)`,
+    /// all entries not given are zero, repeated entries are added up
+    pub fn from_triplets(
+        n: u32,
+        triplets: &[(usize, usize, Float)],
+        seed: u64,
+    ) -> Result<DeGroot, HkError> {
+        let mut rows = vec![Vec::new(); n as usize];
+        for &(i, j, w) in triplets {
+            if i >= n as usize || j >= n as usize {
+                return Err(HkError::InvalidWeights(format!(
+                    "entry ({}, {}) is outside of the {}x{} weight matrix",
+                    i, j, n, n
+                )));
+            }
+            if !(w >= 0. && w.is_finite()) {
+                return Err(HkError::InvalidWeights(format!(
+                    "weight {} of entry ({}, {}) needs to be finite and non-negative",
+                    w, i, j
+                )));
+            }
+            rows[i].push((j, w));
+        }
+        for (i, row) in rows.iter().enumerate() {
+            let sum: Float = row.iter().map(|&(_, w)| w).sum();
+            if (sum - 1.).abs() > STOCHASTIC_TOLERANCE {
+                return Err(HkError::InvalidWeights(format!(
+                    "the weights of row {} sum to {} instead of 1",
+                    i, sum
+                )));
+            }
+        }
+
+        let mut dg = DeGroot {
+            num_agents: n,
+            agents: Vec::new(),
+            rows,
+            accumulated_change: 0.,
+            rng: Pcg64::seed_from_u64(seed),
+        };
+
+        dg.reset();
+        Ok(dg)
+    }
+
+    /// reset the state of a DeGroot struct
+    /// initialize the agents with uniformly distributed opinions
+    pub fn reset(&mut self) {
+        self.agents = (0..self.num_agents)
+            .map(|_| HKAgent::new(self.rng.gen(), 0.))
+            .collect();
+        self.accumulated_change = 0.;
+    }
+
+    /// overwrite the opinions of all agents, e.g., to study specific configurations
+    pub fn set_state(&mut self, opinions: &[Float]) -> Result<(), HkError> {
+        if opinions.len() != self.num_agents as usize {
+            return Err(HkError::InvalidState(format!(
+                "{} opinions given for {} agents",
+                opinions.len(),
+                self.num_agents
+            )));
+        }
+        if let Some((i, x)) = opinions.iter().enumerate().find(|(_, x)| !x.is_finite()) {
+            return Err(HkError::InvalidState(format!(
+                "opinion {} of agent {} needs to be finite",
+                x, i
+            )));
+        }
+        for (agent, &x) in self.agents.iter_mut().zip(opinions) {
+            agent.opinion = x;
+        }
+        self.accumulated_change = 0.;
+        Ok(())
+    }
+
+    /// snapshot of the current opinions of all agents
+    pub fn opinions(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.opinion).collect()
+    }
+
+    /// perform a sweep, i.e., replace all opinions synchronously by their weighted averages
+    pub fn sweep(&mut self) {
+        let new_opinions: Vec<Float> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|&(j, w)| w * self.agents[j].opinion).sum())
+            .collect();
+        self.accumulated_change = 0.;
+
+        for (i, new_opinion) in new_opinions.into_iter().enumerate() {
+            self.accumulated_change += (self.agents[i].opinion - new_opinion).abs();
+
+            self.agents[i].opinion = new_opinion;
+        }
+    }
+
+    pub fn cluster_sizes(&self) -> Vec<usize> {
+        list_clusters(&self.agents, OpinionSpace::Line, EPS)
+            .iter()
+            .map(|c| c.len())
+            .collect()
+    }
+
+    pub fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        write_clusters(
+            &self.agents,
+            &list_clusters(&self.agents, OpinionSpace::Line, EPS),
+            file,
+        )
+    }
+}
@@ -0,0 +1,80 @@
+//! sanity checks of the state of the one-dimensional models, which hold for every
+//! correct simulation, e.g., to assert them after some sweeps in tests
+use crate::deffuant::Deffuant;
+use crate::degroot::DeGroot;
+use crate::float::Float;
+use crate::hegselmannkrause::HegselmannKrause;
+use crate::lattice::HegselmannKrauseLattice;
+
+/// checks of models with one opinion per agent, every model only needs to provide its
+/// opinions and the sizes of its clusters
+pub trait Diagnostics {
+    /// snapshot of the current opinions of all agents
+    fn opinions(&self) -> Vec<Float>;
+
+    /// sizes of the clusters of the current opinions
+    fn cluster_sizes(&self) -> Vec<usize>;
+
+    /// number of agents in all clusters, which is the number of agents, since every
+    /// agent belongs to exactly one cluster
+    fn total_agents_in_clusters(&self) -> usize {
+        self.cluster_sizes().iter().sum()
+    }
+
+    /// unweighted mean of the opinions of all agents, summed up in f64 in agent order
+    /// in the symmetric model, it does not change if every agent sees every other agent
+    fn mean_opinion(&self) -> Float {
+        let opinions = self.opinions();
+        let sum: f64 = opinions.iter().map(|&x| x as f64).sum();
+        (sum / opinions.len() as f64) as Float
+    }
+
+    /// smallest and largest opinion of all agents, averages never leave this interval
+    fn opinion_bounds(&self) -> (Float, Float) {
+        self.opinions()
+            .iter()
+            .fold((Float::INFINITY, Float::NEG_INFINITY), |(lo, hi), &x| {
+                (lo.min(x), hi.max(x))
+            })
+    }
+}
+
+impl Diagnostics for HegselmannKrause {
+    fn opinions(&self) -> Vec<Float> {
+        HegselmannKrause::opinions(self)
+    }
+
+    fn cluster_sizes(&self) -> Vec<usize> {
+        HegselmannKrause::cluster_sizes(self)
+    }
+}
+
+impl Diagnostics for Deffuant {
+    fn opinions(&self) -> Vec<Float> {
+        Deffuant::opinions(self)
+    }
+
+    fn cluster_sizes(&self) -> Vec<usize> {
+        Deffuant::cluster_sizes(self)
+    }
+}
+
+impl Diagnostics for DeGroot {
+    fn opinions(&self) -> Vec<Float> {
+        DeGroot::opinions(self)
+    }
+
+    fn cluster_sizes(&self) -> Vec<usize> {
+        DeGroot::cluster_sizes(self)
+    }
+}
+
+impl Diagnostics for HegselmannKrauseLattice {
+    fn opinions(&self) -> Vec<Float> {
+        HegselmannKrauseLattice::opinions(self)
+    }
+
+    fn cluster_sizes(&self) -> Vec<usize> {
+        HegselmannKrauseLattice::cluster_sizes(self)
+    }
+}
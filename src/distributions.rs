@@ -0,0 +1,814 @@
+/// This file implements the distributions used to draw the initial state of the agents
+use rand::Rng;
+
+use crate::error::HkError;
+use crate::float::Float;
+
+/// distribution of the confidences of the agents
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfidenceDistribution {
+    /// confidences are uniformly distributed in [low, high]
+    Uniform { low: Float, high: Float },
+    /// confidences are distributed according to a power law `p(x) ~ x^-exponent`
+    /// truncated to [min, max]
+    PowerLaw {
+        exponent: Float,
+        min: Float,
+        max: Float,
+    },
+    /// all agents have the same confidence
+    Constant { value: Float },
+    /// a fraction of open-minded agents with confidence `open` (group 0),
+    /// all others are closed-minded with confidence `closed` (group 1)
+    Bimodal {
+        fraction: Float,
+        open: Float,
+        closed: Float,
+    },
+    /// the confidence is a deterministic function of the initial opinion `x`, such that
+    /// centrists are open-minded and extremists closed-minded:
+    /// `min + (max - min) (1 - |2x - 1|)^gamma`
+    OpinionCorrelated {
+        min: Float,
+        max: Float,
+        gamma: Float,
+    },
+}
+
+impl ConfidenceDistribution {
+    /// check that the parameters describe a proper distribution
+    pub fn validate(&self) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidConfidenceDistribution(reason));
+        match *self {
+            ConfidenceDistribution::Uniform { low, high } => {
+                if !(low.is_finite() && high.is_finite()) {
+                    return invalid(format!("bounds [{}, {}] need to be finite", low, high));
+                }
+                if low < 0. {
+                    return invalid(format!("lower bound {} needs to be non-negative", low));
+                }
+                if low > high {
+                    return invalid(format!("lower bound {} exceeds upper bound {}", low, high));
+                }
+            }
+            ConfidenceDistribution::PowerLaw { exponent, min, max } => {
+                if exponent.is_nan() || exponent <= 1. {
+                    return invalid(format!(
+                        "the exponent of a power law needs to be larger than 1, but is {}",
+                        exponent
+                    ));
+                }
+                if !(min > 0. && min.is_finite() && max.is_finite()) {
+                    return invalid(format!(
+                        "the bounds [{}, {}] of a power law need to be finite and positive",
+                        min, max
+                    ));
+                }
+                if min > max {
+                    return invalid(format!("lower bound {} exceeds upper bound {}", min, max));
+                }
+            }
+            ConfidenceDistribution::Constant { value } => {
+                if !(value.is_finite() && value >= 0.) {
+                    return invalid(format!(
+                        "confidence {} needs to be finite and non-negative",
+                        value
+                    ));
+                }
+            }
+            ConfidenceDistribution::Bimodal {
+                fraction,
+                open,
+                closed,
+            } => {
+                if !(0. ..=1.).contains(&fraction) {
+                    return invalid(format!("fraction {} needs to be in [0, 1]", fraction));
+                }
+                if !(open.is_finite() && closed.is_finite() && open >= 0. && closed >= 0.) {
+                    return invalid(format!(
+                        "confidences {} and {} need to be finite and non-negative",
+                        open, closed
+                    ));
+                }
+            }
+            ConfidenceDistribution::OpinionCorrelated { min, max, gamma } => {
+                if !(min.is_finite() && max.is_finite()) {
+                    return invalid(format!("bounds [{}, {}] need to be finite", min, max));
+                }
+                if min < 0. {
+                    return invalid(format!("lower bound {} needs to be non-negative", min));
+                }
+                if min > max {
+                    return invalid(format!("lower bound {} exceeds upper bound {}", min, max));
+                }
+                if !(gamma >= 0. && gamma.is_finite()) {
+                    return invalid(format!(
+                        "the exponent {} needs to be finite and non-negative",
+                        gamma
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// draw a confidence from this distribution
+    /// exactly one random number is consumed, independent of the distribution, such
+    /// that the remaining initial state is the same for every distribution
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Float {
+        self.sample_with_group(rng).0
+    }
+
+    /// draw the confidence of an agent with the initial `opinion` from this distribution
+    /// together with the group the agent belongs to, only the opinion correlated
+    /// distribution depends on the opinion, but it consumes a random number anyway
+    pub fn sample_for_opinion<R: Rng>(&self, opinion: Float, rng: &mut R) -> (Float, u8) {
+        match *self {
+            ConfidenceDistribution::OpinionCorrelated { min, max, gamma } => {
+                let _: Float = rng.gen();
+                let centrality = (1. - (2. * opinion - 1.).abs()).clamp(0., 1.);
+                (non_negative(min + (max - min) * centrality.powf(gamma)), 0)
+            }
+            _ => self.sample_with_group(rng),
+        }
+    }
+
+    /// draw a confidence from this distribution together with the group the agent
+    /// belongs to, which is always 0 for unimodal distributions
+    /// without an opinion, the opinion correlated distribution is uniform in [min, max]
+    pub fn sample_with_group<R: Rng>(&self, rng: &mut R) -> (Float, u8) {
+        let u: Float = rng.gen();
+        let confidence = match *self {
+            ConfidenceDistribution::Uniform { low, high } => u * (high - low) + low,
+            ConfidenceDistribution::PowerLaw { exponent, min, max } => {
+                power_law_quantile(u, exponent, min, max)
+            }
+            ConfidenceDistribution::Constant { value } => value,
+            ConfidenceDistribution::OpinionCorrelated { min, max, .. } => u * (max - min) + min,
+            ConfidenceDistribution::Bimodal {
+                fraction,
+                open,
+                closed,
+            } => {
+                let group = if u < fraction { 0 } else { 1 };
+                return (non_negative([open, closed][group as usize]), group);
+            }
+        };
+        (non_negative(confidence), 0)
+    }
+}
+
+/// a validated distribution only draws non-negative confidences, but an agent with a
+/// negative confidence would not even see itself, so the draws are clamped to be safe
+fn non_negative(confidence: Float) -> Float {
+    debug_assert!(
+        confidence >= 0.,
+        "negative confidence {} drawn from a validated distribution",
+        confidence
+    );
+    confidence.max(0.)
+}
+
+/// distribution of the influence weights of the agents, i.e., how strongly an
+/// agent counts in the averages of its neighbors
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WeightDistribution {
+    /// every agent has weight 1, as in the classic model
+    Unit,
+    /// weights are uniformly distributed in [low, high]
+    Uniform { low: Float, high: Float },
+    /// weights are distributed according to a power law `p(w) ~ w^-exponent`
+    /// truncated to [min, max]
+    PowerLaw {
+        exponent: Float,
+        min: Float,
+        max: Float,
+    },
+}
+
+impl WeightDistribution {
+    /// check that the parameters describe a proper distribution of positive weights
+    pub fn validate(&self) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidWeights(reason));
+        match *self {
+            WeightDistribution::Unit => {}
+            WeightDistribution::Uniform { low, high } => {
+                if !(low > 0. && high.is_finite()) {
+                    return invalid(format!(
+                        "bounds [{}, {}] need to be finite and positive",
+                        low, high
+                    ));
+                }
+                if low > high {
+                    return invalid(format!("lower bound {} exceeds upper bound {}", low, high));
+                }
+            }
+            WeightDistribution::PowerLaw { exponent, min, max } => {
+                if exponent.is_nan() || exponent <= 1. {
+                    return invalid(format!(
+                        "the exponent of a power law needs to be larger than 1, but is {}",
+                        exponent
+                    ));
+                }
+                if !(min > 0. && max.is_finite()) {
+                    return invalid(format!(
+                        "the bounds [{}, {}] of a power law need to be finite and positive",
+                        min, max
+                    ));
+                }
+                if min > max {
+                    return invalid(format!("lower bound {} exceeds upper bound {}", min, max));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// draw a weight from this distribution
+    /// unit weights do not consume random numbers, such that the classic model is
+    /// reproduced exactly, all other distributions consume exactly one
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Float {
+        match *self {
+            WeightDistribution::Unit => 1.,
+            WeightDistribution::Uniform { low, high } => rng.gen::<Float>() * (high - low) + low,
+            WeightDistribution::PowerLaw { exponent, min, max } => {
+                power_law_quantile(rng.gen(), exponent, min, max)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for WeightDistribution {
+    type Err = String;
+
+    /// parse distributions of the form `unit`, `uniform:<low>:<high>` or
+    /// `powerlaw:<exponent>:<min>:<max>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |x: &str| {
+            x.parse::<Float>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        match parts.as_slice() {
+            ["unit"] => Ok(WeightDistribution::Unit),
+            ["uniform", low, high] => Ok(WeightDistribution::Uniform {
+                low: number(low)?,
+                high: number(high)?,
+            }),
+            ["powerlaw", exponent, min, max] => Ok(WeightDistribution::PowerLaw {
+                exponent: number(exponent)?,
+                min: number(min)?,
+                max: number(max)?,
+            }),
+            _ => Err(format!(
+                "unknown weight distribution '{}', use 'unit', 'uniform:<low>:<high>' or 'powerlaw:<exponent>:<min>:<max>'",
+                s
+            )),
+        }
+    }
+}
+
+/// distribution of the activities of the agents, i.e., the probability with which an
+/// agent updates its opinion during a sweep
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActivityDistribution {
+    /// every agent is updated every sweep, as in the classic model
+    Always,
+    /// every agent has the same activity `value`
+    Constant { value: Float },
+    /// activities are uniformly distributed in [low, high]
+    Uniform { low: Float, high: Float },
+}
+
+impl ActivityDistribution {
+    /// check that the parameters describe a proper distribution of probabilities
+    pub fn validate(&self) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidActivities(reason));
+        let probability = |x: Float| (0. ..=1.).contains(&x);
+        match *self {
+            ActivityDistribution::Always => {}
+            ActivityDistribution::Constant { value } => {
+                if !probability(value) {
+                    return invalid(format!("activity {} needs to be in [0, 1]", value));
+                }
+            }
+            ActivityDistribution::Uniform { low, high } => {
+                if !(probability(low) && probability(high)) {
+                    return invalid(format!("bounds [{}, {}] need to be in [0, 1]", low, high));
+                }
+                if low > high {
+                    return invalid(format!("lower bound {} exceeds upper bound {}", low, high));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// draw an activity from this distribution
+    /// only the uniform distribution consumes a random number, such that the classic
+    /// model is reproduced exactly
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Float {
+        match *self {
+            ActivityDistribution::Always => 1.,
+            ActivityDistribution::Constant { value } => value,
+            ActivityDistribution::Uniform { low, high } => rng.gen::<Float>() * (high - low) + low,
+        }
+    }
+}
+
+impl std::str::FromStr for ActivityDistribution {
+    type Err = String;
+
+    /// parse distributions of the form `always`, `constant:<value>` or `uniform:<low>:<high>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |x: &str| {
+            x.parse::<Float>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        match parts.as_slice() {
+            ["always"] => Ok(ActivityDistribution::Always),
+            ["constant", value] => Ok(ActivityDistribution::Constant {
+                value: number(value)?,
+            }),
+            ["uniform", low, high] => Ok(ActivityDistribution::Uniform {
+                low: number(low)?,
+                high: number(high)?,
+            }),
+            _ => Err(format!(
+                "unknown activity distribution '{}', use 'always', 'constant:<value>' or 'uniform:<low>:<high>'",
+                s
+            )),
+        }
+    }
+}
+
+/// inverse of the cumulative distribution function of the power law `p(x) ~ x^-exponent`
+/// truncated to [min, max]
+fn power_law_quantile(u: Float, exponent: Float, min: Float, max: Float) -> Float {
+    let e = 1. - exponent as f64;
+    let lo = (min as f64).powf(e);
+    let hi = (max as f64).powf(e);
+    let x = (lo + u as f64 * (hi - lo)).powf(1. / e) as Float;
+    // avoid leaving the interval due to rounding
+    x.clamp(min, max)
+}
+
+/// distribution of the initial opinions of the agents
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InitialOpinions {
+    /// opinions are uniformly distributed in [0, 1]
+    Uniform,
+    /// opinions are normally distributed, truncated to [0, 1]
+    Gaussian { mean: Float, sd: Float },
+    /// opinions are Beta(alpha, beta) distributed, e.g., centered for `alpha = beta > 1`,
+    /// U-shaped for `alpha = beta < 1` and skewed for `alpha != beta`
+    Beta { alpha: Float, beta: Float },
+    /// the opinion of every agent is given explicitly
+    Custom(Vec<Float>),
+    /// a fraction of extremists with opinions uniformly distributed in
+    /// [0, width] and [1 - width, 1] and the fixed confidence `extremist_confidence`,
+    /// all other agents are uniformly distributed moderates
+    Extremists {
+        fraction: Float,
+        width: Float,
+        extremist_confidence: Float,
+    },
+    /// two blocks of agents: the fraction `split_fraction` forms block 0 with opinions
+    /// uniformly distributed in `range_a` and confidences uniformly distributed in
+    /// `confidence_a`, the remaining agents form block 1 analogously, the block of every
+    /// agent is its group
+    TwoBlocks {
+        split_fraction: Float,
+        range_a: (Float, Float),
+        range_b: (Float, Float),
+        confidence_a: (Float, Float),
+        confidence_b: (Float, Float),
+    },
+}
+
+impl InitialOpinions {
+    /// check that the parameters describe a proper distribution for `n` agents
+    pub fn validate(&self, n: usize) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidInitialOpinions(reason));
+        match self {
+            InitialOpinions::Uniform => {}
+            InitialOpinions::Gaussian { mean, sd } => {
+                if !(mean.is_finite() && sd.is_finite() && *sd > 0.) {
+                    return invalid(format!(
+                        "mean {} and standard deviation {} need to be finite and sd positive",
+                        mean, sd
+                    ));
+                }
+            }
+            InitialOpinions::Beta { alpha, beta } => {
+                if !(alpha.is_finite() && beta.is_finite() && *alpha > 0. && *beta > 0.) {
+                    return invalid(format!(
+                        "shape parameters {} and {} need to be finite and positive",
+                        alpha, beta
+                    ));
+                }
+            }
+            InitialOpinions::Custom(opinions) => {
+                if opinions.len() != n {
+                    return invalid(format!(
+                        "{} opinions given for {} agents",
+                        opinions.len(),
+                        n
+                    ));
+                }
+                if let Some(x) = opinions.iter().find(|x| !(0. ..=1.).contains(*x)) {
+                    return invalid(format!("opinion {} is not within [0, 1]", x));
+                }
+            }
+            InitialOpinions::Extremists {
+                fraction,
+                width,
+                extremist_confidence,
+            } => {
+                if !(0. ..=1.).contains(fraction) {
+                    return invalid(format!("fraction {} needs to be in [0, 1]", fraction));
+                }
+                if !(0. ..=0.5).contains(width) {
+                    return invalid(format!("width {} needs to be in [0, 0.5]", width));
+                }
+                if !(extremist_confidence.is_finite() && *extremist_confidence >= 0.) {
+                    return invalid(format!(
+                        "confidence {} of the extremists needs to be non-negative",
+                        extremist_confidence
+                    ));
+                }
+            }
+            InitialOpinions::TwoBlocks {
+                split_fraction,
+                range_a,
+                range_b,
+                confidence_a,
+                confidence_b,
+            } => {
+                if !(0. ..=1.).contains(split_fraction) {
+                    return invalid(format!(
+                        "split fraction {} needs to be in [0, 1]",
+                        split_fraction
+                    ));
+                }
+                for &(low, high) in &[range_a, range_b] {
+                    if !(0. <= *low && low <= high && *high <= 1.) {
+                        return invalid(format!(
+                            "opinion range [{}, {}] needs to be a subinterval of [0, 1]",
+                            low, high
+                        ));
+                    }
+                }
+                for &(low, high) in &[confidence_a, confidence_b] {
+                    if !(0. <= *low && low <= high && high.is_finite()) {
+                        return invalid(format!(
+                            "confidence range [{}, {}] needs to be finite and non-negative",
+                            low, high
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the block of an agent whose opinion is determined by the uniform random number `u`
+    /// and the distribution of its confidence, if there are blocks
+    fn block(&self, u: Float) -> Option<(u8, ConfidenceDistribution)> {
+        match *self {
+            InitialOpinions::TwoBlocks {
+                split_fraction,
+                confidence_a,
+                confidence_b,
+                ..
+            } => {
+                let (block, (low, high)) = if u < split_fraction {
+                    (0, confidence_a)
+                } else {
+                    (1, confidence_b)
+                };
+                Some((block, ConfidenceDistribution::Uniform { low, high }))
+            }
+            _ => None,
+        }
+    }
+
+    /// draw the initial opinion of agent `idx` from this distribution
+    /// exactly one random number is consumed, independent of the distribution, such
+    /// that the remaining initial state is the same for every distribution
+    pub fn sample<R: Rng>(&self, idx: usize, rng: &mut R) -> Float {
+        self.sample_with_extremist(idx, rng).0
+    }
+
+    /// draw the initial opinion of agent `idx` from this distribution together with
+    /// the confidence of the agent, if it is an extremist
+    /// the same single random number decides whether the agent is an extremist
+    /// and its opinion, such that the extremists are a deterministic function of the seed
+    pub fn sample_with_extremist<R: Rng>(&self, idx: usize, rng: &mut R) -> (Float, Option<Float>) {
+        let (x, extremist_confidence, _) = self.sample_with_role(idx, rng);
+        (x, extremist_confidence)
+    }
+
+    /// like `sample_with_extremist`, but additionally the block of the agent and the
+    /// distribution of its confidence, if there are blocks
+    pub fn sample_with_role<R: Rng>(
+        &self,
+        idx: usize,
+        rng: &mut R,
+    ) -> (Float, Option<Float>, Option<(u8, ConfidenceDistribution)>) {
+        let u: Float = rng.gen();
+        let (x, extremist_confidence) = self.opinion_from_uniform(idx, u);
+        (x, extremist_confidence, self.block(u))
+    }
+
+    /// the opinion of agent `idx` and its confidence, if it is an extremist, given the
+    /// uniform random number `u` in [0, 1)
+    fn opinion_from_uniform(&self, idx: usize, u: Float) -> (Float, Option<Float>) {
+        match *self {
+            InitialOpinions::Uniform => (u, None),
+            InitialOpinions::Gaussian { mean, sd } => {
+                // inverse transform sampling of the truncated normal distribution
+                let (mean, sd) = (mean as f64, sd as f64);
+                let lo = normal_cdf((0. - mean) / sd);
+                let hi = normal_cdf((1. - mean) / sd);
+                let x = mean + sd * normal_quantile(lo + u as f64 * (hi - lo));
+                ((x as Float).clamp(0., 1.), None)
+            }
+            InitialOpinions::Beta { alpha, beta } => {
+                // inverse transform sampling, Beta(1, 1) is the uniform distribution
+                let x = beta_quantile(u as f64, alpha as f64, beta as f64);
+                ((x as Float).clamp(0., 1.), None)
+            }
+            InitialOpinions::Custom(ref opinions) => (opinions[idx], None),
+            InitialOpinions::Extremists {
+                fraction,
+                width,
+                extremist_confidence,
+            } => {
+                if u < fraction {
+                    // rescale to a uniform number in [0, 1) to place the extremist
+                    let v = u / fraction;
+                    let x = if v < 0.5 {
+                        2. * v * width
+                    } else {
+                        1. - width + (2. * v - 1.) * width
+                    };
+                    (x.clamp(0., 1.), Some(extremist_confidence))
+                } else {
+                    // rescale to a uniform number in [0, 1) to place the moderate
+                    ((u - fraction) / (1. - fraction), None)
+                }
+            }
+            InitialOpinions::TwoBlocks {
+                split_fraction,
+                range_a,
+                range_b,
+                ..
+            } => {
+                // rescale to a uniform number in [0, 1) to place the agent within its block
+                let ((low, high), v) = if u < split_fraction {
+                    (range_a, u / split_fraction)
+                } else {
+                    (range_b, (u - split_fraction) / (1. - split_fraction))
+                };
+                ((low + v * (high - low)).clamp(low, high), None)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for InitialOpinions {
+    type Err = String;
+
+    /// parse distributions of the form `uniform`, `gaussian:<mean>:<sd>`,
+    /// `beta:<alpha>:<beta>` or `extremists:<fraction>:<width>:<confidence>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |x: &str| {
+            x.parse::<Float>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        match parts.as_slice() {
+            ["uniform"] => Ok(InitialOpinions::Uniform),
+            ["gaussian", mean, sd] => Ok(InitialOpinions::Gaussian {
+                mean: number(mean)?,
+                sd: number(sd)?,
+            }),
+            ["beta", alpha, beta] => Ok(InitialOpinions::Beta {
+                alpha: number(alpha)?,
+                beta: number(beta)?,
+            }),
+            ["extremists", fraction, width, confidence] => Ok(InitialOpinions::Extremists {
+                fraction: number(fraction)?,
+                width: number(width)?,
+                extremist_confidence: number(confidence)?,
+            }),
+            _ => Err(format!(
+                "unknown initial distribution '{}', use 'uniform', 'gaussian:<mean>:<sd>', 'beta:<alpha>:<beta>' or 'extremists:<fraction>:<width>:<confidence>'",
+                s
+            )),
+        }
+    }
+}
+
+/// natural logarithm of the gamma function for positive arguments
+/// (Lanczos approximation with g = 7, accurate to about 15 digits)
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1. - x);
+    }
+
+    let x = x - 1.;
+    let t = x + G + 0.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (k, c)| sum + c / (x + k as f64 + 1.));
+    0.5 * (2. * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// continued fraction of the incomplete beta function (modified Lentz's method,
+/// following Numerical Recipes)
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let clamp_tiny = |d: f64| if d.abs() < TINY { TINY } else { d };
+
+    let mut c = 1.;
+    let mut d = 1. / clamp_tiny(1. - (a + b) * x / (a + 1.));
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        // even step
+        let numerator = m * (b - m) * x / ((a + 2. * m - 1.) * (a + 2. * m));
+        d = 1. / clamp_tiny(1. + numerator * d);
+        c = clamp_tiny(1. + numerator / c);
+        h *= d * c;
+        // odd step
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2. * m) * (a + 2. * m + 1.));
+        d = 1. / clamp_tiny(1. + numerator * d);
+        c = clamp_tiny(1. + numerator / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.).abs() < 1e-15 {
+            break;
+        }
+    }
+    h
+}
+
+/// regularized incomplete beta function, i.e., the cumulative distribution function
+/// of the Beta(a, b) distribution
+fn regularized_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln()).exp();
+    // the continued fraction converges quickly only below the mean, otherwise use the symmetry
+    if x < (a + 1.) / (a + b + 2.) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1. - front * beta_continued_fraction(b, a, 1. - x) / b
+    }
+}
+
+/// quantile function of the Beta(a, b) distribution, found by Newton's method on its
+/// cumulative distribution function, safeguarded by bisection
+fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    let ln_norm = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b);
+    let density = |x: f64| (ln_norm + (a - 1.) * x.ln() + (b - 1.) * (1. - x).ln()).exp();
+
+    let mut lo = 0.;
+    let mut hi = 1.;
+    let mut x = 0.5;
+    for _ in 0..100 {
+        let residual = regularized_beta(x, a, b) - p;
+        if residual < 0. {
+            lo = x;
+        } else {
+            hi = x;
+        }
+        let newton = x - residual / density(x);
+        // fall back to bisection, whenever the Newton step leaves the bracket
+        let next = if newton > lo && newton < hi {
+            newton
+        } else {
+            0.5 * (lo + hi)
+        };
+        if (next - x).abs() < 1e-15 || hi - lo < 1e-15 {
+            return next;
+        }
+        x = next;
+    }
+    x
+}
+
+/// cumulative distribution function of the standard normal distribution
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// complementary error function with a relative error below 1.2e-7
+/// (Chebyshev approximation from Numerical Recipes)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let r = t
+        * (-z * z - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98
+                                    + t * (1.488_515_87
+                                        + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0. {
+        r
+    } else {
+        2. - r
+    }
+}
+
+/// quantile function of the standard normal distribution
+/// (rational approximation by P. J. Acklam with a relative error below 1.2e-9)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.024_25;
+
+    // the tails are infinitely far away
+    if p <= 0. {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1. {
+        return f64::INFINITY;
+    }
+
+    if p < P_LOW {
+        let q = (-2. * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    } else if p <= 1. - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    } else {
+        let q = (-2. * (1. - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    }
+}
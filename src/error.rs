@@ -0,0 +1,200 @@
+/// This file defines the errors, which can occur while setting up or running a model
+use std::fmt;
+
+use crate::float::Float;
+
+/// errors reported by the models of this crate
+#[derive(Clone, Debug, PartialEq)]
+pub enum HkError {
+    /// the parameters of a distribution of confidences are invalid
+    InvalidConfidenceDistribution(String),
+    /// the parameters of a distribution of initial opinions are invalid
+    InvalidInitialOpinions(String),
+    /// an explicitly given state of the agents is invalid
+    InvalidState(String),
+    /// the parameters of a schedule of confidences are invalid
+    InvalidConfidenceSchedule(String),
+    /// the parameters of a distribution of influence weights are invalid
+    InvalidWeights(String),
+    /// a parameter of the dynamics is out of its valid range
+    InvalidParameter(String),
+    /// the parameters of a profile of confidences are invalid
+    InvalidConfidenceProfile(String),
+    /// the parameters of a distribution of activities are invalid
+    InvalidActivities(String),
+    /// the group assignments or the matrix of inter-group confidences are invalid
+    InvalidGroups(String),
+    /// the tree of opinions does not match the opinions of the agents
+    InconsistentTree(String),
+    /// the opinion or a confidence of the agent with index `agent` became NaN or infinite
+    NonFinite {
+        agent: usize,
+        opinion: Float,
+        confidence: Float,
+        sweep: usize,
+    },
+}
+
+impl fmt::Display for HkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HkError::InvalidConfidenceDistribution(reason) => {
+                write!(f, "invalid confidence distribution: {}", reason)
+            }
+            HkError::InvalidInitialOpinions(reason) => {
+                write!(f, "invalid initial opinions: {}", reason)
+            }
+            HkError::InvalidState(reason) => write!(f, "invalid state: {}", reason),
+            HkError::InvalidConfidenceSchedule(reason) => {
+                write!(f, "invalid confidence schedule: {}", reason)
+            }
+            HkError::InvalidWeights(reason) => write!(f, "invalid weights: {}", reason),
+            HkError::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            HkError::InvalidConfidenceProfile(reason) => {
+                write!(f, "invalid confidence profile: {}", reason)
+            }
+            HkError::InvalidActivities(reason) => write!(f, "invalid activities: {}", reason),
+            HkError::InvalidGroups(reason) => write!(f, "invalid groups: {}", reason),
+            HkError::InconsistentTree(reason) => write!(f, "inconsistent tree: {}", reason),
+            HkError::NonFinite {
+                agent,
+                opinion,
+                confidence,
+                sweep,
+            } => write!(
+                f,
+                "non-finite state: agent {} has the opinion {} and the confidence {} after sweep {}",
+                agent, opinion, confidence, sweep
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HkError {}
+
+/// the convergence criterion was not met within the maximum number of sweeps
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotConverged {
+    /// number of sweeps done
+    pub sweeps: usize,
+    /// total change of the opinions during the last sweep
+    pub last_change: Float,
+}
+
+impl fmt::Display for NotConverged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "not converged within {} sweeps, the last sweep changed the opinions by {}",
+            self.sweeps, self.last_change
+        )
+    }
+}
+
+impl std::error::Error for NotConverged {}
+
+/// an invariant of the state of a model, which `verify_invariants` found to be violated
+/// `group` names the tree of a group, or is `None` for the tree of all opinions
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvariantViolation {
+    /// the opinion of the agent with index `agent` is NaN or infinite
+    NonFiniteOpinion { agent: usize, opinion: Float },
+    /// the opinion of the agent with index `agent` left the opinion interval, although the
+    /// boundary should keep it inside
+    OutsideInterval {
+        agent: usize,
+        opinion: Float,
+        interval: (Float, Float),
+    },
+    /// the tree contains a key, which is no number
+    NanKey { group: Option<usize> },
+    /// the opinion of the agent with index `agent` is no key of the tree
+    MissingOpinion {
+        group: Option<usize>,
+        agent: usize,
+        opinion: Float,
+    },
+    /// the counters of the tree do not sum to the number of agents it should contain
+    CountSum {
+        group: Option<usize>,
+        sum: u64,
+        agents: usize,
+    },
+    /// the tree counts `opinion` `count` times, but `expected` agents hold it
+    WrongCount {
+        group: Option<usize>,
+        opinion: Float,
+        count: u32,
+        expected: u32,
+    },
+}
+
+/// the tree described by `group` for the messages of the violations
+fn tree_name(group: &Option<usize>) -> String {
+    match group {
+        Some(g) => format!("the tree of group {}", g),
+        None => "the tree".to_string(),
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::NonFiniteOpinion { agent, opinion } => {
+                write!(f, "agent {} has the non-finite opinion {}", agent, opinion)
+            }
+            InvariantViolation::OutsideInterval {
+                agent,
+                opinion,
+                interval: (a, b),
+            } => write!(
+                f,
+                "agent {} has the opinion {} outside of the opinion interval [{}, {}]",
+                agent, opinion, a, b
+            ),
+            InvariantViolation::NanKey { group } => {
+                write!(f, "{} contains a NaN key", tree_name(group))
+            }
+            InvariantViolation::MissingOpinion {
+                group,
+                agent,
+                opinion,
+            } => write!(
+                f,
+                "the opinion {} of agent {} is missing in {}",
+                opinion,
+                agent,
+                tree_name(group)
+            ),
+            InvariantViolation::CountSum { group, sum, agents } => write!(
+                f,
+                "the counters of {} sum to {}, but it contains {} agents",
+                tree_name(group),
+                sum,
+                agents
+            ),
+            InvariantViolation::WrongCount {
+                group,
+                opinion,
+                count,
+                expected,
+            } => write!(
+                f,
+                "{} counts the opinion {} {} times, but {} agents hold it",
+                tree_name(group),
+                opinion,
+                count,
+                expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// a violated invariant means that the tree does not match the agents anymore
+impl From<InvariantViolation> for HkError {
+    fn from(violation: InvariantViolation) -> HkError {
+        HkError::InconsistentTree(violation.to_string())
+    }
+}
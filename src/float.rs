@@ -0,0 +1,51 @@
+/// floating point type of all opinions, confidences and weights, which is `f32` by default
+/// and `f64` with the `f64` feature, e.g., to resolve tighter convergence thresholds at the
+/// cost of memory and speed
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+/// floating point type of all opinions, confidences and weights, which is `f32` by default
+/// and `f64` with the `f64` feature, e.g., to resolve tighter convergence thresholds at the
+/// cost of memory and speed
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts::PI;
+#[cfg(feature = "f64")]
+pub use std::f64::consts::PI;
+
+/// unsigned integer with the width of `Float`, whose values are used as keys of the opinions
+#[cfg(not(feature = "f64"))]
+pub(crate) type Key = u32;
+/// unsigned integer with the width of `Float`, whose values are used as keys of the opinions
+#[cfg(feature = "f64")]
+pub(crate) type Key = u64;
+
+/// the sign bit of a `Float`
+const SIGN: Key = 1 << (Key::BITS - 1);
+
+/// key of the opinion `x`, such that the keys are ordered like the opinions
+/// for non-negative floats, the bit patterns are already ordered like the values, so only the
+/// sign bit is set to sort them above all negative floats, whose bit patterns are inverted
+/// to reverse their order
+/// NaN gets a key beyond all numbers, `HegselmannKrause::check_finite` reports such opinions
+#[inline]
+pub(crate) fn to_key(x: Float) -> Key {
+    // adding 0 turns -0 into 0, such that both share a key
+    let bits = (x + 0.).to_bits();
+    if bits & SIGN == 0 {
+        bits | SIGN
+    } else {
+        !bits
+    }
+}
+
+/// opinion of the key `key`, inverse of `to_key`
+#[inline]
+pub(crate) fn from_key(key: Key) -> Float {
+    if key & SIGN == 0 {
+        Float::from_bits(!key)
+    } else {
+        Float::from_bits(key & !SIGN)
+    }
+}
@@ -5,260 +5,5770 @@
 ///               within the confidence interval for the calculation of the next state
 /// `sweep_tree`  uses the improved algorithm, based on a search tree (here a BTree), introduced
 ///               in the corresponding article
-use std::collections::BTreeMap;
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::io::prelude::*;
-use std::ops::Bound::Included;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
 
 use itertools::Itertools;
 use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg64;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-// note that `OrderedFloat` is a technicality to allow using floats as keys in
-// the tree (rooted in the problem that IEEE floats do not have a total order, due to `nan`,
-// which can therefore not be part of a search tree)
+use crate::distributions::{
+    ActivityDistribution, ConfidenceDistribution, InitialOpinions, WeightDistribution,
+};
+use crate::error::{HkError, InvariantViolation, NotConverged};
+use crate::float::{from_key, to_key, Float, Key, PI};
+use crate::network::Network;
+use crate::schedule::{ConfidenceFunction, ConfidenceSchedule};
+
+// note that `OrderedFloat` is a technicality to allow sorting floats (rooted in the problem
+// that IEEE floats do not have a total order, due to `nan`), the trees are instead keyed on
+// the bit patterns of the opinions, see `to_key`, which are cheaper to compare
 use ordered_float::OrderedFloat;
 
-/// numerical tolerance
-const EPS: f32 = 1e-5;
+/// numerical tolerance, default distance below which opinions belong to the same cluster
+pub(crate) const EPS: Float = 1e-5;
+
+/// number of tree-based sweeps with non-unit weights after which the tree is rebuilt
+/// from scratch to get rid of the rounding errors accumulated by the weight updates
+const TREE_REBUILD_INTERVAL: u32 = 1000;
+
+/// number of synchronous sweeps after which the agents are sorted by opinion again, the
+/// order of the opinions changes only slowly, since close agents see similar neighbors
+const ORDER_REFRESH_INTERVAL: u32 = 10;
+
+/// number of agents from which on synchronous sweeps visit the agents in the order of their
+/// opinions by default, smaller systems fit into the caches anyway, such that the sorting
+/// does not pay off
+const SORTED_ITERATION_MIN_AGENTS: u32 = 1_000_000;
+
+/// tolerance (in units of the grid spacing) up to which a rounding error of the mean is
+/// ignored when rounding to the opinion grid, such that halfway cases are always rounded up
+const QUANTIZATION_TOLERANCE: Float = 1e-4;
+
+/// start of every checkpoint, followed by the version of the format and the size of `Float`,
+/// increase the version whenever the serialized fields change, since the encoding does
+/// not describe itself and an old checkpoint would be decoded into garbage
+#[cfg(feature = "serde")]
+const CHECKPOINT_MAGIC: &[u8; 4] = b"HKCP";
+#[cfg(feature = "serde")]
+const CHECKPOINT_VERSION: u8 = 1;
+
+/// topology of the space the opinions live in
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OpinionSpace {
+    /// opinions live on the interval [0, 1], or any other, see `set_opinion_interval`
+    Line,
+    /// opinions live on [0, 1) with periodic boundaries, e.g., for angles
+    Circle,
+}
+
+impl OpinionSpace {
+    /// distance between two opinions in this space
+    pub(crate) fn distance(self, a: Float, b: Float) -> Float {
+        let d = (a - b).abs();
+        match self {
+            OpinionSpace::Line => d,
+            OpinionSpace::Circle => d.min(1. - d),
+        }
+    }
+
+    /// the opinion the fraction `t` of the way from `a` to `b`,
+    /// on the circle along the shorter arc
+    fn interpolate(self, a: Float, b: Float, t: Float) -> Float {
+        match self {
+            OpinionSpace::Line => (1. - t) * a + t * b,
+            OpinionSpace::Circle => {
+                let mut d = b - a;
+                if d > 0.5 {
+                    d -= 1.;
+                } else if d < -0.5 {
+                    d += 1.;
+                }
+                wrap(a + t * d)
+            }
+        }
+    }
+}
+
+/// shape of the influence of a neighbor as a function of the distance of its opinion
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kernel {
+    /// every neighbor within the confidence interval counts fully, all others not at all
+    Hard,
+    /// neighbors count with weight `exp(-d^2 / (2 eps^2))`, i.e., the confidence `eps`
+    /// is the width of a Gaussian, which is truncated at `GAUSSIAN_CUTOFF` widths
+    Gaussian,
+    /// neighbors count with weight `max(0, 1 - d / eps)`, i.e., closer opinions count more
+    Linear,
+}
+
+/// treatment of opinions which are pushed beyond the boundaries of the opinion interval,
+/// e.g., by noise, drift or contrarians, `apply` works on [0, 1]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    /// opinions stop at the boundary
+    Clamp,
+    /// opinions are mirrored at the boundary, as often as necessary
+    Reflect,
+    /// opinions leaving at one end enter at the other, i.e., the opinion space is a circle
+    Periodic,
+    /// opinions may leave the interval
+    Open,
+}
+
+impl Boundary {
+    /// map the opinion `x` into the opinion space according to this boundary condition
+    pub fn apply(self, x: Float) -> Float {
+        match self {
+            Boundary::Clamp => x.clamp(0., 1.),
+            Boundary::Reflect => {
+                // reflections at 0 and 1 are periodic with period 2
+                let y = x.rem_euclid(2.);
+                if y > 1. {
+                    2. - y
+                } else {
+                    y
+                }
+            }
+            Boundary::Periodic => wrap(x),
+            Boundary::Open => x,
+        }
+    }
+}
+
+impl std::str::FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(Boundary::Clamp),
+            "reflect" => Ok(Boundary::Reflect),
+            "periodic" => Ok(Boundary::Periodic),
+            "open" => Ok(Boundary::Open),
+            _ => Err(format!(
+                "unknown boundary '{}', use 'clamp', 'reflect', 'periodic' or 'open'",
+                s
+            )),
+        }
+    }
+}
+
+/// statistic of the opinions of its neighbors an agent adopts
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Aggregator {
+    /// the (weighted) mean, i.e., the classic model
+    Mean,
+    /// the weighted median, halfway between the two middle opinions if the weight is
+    /// split exactly in half
+    Median,
+    /// the weighted mean after discarding the fraction `alpha` of the weight on both ends
+    TrimmedMean { alpha: Float },
+}
+
+impl Aggregator {
+    /// the statistic of the opinions `x`, given as pairs `(x, weight)` in ascending order
+    #[allow(clippy::float_cmp)]
+    fn aggregate(self, sorted: &[(Float, Float)]) -> Float {
+        let total: Float = sorted.iter().map(|&(_, w)| w).sum();
+        match self {
+            Aggregator::Mean => sorted.iter().map(|&(x, w)| w * x).sum::<Float>() / total,
+            Aggregator::Median => {
+                let half = total / 2.;
+                let mut cumulative = 0.;
+                for (k, &(x, w)) in sorted.iter().enumerate() {
+                    cumulative += w;
+                    if cumulative == half && k + 1 < sorted.len() {
+                        return (x + sorted[k + 1].0) / 2.;
+                    }
+                    if cumulative >= half {
+                        return x;
+                    }
+                }
+                sorted[sorted.len() - 1].0
+            }
+            Aggregator::TrimmedMean { alpha } => {
+                let lower = alpha * total;
+                let upper = (1. - alpha) * total;
+                let mut cumulative: Float = 0.;
+                let mut sum = 0.;
+                for &(x, w) in sorted {
+                    // the part of the weight of `x`, which lies within [lower, upper]
+                    let kept = (cumulative + w).min(upper) - cumulative.max(lower);
+                    if kept > 0. {
+                        sum += kept * x;
+                    }
+                    cumulative += w;
+                }
+                sum / (upper - lower)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Aggregator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        match parts.as_slice() {
+            ["mean"] => Ok(Aggregator::Mean),
+            ["median"] => Ok(Aggregator::Median),
+            ["trimmed", alpha] => alpha
+                .parse()
+                .map(|alpha| Aggregator::TrimmedMean { alpha })
+                .map_err(|e| format!("invalid trimming fraction '{}': {}", alpha, e)),
+            _ => Err(format!(
+                "unknown aggregator '{}', use 'mean', 'median' or 'trimmed:<alpha>'",
+                s
+            )),
+        }
+    }
+}
+
+/// choice of the neighbors an agent averages over, if more than the cap are within its
+/// confidence interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CapStrategy {
+    /// the neighbors with the closest opinions, ties are broken like for the nearest neighbors
+    Closest,
+    /// neighbors drawn uniformly from all within the confidence interval
+    Random,
+}
+
+impl std::str::FromStr for CapStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "closest" => Ok(CapStrategy::Closest),
+            "random" => Ok(CapStrategy::Random),
+            _ => Err(format!(
+                "unknown cap strategy '{}', use 'closest' or 'random'",
+                s
+            )),
+        }
+    }
+}
+
+/// data structure answering the range queries of a synchronous sweep
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backend {
+    /// a tree of the distinct opinions, which is kept up to date during the sweep
+    Tree,
+    /// sorted arrays of the distinct opinions and their cumulative sums, which are
+    /// rebuilt from the agents at the start of every sweep
+    Sorted,
+    /// the sorted arrays wherever they suffice, otherwise the tree
+    Auto,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tree" => Ok(Backend::Tree),
+            "sorted" => Ok(Backend::Sorted),
+            "auto" => Ok(Backend::Auto),
+            _ => Err(format!(
+                "unknown backend '{}', use 'tree', 'sorted' or 'auto'",
+                s
+            )),
+        }
+    }
+}
+
+/// criterion deciding when `run_until_converged` stops sweeping
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConvergenceCriterion {
+    /// the total change of all opinions during a sweep drops below the threshold,
+    /// which therefore has to grow with the number of agents
+    SumChange { threshold: Float },
+    /// the largest change of a single opinion during a sweep drops below the threshold
+    MaxChange { threshold: Float },
+    /// the cluster sizes did not change during the given number of consecutive sweeps
+    ClusterStability { sweeps: usize },
+}
+
+impl ConvergenceCriterion {
+    /// whether a system, which no sweep can change anymore, meets the criterion without
+    /// any sweep, a threshold of 0 is never met, e.g., to sweep a fixed number of times,
+    /// and the stability of the clusters is only observed over the given number of sweeps
+    pub fn accepts_static(&self) -> bool {
+        match *self {
+            ConvergenceCriterion::SumChange { threshold }
+            | ConvergenceCriterion::MaxChange { threshold } => threshold > 0.,
+            ConvergenceCriterion::ClusterStability { .. } => false,
+        }
+    }
+}
+
+/// state of the convergence test over consecutive sweeps
+pub struct ConvergenceCheck {
+    criterion: ConvergenceCriterion,
+    /// cluster sizes after the last sweep, if the criterion needs them
+    cluster_sizes: Option<Vec<usize>>,
+    /// number of consecutive sweeps without a change of the cluster sizes
+    stable_sweeps: usize,
+}
+
+impl ConvergenceCheck {
+    pub fn new(criterion: ConvergenceCriterion) -> ConvergenceCheck {
+        ConvergenceCheck {
+            criterion,
+            cluster_sizes: None,
+            stable_sweeps: 0,
+        }
+    }
+
+    /// test after a sweep with the given total and maximum change of the opinions,
+    /// the cluster sizes are only determined if the criterion needs them
+    pub fn converged<F: FnOnce() -> Vec<usize>>(
+        &mut self,
+        accumulated_change: Float,
+        max_change: Float,
+        cluster_sizes: F,
+    ) -> bool {
+        match self.criterion {
+            ConvergenceCriterion::SumChange { threshold } => accumulated_change < threshold,
+            ConvergenceCriterion::MaxChange { threshold } => max_change < threshold,
+            ConvergenceCriterion::ClusterStability { sweeps } => {
+                let sizes = cluster_sizes();
+                if self.cluster_sizes.as_ref() == Some(&sizes) {
+                    self.stable_sweeps += 1;
+                } else {
+                    self.stable_sweeps = 0;
+                    self.cluster_sizes = Some(sizes);
+                }
+                self.stable_sweeps >= sweeps
+            }
+        }
+    }
+}
+
+/// summary of one sweep yielded by `HegselmannKrause::sweeps`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepReport {
+    /// number of sweeps since the last reset, including this one
+    pub sweep: usize,
+    /// total change of all opinions during the sweep
+    pub accumulated_change: Float,
+    /// largest change of a single opinion during the sweep
+    pub max_change: Float,
+    /// number of clusters after the sweep, only determined if requested by
+    /// `Sweeps::with_clusters`, since counting them costs about as much as a sweep
+    /// and would slow down the plain convergence loop considerably
+    pub num_clusters: Option<usize>,
+}
+
+/// endless iterator, which performs one sweep per call of `next`
+/// combine it with `take`, `take_while` or `find` to stop
+pub struct Sweeps<'a> {
+    hk: &'a mut HegselmannKrause,
+    clusters: bool,
+}
+
+impl<'a> Sweeps<'a> {
+    /// also count the clusters after every sweep, which costs about as much as a sweep
+    pub fn with_clusters(mut self) -> Sweeps<'a> {
+        self.clusters = true;
+        self
+    }
+}
+
+impl Iterator for Sweeps<'_> {
+    type Item = SweepReport;
+
+    /// panics if the tree turns out to be inconsistent, like `HegselmannKrause::sweep`
+    fn next(&mut self) -> Option<SweepReport> {
+        self.hk.sweep();
+        Some(SweepReport {
+            sweep: self.hk.sweeps,
+            accumulated_change: self.hk.accumulated_change,
+            max_change: self.hk.max_change,
+            num_clusters: if self.clusters {
+                Some(self.hk.list_clusters().len())
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// summary of a run by `run_to_convergence`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvergenceResult {
+    /// number of sweeps, 0 if no opinion moved during the first one
+    pub sweeps: usize,
+    /// whether the criterion was met within the maximum number of sweeps
+    pub converged: bool,
+    /// sizes of the clusters after the run
+    pub cluster_sizes: Vec<usize>,
+    /// opinions of the clusters after the run, in the same order as `cluster_sizes`
+    pub cluster_positions: Vec<Float>,
+}
+
+impl ConvergenceResult {
+    /// summary of a run which ended with the `clusters` of `agents`
+    pub(crate) fn new(
+        sweeps: usize,
+        converged: bool,
+        agents: &[HKAgent],
+        clusters: &[Vec<usize>],
+    ) -> ConvergenceResult {
+        ConvergenceResult {
+            sweeps,
+            converged,
+            cluster_sizes: clusters.iter().map(|c| c.len()).collect(),
+            // like the output of `write_cluster_sizes`
+            cluster_positions: clusters.iter().map(|c| agents[c[0]].opinion).collect(),
+        }
+    }
+}
+
+/// the weight of a media field needs to be finite and non-negative
+fn validate_media_weight(weight: Float) -> Result<(), HkError> {
+    if !(weight >= 0. && weight.is_finite()) {
+        return Err(HkError::InvalidParameter(format!(
+            "media weight {} needs to be finite and non-negative",
+            weight
+        )));
+    }
+    Ok(())
+}
+
+/// number of widths after which the Gaussian kernel is truncated
+pub const GAUSSIAN_CUTOFF: Float = 3.;
+
+/// round an opinion to the nearest of `levels` equally spaced values in [0, 1], on the
+/// circle the values are `k / levels`, since 0 and 1 coincide, `levels = 0` is continuous
+fn quantize(x: Float, levels: u32, topology: OpinionSpace) -> Float {
+    match (levels, topology) {
+        (0, _) => x,
+        (_, OpinionSpace::Line) => {
+            let spacing = (levels - 1) as Float;
+            let level = (x.clamp(0., 1.) * spacing + 0.5 + QUANTIZATION_TOLERANCE).floor();
+            level.min(spacing) / spacing
+        }
+        (_, OpinionSpace::Circle) => {
+            let spacing = levels as Float;
+            wrap((wrap(x) * spacing + 0.5 + QUANTIZATION_TOLERANCE).floor() / spacing)
+        }
+    }
+}
+
+/// map an opinion back onto the circle [0, 1)
+fn wrap(x: Float) -> Float {
+    let y = x.rem_euclid(1.);
+    // for tiny negative `x` the result is rounded to 1
+    if y >= 1. {
+        0.
+    } else {
+        y
+    }
+}
+
+/// unit vector corresponding to the opinion `x` on the circle
+fn unit_vector(x: Float) -> (Float, Float) {
+    let angle = 2. * PI * x;
+    (angle.cos(), angle.sin())
+}
+
+/// circular mean of opinions, given the sum of their unit vectors
+fn circular_mean(cos: Float, sin: Float) -> Float {
+    wrap(sin.atan2(cos) / (2. * PI))
+}
+
+/// an external source of information, like mass media, with a fixed opinion
+/// it acts like a virtual agent with `weight` times the influence of a normal agent
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Media {
+    /// opinion promoted by the media
+    pub opinion: Float,
+    /// weight of the media in the average of every agent which sees it
+    pub weight: Float,
+}
+
+/// broadcast exposure to media, which replaces the update of an agent at random
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct MediaExposure {
+    /// probability of every agent to be exposed during a sweep
+    probability: Float,
+    /// fraction of the way to the opinion of the media an exposed agent moves
+    mu: Float,
+    /// opinion promoted by the media
+    opinion: Float,
+}
+
+/// oscillation of the opinion of the media around the center of the opinion space,
+/// `m(t) = center + amplitude * sin(2 pi t / period)` with the number of sweeps `t`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct MediaSchedule {
+    /// center of the opinion interval
+    center: Float,
+    /// maximal deviation of the media's opinion from the center
+    amplitude: Float,
+    /// number of sweeps of one full oscillation
+    period: Float,
+}
+
+impl MediaSchedule {
+    /// opinion of the media after `t` sweeps
+    fn opinion(&self, t: u64) -> Float {
+        let phase = 2. * PI * (t as Float / self.period).fract();
+        self.center + self.amplitude * phase.sin()
+    }
+}
+
+/// parameters of the adaptation of the confidences to the local agreement
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct AdaptiveConfidence {
+    /// change of the confidence per sweep
+    delta: Float,
+    /// lower bound of the confidences
+    min: Float,
+    /// upper bound of the confidences
+    max: Float,
+}
+
+/// growth of the confidences of agents, which did not see anybody else for a while
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IsolationGrowth {
+    /// number of consecutive sweeps without neighbors after which the confidence grows
+    sweeps: u32,
+    /// factor by which the confidence grows
+    factor: Float,
+    /// upper bound of the confidences
+    max: Float,
+    /// number of consecutive sweeps the agent with this index did not have any neighbors
+    counters: Vec<u32>,
+}
+
+/// running sum, which compensates its rounding errors following Kahan, if `compensated`
+#[derive(Clone, Copy, Debug, Default)]
+struct Sum {
+    value: Float,
+    /// the negative of the rounding error of the last addition
+    compensation: Float,
+    compensated: bool,
+}
+
+impl Sum {
+    fn new(compensated: bool) -> Sum {
+        Sum {
+            value: 0.,
+            compensation: 0.,
+            compensated,
+        }
+    }
+
+    fn add(&mut self, x: Float) {
+        if self.compensated {
+            let y = x - self.compensation;
+            let t = self.value + y;
+            self.compensation = (t - self.value) - y;
+            self.value = t;
+        } else {
+            self.value += x;
+        }
+    }
+}
+
+/// bookkeeping to reuse the new opinions of the last synchronous tree sweep for agents,
+/// whose neighborhood did not change since
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ChangeTracking {
+    /// whether the new opinions of the last synchronous tree sweep can be reused
+    valid: bool,
+    /// keys of the agents, when their new opinions were calculated
+    keys: Vec<[u64; 5]>,
+    /// opinions inserted into or removed from the tree since then
+    changed: Vec<Float>,
+    /// the media at that time, which every agent might see
+    media: Option<Media>,
+}
+
+impl ChangeTracking {
+    /// whether any opinion within [lower, upper] changed, `changed` needs to be sorted
+    fn touches(&self, lower: Float, upper: Float) -> bool {
+        let k = self.changed.partition_point(|&x| x < lower);
+        k < self.changed.len() && self.changed[k] <= upper
+    }
+}
+
+/// all agents sharing one opinion, stored in the nodes of the tree
+#[derive(Clone, Copy, Debug, Default)]
+struct OpinionEntry {
+    /// number of agents with this opinion
+    count: u32,
+    /// total influence weight of the agents with this opinion, accumulated in f64, since
+    /// an f32 stops counting unit weights at 2^24
+    weight: f64,
+}
+
+/// cumulative sums over the sorted distinct opinions of the tree, such that the sums over
+/// any interval are two binary searches away
+/// the sums are accumulated in f64, since they are differences of large numbers
+/// they are accumulated in ascending order of the opinions, such that they do not depend on
+/// the order of the agents
+#[derive(Default)]
+struct PrefixSums {
+    /// the distinct opinions in ascending order, separate from their entries, such that
+    /// the binary searches touch as little memory as possible
+    opinions: Vec<Float>,
+    /// the entries of the distinct opinions
+    entries: Vec<OpinionEntry>,
+    /// number of agents with an opinion below the k-th opinion
+    counts: Vec<u32>,
+    /// total weight of the opinions below the k-th opinion
+    weights: Vec<f64>,
+    /// total weighted opinion of the opinions below the k-th opinion
+    moments: Vec<f64>,
+    /// the opinions and weights of the agents, which `fill_from_agents` sorts
+    sorted: Vec<(Float, Float)>,
+}
+
+impl PrefixSums {
+    fn from_tree(tree: &BTreeMap<Key, OpinionEntry>) -> PrefixSums {
+        let mut prefix = PrefixSums::default();
+        for (&x, entry) in tree.iter() {
+            prefix.opinions.push(from_key(x));
+            prefix.entries.push(*entry);
+        }
+        prefix.accumulate();
+        prefix
+    }
+
+    /// replace the content by the sums over the opinions of `agents`, agents sharing an
+    /// opinion collapse into a single entry, the storage is reused
+    fn fill_from_agents(&mut self, agents: &[HKAgent]) {
+        self.sorted.clear();
+        self.sorted
+            .extend(agents.iter().map(|i| (i.opinion, i.mass())));
+        self.sorted.sort_unstable_by_key(|&(x, _)| OrderedFloat(x));
+        self.opinions.clear();
+        self.entries.clear();
+        for &(x, w) in self.sorted.iter() {
+            match (self.opinions.last(), self.entries.last_mut()) {
+                #[allow(clippy::float_cmp)]
+                (Some(&y), Some(entry)) if y == x => {
+                    entry.count += 1;
+                    entry.weight += w as f64;
+                }
+                _ => {
+                    self.opinions.push(x);
+                    self.entries.push(OpinionEntry {
+                        count: 1,
+                        weight: w as f64,
+                    });
+                }
+            }
+        }
+        self.accumulate();
+    }
+
+    /// calculate the cumulative sums over `opinions` and `entries` in ascending order
+    fn accumulate(&mut self) {
+        self.counts.clear();
+        self.weights.clear();
+        self.moments.clear();
+        let (mut count, mut weight, mut moment) = (0, 0., 0.);
+        self.counts.push(count);
+        self.weights.push(weight);
+        self.moments.push(moment);
+        for (&x, entry) in self.opinions.iter().zip(&self.entries) {
+            count += entry.count;
+            weight += entry.weight;
+            moment += entry.weight * x as f64;
+            self.counts.push(count);
+            self.weights.push(weight);
+            self.moments.push(moment);
+        }
+    }
+
+    /// weighted sum, total weight and number of all opinions within [lower, upper]
+    fn range_sum(&self, lower: Float, upper: Float) -> (Float, Float, u32) {
+        let a = self.opinions.partition_point(|&x| x < lower);
+        let b = self.opinions.partition_point(|&x| x <= upper);
+        if b <= a {
+            return (0., 0., 0);
+        }
+        // a single opinion is summed up exactly, such that converged clusters stay put
+        if b == a + 1 {
+            let (x, entry) = (self.opinions[a], self.entries[a]);
+            let weight = entry.weight as Float;
+            return (weight * x, weight, entry.count);
+        }
+        (
+            (self.moments[b] - self.moments[a]) as Float,
+            (self.weights[b] - self.weights[a]) as Float,
+            self.counts[b] - self.counts[a],
+        )
+    }
+}
+
+/// cumulative sums over the sorted distinct opinions, which answer the sums over the
+/// confidence interval of the hard kernel on the line
+trait CumulativeSums: Sync {
+    /// number of distinct opinions below `lower` and up to `upper`, such that agents with
+    /// the same bounds see the same entries
+    fn bounds(&self, lower: Float, upper: Float) -> (usize, usize);
+    /// weighted sum, total weight and number of all opinions within [lower, upper]
+    fn range_sum(&self, lower: Float, upper: Float) -> (Float, Float, u32);
+}
+
+impl CumulativeSums for PrefixSums {
+    fn bounds(&self, lower: Float, upper: Float) -> (usize, usize) {
+        (
+            self.opinions.partition_point(|&x| x < lower),
+            self.opinions.partition_point(|&x| x <= upper),
+        )
+    }
+
+    fn range_sum(&self, lower: Float, upper: Float) -> (Float, Float, u32) {
+        PrefixSums::range_sum(self, lower, upper)
+    }
+}
+
+/// index of no node of a `SumTree`
+const NIL: u32 = u32::MAX;
+
+/// a distinct opinion in a `SumTree` together with the sums over its subtree
+#[derive(Clone, Debug)]
+struct SumNode {
+    key: Key,
+    entry: OpinionEntry,
+    left: u32,
+    right: u32,
+    /// number of distinct opinions in the subtree
+    size: u32,
+    /// number of agents in the subtree
+    count: u32,
+    /// total weight of the subtree
+    weight: f64,
+    /// total weighted opinion of the subtree
+    moment: f64,
+}
+
+/// cumulative sums over the distinct opinions of the tree, which are updated with every
+/// insertion and removal, such that sweeps, which change the tree after every agent, find
+/// the sums over a confidence interval in O(log n) as well
+/// it is a treap, whose nodes hold the sums over their subtrees, the priorities are hashes
+/// of the keys, such that its shape and therefore the rounding of the sums only depend on
+/// the opinions it contains, but not on the order of their insertion
+#[derive(Clone, Debug)]
+struct SumTree {
+    nodes: Vec<SumNode>,
+    /// indices of removed nodes, which are reused
+    free: Vec<u32>,
+    root: u32,
+}
+
+impl Default for SumTree {
+    fn default() -> SumTree {
+        SumTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+        }
+    }
+}
+
+impl SumTree {
+    fn from_tree(tree: &BTreeMap<Key, OpinionEntry>) -> SumTree {
+        let mut sums = SumTree::default();
+        for (&key, &entry) in tree.iter() {
+            sums.root = sums.add(sums.root, key, entry.count, entry.weight);
+        }
+        sums
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = NIL;
+    }
+
+    /// like `insert_entry`
+    fn insert(&mut self, opinion: Float, weight: Float) {
+        self.root = self.add(self.root, to_key(opinion), 1, weight as f64);
+    }
+
+    /// like `remove_entry`, an opinion, which is not in the tree, is ignored
+    fn remove(&mut self, opinion: Float, weight: Float) {
+        self.root = self.subtract(self.root, to_key(opinion), weight as f64);
+    }
+
+    /// priority of a node with `key`, nodes with higher priorities are closer to the root
+    fn priority(key: Key) -> (u64, Key) {
+        // the finalizer of splitmix64, ties are broken by the key
+        let mut z = (key as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (z ^ (z >> 31), key)
+    }
+
+    /// sizes, counts, weights and moments of the subtree of `node`
+    fn sums(&self, node: u32) -> (u32, u32, f64, f64) {
+        if node == NIL {
+            return (0, 0, 0., 0.);
+        }
+        let n = &self.nodes[node as usize];
+        (n.size, n.count, n.weight, n.moment)
+    }
+
+    /// recalculate the sums of `node` from its children
+    fn update(&mut self, node: u32) {
+        let n = &self.nodes[node as usize];
+        let (left, right, entry, x) = (n.left, n.right, n.entry, from_key(n.key) as f64);
+        let (ls, lc, lw, lm) = self.sums(left);
+        let (rs, rc, rw, rm) = self.sums(right);
+        let n = &mut self.nodes[node as usize];
+        n.size = ls + 1 + rs;
+        n.count = lc + entry.count + rc;
+        n.weight = lw + entry.weight + rw;
+        n.moment = lm + entry.weight * x + rm;
+    }
+
+    fn allocate(&mut self, key: Key, count: u32, weight: f64) -> u32 {
+        let node = SumNode {
+            key,
+            entry: OpinionEntry::default(),
+            left: NIL,
+            right: NIL,
+            size: 0,
+            count: 0,
+            weight: 0.,
+            moment: 0.,
+        };
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx as usize] = node;
+                idx
+            }
+            None => {
+                self.nodes.push(node);
+                (self.nodes.len() - 1) as u32
+            }
+        };
+        // accumulated like the entries of the tree, such that the weights agree bitwise
+        let entry = &mut self.nodes[idx as usize].entry;
+        entry.count += count;
+        entry.weight += weight;
+        self.update(idx);
+        idx
+    }
+
+    /// add `count` agents of total weight `weight` with `key` to the subtree of `node`
+    /// and return its new root
+    fn add(&mut self, node: u32, key: Key, count: u32, weight: f64) -> u32 {
+        if node == NIL {
+            return self.allocate(key, count, weight);
+        }
+        let n = &self.nodes[node as usize];
+        let (left, right) = (n.left, n.right);
+        match key.cmp(&n.key) {
+            std::cmp::Ordering::Less => {
+                let left = self.add(left, key, count, weight);
+                self.nodes[node as usize].left = left;
+                if self.above(left, node) {
+                    return self.rotate_right(node);
+                }
+            }
+            std::cmp::Ordering::Greater => {
+                let right = self.add(right, key, count, weight);
+                self.nodes[node as usize].right = right;
+                if self.above(right, node) {
+                    return self.rotate_left(node);
+                }
+            }
+            std::cmp::Ordering::Equal => {
+                let entry = &mut self.nodes[node as usize].entry;
+                entry.count += count;
+                entry.weight += weight;
+            }
+        }
+        self.update(node);
+        node
+    }
+
+    /// remove one agent of weight `weight` with `key` from the subtree of `node` and
+    /// return its new root
+    fn subtract(&mut self, node: u32, key: Key, weight: f64) -> u32 {
+        if node == NIL {
+            return NIL;
+        }
+        let n = &self.nodes[node as usize];
+        let (left, right) = (n.left, n.right);
+        match key.cmp(&n.key) {
+            std::cmp::Ordering::Less => {
+                let left = self.subtract(left, key, weight);
+                self.nodes[node as usize].left = left;
+            }
+            std::cmp::Ordering::Greater => {
+                let right = self.subtract(right, key, weight);
+                self.nodes[node as usize].right = right;
+            }
+            std::cmp::Ordering::Equal => {
+                let entry = &mut self.nodes[node as usize].entry;
+                entry.count -= 1;
+                entry.weight -= weight;
+                if entry.count == 0 {
+                    self.free.push(node);
+                    return self.merge(left, right);
+                }
+            }
+        }
+        self.update(node);
+        node
+    }
+
+    /// whether `a` belongs above `b`
+    fn above(&self, a: u32, b: u32) -> bool {
+        SumTree::priority(self.nodes[a as usize].key)
+            > SumTree::priority(self.nodes[b as usize].key)
+    }
+
+    fn rotate_right(&mut self, node: u32) -> u32 {
+        let left = self.nodes[node as usize].left;
+        self.nodes[node as usize].left = self.nodes[left as usize].right;
+        self.nodes[left as usize].right = node;
+        self.update(node);
+        self.update(left);
+        left
+    }
+
+    fn rotate_left(&mut self, node: u32) -> u32 {
+        let right = self.nodes[node as usize].right;
+        self.nodes[node as usize].right = self.nodes[right as usize].left;
+        self.nodes[right as usize].left = node;
+        self.update(node);
+        self.update(right);
+        right
+    }
+
+    /// join the subtrees `a` and `b`, whose keys are all below the keys of `b`
+    fn merge(&mut self, a: u32, b: u32) -> u32 {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.above(a, b) {
+            let right = self.merge(self.nodes[a as usize].right, b);
+            self.nodes[a as usize].right = right;
+            self.update(a);
+            a
+        } else {
+            let left = self.merge(a, self.nodes[b as usize].left);
+            self.nodes[b as usize].left = left;
+            self.update(b);
+            b
+        }
+    }
+
+    /// sizes, counts, weights and moments of all opinions `x` with `below(x)`, which
+    /// needs to hold for a prefix of the opinions
+    fn prefix<F: Fn(Float) -> bool>(&self, below: F) -> (usize, u32, f64, f64) {
+        let (mut size, mut count, mut weight, mut moment) = (0, 0, 0., 0.);
+        let mut node = self.root;
+        while node != NIL {
+            let n = &self.nodes[node as usize];
+            let x = from_key(n.key);
+            if below(x) {
+                let (s, c, w, m) = self.sums(n.left);
+                size += s as usize + 1;
+                count += c + n.entry.count;
+                weight += w + n.entry.weight;
+                moment += m + n.entry.weight * x as f64;
+                node = n.right;
+            } else {
+                node = n.left;
+            }
+        }
+        (size, count, weight, moment)
+    }
+
+    /// the `k`-th distinct opinion and its entry
+    fn nth(&self, mut k: usize) -> (Float, OpinionEntry) {
+        let mut node = self.root;
+        loop {
+            let n = &self.nodes[node as usize];
+            let (s, _, _, _) = self.sums(n.left);
+            let s = s as usize;
+            if k < s {
+                node = n.left;
+            } else if k == s {
+                return (from_key(n.key), n.entry);
+            } else {
+                k -= s + 1;
+                node = n.right;
+            }
+        }
+    }
+}
+
+impl CumulativeSums for SumTree {
+    fn bounds(&self, lower: Float, upper: Float) -> (usize, usize) {
+        (self.prefix(|x| x < lower).0, self.prefix(|x| x <= upper).0)
+    }
+
+    /// like `PrefixSums::range_sum`
+    fn range_sum(&self, lower: Float, upper: Float) -> (Float, Float, u32) {
+        let (a, count_a, weight_a, moment_a) = self.prefix(|x| x < lower);
+        let (b, count_b, weight_b, moment_b) = self.prefix(|x| x <= upper);
+        if b <= a {
+            return (0., 0., 0);
+        }
+        if b == a + 1 {
+            let (x, entry) = self.nth(a);
+            let weight = entry.weight as Float;
+            return (weight * x, weight, entry.count);
+        }
+        (
+            (moment_b - moment_a) as Float,
+            (weight_b - weight_a) as Float,
+            count_b - count_a,
+        )
+    }
+}
+
+/// a fixed true value, which attracts the truth seekers among the agents
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Truth {
+    /// the true opinion
+    value: Float,
+    /// fraction of the way from the mean of its neighbors to the truth a seeker moves
+    strength: Float,
+    /// whether the agent with this index is a truth seeker
+    seekers: Vec<bool>,
+}
+
+/// repulsion by the agents just beyond the confidence of an agent
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Repulsion {
+    /// distance up to which agents beyond the confidence repel
+    range: Float,
+    /// fraction of the distance to the mean of the repelling agents an agent is pushed away
+    strength: Float,
+}
+
+/// agents which move away from the mean opinion of their neighbors
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Contrarians {
+    /// fraction of the distance to the mean a contrarian moves away
+    mu: Float,
+    /// whether the agent with this index is a contrarian
+    agents: Vec<bool>,
+}
+
+/// labelled groups of agents, whose confidence towards each other is given by a matrix
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Groups {
+    /// group of the agent with this index
+    assignments: Vec<u8>,
+    /// `matrix[a][b]` is the confidence of members of group `a` towards members of group `b`
+    matrix: Vec<Vec<Float>>,
+    /// one tree of opinions per group
+    #[cfg_attr(feature = "serde", serde(skip))]
+    trees: Vec<BTreeMap<Key, OpinionEntry>>,
+}
+
+/// structure representing an agent
+/// the derived comparison is exact, see `approx_eq` for a comparison with a tolerance
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct HKAgent {
+    /// current opinion of the agent
+    pub(crate) opinion: Float,
+    /// idiosyncratic confidence of the agent towards lower opinions
+    pub(crate) confidence_left: Float,
+    /// idiosyncratic confidence of the agent towards higher opinions
+    pub(crate) confidence_right: Float,
+    /// zealots never change their opinion, but still influence others
+    pub(crate) zealot: bool,
+    /// label of the subpopulation the agent belongs to
+    pub(crate) group: u8,
+    /// influence of the agent in the averages of its neighbors
+    pub(crate) weight: Float,
+    /// extremists start close to the boundaries of the opinion space
+    pub(crate) extremist: bool,
+    /// probability with which the agent updates its opinion during a sweep
+    pub(crate) activity: Float,
+    /// number of copies of the agent, as which it counts in every average, leaders have
+    /// a multiplicity larger than 1
+    pub(crate) multiplicity: u32,
+}
+
+impl HKAgent {
+    /// agent with a symmetric confidence interval
+    pub(crate) fn new(opinion: Float, confidence: Float) -> HKAgent {
+        HKAgent::asymmetric(opinion, confidence, confidence)
+    }
+
+    /// agent accepting opinions up to `confidence_left` below and up to
+    /// `confidence_right` above its own opinion
+    pub(crate) fn asymmetric(
+        opinion: Float,
+        confidence_left: Float,
+        confidence_right: Float,
+    ) -> HKAgent {
+        HKAgent {
+            opinion,
+            confidence_left,
+            confidence_right,
+            zealot: false,
+            group: 0,
+            weight: 1.,
+            extremist: false,
+            activity: 1.,
+            multiplicity: 1,
+        }
+    }
+
+    /// total weight of the agent in every average, i.e., its influence weight times
+    /// its multiplicity
+    fn mass(&self) -> Float {
+        self.weight * self.multiplicity as Float
+    }
+
+    /// bits of all properties determining the new opinion of a synchronous update,
+    /// agents with the same key arrive at the same new opinion
+    fn update_key(&self) -> [u64; 5] {
+        [
+            self.opinion.to_bits() as u64,
+            self.confidence_left.to_bits() as u64,
+            self.confidence_right.to_bits() as u64,
+            self.weight.to_bits() as u64,
+            self.multiplicity as u64,
+        ]
+    }
+
+    /// whether the opinion `x` lies within the confidence interval of the agent
+    pub(crate) fn accepts(&self, topology: OpinionSpace, x: Float) -> bool {
+        self.accepts_within(topology, x, self.confidence_left, self.confidence_right)
+    }
+
+    /// whether the opinion `x` lies at most `left` below or `right` above the opinion
+    /// of the agent
+    /// the confidence interval is closed, which is the canonical convention of all methods,
+    /// on the line, the bounds are calculated like the bounds of the range queries of the
+    /// tree and the sorted arrays, such that all methods agree even on the boundary
+    /// since the confidences are non-negative, every agent accepts its own opinion, even
+    /// with zero confidence, so no agent is left without neighbors
+    fn accepts_within(&self, topology: OpinionSpace, x: Float, left: Float, right: Float) -> bool {
+        match topology {
+            OpinionSpace::Line => self.opinion - left <= x && x <= self.opinion + right,
+            OpinionSpace::Circle => {
+                let (down, up) = self.circle_offsets(x);
+                down <= left || up <= right
+            }
+        }
+    }
+
+    /// distance to the opinion `x` going down and going up around the circle
+    fn circle_offsets(&self, x: Float) -> (Float, Float) {
+        if x <= self.opinion {
+            (self.opinion - x, x - self.opinion + 1.)
+        } else {
+            (self.opinion - x + 1., x - self.opinion)
+        }
+    }
+
+    /// half widths of the interval below and above the opinion of the agent, in which
+    /// other opinions have an influence on the agent
+    fn reach(&self, kernel: Kernel) -> (Float, Float) {
+        match kernel {
+            Kernel::Hard | Kernel::Linear => (self.confidence_left, self.confidence_right),
+            Kernel::Gaussian => (
+                GAUSSIAN_CUTOFF * self.confidence_left,
+                GAUSSIAN_CUTOFF * self.confidence_right,
+            ),
+        }
+    }
+
+    /// whether the opinion `x` has any influence on the agent
+    fn sees(&self, kernel: Kernel, topology: OpinionSpace, x: Float) -> bool {
+        let (left, right) = self.reach(kernel);
+        self.accepts_within(topology, x, left, right)
+    }
+
+    /// relative influence of an opinion `x`, which the agent sees
+    fn influence(&self, kernel: Kernel, topology: OpinionSpace, x: Float) -> Float {
+        match kernel {
+            Kernel::Hard => 1.,
+            Kernel::Gaussian => {
+                // an agent always counts its own opinion fully, even without any confidence
+                let gaussian = |d: Float, width: Float| {
+                    if d <= 0. {
+                        1.
+                    } else {
+                        (-d * d / (2. * width * width)).exp()
+                    }
+                };
+                let (down, up) = match topology {
+                    OpinionSpace::Line if x <= self.opinion => (self.opinion - x, Float::INFINITY),
+                    OpinionSpace::Line => (Float::INFINITY, x - self.opinion),
+                    OpinionSpace::Circle => self.circle_offsets(x),
+                };
+                gaussian(down, self.confidence_left).max(gaussian(up, self.confidence_right))
+            }
+            Kernel::Linear => {
+                // an agent always counts its own opinion fully, even without any confidence
+                let linear = |d: Float, width: Float| {
+                    if d <= 0. {
+                        1.
+                    } else {
+                        (1. - d / width).max(0.)
+                    }
+                };
+                let (down, up) = match topology {
+                    OpinionSpace::Line if x <= self.opinion => (self.opinion - x, Float::INFINITY),
+                    OpinionSpace::Line => (Float::INFINITY, x - self.opinion),
+                    OpinionSpace::Circle => self.circle_offsets(x),
+                };
+                linear(down, self.confidence_left).max(linear(up, self.confidence_right))
+            }
+        }
+    }
+}
+
+impl HKAgent {
+    /// whether opinion and confidences of both agents differ by at most `tol`
+    pub(crate) fn approx_eq(&self, other: &HKAgent, tol: Float) -> bool {
+        (self.opinion - other.opinion).abs() <= tol
+            && (self.confidence_left - other.confidence_left).abs() <= tol
+            && (self.confidence_right - other.confidence_right).abs() <= tol
+    }
+}
+
+/// structure representing a realization of the HK model
+/// with the `serde` feature it can be serialized, e.g., to a checkpoint, see
+/// `save_checkpoint`, the trees are not serialized but rebuilt from the agents
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(remote = "Self")
+)]
+pub struct HegselmannKrause {
+    /// number of agents in the system
+    num_agents: u32,
+    /// vector of all agents constituting the system
+    agents: Vec<HKAgent>,
+    /// distribution from which the initial opinions of the agents are drawn
+    initial_opinions: InitialOpinions,
+    /// distribution from which the confidences of the agents are drawn
+    confidence_distribution: ConfidenceDistribution,
+    /// distribution from which the influence weights of the agents are drawn
+    weight_distribution: WeightDistribution,
+    /// distribution of the activities of the agents
+    activity_distribution: ActivityDistribution,
+    /// distribution of the confidences towards higher opinions, if they are drawn
+    /// independently, otherwise the confidence intervals are symmetric
+    confidence_distribution_right: Option<ConfidenceDistribution>,
+    /// topology of the opinion space
+    topology: OpinionSpace,
+    /// interval [a, b] of the opinions on the line, all distributions of initial opinions
+    /// are defined relative to it
+    opinion_interval: (Float, Float),
+
+    /// the tree structure used to efficiently update the system
+    #[cfg_attr(feature = "serde", serde(skip))]
+    opinion_set: BTreeMap<Key, OpinionEntry>,
+    /// cumulative sums over `opinion_set`, which change together with it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    opinion_sums: SumTree,
+    /// number of tree-based sweeps since the tree was last built from scratch
+    sweeps_since_rebuild: u32,
+    /// whether `sweep` used a method without the tree since the tree was last built, such
+    /// that the tree has to be rebuilt before it is used again
+    tree_outdated: bool,
+    /// indices of the agents in ascending order of their opinions at the last sorting, in
+    /// which synchronous sweeps calculate the new opinions, such that consecutive queries
+    /// touch the same parts of the tree or the sorted arrays
+    order: Vec<u32>,
+    /// number of synchronous sweeps since `order` was last sorted
+    sweeps_since_sort: u32,
+    /// first error of the maintenance of the trees during the current sweep
+    #[cfg_attr(feature = "serde", serde(skip))]
+    tree_error: Option<HkError>,
+    /// whether synchronous sweeps visit the agents in `order`
+    sorted_iteration: bool,
+    /// buffer for the new opinions of a synchronous sweep, kept to avoid allocations
+    #[cfg_attr(feature = "serde", serde(skip))]
+    new_opinions: Vec<(Float, u32)>,
+    /// buffer for the new opinions of the sweeps over the nearest neighbors, which do not
+    /// count the agents within the confidence intervals
+    #[cfg_attr(feature = "serde", serde(skip))]
+    nearest_opinions: Vec<Float>,
+    /// storage of the cumulative sums of `sweep_sorted`, kept to avoid allocations
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sorted_sums: PrefixSums,
+    /// changes since the last synchronous tree sweep, if unchanged agents are skipped
+    change_tracking: Option<ChangeTracking>,
+    /// whether agents with identical new opinions are grouped in synchronous sweeps
+    coarsening: bool,
+    /// total change of agents opinion during the last sweep
+    accumulated_change: Float,
+    /// largest change of a single agents opinion during the last sweep
+    max_change: Float,
+
+    /// amplitude of the uniform noise added to every new opinion, 0 disables noise
+    noise: Float,
+    /// indices of the agents which never change their opinion
+    zealots: Vec<usize>,
+    /// adjacency lists of the social network restricting the interactions, if any
+    network: Option<Vec<Vec<u32>>>,
+    /// external media field, which every agent includes, which sees its opinion
+    media: Option<Media>,
+    /// time dependence of the media's opinion, if it is not static
+    media_schedule: Option<MediaSchedule>,
+    /// broadcast exposure to media, regardless of the confidences
+    media_exposure: Option<MediaExposure>,
+    /// shape of the influence of the neighbors
+    kernel: Kernel,
+    /// data structure used by `sweep` for the range queries
+    backend: Backend,
+    /// fraction of the way towards the mean of its neighbors an agent moves per update
+    mu: Float,
+    /// true value attracting a part of the agents, if any
+    truth: Option<Truth>,
+    /// susceptibilities of all agents to their initial opinions, if anchored
+    anchoring: Option<Vec<Float>>,
+    /// opinions of all agents at the start of the simulation, to which they are anchored
+    anchors: Vec<Float>,
+    /// agents as drawn by the latest `reset()` or given to `set_state`, to replay them
+    initial_agents: Vec<HKAgent>,
+    /// agents moving away from the mean of their neighbors, if any
+    contrarians: Option<Contrarians>,
+    /// repulsion between agents beyond the confidence, if any
+    repulsion: Option<Repulsion>,
+    /// indices of the leaders and the multiplicity with which they count
+    leaders: Vec<usize>,
+    leader_weight: u32,
+    /// groups with a matrix of inter-group confidences, if any
+    groups: Option<Groups>,
+    /// expected number of agents replaced by newcomers after every sweep
+    turnover: Float,
+    /// probability with which an agent jumps to a random opinion instead of updating
+    jump_probability: Float,
+    /// amplitude of the uniform noise on the perceived opinions of others
+    perception_noise: Float,
+    /// treatment of opinions leaving the opinion space
+    boundary: Boundary,
+    /// global trend of all opinions
+    drift: Float,
+    /// whether `accumulated_change` ignores the shift due to the drift
+    drift_corrected_change: bool,
+    /// whether the sums over the neighbors and `accumulated_change` compensate their
+    /// rounding errors
+    compensated_summation: bool,
+    /// rounding error of `accumulated_change`, if it is compensated
+    change_compensation: Float,
+    /// number of past opinions averaged to the effective opinion, 1 disables the memory
+    memory: usize,
+    /// the last opinions of every agent, whose mean is its effective opinion, empty without
+    /// memory, such that the agents themselves stay small
+    histories: Vec<VecDeque<Float>>,
+    /// average over the `k` nearest opinions instead of a confidence interval, if set
+    nearest_neighbors: Option<usize>,
+    /// statistic of the opinions of the neighbors, which is adopted
+    aggregator: Aggregator,
+    /// maximum number of agents averaged over (including the own opinion) and their choice
+    neighbor_cap: Option<(usize, CapStrategy)>,
+    /// probability with which a potential neighbor is considered in an update
+    interaction_probability: Float,
+    /// fraction of the agents updating their opinion in every sweep
+    update_fraction: Float,
+    /// number of groups, assigned round-robin, which take turns to update their opinions
+    rotation_groups: u32,
+    /// number of sweeps performed with rotation groups since the last reset
+    rotation_step: u64,
+    /// number of values of the opinion grid, 0 for continuous opinions
+    quantization: u32,
+    /// weight of the own opinion of an agent in its average, 1 is the classic model
+    self_weight: Float,
+    /// time dependence of the confidences
+    confidence_schedule: ConfidenceSchedule,
+    /// dependence of the confidences on the current opinions
+    confidence_function: ConfidenceFunction,
+    /// adaptation of the confidences to the local agreement after every sweep, if enabled
+    adaptive_confidence: Option<AdaptiveConfidence>,
+    /// growth of the confidences of isolated agents, if enabled
+    isolation_growth: Option<IsolationGrowth>,
+    /// confidences (left, right) of all agents at the start of the simulation,
+    /// to which the schedule is applied
+    initial_confidences: Vec<(Float, Float)>,
+    /// agents keep their opinion if it would change by no more than this threshold
+    update_threshold: Float,
+    /// opinions closer than this tolerance belong to the same cluster
+    cluster_eps: Float,
+    /// whether the confidences are redrawn from their distributions before every sweep
+    annealed_confidences: bool,
+    /// number of sweeps performed since the last reset
+    sweeps: usize,
+
+    /// we need many, good (but not crypto) random numbers
+    /// we will use here the pcg generator
+    rng: Pcg64,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HegselmannKrause {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        HegselmannKrause::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HegselmannKrause {
+    /// the derived deserialization followed by the reconstruction of the trees
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut hk = HegselmannKrause::deserialize(deserializer)?;
+        hk.restore_trees().map_err(serde::de::Error::custom)?;
+        Ok(hk)
+    }
+}
+
+/// exact comparison of the states of all agents, e.g., for snapshots of a simulation
+/// results of different methods differ by rounding errors, compare them with `approx_eq`
+impl PartialEq for HegselmannKrause {
+    fn eq(&self, other: &HegselmannKrause) -> bool {
+        self.agents == other.agents
+    }
+}
+
+/// step by step configuration of a `HegselmannKrause` model, which is validated at once
+/// by `build`, all options not given keep the defaults of `HegselmannKrause::new`
+/// the number of agents has no default and needs to be given
+#[derive(Clone, Debug)]
+pub struct HegselmannKrauseBuilder {
+    num_agents: u32,
+    confidence_range: (Float, Float),
+    seed: u64,
+    network: Option<Network>,
+    opinion_interval: (Float, Float),
+    initial_opinions: InitialOpinions,
+    confidence_distribution: Option<ConfidenceDistribution>,
+    boundary: Option<Boundary>,
+    kernel: Kernel,
+    aggregator: Aggregator,
+    noise: Float,
+    cluster_eps: Float,
+    backend: Backend,
+}
+
+impl Default for HegselmannKrauseBuilder {
+    fn default() -> Self {
+        HegselmannKrauseBuilder {
+            num_agents: 0,
+            confidence_range: (0., 1.),
+            seed: 1,
+            network: None,
+            opinion_interval: (0., 1.),
+            initial_opinions: InitialOpinions::Uniform,
+            confidence_distribution: None,
+            boundary: None,
+            kernel: Kernel::Hard,
+            aggregator: Aggregator::Mean,
+            noise: 0.,
+            cluster_eps: EPS,
+            backend: Backend::Auto,
+        }
+    }
+}
+
+impl HegselmannKrauseBuilder {
+    /// confidences uniformly distributed in [0, 1] and seed 1, like the command line
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the number of agents `n >= 1`
+    pub fn agents(mut self, n: u32) -> Self {
+        self.num_agents = n;
+        self
+    }
+
+    /// draw the confidences uniformly from [min, max]
+    pub fn confidence_range(mut self, min: Float, max: Float) -> Self {
+        self.confidence_range = (min, max);
+        self
+    }
+
+    /// seed of the random number generator
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// let the agents interact only along the edges of a generated social network, which
+    /// is drawn after the initial state, like `try_with_generated_network`
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// let the opinions live in [min, max] instead of [0, 1], see `set_opinion_interval`
+    pub fn opinion_interval(mut self, min: Float, max: Float) -> Self {
+        self.opinion_interval = (min, max);
+        self
+    }
+
+    /// distribution of the initial opinions, see `set_initial_opinions`
+    pub fn initial_opinions(mut self, initial_opinions: InitialOpinions) -> Self {
+        self.initial_opinions = initial_opinions;
+        self
+    }
+
+    /// distribution of the confidences, which replaces the `confidence_range`
+    pub fn confidence_distribution(mut self, distribution: ConfidenceDistribution) -> Self {
+        self.confidence_distribution = Some(distribution);
+        self
+    }
+
+    /// treatment of opinions leaving the opinion interval, see `set_boundary`
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = Some(boundary);
+        self
+    }
+
+    /// shape of the influence of the neighbors, see `set_kernel`
+    pub fn kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    /// statistic of the opinions of the neighbors, see `set_aggregator`
+    pub fn aggregator(mut self, aggregator: Aggregator) -> Self {
+        self.aggregator = aggregator;
+        self
+    }
+
+    /// amplitude of the additive noise, see `set_noise`
+    pub fn noise(mut self, eta: Float) -> Self {
+        self.noise = eta;
+        self
+    }
+
+    /// tolerance of the clusters, see `set_cluster_eps`
+    pub fn cluster_eps(mut self, eps: Float) -> Self {
+        self.cluster_eps = eps;
+        self
+    }
+
+    /// data structure of the range queries, see `set_backend`
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// validate the configuration and draw the initial state of the model
+    pub fn build(&self) -> Result<HegselmannKrause, HkError> {
+        let (min_confidence, max_confidence) = self.confidence_range;
+        validate_model_parameters(self.num_agents, min_confidence, max_confidence)?;
+        // the topology follows from the boundary, like for `set_boundary`, but the model
+        // starts out in it, since there are no agents yet, which could be moved
+        let topology = match self.boundary {
+            Some(Boundary::Periodic) => OpinionSpace::Circle,
+            _ => OpinionSpace::Line,
+        };
+        let mut hk = HegselmannKrause::without_agents(
+            self.num_agents,
+            min_confidence,
+            max_confidence,
+            self.seed,
+            topology,
+        );
+        let (min_opinion, max_opinion) = self.opinion_interval;
+        hk.set_opinion_interval(min_opinion, max_opinion)?;
+        hk.set_initial_opinions(self.initial_opinions.clone())?;
+        if let Some(distribution) = &self.confidence_distribution {
+            hk.set_confidence_distribution(distribution.clone())?;
+        }
+        // the topology restricts the aggregators
+        if let Some(boundary) = self.boundary {
+            hk.set_boundary(boundary)?;
+        }
+        hk.set_aggregator(self.aggregator)?;
+        hk.set_kernel(self.kernel);
+        hk.set_noise(self.noise)?;
+        hk.set_cluster_eps(self.cluster_eps)?;
+        hk.backend = self.backend;
+        hk.reset();
+        if let Some(network) = &self.network {
+            hk.network = Some(network.edges(self.num_agents, &mut hk.rng));
+        }
+        Ok(hk)
+    }
+}
+
+impl HegselmannKrause {
+    /// panics on invalid parameters, see `try_new`
+    pub fn new(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+    ) -> HegselmannKrause {
+        HegselmannKrause::try_new(n, min_confidence, max_confidence, seed)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// construct a model of `n >= 1` agents, whose confidences are uniformly distributed
+    /// in [min_confidence, max_confidence] with finite bounds 0 <= min <= max
+    pub fn try_new(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+    ) -> Result<HegselmannKrause, HkError> {
+        HegselmannKrauseBuilder::new()
+            .agents(n)
+            .confidence_range(min_confidence, max_confidence)
+            .seed(seed)
+            .build()
+    }
+
+    /// construct a model whose opinions live in the interval [min_opinion, max_opinion]
+    /// on the line instead of [0, 1], the confidences are absolute distances of opinions
+    pub fn with_opinion_interval(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        min_opinion: Float,
+        max_opinion: Float,
+    ) -> Result<HegselmannKrause, HkError> {
+        HegselmannKrauseBuilder::new()
+            .agents(n)
+            .confidence_range(min_confidence, max_confidence)
+            .seed(seed)
+            .opinion_interval(min_opinion, max_opinion)
+            .build()
+    }
+
+    /// construct a model whose opinions live in the given opinion space
+    /// panics on invalid parameters, see `try_new`
+    pub fn with_topology(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        topology: OpinionSpace,
+    ) -> HegselmannKrause {
+        validate_model_parameters(n, min_confidence, max_confidence)
+            .unwrap_or_else(|e| panic!("{}", e));
+        let mut hk =
+            HegselmannKrause::without_agents(n, min_confidence, max_confidence, seed, topology);
+        hk.reset();
+        hk
+    }
+
+    /// the configuration of a model, whose agents are not yet drawn by `reset()`
+    fn without_agents(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        topology: OpinionSpace,
+    ) -> HegselmannKrause {
+        let rng = Pcg64::seed_from_u64(seed);
+        let agents: Vec<HKAgent> = Vec::new();
+
+        let opinion_set = BTreeMap::new();
+
+        HegselmannKrause {
+            num_agents: n,
+            agents,
+            initial_opinions: InitialOpinions::Uniform,
+            confidence_distribution: ConfidenceDistribution::Uniform {
+                low: min_confidence,
+                high: max_confidence,
+            },
+            confidence_distribution_right: None,
+            weight_distribution: WeightDistribution::Unit,
+            activity_distribution: ActivityDistribution::Always,
+            topology,
+            opinion_interval: (0., 1.),
+            opinion_set,
+            opinion_sums: SumTree::default(),
+            sweeps_since_rebuild: 0,
+            order: Vec::new(),
+            sweeps_since_sort: 0,
+            tree_error: None,
+            sorted_iteration: n >= SORTED_ITERATION_MIN_AGENTS,
+            tree_outdated: false,
+            new_opinions: Vec::new(),
+            nearest_opinions: Vec::new(),
+            sorted_sums: PrefixSums::default(),
+            change_tracking: None,
+            accumulated_change: 0.,
+            max_change: 0.,
+            noise: 0.,
+            zealots: Vec::new(),
+            network: None,
+            media: None,
+            media_schedule: None,
+            media_exposure: None,
+            kernel: Kernel::Hard,
+            backend: Backend::Auto,
+            mu: 1.,
+            truth: None,
+            anchoring: None,
+            anchors: Vec::new(),
+            initial_agents: Vec::new(),
+            contrarians: None,
+            repulsion: None,
+            groups: None,
+            leaders: Vec::new(),
+            leader_weight: 1,
+            turnover: 0.,
+            jump_probability: 0.,
+            perception_noise: 0.,
+            boundary: match topology {
+                OpinionSpace::Line => Boundary::Clamp,
+                OpinionSpace::Circle => Boundary::Periodic,
+            },
+            drift: 0.,
+            drift_corrected_change: false,
+            compensated_summation: false,
+            change_compensation: 0.,
+            coarsening: false,
+            memory: 1,
+            histories: Vec::new(),
+            nearest_neighbors: None,
+            aggregator: Aggregator::Mean,
+            neighbor_cap: None,
+            interaction_probability: 1.,
+            update_fraction: 1.,
+            rotation_groups: 1,
+            rotation_step: 0,
+            quantization: 0,
+            self_weight: 1.,
+            confidence_schedule: ConfidenceSchedule::Constant,
+            confidence_function: ConfidenceFunction::Constant,
+            adaptive_confidence: None,
+            isolation_growth: None,
+            initial_confidences: Vec::new(),
+            update_threshold: 0.,
+            cluster_eps: EPS,
+            annealed_confidences: false,
+            sweeps: 0,
+            rng,
+        }
+    }
+
+    /// construct a model whose agents only interact with their neighbors in a social network
+    /// `edges[i]` contains the indices of all agents agent `i` can see
+    pub fn with_network(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        mut edges: Vec<Vec<u32>>,
+    ) -> HegselmannKrause {
+        assert_eq!(
+            edges.len(),
+            n as usize,
+            "every agent needs an adjacency list"
+        );
+        for (i, neighbors) in edges.iter_mut().enumerate() {
+            assert!(
+                neighbors.iter().all(|&j| j < n),
+                "neighbor index out of range"
+            );
+            // sorted lists ensure the same order of summation as the naive method
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            neighbors.retain(|&j| j as usize != i);
+        }
+
+        let mut hk = HegselmannKrause::new(n, min_confidence, max_confidence, seed);
+        hk.network = Some(edges);
+        hk
+    }
+
+    /// construct a model on a generated social network
+    /// random networks are drawn from the random number generator of the model, after
+    /// the initial state of the agents
+    /// panics on invalid parameters, see `try_with_generated_network`
+    pub fn with_generated_network(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        network: &Network,
+    ) -> HegselmannKrause {
+        HegselmannKrause::try_with_generated_network(
+            n,
+            min_confidence,
+            max_confidence,
+            seed,
+            network,
+        )
+        .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// like `with_generated_network`, but invalid parameters are reported as in `try_new`
+    pub fn try_with_generated_network(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        network: &Network,
+    ) -> Result<HegselmannKrause, HkError> {
+        HegselmannKrauseBuilder::new()
+            .agents(n)
+            .confidence_range(min_confidence, max_confidence)
+            .seed(seed)
+            .network(network.clone())
+            .build()
+    }
+
+    /// set the distribution from which the initial opinions of the agents are drawn
+    /// the new distribution takes effect at the next `reset()`
+    pub fn set_initial_opinions(
+        &mut self,
+        initial_opinions: InitialOpinions,
+    ) -> Result<(), HkError> {
+        initial_opinions.validate(self.num_agents as usize)?;
+        self.initial_opinions = initial_opinions;
+        Ok(())
+    }
+
+    /// set the distribution from which the confidences of the agents are drawn
+    /// the new distribution takes effect at the next `reset()`
+    pub fn set_confidence_distribution(
+        &mut self,
+        distribution: ConfidenceDistribution,
+    ) -> Result<(), HkError> {
+        distribution.validate()?;
+        self.confidence_distribution = distribution;
+        self.confidence_distribution_right = None;
+        Ok(())
+    }
+
+    /// draw the confidences towards lower and higher opinions independently from
+    /// the given distributions, the group of an agent is determined by `left`
+    /// the new distributions take effect at the next `reset()`
+    pub fn set_asymmetric_confidence_distributions(
+        &mut self,
+        left: ConfidenceDistribution,
+        right: ConfidenceDistribution,
+    ) -> Result<(), HkError> {
+        left.validate()?;
+        right.validate()?;
+        self.confidence_distribution = left;
+        self.confidence_distribution_right = Some(right);
+        Ok(())
+    }
+
+    /// draw the confidences towards lower and higher opinions uniformly from
+    /// [min_left, max_left] and [min_right, max_right]
+    /// the new ranges take effect at the next `reset()`
+    pub fn set_asymmetric_confidences(
+        &mut self,
+        min_left: Float,
+        max_left: Float,
+        min_right: Float,
+        max_right: Float,
+    ) -> Result<(), HkError> {
+        self.set_asymmetric_confidence_distributions(
+            ConfidenceDistribution::Uniform {
+                low: min_left,
+                high: max_left,
+            },
+            ConfidenceDistribution::Uniform {
+                low: min_right,
+                high: max_right,
+            },
+        )
+    }
+
+    /// set the distribution from which the influence weights of the agents are drawn
+    /// the new distribution takes effect at the next `reset()`
+    pub fn set_weight_distribution(
+        &mut self,
+        distribution: WeightDistribution,
+    ) -> Result<(), HkError> {
+        distribution.validate()?;
+        self.weight_distribution = distribution;
+        Ok(())
+    }
+
+    /// set the distribution from which the activities of the agents are drawn
+    /// inactive agents keep their opinion during a sweep, but still influence others
+    /// the new distribution takes effect at the next `reset()`
+    pub fn set_activity_distribution(
+        &mut self,
+        distribution: ActivityDistribution,
+    ) -> Result<(), HkError> {
+        distribution.validate()?;
+        self.activity_distribution = distribution;
+        Ok(())
+    }
+
+    /// set the shape of the influence of the neighbors, by default a hard cutoff
+    pub fn set_kernel(&mut self, kernel: Kernel) {
+        self.kernel = kernel;
+    }
+
+    /// let synchronous tree sweeps on the line reuse the new opinions of the last one for
+    /// all agents, whose own state and whose neighborhood did not change since
+    /// this assumes that no parameters are changed between the sweeps, after changing
+    /// parameters, enable it again to start from scratch
+    pub fn set_skip_unchanged(&mut self, skip: bool) {
+        self.change_tracking = if skip {
+            Some(ChangeTracking::default())
+        } else {
+            None
+        };
+    }
+
+    /// let synchronous sweeps on the line with the hard kernel calculate the new opinion only
+    /// once for every group of agents sharing their opinion and weight, whose confidence
+    /// intervals contain the same opinions, e.g., the members of a converged cluster,
+    /// which does not change the results
+    pub fn set_coarsening(&mut self, coarsening: bool) {
+        self.coarsening = coarsening;
+    }
+
+    /// let synchronous sweeps calculate the new opinions in ascending order of the opinions
+    /// instead of the order of the agents, which is faster for large systems and does not
+    /// change the results, enabled by default from a million agents on
+    pub fn set_sorted_iteration(&mut self, sorted: bool) {
+        self.sorted_iteration = sorted;
+    }
+
+    /// whether the new opinions are calculated per group, see `set_coarsening`
+    /// repulsion depends on the confidences beyond the neighbors, so it is excluded
+    fn coarsens(&self) -> bool {
+        self.coarsening
+            && self.topology == OpinionSpace::Line
+            && self.kernel == Kernel::Hard
+            && self.repulsion.is_none()
+    }
+
+    /// calculate all new opinions in the next synchronous tree sweep
+    fn invalidate_tracking(&mut self) {
+        if let Some(tracking) = &mut self.change_tracking {
+            tracking.valid = false;
+            tracking.changed.clear();
+        }
+    }
+
+    /// note the change of an opinion in the tree for the next synchronous tree sweep
+    /// once more opinions changed than there are agents, all are recalculated anyway
+    fn record_change(&mut self, old_opinion: Float, new_opinion: Float) {
+        if let Some(tracking) = &mut self.change_tracking {
+            if tracking.valid && tracking.changed.len() < 2 * self.agents.len() {
+                tracking.changed.push(old_opinion);
+                tracking.changed.push(new_opinion);
+            } else {
+                tracking.valid = false;
+                tracking.changed.clear();
+            }
+        }
+    }
+
+    /// choose the data structure `sweep` uses for the range queries, by default the sorted
+    /// arrays wherever they suffice and the tree otherwise
+    /// the sorted backend does not maintain the tree, so it is rebuilt on every change
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+        self.rebuild_tree();
+    }
+
+    /// let agents move only a fraction `mu` in (0, 1] of the way towards the mean
+    /// of their neighbors, i.e., `x_i(t+1) = (1 - mu) x_i(t) + mu mean`
+    /// since the change per sweep shrinks with `mu`, convergence criteria based on
+    /// `accumulated_change` should be scaled by `mu`
+    pub fn set_mu(&mut self, mu: Float) -> Result<(), HkError> {
+        if !(mu > 0. && mu <= 1.) {
+            return Err(HkError::InvalidParameter(format!(
+                "mu {} needs to be in (0, 1]",
+                mu
+            )));
+        }
+        self.mu = mu;
+        Ok(())
+    }
+
+    /// let a randomly chosen `fraction` of the agents seek the truth `value`:
+    /// their new opinion is `(1 - strength) mean + strength value`
+    /// the seekers are drawn from the random number generator of the model, unless
+    /// the fraction is 0, and they keep their role after `reset()`
+    pub fn set_truth(
+        &mut self,
+        value: Float,
+        strength: Float,
+        fraction: Float,
+    ) -> Result<(), HkError> {
+        if !self.in_opinion_interval(value) {
+            return Err(HkError::InvalidParameter(format!(
+                "truth {} needs to be in {}",
+                value,
+                self.opinion_interval_string()
+            )));
+        }
+        for (name, x) in &[("strength", strength), ("fraction", fraction)] {
+            if !(0. ..=1.).contains(x) {
+                return Err(HkError::InvalidParameter(format!(
+                    "{} {} needs to be in [0, 1]",
+                    name, x
+                )));
+            }
+        }
+
+        let seekers = self.choose_agents(fraction);
+        self.truth = Some(Truth {
+            value,
+            strength,
+            seekers,
+        });
+        Ok(())
+    }
+
+    /// let agents at a distance in `[confidence, eps_rep]` repel each other: the mean
+    /// of the neighbors is shifted by `lambda (x_i - r)` away from the mean `r` of the
+    /// repelling agents, the boundary handling is applied afterwards
+    /// `eps_rep` needs to exceed the confidences of all agents, only the line is supported
+    pub fn set_repulsion(&mut self, eps_rep: Float, lambda: Float) -> Result<(), HkError> {
+        if self.topology != OpinionSpace::Line {
+            return Err(HkError::InvalidParameter(
+                "repulsion is only supported on the line".to_string(),
+            ));
+        }
+        if !(lambda >= 0. && lambda.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "repulsion strength {} needs to be finite and non-negative",
+                lambda
+            )));
+        }
+        let max_reach = self
+            .agents
+            .iter()
+            .map(|i| {
+                let (left, right) = i.reach(self.kernel);
+                left.max(right)
+            })
+            .fold(0., Float::max);
+        if !(eps_rep > max_reach && eps_rep.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "repulsion range {} needs to be finite and larger than the confidences (up to {})",
+                eps_rep, max_reach
+            )));
+        }
+
+        self.repulsion = Some(Repulsion {
+            range: eps_rep,
+            strength: lambda,
+        });
+        Ok(())
+    }
+
+    /// let agents beyond the confidence not interact again
+    pub fn remove_repulsion(&mut self) {
+        self.repulsion = None;
+    }
+
+    /// shift the new opinion `x` of agent `i` away from the repelling agents, given their
+    /// weighted sum and total weight
+    fn repel(&self, i: &HKAgent, x: Float, sum: Float, weight: Float) -> Float {
+        match self.repulsion {
+            Some(r) if weight > 0. => x + r.strength * (i.opinion - sum / weight),
+            _ => x,
+        }
+    }
+
+    /// anchor every agent to its initial opinion (Friedkin-Johnsen): its new opinion is
+    /// `(1 - g_i) mean + g_i x_i(0)` with a susceptibility `g_i` drawn uniformly from
+    /// [min_g, max_g], which consumes no random numbers if `min_g = max_g`
+    /// the susceptibilities are bound to the agents and kept after `reset()`
+    pub fn set_anchoring(&mut self, min_g: Float, max_g: Float) -> Result<(), HkError> {
+        if !(0. <= min_g && min_g <= max_g && max_g <= 1.) {
+            return Err(HkError::InvalidParameter(format!(
+                "susceptibilities [{}, {}] need to be an interval within [0, 1]",
+                min_g, max_g
+            )));
+        }
+
+        let susceptibilities = (0..self.num_agents)
+            .map(|_| {
+                if min_g < max_g {
+                    self.rng.gen_range(min_g, max_g)
+                } else {
+                    min_g
+                }
+            })
+            .collect();
+        self.anchoring = Some(susceptibilities);
+        Ok(())
+    }
+
+    /// let the agents forget their initial opinions
+    pub fn remove_anchoring(&mut self) {
+        self.anchoring = None;
+    }
+
+    /// randomly choose the given fraction of the agents, without consuming random
+    /// numbers if nobody is chosen, the result marks the chosen agents
+    fn choose_agents(&mut self, fraction: Float) -> Vec<bool> {
+        let n = self.num_agents as usize;
+        let mut chosen = vec![false; n];
+        let num_chosen = (fraction * n as Float).round() as usize;
+        if num_chosen > 0 {
+            for i in rand::seq::index::sample(&mut self.rng, n, num_chosen).into_iter() {
+                chosen[i] = true;
+            }
+        }
+        chosen
+    }
+
+    /// let a randomly chosen `fraction` of the agents be contrarians, which move away
+    /// from the mean of their neighbors: `x_i(t+1) = x_i(t) - mu (mean - x_i(t))`
+    /// the contrarians are drawn from the random number generator of the model, unless
+    /// the fraction is 0, and they keep their role after `reset()`
+    pub fn set_contrarians(&mut self, fraction: Float, mu: Float) -> Result<(), HkError> {
+        if !(0. ..=1.).contains(&fraction) {
+            return Err(HkError::InvalidParameter(format!(
+                "fraction {} needs to be in [0, 1]",
+                fraction
+            )));
+        }
+        if !(mu.is_finite() && mu >= 0.) {
+            return Err(HkError::InvalidParameter(format!(
+                "mu {} of the contrarians needs to be non-negative",
+                mu
+            )));
+        }
+
+        let agents = self.choose_agents(fraction);
+        self.contrarians = Some(Contrarians { mu, agents });
+        Ok(())
+    }
+
+    /// indices of all contrarian agents
+    pub fn contrarians(&self) -> Vec<usize> {
+        match &self.contrarians {
+            Some(contrarians) => contrarians
+                .agents
+                .iter()
+                .enumerate()
+                .filter(|(_, &c)| c)
+                .map(|(i, _)| i)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// remove the truth, all agents are ordinary agents again
+    pub fn remove_truth(&mut self) {
+        self.truth = None;
+    }
+
+    /// consider every potential neighbor only with probability `p` in (0, 1]
+    /// for `p < 1`, `sweep()` uses the naive method, since the tree can not skip
+    /// entries within a range query cheaply
+    pub fn set_interaction_probability(&mut self, p: Float) -> Result<(), HkError> {
+        if !(p > 0. && p <= 1.) {
+            return Err(HkError::InvalidParameter(format!(
+                "interaction probability {} needs to be in (0, 1]",
+                p
+            )));
+        }
+        self.interaction_probability = p;
+        // naive sweeps do not maintain the tree, so start from a consistent one
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// only let a random fraction `f` of the agents update their opinion in every sweep
+    pub fn set_update_fraction(&mut self, f: Float) -> Result<(), HkError> {
+        if !(f > 0. && f <= 1.) {
+            return Err(HkError::InvalidParameter(format!(
+                "update fraction {} needs to be in (0, 1]",
+                f
+            )));
+        }
+        self.update_fraction = f;
+        Ok(())
+    }
+
+    /// partition the agents round-robin into `k` groups, agent `i` belongs to group `i % k`,
+    /// which take turns: during the `t`-th sweep since `reset()` only group `t % k` updates,
+    /// while all agents act as neighbors, `k = 1` lets every agent update in every sweep
+    /// this is honored by `sweep_naive` and `sweep_tree`
+    pub fn set_rotation_groups(&mut self, k: u32) -> Result<(), HkError> {
+        if k == 0 {
+            return Err(HkError::InvalidParameter(
+                "at least one rotation group is needed".to_string(),
+            ));
+        }
+        self.rotation_groups = k;
+        Ok(())
+    }
+
+    /// which agents update during the current sweep according to the rotation groups
+    /// and advance the rotation
+    fn next_rotation(&mut self) -> Vec<bool> {
+        let k = self.rotation_groups as usize;
+        let active = (self.rotation_step % k as u64) as usize;
+        self.rotation_step += 1;
+        (0..self.agents.len()).map(|i| i % k == active).collect()
+    }
+
+    /// restrict the opinions to a grid of `levels` equally spaced values in the opinion
+    /// interval, every
+    /// new opinion is rounded to the nearest grid value, `levels = 0` means continuous
+    /// the current opinions are rounded immediately and all future ones after `reset()`
+    pub fn set_quantization(&mut self, levels: u32) -> Result<(), HkError> {
+        if levels == 1 {
+            return Err(HkError::InvalidParameter(
+                "the opinion grid needs at least 2 levels (or 0 for continuous opinions)"
+                    .to_string(),
+            ));
+        }
+        self.quantization = levels;
+        self.quantize_opinions();
+        self.clear_memory();
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// round the opinions of all agents to the opinion grid, if any
+    fn quantize_opinions(&mut self) {
+        for idx in 0..self.agents.len() {
+            self.agents[idx].opinion = self.quantize(self.agents[idx].opinion);
+        }
+    }
+
+    /// shift the opinions of all agents by `v` every sweep, after the averaging step,
+    /// and treat opinions leaving the opinion space according to `boundary`
+    pub fn set_drift(&mut self, v: Float, boundary: Boundary) -> Result<(), HkError> {
+        if !v.is_finite() {
+            return Err(HkError::InvalidParameter(format!(
+                "drift {} needs to be finite",
+                v
+            )));
+        }
+        self.set_boundary(boundary)?;
+        self.drift = v;
+        Ok(())
+    }
+
+    /// remove the drift, opinions only change due to their neighbors again
+    pub fn remove_drift(&mut self) {
+        self.drift = 0.;
+    }
+
+    /// set the treatment of opinions leaving the opinion interval
+    /// since neighbors across a periodic boundary are close, `Periodic` switches the
+    /// topology to the circle, whose range queries are split at the boundary, and all
+    /// other boundaries switch it to the line
+    pub fn set_boundary(&mut self, boundary: Boundary) -> Result<(), HkError> {
+        if boundary == Boundary::Periodic && self.network.is_some() {
+            return Err(HkError::InvalidParameter(
+                "periodic boundaries are not supported on networks".to_string(),
+            ));
+        }
+        if boundary == Boundary::Periodic && self.opinion_interval != (0., 1.) {
+            return Err(HkError::InvalidParameter(
+                "periodic boundaries are only supported for opinions in [0, 1]".to_string(),
+            ));
+        }
+        self.boundary = boundary;
+        let topology = match boundary {
+            Boundary::Periodic => OpinionSpace::Circle,
+            _ => OpinionSpace::Line,
+        };
+        if topology != self.topology {
+            self.topology = topology;
+            for idx in 0..self.agents.len() {
+                self.agents[idx].opinion = self.apply_boundary(self.agents[idx].opinion);
+            }
+            self.quantize_opinions();
+            self.clear_memory();
+            self.rebuild_tree();
+        }
+        Ok(())
+    }
+
+    /// the treatment of opinions leaving the opinion interval
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// let the opinions live in the interval [a, b] on the line instead of [0, 1]
+    /// the distributions of the initial opinions are scaled to it, which takes effect at
+    /// the next `reset()`
+    pub fn set_opinion_interval(&mut self, a: Float, b: Float) -> Result<(), HkError> {
+        if !(a < b && a.is_finite() && b.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "opinion interval [{}, {}] needs to be finite and non-empty",
+                a, b
+            )));
+        }
+        if self.topology == OpinionSpace::Circle && (a, b) != (0., 1.) {
+            return Err(HkError::InvalidParameter(
+                "the circle only supports opinions in [0, 1)".to_string(),
+            ));
+        }
+        self.opinion_interval = (a, b);
+        Ok(())
+    }
+
+    /// the interval [a, b] of the opinions
+    pub fn opinion_interval(&self) -> (Float, Float) {
+        self.opinion_interval
+    }
+
+    /// map `u` from [0, 1] affinely onto the opinion interval
+    fn unit_to_opinion(&self, u: Float) -> Float {
+        let (a, b) = self.opinion_interval;
+        a + (b - a) * u
+    }
+
+    /// map the opinion `x` affinely from the opinion interval onto [0, 1]
+    fn opinion_to_unit(&self, x: Float) -> Float {
+        let (a, b) = self.opinion_interval;
+        (x - a) / (b - a)
+    }
+
+    /// whether `x` lies within the opinion interval
+    fn in_opinion_interval(&self, x: Float) -> bool {
+        let (a, b) = self.opinion_interval;
+        (a..=b).contains(&x)
+    }
+
+    /// the opinion interval formatted for error messages
+    fn opinion_interval_string(&self) -> String {
+        let (a, b) = self.opinion_interval;
+        format!("[{}, {}]", a, b)
+    }
+
+    /// round `x` to the opinion grid, if any, which spans the opinion interval
+    fn quantize(&self, x: Float) -> Float {
+        if self.quantization == 0 {
+            return x;
+        }
+        self.unit_to_opinion(quantize(
+            self.opinion_to_unit(x),
+            self.quantization,
+            self.topology,
+        ))
+    }
+
+    /// let `accumulated_change` only account for the change before the drift is applied,
+    /// since the drift alone prevents any convergence
+    pub fn set_drift_corrected_change(&mut self, corrected: bool) {
+        self.drift_corrected_change = corrected;
+    }
+
+    /// use compensated (Kahan) summation for the sums over the neighbors of the naive
+    /// method and of walks through the tree, and for `accumulated_change`, which is slower,
+    /// but keeps the precision for large numbers of neighbors
+    pub fn set_compensated_summation(&mut self, compensated: bool) {
+        self.compensated_summation = compensated;
+    }
+
+    /// let agents keep their opinion unless it would change by more than `delta`,
+    /// such that the system can freeze with a finite disagreement
+    pub fn set_update_threshold(&mut self, delta: Float) -> Result<(), HkError> {
+        if !(delta >= 0. && delta.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "update threshold {} needs to be finite and non-negative",
+                delta
+            )));
+        }
+        self.update_threshold = delta;
+        Ok(())
+    }
+
+    /// let opinions closer than `eps` belong to the same cluster (default 1e-5), which
+    /// should be looser than the spread left by the convergence criterion
+    pub fn set_cluster_eps(&mut self, eps: Float) -> Result<(), HkError> {
+        validate_cluster_eps(eps)?;
+        self.cluster_eps = eps;
+        Ok(())
+    }
+
+    /// redraw the confidences of all agents from the configured distributions before every
+    /// sweep (annealed) instead of keeping the ones drawn at `reset()` (quenched)
+    pub fn set_annealed_confidences(&mut self, annealed: bool) {
+        self.annealed_confidences = annealed;
+    }
+
+    /// let every agent average over the `k` nearest opinions (including its own)
+    /// instead of all opinions within its confidence interval, `sweep()` then uses `sweep_knn`
+    pub fn set_nearest_neighbors(&mut self, k: usize) -> Result<(), HkError> {
+        if k < 1 || k > self.num_agents as usize {
+            return Err(HkError::InvalidParameter(format!(
+                "number of nearest neighbors {} needs to be in [1, {}]",
+                k, self.num_agents
+            )));
+        }
+        self.nearest_neighbors = Some(k);
+        Ok(())
+    }
+
+    /// let every agent adopt the given statistic of the opinions of its neighbors instead
+    /// of their mean, the statistics are weighted like the mean, but ignore the self
+    /// weight and the media, only the line is supported
+    pub fn set_aggregator(&mut self, aggregator: Aggregator) -> Result<(), HkError> {
+        if aggregator != Aggregator::Mean && self.topology != OpinionSpace::Line {
+            return Err(HkError::InvalidParameter(
+                "aggregators other than the mean are only supported on the line".to_string(),
+            ));
+        }
+        if let Aggregator::TrimmedMean { alpha } = aggregator {
+            if !(0. ..0.5).contains(&alpha) {
+                return Err(HkError::InvalidParameter(format!(
+                    "trimming fraction {} needs to be in [0, 0.5)",
+                    alpha
+                )));
+            }
+        }
+        self.aggregator = aggregator;
+        Ok(())
+    }
+
+    /// let every agent average over at most `c` agents (including itself) within its
+    /// confidence interval, chosen according to `strategy`, every agent counts once
+    /// `sweep()` then uses `sweep_capped` for the closest and the naive
+    /// `sweep_capped_naive` for random neighbors
+    pub fn set_neighbor_cap(&mut self, c: usize, strategy: CapStrategy) -> Result<(), HkError> {
+        if c < 1 || c > self.num_agents as usize {
+            return Err(HkError::InvalidParameter(format!(
+                "neighbor cap {} needs to be in [1, {}]",
+                c, self.num_agents
+            )));
+        }
+        self.neighbor_cap = Some((c, strategy));
+        // naive sweeps do not maintain the tree, so start from a consistent one
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// let every agent average over all agents within its confidence interval again
+    pub fn remove_neighbor_cap(&mut self) {
+        self.neighbor_cap = None;
+    }
+
+    /// let the effective opinion of every agent, which its neighbors see, be the mean of
+    /// its last `m` opinions, `m = 1` is the classic model
+    /// the memory starts with the current opinion only
+    pub fn enable_memory(&mut self, m: usize) -> Result<(), HkError> {
+        if m < 1 {
+            return Err(HkError::InvalidParameter(
+                "the memory needs to hold at least one opinion".to_string(),
+            ));
+        }
+        self.memory = m;
+        self.clear_memory();
+        Ok(())
+    }
+
+    /// forget all past opinions except the current one
+    fn clear_memory(&mut self) {
+        let m = self.memory;
+        self.histories.clear();
+        if m > 1 {
+            self.histories.extend(self.agents.iter().map(|i| {
+                let mut history = VecDeque::with_capacity(m);
+                history.push_back(i.opinion);
+                history
+            }));
+        }
+    }
+
+    /// add a media field with a fixed opinion, which every agent within its confidence
+    /// of the media's opinion includes in its average with the given weight
+    pub fn set_media(&mut self, opinion: Float, weight: Float) -> Result<(), HkError> {
+        if !self.in_opinion_interval(opinion) {
+            return Err(HkError::InvalidParameter(format!(
+                "media opinion {} needs to be in {}",
+                opinion,
+                self.opinion_interval_string()
+            )));
+        }
+        validate_media_weight(weight)?;
+        self.media = Some(Media { opinion, weight });
+        self.media_schedule = None;
+        Ok(())
+    }
+
+    /// add a media field with the given weight, whose opinion oscillates as
+    /// `center + amplitude * sin(2 pi t / period)` around the center of the opinion interval,
+    /// where `t` counts the sweeps since `reset()`
+    pub fn set_media_schedule(
+        &mut self,
+        amplitude: Float,
+        period: Float,
+        weight: Float,
+    ) -> Result<(), HkError> {
+        let (a, b) = self.opinion_interval;
+        let half_width = (b - a) / 2.;
+        if !(0. ..=half_width).contains(&amplitude) {
+            return Err(HkError::InvalidParameter(format!(
+                "media amplitude {} needs to be in [0, {}]",
+                amplitude, half_width
+            )));
+        }
+        if !(period > 0. && period.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "media period {} needs to be positive and finite",
+                period
+            )));
+        }
+        validate_media_weight(weight)?;
+        let schedule = MediaSchedule {
+            center: (a + b) / 2.,
+            amplitude,
+            period,
+        };
+        self.media = Some(Media {
+            opinion: schedule.opinion(self.sweeps as u64),
+            weight,
+        });
+        self.media_schedule = Some(schedule);
+        Ok(())
+    }
+
+    /// remove the media field
+    pub fn remove_media(&mut self) {
+        self.media = None;
+        self.media_schedule = None;
+    }
+
+    /// let every agent, independently with probability `q` per sweep, replace its update
+    /// by moving the fraction `mu` of the way towards `media_opinion`, regardless of its
+    /// confidence, the decisions are drawn in the order of the agents, `q = 0` disables it
+    pub fn set_media_exposure(
+        &mut self,
+        q: Float,
+        mu: Float,
+        media_opinion: Float,
+    ) -> Result<(), HkError> {
+        if !(0. ..=1.).contains(&q) {
+            return Err(HkError::InvalidParameter(format!(
+                "exposure probability {} needs to be in [0, 1]",
+                q
+            )));
+        }
+        if !(0. ..=1.).contains(&mu) {
+            return Err(HkError::InvalidParameter(format!(
+                "mu {} of the exposure needs to be in [0, 1]",
+                mu
+            )));
+        }
+        if !self.in_opinion_interval(media_opinion) {
+            return Err(HkError::InvalidParameter(format!(
+                "media opinion {} needs to be in {}",
+                media_opinion,
+                self.opinion_interval_string()
+            )));
+        }
+        self.media_exposure = Some(MediaExposure {
+            probability: q,
+            mu,
+            opinion: media_opinion,
+        });
+        Ok(())
+    }
+
+    /// disable the broadcast exposure to media
+    pub fn remove_media_exposure(&mut self) {
+        self.media_exposure = None;
+    }
+
+    /// the opinion of an agent with the opinion `x` after exposure to the media, if it is
+    /// exposed, no random numbers are consumed, if the exposure is disabled
+    fn expose(&mut self, x: Float) -> Option<Float> {
+        let exposure = self.media_exposure?;
+        if exposure.probability > 0. && self.rng.gen::<Float>() < exposure.probability {
+            Some(self.topology.interpolate(x, exposure.opinion, exposure.mu))
+        } else {
+            None
+        }
+    }
+
+    /// current opinion of the media field, if any
+    pub fn media_opinion(&self) -> Option<Float> {
+        self.media.map(|m| m.opinion)
+    }
+
+    /// number of sweeps performed since the last `reset()`, `reset_to_initial()` or `set_state()`
+    pub fn sweep_count(&self) -> usize {
+        self.sweeps
+    }
+
+    /// set the amplitude `eta` of the additive noise: after every update an agent's
+    /// opinion is perturbed by a uniform random number from [-eta, eta] and
+    /// treated according to the boundary condition, `eta = 0` disables the noise
+    pub fn set_noise(&mut self, eta: Float) -> Result<(), HkError> {
+        if !(eta >= 0. && eta.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "noise amplitude {} needs to be finite and non-negative",
+                eta
+            )));
+        }
+        self.noise = eta;
+        Ok(())
+    }
+
+    /// replace on average `rate` randomly chosen agents after every sweep of `sweep()` by
+    /// newcomers with a uniformly distributed opinion and a confidence drawn from the
+    /// confidence distribution, `rate = 0` is a closed system
+    /// roles bound to the index of an agent, like zealotry, are passed on to the newcomer
+    /// since the system never converges, it should be run for a fixed number of sweeps
+    pub fn set_turnover(&mut self, rate: Float) -> Result<(), HkError> {
+        if !(rate >= 0. && rate.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "turnover rate {} needs to be finite and non-negative",
+                rate
+            )));
+        }
+        self.turnover = rate;
+        Ok(())
+    }
+
+    /// let every agent, independently with probability `p` per sweep, discard the result of
+    /// its update and jump to a uniformly distributed opinion instead, `p = 0` disables it
+    /// the decisions are drawn in the order of the agents, the system never converges
+    pub fn set_jump_probability(&mut self, p: Float) -> Result<(), HkError> {
+        if !(0. ..=1.).contains(&p) {
+            return Err(HkError::InvalidParameter(format!(
+                "jump probability {} needs to be in [0, 1]",
+                p
+            )));
+        }
+        self.jump_probability = p;
+        Ok(())
+    }
+
+    /// a fresh uniformly distributed opinion, if the agent decides to jump
+    /// no random numbers are consumed, if jumps are disabled
+    fn jump(&mut self) -> Option<Float> {
+        if self.jump_probability > 0. && self.rng.gen::<Float>() < self.jump_probability {
+            let u = self.rng.gen();
+            Some(self.unit_to_opinion(u))
+        } else {
+            None
+        }
+    }
+
+    /// set the amplitude `sigma` of the noise with which agents perceive the opinions of
+    /// others, `sigma = 0` disables it, otherwise `sweep()` uses the naive method
+    pub fn set_perception_noise(&mut self, sigma: Float) -> Result<(), HkError> {
+        if !(sigma >= 0. && sigma.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "perception noise {} needs to be finite and non-negative",
+                sigma
+            )));
+        }
+        self.perception_noise = sigma;
+        // naive sweeps do not maintain the tree, so start from a consistent one
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// set the weight `s >= 0` of the own opinion of every agent in its average,
+    /// i.e., the new opinion is `(s x_i + sum of neighbors) / (s + count - 1)`
+    /// `s = 0` ignores the own opinion, large `s` makes agents stubborn
+    pub fn set_self_weight(&mut self, s: Float) -> Result<(), HkError> {
+        if !(s >= 0. && s.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "self weight {} needs to be finite and non-negative",
+                s
+            )));
+        }
+        self.self_weight = s;
+        Ok(())
+    }
+
+    /// set the schedule according to which the confidences change with every `sweep()`
+    /// the schedule is always applied to the confidences at the last reset (or `set_state`)
+    pub fn set_confidence_schedule(&mut self, schedule: ConfidenceSchedule) -> Result<(), HkError> {
+        schedule.validate()?;
+        self.confidence_schedule = schedule;
+        Ok(())
+    }
+
+    /// let the confidence of every agent be a function of its current opinion, which
+    /// is evaluated at the start of every `sweep()` and takes precedence over the
+    /// confidences drawn at `reset()` and a confidence schedule
+    pub fn set_confidence_function(&mut self, function: ConfidenceFunction) -> Result<(), HkError> {
+        function.validate()?;
+        self.confidence_function = function;
+        Ok(())
+    }
+
+    /// after every synchronous sweep increase the confidence of every agent by `delta`
+    /// if it had at least one neighbor besides itself and decrease it otherwise,
+    /// the confidences are kept within [eps_min, eps_max]
+    pub fn enable_adaptive_confidence(
+        &mut self,
+        delta: Float,
+        eps_min: Float,
+        eps_max: Float,
+    ) -> Result<(), HkError> {
+        if !(delta >= 0. && delta.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "change {} of the confidences needs to be finite and non-negative",
+                delta
+            )));
+        }
+        if !(0. <= eps_min && eps_min <= eps_max && eps_max.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "confidence bounds [{}, {}] need to be finite with 0 <= min <= max",
+                eps_min, eps_max
+            )));
+        }
+        self.adaptive_confidence = Some(AdaptiveConfidence {
+            delta,
+            min: eps_min,
+            max: eps_max,
+        });
+        Ok(())
+    }
+
+    /// keep the confidences constant again
+    pub fn disable_adaptive_confidence(&mut self) {
+        self.adaptive_confidence = None;
+    }
+
+    /// multiply the confidence of every agent by `gamma` after it did not have any neighbor
+    /// besides itself for `s` consecutive sweeps, the confidences do not grow beyond `eps_max`
+    pub fn enable_isolation_growth(
+        &mut self,
+        s: u32,
+        gamma: Float,
+        eps_max: Float,
+    ) -> Result<(), HkError> {
+        if s == 0 {
+            return Err(HkError::InvalidParameter(
+                "the number of isolated sweeps needs to be at least 1".to_string(),
+            ));
+        }
+        if !(gamma > 1. && gamma.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "growth factor {} needs to be finite and larger than 1",
+                gamma
+            )));
+        }
+        if !(eps_max >= 0. && eps_max.is_finite()) {
+            return Err(HkError::InvalidParameter(format!(
+                "maximal confidence {} needs to be finite and non-negative",
+                eps_max
+            )));
+        }
+        self.isolation_growth = Some(IsolationGrowth {
+            sweeps: s,
+            factor: gamma,
+            max: eps_max,
+            counters: vec![0; self.num_agents as usize],
+        });
+        Ok(())
+    }
+
+    /// keep the confidences of isolated agents constant again
+    pub fn disable_isolation_growth(&mut self) {
+        self.isolation_growth = None;
+    }
+
+    /// forget how long the agents were isolated
+    fn clear_isolation(&mut self) {
+        if let Some(growth) = &mut self.isolation_growth {
+            growth.counters.iter_mut().for_each(|c| *c = 0);
+        }
+    }
+
+    /// mark the agents with the given indices as zealots, which keep their opinion
+    /// forever but still count as neighbors of others
+    /// the choice survives `reset()`, an empty slice removes all zealots
+    pub fn set_zealots(&mut self, indices: &[usize]) -> Result<(), HkError> {
+        if let Some(&i) = indices.iter().find(|&&i| i >= self.num_agents as usize) {
+            return Err(HkError::InvalidParameter(format!(
+                "zealot index {} out of range for {} agents",
+                i, self.num_agents
+            )));
+        }
+        self.zealots = indices.to_vec();
+        self.mark_zealots();
+        Ok(())
+    }
+
+    /// assign agent `i` to group `assignments[i]` and let the confidence of a member of
+    /// group `a` towards a member of group `b` be `matrix[a][b]` instead of its own
+    /// confidence, which `sweep()` then uses `sweep_groups_tree` for
+    /// the assignment survives `reset()`, only the line is supported
+    pub fn set_groups(&mut self, assignments: &[u8], matrix: &[Vec<Float>]) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidGroups(reason));
+        if self.topology != OpinionSpace::Line {
+            return invalid("groups are only supported on the line".to_string());
+        }
+        if assignments.len() != self.num_agents as usize {
+            return invalid(format!(
+                "{} group assignments given for {} agents",
+                assignments.len(),
+                self.num_agents
+            ));
+        }
+        let k = matrix.len();
+        if k == 0 || k > u8::MAX as usize + 1 || matrix.iter().any(|row| row.len() != k) {
+            return invalid(format!(
+                "the confidence matrix needs to be square with 1 to 256 rows, but has {} rows",
+                k
+            ));
+        }
+        if let Some(g) = assignments.iter().find(|&&g| g as usize >= k) {
+            return invalid(format!("group {} has no row in the confidence matrix", g));
+        }
+        if let Some(e) = matrix
+            .iter()
+            .flatten()
+            .find(|e| !(**e >= 0. && e.is_finite()))
+        {
+            return invalid(format!(
+                "confidence {} needs to be finite and non-negative",
+                e
+            ));
+        }
+
+        self.groups = Some(Groups {
+            assignments: assignments.to_vec(),
+            matrix: matrix.to_vec(),
+            trees: vec![BTreeMap::new(); k],
+        });
+        self.mark_groups();
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// remove the groups, all agents use their own confidences again
+    pub fn remove_groups(&mut self) {
+        self.groups = None;
+    }
+
+    /// apply the stored group assignments to the current agents
+    fn mark_groups(&mut self) {
+        if let Some(groups) = &self.groups {
+            for (i, &g) in self.agents.iter_mut().zip(&groups.assignments) {
+                i.group = g;
+            }
+        }
+    }
+
+    /// let the agents with the given indices count as `weight` copies of themselves in
+    /// every average, including their own, on top of their influence weight
+    /// the tree stores the total weight of every opinion, such that a leader needs a single
+    /// entry, while the counts of agents stay unchanged
+    /// the choice survives `reset()`, an empty slice removes all leaders
+    pub fn set_leaders(&mut self, indices: &[usize], weight: u32) -> Result<(), HkError> {
+        if let Some(&i) = indices.iter().find(|&&i| i >= self.num_agents as usize) {
+            return Err(HkError::InvalidParameter(format!(
+                "leader index {} out of range for {} agents",
+                i, self.num_agents
+            )));
+        }
+        if weight < 1 {
+            return Err(HkError::InvalidParameter(
+                "leaders need a weight of at least 1".to_string(),
+            ));
+        }
+        self.leaders = indices.to_vec();
+        self.leader_weight = weight;
+        self.mark_leaders();
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// apply the stored leader indices to the current agents
+    fn mark_leaders(&mut self) {
+        for i in self.agents.iter_mut() {
+            i.multiplicity = 1;
+        }
+        for &i in &self.leaders {
+            self.agents[i].multiplicity = self.leader_weight;
+        }
+    }
+
+    /// apply the stored zealot indices to the current agents
+    fn mark_zealots(&mut self) {
+        for i in self.agents.iter_mut() {
+            i.zealot = false;
+        }
+        for &i in &self.zealots {
+            self.agents[i].zealot = true;
+        }
+    }
+
+    /// whether both models have the same number of agents and the opinions and confidences
+    /// of every agent differ by at most `tol`, e.g., to compare the results of different methods
+    pub fn approx_eq(&self, other: &HegselmannKrause, tol: Float) -> bool {
+        self.agents.len() == other.agents.len()
+            && self
+                .agents
+                .iter()
+                .zip(&other.agents)
+                .all(|(a, b)| a.approx_eq(b, tol))
+    }
+
+    /// the index and both opinions of the first agent whose opinion differs between the
+    /// models, or `None` if all opinions are identical, e.g., to report failed comparisons
+    /// the opinions are compared bitwise, such that equal NaNs are no difference
+    /// if one model has more agents, the first agent missing in the other one is `None`
+    pub fn first_difference(
+        &self,
+        other: &HegselmannKrause,
+    ) -> Option<(usize, Option<Float>, Option<Float>)> {
+        let opinion = |hk: &HegselmannKrause, idx: usize| hk.agents.get(idx).map(|i| i.opinion);
+        (0..self.agents.len().max(other.agents.len()))
+            .map(|idx| (idx, opinion(self, idx), opinion(other, idx)))
+            .find(|&(_, a, b)| a.map(Float::to_bits) != b.map(Float::to_bits))
+    }
+
+    /// hash of the opinions and confidences of all agents, which is identical if and only if
+    /// (up to collisions) the states are bitwise identical, on every platform
+    /// comparing fingerprints of runs on different machines detects diverging results early,
+    /// see the section on reproducibility in the readme
+    pub fn state_fingerprint(&self) -> u64 {
+        // FNV-1a, since the hasher of the standard library may change between releases
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mut hash = OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+        feed(&(self.agents.len() as u64).to_le_bytes());
+        for i in &self.agents {
+            feed(&i.opinion.to_bits().to_le_bytes());
+            feed(&i.confidence_left.to_bits().to_le_bytes());
+            feed(&i.confidence_right.to_bits().to_le_bytes());
+        }
+        hash
+    }
+
+    /// snapshot of the current opinions of all agents
+    pub fn opinions(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.opinion).collect()
+    }
+
+    /// snapshot of the current confidences of all agents
+    /// for asymmetric confidence intervals these are the confidences towards lower opinions
+    pub fn confidences(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.confidence_left).collect()
+    }
+
+    /// snapshot of the influence weights of all agents
+    pub fn weights(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.weight).collect()
+    }
+
+    /// snapshot of the activities of all agents
+    pub fn activities(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.activity).collect()
+    }
+
+    /// snapshot of the current confidences of all agents towards higher opinions
+    pub fn confidences_right(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.confidence_right).collect()
+    }
+
+    /// number of agents
+    pub fn num_agents(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// snapshot of the current opinion and confidence of the agent `idx`, or `None` if there
+    /// is no such agent, for asymmetric confidence intervals this is the confidence towards
+    /// lower opinions
+    pub fn agent(&self, idx: usize) -> Option<(Float, Float)> {
+        self.agents.get(idx).map(|i| (i.opinion, i.confidence_left))
+    }
+
+    /// the current opinions of all agents in their order, without copying them
+    pub fn iter_opinions(&self) -> impl Iterator<Item = Float> + '_ {
+        self.agents.iter().map(|i| i.opinion)
+    }
+
+    /// reseed the random number generator and `reset()`, such that the sample depends
+    /// only on `seed` and the configuration, but not on the samples simulated before
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.rng = Pcg64::seed_from_u64(seed);
+        self.reset();
+    }
+
+    /// reset the state of an HegselmannKrause struct
+    /// initialize the agents with random initial conditions
+    /// and prepare all internal datastructures
+    /// afterwards the object will be ready for a fresh simulation
+    pub fn reset(&mut self) {
+        // initialize a vector of n agents with opinions and confidences drawn
+        // from the configured distributions, reusing the allocation of the previous agents
+        let mut agents = std::mem::take(&mut self.agents);
+        agents.clear();
+        agents.extend((0..self.num_agents as usize).map(|idx| {
+            // the initial opinions are drawn relative to the opinion interval
+            let (unit_opinion, extremist_confidence, block) =
+                self.initial_opinions.sample_with_role(idx, &mut self.rng);
+            let opinion = self.unit_to_opinion(unit_opinion);
+            // agents of a block draw their symmetric confidence from the distribution
+            // of the block, the group of an agent is its block
+            let in_block = block.is_some();
+            let (confidence, group) = match block {
+                Some((block, distribution)) => (distribution.sample(&mut self.rng), block),
+                None => self
+                    .confidence_distribution
+                    .sample_for_opinion(unit_opinion, &mut self.rng),
+            };
+            // symmetric agents do not consume an additional random number
+            let confidence_right = match &self.confidence_distribution_right {
+                Some(distribution) if in_block => {
+                    // consumed anyway, such that the state does not depend on the blocks
+                    distribution.sample(&mut self.rng);
+                    confidence
+                }
+                Some(distribution) => distribution.sample(&mut self.rng),
+                None => confidence,
+            };
+            let mut agent = match extremist_confidence {
+                // the confidences are drawn anyway, such that the random numbers
+                // consumed do not depend on the role of the agent
+                Some(c) => {
+                    let mut agent = HKAgent::new(opinion, c);
+                    agent.extremist = true;
+                    agent
+                }
+                None => HKAgent::asymmetric(opinion, confidence, confidence_right),
+            };
+            agent.group = group;
+            agent.weight = self.weight_distribution.sample(&mut self.rng);
+            agent.activity = self.activity_distribution.sample(&mut self.rng);
+            agent
+        }));
+        self.agents = agents;
+        self.initial_agents.clone_from(&self.agents);
+        self.prepare_initial_agents();
+    }
+
+    /// restore the agents drawn by the latest `reset()` or given to `set_state` without
+    /// drawing random numbers, e.g., to simulate the same initial conditions with other
+    /// parameters, zealots, leaders, groups and the quantization are applied as configured now
+    /// before any initial conditions exist, this is `reset()`
+    pub fn reset_to_initial(&mut self) {
+        if self.initial_agents.len() != self.num_agents as usize {
+            self.reset();
+            return;
+        }
+        self.agents.clone_from(&self.initial_agents);
+        self.prepare_initial_agents();
+    }
+
+    /// opinions and confidences of the initial conditions `reset_to_initial` restores
+    /// for asymmetric confidence intervals these are the confidences towards lower opinions
+    pub fn initial_state(&self) -> (Vec<Float>, Vec<Float>) {
+        self.initial_agents
+            .iter()
+            .map(|i| (i.opinion, i.confidence_left))
+            .unzip()
+    }
+
+    /// apply the configuration to freshly initialized agents and prepare all internal
+    /// datastructures for a simulation starting from them
+    fn prepare_initial_agents(&mut self) {
+        self.quantize_opinions();
+        self.mark_zealots();
+        self.mark_leaders();
+        self.mark_groups();
+        self.clear_memory();
+        self.clear_isolation();
+        self.rotation_step = 0;
+        self.order.clear();
+        self.invalidate_tracking();
+        self.store_initial_confidences();
+        self.store_initial_opinions();
+        self.apply_media_schedule();
+
+        // initialize the tree of opinions with the initial conditions of the agents
+        self.rebuild_tree();
+    }
+
+    /// overwrite the opinions and confidences of all agents, e.g., to study
+    /// specific configurations, and prepare all internal datastructures
+    /// all other properties of the agents, like zealotry or groups, are kept
+    pub fn set_state(&mut self, opinions: &[Float], confidences: &[Float]) -> Result<(), HkError> {
+        self.set_state_asymmetric(opinions, confidences, confidences)
+    }
+
+    /// like `set_state`, but with separate confidences towards lower and higher opinions
+    pub fn set_state_asymmetric(
+        &mut self,
+        opinions: &[Float],
+        confidences_left: &[Float],
+        confidences_right: &[Float],
+    ) -> Result<(), HkError> {
+        let n = self.num_agents as usize;
+        if opinions.len() != n || confidences_left.len() != n || confidences_right.len() != n {
+            return Err(HkError::InvalidState(format!(
+                "{} opinions and {}/{} confidences given for {} agents",
+                opinions.len(),
+                confidences_left.len(),
+                confidences_right.len(),
+                n
+            )));
+        }
+        if let Some(i) = (0..n).find(|&i| {
+            !(opinions[i].is_finite()
+                && confidences_left[i].is_finite()
+                && confidences_right[i].is_finite())
+        }) {
+            return Err(HkError::InvalidState(format!(
+                "agent {} has the opinion {} and the confidences {}/{}, which need to be finite",
+                i, opinions[i], confidences_left[i], confidences_right[i]
+            )));
+        }
+        if let Some((i, x)) = opinions
+            .iter()
+            .enumerate()
+            .find(|(_, x)| !self.in_opinion_interval(**x))
+        {
+            return Err(HkError::InvalidState(format!(
+                "opinion {} of agent {} is not within {}",
+                x,
+                i,
+                self.opinion_interval_string()
+            )));
+        }
+        if let Some((i, c)) = confidences_left
+            .iter()
+            .chain(confidences_right.iter())
+            .enumerate()
+            .find(|(_, c)| !(c.is_finite() && **c >= 0.))
+        {
+            return Err(HkError::InvalidState(format!(
+                "confidence {} of agent {} is not a non-negative number",
+                c,
+                i % n
+            )));
+        }
+
+        for (agent, ((&x, &l), &r)) in self.agents.iter_mut().zip(
+            opinions
+                .iter()
+                .zip(confidences_left.iter())
+                .zip(confidences_right.iter()),
+        ) {
+            agent.opinion = x;
+            agent.confidence_left = l;
+            agent.confidence_right = r;
+        }
+        self.initial_agents.clone_from(&self.agents);
+        self.quantize_opinions();
+        self.clear_memory();
+        self.clear_isolation();
+        self.rotation_step = 0;
+        self.order.clear();
+        self.invalidate_tracking();
+        self.store_initial_confidences();
+        self.store_initial_opinions();
+
+        self.rebuild_tree();
+        self.reset_change();
+        Ok(())
+    }
+
+    /// move the agent `idx` to the opinion `x` in the middle of a simulation, e.g., to kick
+    /// an agent out of a converged cluster and watch whether the clusters rearrange
+    /// the trees and the memory of the agent follow, unlike `set_state` all other agents
+    /// and the initial conditions of `reset_to_initial` are kept
+    pub fn set_opinion(&mut self, idx: usize, x: Float) -> Result<(), HkError> {
+        self.check_agent(idx)?;
+        if !(x.is_finite() && self.in_opinion_interval(x)) {
+            return Err(HkError::InvalidState(format!(
+                "opinion {} of agent {} is not within {}",
+                x,
+                idx,
+                self.opinion_interval_string()
+            )));
+        }
+        let x = self.quantize(x);
+        let (old_opinion, mass, group) = {
+            let i = &self.agents[idx];
+            (i.opinion, i.mass(), i.group)
+        };
+
+        if let Some(groups) = &mut self.groups {
+            let tree = &mut groups.trees[group as usize];
+            let removed = remove_entry(tree, old_opinion, mass);
+            insert_entry(tree, x, mass);
+            if let Err(e) = removed {
+                self.record_tree_error(e);
+            }
+        }
+        // an outdated tree is rebuilt before it is used again anyway
+        if self.tree_outdated {
+            self.invalidate_tracking();
+        } else if let Err(e) = self.update_entry(old_opinion, x, mass) {
+            self.record_tree_error(e);
+        }
+        if self.memory > 1 {
+            let history = &mut self.histories[idx];
+            history.clear();
+            history.push_back(x);
+        }
+        self.agents[idx].opinion = x;
+        Ok(())
+    }
+
+    /// change the confidence of the agent `idx` towards both sides in the middle of a
+    /// simulation, confidence schedules continue from it
+    pub fn set_confidence(&mut self, idx: usize, confidence: Float) -> Result<(), HkError> {
+        self.check_agent(idx)?;
+        if !(confidence.is_finite() && confidence >= 0.) {
+            return Err(HkError::InvalidState(format!(
+                "confidence {} of agent {} is not a non-negative number",
+                confidence, idx
+            )));
+        }
+        let agent = &mut self.agents[idx];
+        agent.confidence_left = confidence;
+        agent.confidence_right = confidence;
+        // skipping unchanged agents notices the new confidence by the key of the agent
+        self.initial_confidences[idx] = (confidence, confidence);
+        Ok(())
+    }
+
+    /// an error for indices of agents, which do not exist
+    fn check_agent(&self, idx: usize) -> Result<(), HkError> {
+        if idx >= self.agents.len() {
+            return Err(HkError::InvalidState(format!(
+                "there is no agent {}, the model has {} agents",
+                idx,
+                self.agents.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// remember the current confidences as the origin of the confidence schedule
+    fn store_initial_confidences(&mut self) {
+        self.initial_confidences.clear();
+        self.initial_confidences.extend(
+            self.agents
+                .iter()
+                .map(|i| (i.confidence_left, i.confidence_right)),
+        );
+        self.sweeps = 0;
+    }
+
+    /// remember the current opinions as the anchors of the agents
+    fn store_initial_opinions(&mut self) {
+        self.anchors.clear();
+        self.anchors.extend(self.agents.iter().map(|i| i.opinion));
+    }
+
+    /// draw new confidences for all agents in the order of their indices, if annealed
+    /// like in `reset()`, every agent consumes the same random numbers regardless of its role,
+    /// but extremists keep their confidence and all agents keep their groups
+    fn redraw_confidences(&mut self) {
+        if !self.annealed_confidences {
+            return;
+        }
+        for idx in 0..self.agents.len() {
+            let (confidence, _) = self.confidence_distribution.sample_for_opinion(
+                self.opinion_to_unit(self.agents[idx].opinion),
+                &mut self.rng,
+            );
+            let confidence_right = match &self.confidence_distribution_right {
+                Some(distribution) => distribution.sample(&mut self.rng),
+                None => confidence,
+            };
+            let agent = &mut self.agents[idx];
+            if !agent.extremist {
+                agent.confidence_left = confidence;
+                agent.confidence_right = confidence_right;
+                self.initial_confidences[idx] = (confidence, confidence_right);
+            }
+        }
+    }
+
+    /// set the confidences of all agents according to the schedule at the current time
+    fn apply_confidence_schedule(&mut self) {
+        if self.confidence_schedule == ConfidenceSchedule::Constant {
+            return;
+        }
+        for (i, &(left, right)) in self.agents.iter_mut().zip(&self.initial_confidences) {
+            i.confidence_left = self
+                .confidence_schedule
+                .confidence(left, self.sweeps as u64);
+            i.confidence_right = self
+                .confidence_schedule
+                .confidence(right, self.sweeps as u64);
+        }
+    }
+
+    /// set the opinion of the media according to its schedule at the current time
+    fn apply_media_schedule(&mut self) {
+        if let (Some(media), Some(schedule)) = (&mut self.media, &self.media_schedule) {
+            media.opinion = schedule.opinion(self.sweeps as u64);
+        }
+    }
+
+    /// set the confidences of all agents according to their current opinions
+    fn apply_confidence_function(&mut self) {
+        if self.confidence_function == ConfidenceFunction::Constant {
+            return;
+        }
+        // the functions are defined relative to the opinion interval
+        let (a, b) = self.opinion_interval;
+        let to_unit = |x: Float| (x - a) / (b - a);
+        for i in self.agents.iter_mut() {
+            if let Some(c) = self.confidence_function.confidence(to_unit(i.opinion)) {
+                i.confidence_left = c;
+                i.confidence_right = c;
+            }
+        }
+    }
+
+    /// construct the tree of opinions from scratch from the current state of the agents
+    /// as well as the trees of the groups, if any
+    /// this restores the consistency after the opinions were changed without the tree
+    pub fn rebuild_tree(&mut self) {
+        self.opinion_set.clear();
+        self.opinion_sums.clear();
+        for i in self.agents.iter() {
+            insert_entry(&mut self.opinion_set, i.opinion, i.mass());
+            self.opinion_sums.insert(i.opinion, i.mass());
+        }
+        self.sweeps_since_rebuild = 0;
+        self.tree_outdated = false;
+
+        // assert that every agent has a corresponding opinion in the tree
+        assert!(self.opinion_set.values().map(|e| e.count).sum::<u32>() == self.num_agents);
+
+        if let Some(groups) = &mut self.groups {
+            for tree in groups.trees.iter_mut() {
+                tree.clear();
+            }
+            for i in self.agents.iter() {
+                insert_entry(&mut groups.trees[i.group as usize], i.opinion, i.mass());
+            }
+        }
+    }
+
+    /// build the trees of a deserialized model, which are not serialized, and keep the
+    /// schedule of the regular rebuilds, such that the model continues like the original
+    #[cfg(feature = "serde")]
+    fn restore_trees(&mut self) -> Result<(), HkError> {
+        if self.agents.len() != self.num_agents as usize {
+            return Err(HkError::InvalidState(format!(
+                "{} agents instead of {}",
+                self.agents.len(),
+                self.num_agents
+            )));
+        }
+        if let Some(groups) = &mut self.groups {
+            let k = groups.matrix.len();
+            if let Some(i) = self.agents.iter().find(|i| i.group as usize >= k) {
+                return Err(HkError::InvalidGroups(format!(
+                    "an agent belongs to group {} of {}",
+                    i.group, k
+                )));
+            }
+            groups.trees = vec![BTreeMap::new(); k];
+        }
+        let (outdated, sweeps_since_rebuild) = (self.tree_outdated, self.sweeps_since_rebuild);
+        self.rebuild_tree();
+        self.tree_outdated = outdated;
+        self.sweeps_since_rebuild = sweeps_since_rebuild;
+        Ok(())
+    }
+
+    /// write the complete state of the model including its random number generator in
+    /// bincode behind a short header to `file`, from which `load_checkpoint` resumes the
+    /// simulation exactly
+    /// with non-unit weights the rebuilt tree lacks the rounding errors of the original one,
+    /// such that the resumed run may deviate in the last bits
+    #[cfg(feature = "serde")]
+    pub fn save_checkpoint<W: Write>(&self, mut file: W) -> std::io::Result<()> {
+        file.write_all(CHECKPOINT_MAGIC)?;
+        file.write_all(&[CHECKPOINT_VERSION, std::mem::size_of::<Float>() as u8])?;
+        bincode::serialize_into(file, self).map_err(|e| match *e {
+            bincode::ErrorKind::Io(e) => e,
+            e => std::io::Error::other(e),
+        })
+    }
+
+    /// read a model written by `save_checkpoint`, checkpoints of other versions of the
+    /// format or written with another precision of `Float` are rejected as invalid data
+    #[cfg(feature = "serde")]
+    pub fn load_checkpoint<R: Read>(mut file: R) -> std::io::Result<HegselmannKrause> {
+        let invalid = |e| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+        let mut header = [0; 6];
+        file.read_exact(&mut header)
+            .map_err(|_| invalid("too short for a checkpoint".to_string()))?;
+        if &header[..4] != CHECKPOINT_MAGIC {
+            return Err(invalid("not a checkpoint".to_string()));
+        }
+        if header[4..] != [CHECKPOINT_VERSION, std::mem::size_of::<Float>() as u8] {
+            return Err(invalid(format!(
+                "checkpoint of version {} with {} byte floats, expected version {} with {} byte floats",
+                header[4],
+                header[5],
+                CHECKPOINT_VERSION,
+                std::mem::size_of::<Float>()
+            )));
+        }
+        bincode::deserialize_from(file).map_err(|e| invalid(e.to_string()))
+    }
+
+    /// test whether all opinions are finite and, unless the boundary is open, within the
+    /// opinion interval, and whether the tree of opinions and the trees of the groups
+    /// contain exactly the opinions of the agents with their counts, the tree of all
+    /// opinions is skipped while it is outdated
+    /// `sweep` calls this in debug builds and panics on a violation with the `paranoid`
+    /// feature, tests of new dynamics can call it explicitly
+    pub fn verify_invariants(&self) -> Result<(), InvariantViolation> {
+        for (idx, i) in self.agents.iter().enumerate() {
+            if !i.opinion.is_finite() {
+                return Err(InvariantViolation::NonFiniteOpinion {
+                    agent: idx,
+                    opinion: i.opinion,
+                });
+            }
+            if self.boundary != Boundary::Open && !self.in_opinion_interval(i.opinion) {
+                return Err(InvariantViolation::OutsideInterval {
+                    agent: idx,
+                    opinion: i.opinion,
+                    interval: self.opinion_interval,
+                });
+            }
+        }
+        if !self.tree_outdated {
+            verify_tree(&self.opinion_set, self.agents.iter().enumerate(), None)?;
+        }
+        if let Some(groups) = &self.groups {
+            for (g, tree) in groups.trees.iter().enumerate() {
+                let members = self
+                    .agents
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, i)| i.group as usize == g);
+                verify_tree(tree, members, Some(g))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// report the first agent, whose opinion or confidences are NaN or infinite, which would
+    /// corrupt the order of the tree and thus silently the neighbors found by range queries
+    /// `try_sweep` checks this after every sweep in debug builds
+    pub fn check_finite(&self) -> Result<(), HkError> {
+        let invalid = self.agents.iter().position(|i| {
+            !(i.opinion.is_finite()
+                && i.confidence_left.is_finite()
+                && i.confidence_right.is_finite())
+        });
+        match invalid {
+            Some(idx) => {
+                let agent = &self.agents[idx];
+                let confidence = if agent.confidence_left.is_finite() {
+                    agent.confidence_right
+                } else {
+                    agent.confidence_left
+                };
+                Err(HkError::NonFinite {
+                    agent: idx,
+                    opinion: agent.opinion,
+                    confidence,
+                    sweep: self.sweeps,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// overwrite the opinion of agent `idx` without any validation and rebuild the tree,
+    /// such that tests can check the detection of invalid opinions
+    #[doc(hidden)]
+    pub fn inject_opinion(&mut self, idx: usize, opinion: Float) {
+        self.agents[idx].opinion = opinion;
+        self.rebuild_tree();
+    }
+
+    /// count the opinion of the first agent twice in the tree without changing the agents,
+    /// such that tests can check the detection and repair of an inconsistent tree
+    #[doc(hidden)]
+    pub fn desync_tree(&mut self) {
+        self.rebuild_outdated_tree();
+        let opinion = self.agents[0].opinion;
+        insert_entry(&mut self.opinion_set, opinion, self.agents[0].mass());
+        self.opinion_sums = SumTree::from_tree(&self.opinion_set);
+    }
+
+    /// like `desync_tree`, but drop the opinion of the first agent from the tree, such that
+    /// removing it during the next update fails
+    #[doc(hidden)]
+    pub fn desync_tree_removing(&mut self) {
+        self.rebuild_outdated_tree();
+        let key = to_key(self.agents[0].opinion);
+        self.opinion_set.remove(&key);
+        self.opinion_sums = SumTree::from_tree(&self.opinion_set);
+    }
+
+    /// change the counter of the entry of the opinion of agent `idx` in the tree by `delta`
+    /// without removing the entry, such that tests can corrupt single counters
+    #[doc(hidden)]
+    pub fn desync_tree_count(&mut self, idx: usize, delta: i32) {
+        self.rebuild_outdated_tree();
+        let key = to_key(self.agents[idx].opinion);
+        if let Some(entry) = self.opinion_set.get_mut(&key) {
+            entry.count = (entry.count as i64 + delta as i64) as u32;
+        }
+        self.opinion_sums = SumTree::from_tree(&self.opinion_set);
+    }
+
+    /// add a NaN key to the tree without changing the agents
+    #[doc(hidden)]
+    pub fn desync_tree_nan(&mut self) {
+        self.rebuild_outdated_tree();
+        insert_entry(&mut self.opinion_set, Float::NAN, 1.);
+        self.opinion_sums = SumTree::from_tree(&self.opinion_set);
+    }
+
+    /// rebuild the tree, if `sweep` used a method without the tree since it was last built
+    fn rebuild_outdated_tree(&mut self) {
+        if self.tree_outdated {
+            self.rebuild_tree();
+        }
+    }
+
+    /// rebuild the tree regularly, if the weights of its entries accumulate rounding errors
+    /// unit weights are always represented exactly
+    fn refresh_tree(&mut self) {
+        if self.weight_distribution == WeightDistribution::Unit && self.leaders.is_empty() {
+            return;
+        }
+        self.sweeps_since_rebuild += 1;
+        if self.sweeps_since_rebuild >= TREE_REBUILD_INTERVAL {
+            self.rebuild_tree();
+        }
+    }
+
+    /// calculate the new opinion of a single agent using the naive method of iterating all agents
+    /// together with the number of agents within its confidence interval (including itself)
+    fn new_opinion_naive(&self, i: &HKAgent) -> (Float, u32) {
+        self.new_opinion_naive_among(i, None, None)
+    }
+
+    /// like `new_opinion_naive`, but if `considered` is given, only the agents `j` with
+    /// `considered[j]` are taken into account and if `perceived` is given, agent `j`
+    /// is seen with the opinion `perceived[j]` instead of its actual opinion
+    fn new_opinion_naive_among(
+        &self,
+        i: &HKAgent,
+        considered: Option<&[bool]>,
+        perceived: Option<&[Float]>,
+    ) -> (Float, u32) {
+        if self.topology == OpinionSpace::Circle {
+            return self.new_opinion_circle_naive(i, considered, perceived);
+        }
+
+        let mut sum = Sum::new(self.compensated_summation);
+        let mut weight = Sum::new(self.compensated_summation);
+        let mut count = 0;
+        let mut repelling_sum = Sum::new(self.compensated_summation);
+        let mut repelling_weight = Sum::new(self.compensated_summation);
+        // the opinions of the neighbors are only needed for other statistics than the mean
+        let mut neighbors = Vec::new();
+        for (k, j) in self.agents.iter().enumerate() {
+            let x = perceived.map_or(j.opinion, |p| p[k]);
+            if !considered.map_or(true, |c| c[k]) {
+                continue;
+            }
+            if !i.sees(self.kernel, OpinionSpace::Line, x) {
+                if self
+                    .repulsion
+                    .is_some_and(|r| (x - i.opinion).abs() <= r.range)
+                {
+                    repelling_sum.add(j.mass() * x);
+                    repelling_weight.add(j.mass());
+                }
+                continue;
+            }
+            let w = i.influence(self.kernel, OpinionSpace::Line, x) * j.mass();
+            sum.add(w * x);
+            weight.add(w);
+            count += 1;
+            if self.aggregator != Aggregator::Mean {
+                neighbors.push((x, w));
+            }
+        }
+
+        let mean = if self.aggregator == Aggregator::Mean {
+            self.neighborhood_mean(i, sum.value, weight.value)
+        } else if neighbors.is_empty() {
+            i.opinion
+        } else {
+            neighbors.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            self.aggregator.aggregate(&neighbors)
+        };
+        (
+            self.repel(i, mean, repelling_sum.value, repelling_weight.value),
+            count,
+        )
+    }
+
+    /// new opinion of agent `i` given the weighted sum and the total weight of the opinions
+    /// of its neighbors (including `i` itself once)
+    /// the self weight and the media are taken into account here
+    /// an agent without any weight in its average, e.g., without self weight and neighbors,
+    /// keeps its opinion
+    fn neighborhood_mean(&self, i: &HKAgent, sum: Float, count: Float) -> Float {
+        let (sum, count) = if self.self_weight != 1. {
+            // `i` is already contained once in the sum
+            let excess = self.self_weight - 1.;
+            (
+                sum + excess * i.mass() * i.opinion,
+                count + excess * i.mass(),
+            )
+        } else {
+            (sum, count)
+        };
+
+        let (sum, count) = match self.media {
+            Some(media) if i.sees(self.kernel, self.topology, media.opinion) => {
+                let w = i.influence(self.kernel, self.topology, media.opinion) * media.weight;
+                (sum + w * media.opinion, count + w)
+            }
+            _ => (sum, count),
+        };
+
+        // without any weight, there is nothing to move towards
+        if count <= 0. {
+            return i.opinion;
+        }
+        sum / count
+    }
+
+    /// correct the sum of unit vectors of the neighbors of `i` on the circle for the
+    /// self weight and add the weighted unit vector of the media, if `i` sees it
+    fn adjust_vector_sum(&self, i: &HKAgent, cos: Float, sin: Float) -> (Float, Float) {
+        let (cos, sin) = if self.self_weight != 1. {
+            let excess = self.self_weight - 1.;
+            let (c, s) = unit_vector(i.opinion);
+            let excess = excess * i.mass();
+            (cos + excess * c, sin + excess * s)
+        } else {
+            (cos, sin)
+        };
+
+        match self.media {
+            Some(media) if i.sees(self.kernel, self.topology, media.opinion) => {
+                let w = i.influence(self.kernel, self.topology, media.opinion) * media.weight;
+                let (c, s) = unit_vector(media.opinion);
+                (cos + w * c, sin + w * s)
+            }
+            _ => (cos, sin),
+        }
+    }
+
+    /// direction of the sum of unit vectors `(cos, sin)` of the neighbors of `i`, an empty
+    /// sum has no direction, then `i` keeps its opinion
+    fn circular_mean_of(&self, i: &HKAgent, cos: Float, sin: Float) -> Float {
+        if cos == 0. && sin == 0. {
+            return i.opinion;
+        }
+        circular_mean(cos, sin)
+    }
+
+    /// calculate the new opinion of a single agent on the circle using the naive method
+    /// the new opinion is the circular mean of all neighbors
+    fn new_opinion_circle_naive(
+        &self,
+        i: &HKAgent,
+        considered: Option<&[bool]>,
+        perceived: Option<&[Float]>,
+    ) -> (Float, u32) {
+        let mut cos = 0.;
+        let mut sin = 0.;
+        let mut count = 0;
+        for (k, j) in self.agents.iter().enumerate() {
+            let x = perceived.map_or(j.opinion, |p| p[k]);
+            if !(considered.map_or(true, |c| c[k]) && i.sees(self.kernel, OpinionSpace::Circle, x))
+            {
+                continue;
+            }
+            let w = i.influence(self.kernel, OpinionSpace::Circle, x) * j.mass();
+            let (c, s) = unit_vector(x);
+            cos += w * c;
+            sin += w * s;
+            count += 1;
+        }
+
+        let (cos, sin) = self.adjust_vector_sum(i, cos, sin);
+        (self.circular_mean_of(i, cos, sin), count)
+    }
+
+    /// calculate all new opinions using the naive method of iterating all agents
+    /// together with the number of agents within the confidence interval of every agent
+    /// the previous content of `new_opinions` is replaced
+    fn sync_new_opinions_naive(&self, new_opinions: &mut Vec<(Float, u32)>) {
+        self.fill_new_opinions(new_opinions, |i| self.new_opinion_naive(i));
+    }
+
+    /// replace the content of `buffer` by the new opinions `new_opinion(i)` of all agents
+    /// of a synchronous sweep, which are calculated in parallel with the `rayon` feature
+    /// every new opinion is calculated independently, such that the result does not
+    /// depend on the number of threads
+    #[cfg(feature = "rayon")]
+    fn fill_new_opinions<F>(&self, buffer: &mut Vec<(Float, u32)>, new_opinion: F)
+    where
+        F: Fn(&HKAgent) -> (Float, u32) + Sync + Send,
+    {
+        buffer.clear();
+        buffer.par_extend(self.agents.par_iter().map(new_opinion));
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    fn fill_new_opinions<F>(&self, buffer: &mut Vec<(Float, u32)>, new_opinion: F)
+    where
+        F: Fn(&HKAgent) -> (Float, u32),
+    {
+        buffer.clear();
+        buffer.extend(self.agents.iter().map(new_opinion));
+    }
+
+    /// like `fill_new_opinions`, but the agents are visited in `order`, if it is sorted,
+    /// while the new opinions are stored at the indices of their agents
+    #[cfg(feature = "rayon")]
+    fn fill_new_opinions_in_order<F>(&self, buffer: &mut Vec<(Float, u32)>, new_opinion: F)
+    where
+        F: Fn(&HKAgent) -> (Float, u32) + Sync + Send,
+    {
+        if self.order.len() != self.agents.len() {
+            return self.fill_new_opinions(buffer, new_opinion);
+        }
+        let sorted: Vec<(Float, u32)> = self
+            .order
+            .par_iter()
+            .map(|&idx| new_opinion(&self.agents[idx as usize]))
+            .collect();
+        buffer.clear();
+        buffer.resize(self.agents.len(), (0., 0));
+        for (&idx, x) in self.order.iter().zip(sorted) {
+            buffer[idx as usize] = x;
+        }
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    fn fill_new_opinions_in_order<F>(&self, buffer: &mut Vec<(Float, u32)>, new_opinion: F)
+    where
+        F: Fn(&HKAgent) -> (Float, u32),
+    {
+        if self.order.len() != self.agents.len() {
+            return self.fill_new_opinions(buffer, new_opinion);
+        }
+        buffer.clear();
+        buffer.resize(self.agents.len(), (0., 0));
+        for &idx in &self.order {
+            buffer[idx as usize] = new_opinion(&self.agents[idx as usize]);
+        }
+    }
+
+    /// sort the agents by their opinions again, if `order` is outdated
+    fn refresh_order(&mut self) {
+        if !self.sorted_iteration {
+            self.order.clear();
+            return;
+        }
+        if self.order.len() == self.agents.len() && self.sweeps_since_sort < ORDER_REFRESH_INTERVAL
+        {
+            self.sweeps_since_sort += 1;
+            return;
+        }
+        let mut keyed: Vec<(Key, u32)> = self
+            .agents
+            .iter()
+            .enumerate()
+            .map(|(idx, i)| (to_key(i.opinion), idx as u32))
+            .collect();
+        keyed.sort_unstable();
+        self.order.clear();
+        self.order.extend(keyed.into_iter().map(|(_, idx)| idx));
+        self.sweeps_since_sort = 1;
+    }
+
+    /// move the buffer of the new opinions out of the struct for a sweep, which does not
+    /// calculate them like `sweep_tree`, such that a following sweep skipping unchanged
+    /// agents does not reuse them
+    fn take_new_opinions(&mut self) -> Vec<(Float, u32)> {
+        self.invalidate_tracking();
+        std::mem::take(&mut self.new_opinions)
+    }
+
+    // perform a sweep (update every agent) with the naive method
+    // with rotation groups, only the agents of the current group update
+    pub fn sweep_naive(&mut self) {
+        if self.rotation_groups > 1 {
+            let updated = self.next_rotation();
+            self.sweep_updating(&updated, false);
+            return;
+        }
+
+        // the buffer is moved out of the struct while the opinions are committed
+        let mut new_opinions = self.take_new_opinions();
+        self.sync_new_opinions_naive(&mut new_opinions);
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+    }
+
+    /// adapt the confidences given the number of agents every agent saw during
+    /// the last sweep (including itself)
+    fn adapt_confidences(&mut self, new_opinions: &[(Float, u32)]) {
+        self.adapt_confidences_of(new_opinions, None);
+    }
+
+    /// like `adapt_confidences`, but if `updated` is given, only for the agents `i`
+    /// with `updated[i]`
+    fn adapt_confidences_of(&mut self, new_opinions: &[(Float, u32)], updated: Option<&[bool]>) {
+        self.grow_isolated(new_opinions, updated);
+        let adaptive = match self.adaptive_confidence {
+            Some(adaptive) => adaptive,
+            None => return,
+        };
+        for (k, (i, &(_, count))) in self.agents.iter_mut().zip(new_opinions).enumerate() {
+            if updated.is_some_and(|u| !u[k]) {
+                continue;
+            }
+            let delta = if count > 1 {
+                adaptive.delta
+            } else {
+                -adaptive.delta
+            };
+            i.confidence_left = (i.confidence_left + delta).clamp(adaptive.min, adaptive.max);
+            i.confidence_right = (i.confidence_right + delta).clamp(adaptive.min, adaptive.max);
+        }
+    }
+
+    /// count the consecutive sweeps every agent saw nobody but itself and grow the
+    /// confidences of the agents isolated for long enough, whose counters start anew
+    /// if `updated` is given, only the agents `i` with `updated[i]` are considered
+    fn grow_isolated(&mut self, new_opinions: &[(Float, u32)], updated: Option<&[bool]>) {
+        let growth = match &mut self.isolation_growth {
+            Some(growth) => growth,
+            None => return,
+        };
+        for (k, (i, &(_, count))) in self.agents.iter_mut().zip(new_opinions).enumerate() {
+            if updated.is_some_and(|u| !u[k]) {
+                continue;
+            }
+            let counter = &mut growth.counters[k];
+            if count > 1 {
+                *counter = 0;
+                continue;
+            }
+            *counter += 1;
+            if *counter >= growth.sweeps {
+                *counter = 0;
+                // confidences already beyond the bound are not reduced
+                let grow = |c: Float| c.max((c * growth.factor).min(growth.max));
+                i.confidence_left = grow(i.confidence_left);
+                i.confidence_right = grow(i.confidence_right);
+            }
+        }
+    }
+
+    /// move only the fraction `mu` of the way from the old to the new opinion
+    /// on the circle along the shorter arc
+    fn relax(&self, old_opinion: Float, new_opinion: Float) -> Float {
+        if self.mu == 1. {
+            return new_opinion;
+        }
+        self.topology.interpolate(old_opinion, new_opinion, self.mu)
+    }
+
+    /// decide whether agent `idx` updates its opinion now, according to its activity
+    /// fully active agents do not consume random numbers
+    fn is_active(&mut self, idx: usize) -> bool {
+        let activity = self.agents[idx].activity;
+        activity >= 1. || self.rng.gen::<Float>() < activity
+    }
+
+    /// add the new opinion to the memory of the agent and return its effective opinion,
+    /// i.e., the mean of its remembered opinions
+    fn remember(&mut self, idx: usize, new_opinion: Float) -> Float {
+        if self.memory == 1 {
+            return new_opinion;
+        }
+
+        let history = &mut self.histories[idx];
+        if history.len() == self.memory {
+            history.pop_front();
+        }
+        history.push_back(new_opinion);
+
+        match self.topology {
+            OpinionSpace::Line => history.iter().sum::<Float>() / history.len() as Float,
+            OpinionSpace::Circle => {
+                let (cos, sin) = history
+                    .iter()
+                    .map(|&x| unit_vector(x))
+                    .fold((0., 0.), |(cos, sin), (c, s)| (cos + c, sin + s));
+                circular_mean(cos, sin)
+            }
+        }
+    }
+
+    /// move the fraction `mu` of the distance to the mean of the neighbors away from it
+    /// the result may leave the opinion space, see `apply_boundary`
+    fn oppose(&self, old_opinion: Float, mean: Float, mu: Float) -> Float {
+        self.topology.interpolate(old_opinion, mean, -mu)
+    }
+
+    /// pull the new opinion of agent `idx` the fraction `g_i` of the way back to its
+    /// initial opinion, if the agents are anchored
+    fn anchor(&self, idx: usize, new_opinion: Float) -> Float {
+        match &self.anchoring {
+            Some(susceptibilities) => {
+                self.topology
+                    .interpolate(new_opinion, self.anchors[idx], susceptibilities[idx])
+            }
+            None => new_opinion,
+        }
+    }
+
+    /// pull the new opinion of truth seekers the fraction `strength` of the way to the truth
+    fn seek_truth(&self, idx: usize, new_opinion: Float) -> Float {
+        match &self.truth {
+            Some(truth) if truth.seekers[idx] => {
+                self.topology
+                    .interpolate(new_opinion, truth.value, truth.strength)
+            }
+            _ => new_opinion,
+        }
+    }
+
+    /// apply the extensions of the update rule, which act on top of the
+    /// average over the neighbors, e.g., noise
+    /// the result may leave the opinion space, see `apply_boundary`
+    fn finalize_opinion(&mut self, new_opinion: Float) -> Float {
+        if self.noise > 0. {
+            new_opinion + self.rng.gen_range(-self.noise, self.noise)
+        } else {
+            new_opinion
+        }
+    }
+
+    /// map an opinion back into the opinion space according to the boundary condition,
+    /// every new opinion passes through here before it is written into the tree
+    fn apply_boundary(&self, x: Float) -> Float {
+        self.unit_to_opinion(self.boundary.apply(self.opinion_to_unit(x)))
+    }
+
+    /// set the opinion of agent `idx` to its new value and account for the change
+    /// if `update_tree` is set, the tree is kept consistent with the agents
+    /// zealots are skipped, they keep their opinion (and their entry in the tree)
+    /// as are agents whose opinion would change by no more than the update threshold,
+    /// though their memory already records the rejected opinion
+    fn commit_opinion(&mut self, idx: usize, new_opinion: Float, update_tree: bool) {
+        if self.agents[idx].zealot || !self.is_active(idx) {
+            return;
+        }
+
+        let old_opinion = self.agents[idx].opinion;
+        let new_opinion = match &self.contrarians {
+            Some(contrarians) if contrarians.agents[idx] => {
+                self.oppose(old_opinion, new_opinion, contrarians.mu)
+            }
+            _ => {
+                let new_opinion = self.anchor(idx, new_opinion);
+                let new_opinion = self.seek_truth(idx, new_opinion);
+                self.relax(old_opinion, new_opinion)
+            }
+        };
+        // exposure to the media replaces the usual update
+        let new_opinion = self.expose(old_opinion).unwrap_or(new_opinion);
+        // a jump replaces the result of the update including noise and drift
+        let (undrifted, new_opinion) = match self.jump() {
+            Some(x) => (x, x),
+            None => {
+                let undrifted = self.finalize_opinion(new_opinion);
+                (undrifted, self.apply_boundary(undrifted + self.drift))
+            }
+        };
+        let new_opinion = self.remember(idx, new_opinion);
+        let new_opinion = self.quantize(new_opinion);
+        if self.update_threshold > 0.
+            && self.topology.distance(old_opinion, new_opinion) <= self.update_threshold
+        {
+            return;
+        }
+        if update_tree {
+            if let Err(e) = self.update_entry(old_opinion, new_opinion, self.agents[idx].mass()) {
+                self.record_tree_error(e);
+            }
+        } else {
+            self.invalidate_tracking();
+            self.tree_outdated = true;
+        }
+
+        let change = if self.drift_corrected_change {
+            (old_opinion - self.apply_boundary(undrifted)).abs()
+        } else {
+            (old_opinion - new_opinion).abs()
+        };
+        self.add_change(change);
+
+        self.agents[idx].opinion = new_opinion;
+    }
+
+    /// reset the total and the maximum change at the start of a sweep
+    fn reset_change(&mut self) {
+        self.accumulated_change = 0.;
+        self.max_change = 0.;
+    }
+
+    /// add the change of one opinion to `accumulated_change` and `max_change`
+    fn add_change(&mut self, change: Float) {
+        if change > self.max_change {
+            self.max_change = change;
+        }
+        if !self.compensated_summation {
+            self.accumulated_change += change;
+            return;
+        }
+        // the sweeps reset `accumulated_change` to zero, which it can only be without
+        // a rounding error, since all changes are non-negative
+        if self.accumulated_change == 0. {
+            self.change_compensation = 0.;
+        }
+        let mut sum = Sum {
+            value: self.accumulated_change,
+            compensation: self.change_compensation,
+            compensated: true,
+        };
+        sum.add(change);
+        self.accumulated_change = sum.value;
+        self.change_compensation = sum.compensation;
+    }
+
+    // we use float comparision to test if an entry did change during an iteration for performance
+    // false negatives do not lead to wrong results
+    #[allow(clippy::float_cmp)]
+    /// update the internal datastructure in case, any opinion was updated
+    fn update_entry(
+        &mut self,
+        old_opinion: Float,
+        new_opinion: Float,
+        weight: Float,
+    ) -> Result<(), HkError> {
+        // often, nothing changes -> optimize for this converged case
+        if old_opinion == new_opinion {
+            return Ok(());
+        }
+
+        // if something changes, we have to update the tree
+        // the cumulative sums follow every change of the tree
+        remove_entry(&mut self.opinion_set, old_opinion, weight)?;
+        self.opinion_sums.remove(old_opinion, weight);
+        insert_entry(&mut self.opinion_set, new_opinion, weight);
+        self.opinion_sums.insert(new_opinion, weight);
+        self.record_change(old_opinion, new_opinion);
+        Ok(())
+    }
+
+    /// remember the first error of the maintenance of the trees during a sweep, which
+    /// `try_sweep` returns, the tree is rebuilt before it is used again
+    fn record_tree_error(&mut self, error: HkError) {
+        self.tree_outdated = true;
+        if self.tree_error.is_none() {
+            self.tree_error = Some(error);
+        }
+    }
+
+    /// weighted sum, total weight and number of all opinions in the tree within [lower, upper]
+    /// as seen by agent `i`, i.e., weighted by the kernel
+    fn range_sum(&self, i: &HKAgent, lower: Float, upper: Float) -> (Float, Float, u32) {
+        let (sum, weight, count) = self
+            .opinion_set
+            // this method traverses the tree starting from lower up to upper, the ascending
+            // order of the keys fixes the order of the summation and thus the rounding
+            .range(to_key(lower)..=to_key(upper))
+            // from_key converts a key back into a Float
+            .map(|(&x, entry)| (from_key(x), entry))
+            .fold(
+                (
+                    Sum::new(self.compensated_summation),
+                    Sum::new(self.compensated_summation),
+                    0,
+                ),
+                |(mut sum, mut weight, count), (x, entry)| {
+                    let w = i.influence(self.kernel, OpinionSpace::Line, x) * entry.weight as Float;
+                    sum.add(w * x);
+                    weight.add(w);
+                    (sum, weight, count + entry.count)
+                },
+            );
+        (sum.value, weight.value, count)
+    }
+
+    /// calculate all new opinions using the naive method, where every other agent is only
+    /// considered with probability `p`, independently for every agent and sweep
+    /// the previous content of `new_opinions` is replaced
+    fn sync_new_opinions_naive_diluted(&mut self, p: Float, new_opinions: &mut Vec<(Float, u32)>) {
+        let n = self.agents.len();
+        let mut considered = vec![true; n];
+        new_opinions.clear();
+        new_opinions.extend((0..n).map(|idx| {
+            // an agent always considers itself
+            for (k, c) in considered.iter_mut().enumerate() {
+                *c = k == idx || self.rng.gen::<Float>() < p;
+            }
+            self.new_opinion_naive_among(&self.agents[idx], Some(&considered), None)
+        }));
+    }
+
+    // perform a sweep with the naive method, where every potential neighbor is only
+    // considered with probability `p`
+    // the dilution can not be applied within the range queries of the tree
+    pub fn sweep_naive_diluted(&mut self, p: Float) {
+        if p >= 1. {
+            self.sweep_naive();
+            return;
+        }
+
+        let mut new_opinions = self.take_new_opinions();
+        self.sync_new_opinions_naive_diluted(p, &mut new_opinions);
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+    }
+
+    /// calculate all new opinions using the naive method, where every agent perceives the
+    /// opinion of every other agent with an independent uniform error from [-sigma, sigma],
+    /// perceived opinions stay within the opinion space
+    /// the errors are drawn in the fixed order of the pairs (i, j), for `sigma = 0` no random
+    /// numbers are consumed
+    /// the previous content of `new_opinions` is replaced
+    fn sync_new_opinions_naive_noisy_perception(
+        &mut self,
+        sigma: Float,
+        new_opinions: &mut Vec<(Float, u32)>,
+    ) {
+        let n = self.agents.len();
+        let mut perceived: Vec<Float> = self.agents.iter().map(|j| j.opinion).collect();
+        new_opinions.clear();
+        for idx in 0..n {
+            for (k, x) in perceived.iter_mut().enumerate() {
+                let opinion = self.agents[k].opinion;
+                // every agent knows its own opinion exactly
+                *x = if k == idx || sigma == 0. {
+                    opinion
+                } else {
+                    let noisy = opinion + self.rng.gen_range(-sigma, sigma);
+                    self.apply_boundary(noisy)
+                };
+            }
+            new_opinions.push(self.new_opinion_naive_among(
+                &self.agents[idx],
+                None,
+                Some(&perceived),
+            ));
+        }
+    }
+
+    // perform a sweep (update every agent) with the naive method, where agents perceive
+    // the opinions of the others with noise of amplitude `sigma`
+    // the tree can not be used, since every agent sees a differently perturbed set of opinions
+    pub fn sweep_naive_noisy_perception(&mut self, sigma: Float) {
+        let mut new_opinions = self.take_new_opinions();
+        self.sync_new_opinions_naive_noisy_perception(sigma, &mut new_opinions);
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+    }
+
+    /// calculate the new opinion of a single agent, whose confidence towards every other
+    /// agent is given by the matrix of inter-group confidences, using the naive method
+    /// together with the number of agents it takes into account (including itself)
+    fn new_opinion_groups_naive(&self, i: &HKAgent) -> (Float, u32) {
+        let groups = self
+            .groups
+            .as_ref()
+            .expect("sweep_groups needs groups, see set_groups");
+        let confidences = &groups.matrix[i.group as usize];
+        let mut sum = 0.;
+        let mut weight = 0.;
+        let mut count = 0;
+        for j in self.agents.iter() {
+            let e = confidences[j.group as usize];
+            if i.accepts_within(OpinionSpace::Line, j.opinion, e, e) {
+                sum += j.mass() * j.opinion;
+                weight += j.mass();
+                count += 1;
+            }
+        }
+
+        (self.neighborhood_mean(i, sum, weight), count)
+    }
+
+    /// like `new_opinion_groups_naive`, but with one range query in the tree of every group
+    fn new_opinion_groups_tree(&self, i: &HKAgent) -> (Float, u32) {
+        let groups = self
+            .groups
+            .as_ref()
+            .expect("sweep_groups_tree needs groups, see set_groups");
+        let confidences = &groups.matrix[i.group as usize];
+        let mut sum = 0.;
+        let mut weight = 0.;
+        let mut count = 0;
+        for (tree, &e) in groups.trees.iter().zip(confidences) {
+            let range = tree.range(to_key(i.opinion - e)..=to_key(i.opinion + e));
+            for (&x, entry) in range {
+                sum += entry.weight as Float * from_key(x);
+                weight += entry.weight as Float;
+                count += entry.count;
+            }
+        }
+
+        (self.neighborhood_mean(i, sum, weight), count)
+    }
+
+    // perform a sweep (update every agent) with the naive method, where the confidences
+    // are given by the matrix of inter-group confidences
+    pub fn sweep_groups(&mut self) {
+        let mut new_opinions = self.take_new_opinions();
+        new_opinions.clear();
+        new_opinions.extend(self.agents.iter().map(|i| self.new_opinion_groups_naive(i)));
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+    }
+
+    // perform a sweep (update every agent) with one tree per group, where the confidences
+    // are given by the matrix of inter-group confidences
+    // only the trees of the groups are maintained, not the tree of all opinions
+    pub fn sweep_groups_tree(&mut self) {
+        let mut new_opinions = self.take_new_opinions();
+        new_opinions.clear();
+        new_opinions.extend(self.agents.iter().map(|i| self.new_opinion_groups_tree(i)));
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            let old_opinion = self.agents[i].opinion;
+            self.commit_opinion(i, new_opinion, false);
+            let agent = &self.agents[i];
+            #[allow(clippy::float_cmp)]
+            if agent.opinion != old_opinion {
+                let groups = self.groups.as_mut().unwrap();
+                let tree = &mut groups.trees[agent.group as usize];
+                let removed = remove_entry(tree, old_opinion, agent.mass());
+                insert_entry(tree, agent.opinion, agent.mass());
+                if let Err(e) = removed {
+                    self.record_tree_error(e);
+                }
+            }
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+        self.refresh_tree();
+    }
+
+    /// calculate the new opinion of a single agent using the improved method using the tree
+    /// together with the number of agents within its confidence interval (including itself)
+    /// the sums over the confidence interval are taken from the cumulative sums, which
+    /// are maintained together with the tree, if possible
+    fn new_opinion_tree(&self, i: &HKAgent) -> (Float, u32) {
+        if self.has_prefix_sums() {
+            self.new_opinion_tree_with(i, Some(&self.opinion_sums))
+        } else {
+            self.new_opinion_tree_with(i, None)
+        }
+    }
+
+    /// whether cumulative sums can replace the walk through the confidence interval,
+    /// which is the case for the hard kernel on the line
+    fn has_prefix_sums(&self) -> bool {
+        self.topology == OpinionSpace::Line && self.kernel == Kernel::Hard
+    }
+
+    /// cumulative sums of the current tree, if they can replace the walk through the
+    /// confidence interval
+    /// synchronous sweeps build them once, since they are summed up in ascending order
+    /// like the sorted arrays, such that both methods agree bitwise
+    fn prefix_sums(&self) -> Option<PrefixSums> {
+        if self.has_prefix_sums() {
+            Some(PrefixSums::from_tree(&self.opinion_set))
+        } else {
+            None
+        }
+    }
+
+    /// like `new_opinion_tree`, but the sums over the confidence interval are taken from
+    /// the cumulative sums `prefix` of the tree, if given
+    fn new_opinion_tree_with(
+        &self,
+        i: &HKAgent,
+        prefix: Option<&dyn CumulativeSums>,
+    ) -> (Float, u32) {
+        if self.topology == OpinionSpace::Circle {
+            return self.new_opinion_circle_tree(i);
+        }
+
+        let (left, right) = i.reach(self.kernel);
+        let (sum, weight, count) = match prefix {
+            Some(p) => p.range_sum(i.opinion - left, i.opinion + right),
+            None => self.range_sum(i, i.opinion - left, i.opinion + right),
+        };
+        let mean = if self.aggregator == Aggregator::Mean {
+            self.neighborhood_mean(i, sum, weight)
+        } else if let Aggregator::TrimmedMean { alpha } = self.aggregator {
+            self.trimmed_mean_tree(i, i.opinion - left, i.opinion + right, alpha)
+        } else {
+            // the tree yields the neighbors in ascending order
+            let neighbors: Vec<(Float, Float)> = self
+                .opinion_set
+                .range(to_key(i.opinion - left)..=to_key(i.opinion + right))
+                .map(|(&x, entry)| {
+                    let x = from_key(x);
+                    (
+                        x,
+                        i.influence(self.kernel, OpinionSpace::Line, x) * entry.weight as Float,
+                    )
+                })
+                .collect();
+            self.aggregator.aggregate(&neighbors)
+        };
+
+        match self.repulsion {
+            Some(r) => {
+                // the two bands beyond the confidence interval on both sides
+                let (sum_below, weight_below) = if r.range > left {
+                    self.repelling_sum(Included(i.opinion - r.range), Excluded(i.opinion - left))
+                } else {
+                    (0., 0.)
+                };
+                let (sum_above, weight_above) = if r.range > right {
+                    self.repelling_sum(Excluded(i.opinion + right), Included(i.opinion + r.range))
+                } else {
+                    (0., 0.)
+                };
+                let x = self.repel(i, mean, sum_below + sum_above, weight_below + weight_above);
+                (x, count)
+            }
+            None => (mean, count),
+        }
+    }
+
+    /// mean of all opinions in the tree within [lower, upper] as seen by agent `i`, after
+    /// discarding the fraction `alpha` of their total weight at both ends
+    /// the range is walked from both ends, such that no sorting is necessary, the total
+    /// weight is summed up from the same weights, since cumulative sums differ by
+    /// rounding errors, which could trim more than the whole range for `alpha` near 0.5
+    fn trimmed_mean_tree(&self, i: &HKAgent, lower: Float, upper: Float, alpha: Float) -> Float {
+        let entries: Vec<(Float, Float)> = self
+            .opinion_set
+            .range(to_key(lower)..=to_key(upper))
+            .map(|(&x, entry)| {
+                let x = from_key(x);
+                (
+                    x,
+                    i.influence(self.kernel, OpinionSpace::Line, x) * entry.weight as Float,
+                )
+            })
+            .collect();
+        let total: Float = entries.iter().map(|&(_, w)| w).sum();
+        let mut entries = entries.into_iter();
+
+        // skip the trimmed weight from below, the first kept entry may be cut partially
+        let mut cut = alpha * total;
+        let mut first = None;
+        for (x, w) in entries.by_ref() {
+            if w > cut {
+                first = Some((x, w - cut));
+                break;
+            }
+            cut -= w;
+        }
+        // and from above
+        let mut cut = alpha * total;
+        let mut last = None;
+        for (x, w) in entries.by_ref().rev() {
+            if w > cut {
+                last = Some((x, w - cut));
+                break;
+            }
+            cut -= w;
+        }
+        // if both ends meet within one entry, it is cut from both sides
+        if last.is_none() {
+            first = first.map(|(x, w)| (x, w - cut));
+        }
+
+        let (sum, weight) = entries
+            .chain(first)
+            .chain(last)
+            .fold((0., 0.), |(sum, weight), (x, w)| (sum + w * x, weight + w));
+        // nothing is left, if rounding errors cut away the last bit of the range
+        if weight > 0. {
+            sum / weight
+        } else {
+            i.opinion
+        }
+    }
+
+    /// weighted sum and total weight of all opinions in the tree between the bounds
+    fn repelling_sum(&self, lower: Bound<Float>, upper: Bound<Float>) -> (Float, Float) {
+        let ordered = |b: Bound<Float>| match b {
+            Included(x) => Included(to_key(x)),
+            Excluded(x) => Excluded(to_key(x)),
+            Unbounded => Unbounded,
+        };
+        self.opinion_set
+            .range((ordered(lower), ordered(upper)))
+            .fold((0., 0.), |(sum, weight), (&x, entry)| {
+                (
+                    sum + entry.weight as Float * from_key(x),
+                    weight + entry.weight as Float,
+                )
+            })
+    }
+
+    /// weighted sum of the unit vectors and number of all opinions in the tree within
+    /// [lower, upper] as seen by agent `i`
+    fn range_vector_sum(&self, i: &HKAgent, lower: Float, upper: Float) -> (Float, Float, u32) {
+        self.opinion_set.range(to_key(lower)..=to_key(upper)).fold(
+            (0., 0., 0),
+            |(cos, sin, count), (&x, entry)| {
+                let x = from_key(x);
+                let w = i.influence(self.kernel, OpinionSpace::Circle, x) * entry.weight as Float;
+                let (c, s) = unit_vector(x);
+                (cos + w * c, sin + w * s, count + entry.count)
+            },
+        )
+    }
+
+    /// calculate the new opinion of a single agent on the circle using the tree
+    /// if the confidence interval wraps around, it is split into two range queries
+    fn new_opinion_circle_tree(&self, i: &HKAgent) -> (Float, u32) {
+        let (left, right) = i.reach(self.kernel);
+        let lower = i.opinion - left;
+        let upper = i.opinion + right;
+
+        let (cos, sin, count) = if left + right >= 1. {
+            // everybody is a neighbor
+            self.range_vector_sum(i, 0., 1.)
+        } else {
+            let (cos, sin, count) = self.range_vector_sum(i, lower.max(0.), upper.min(1.));
+            let (c, s, n) = if lower < 0. {
+                self.range_vector_sum(i, lower + 1., 1.)
+            } else if upper > 1. {
+                self.range_vector_sum(i, 0., upper - 1.)
+            } else {
+                (0., 0., 0)
+            };
+            (cos + c, sin + s, count + n)
+        };
+
+        let (cos, sin) = self.adjust_vector_sum(i, cos, sin);
+        (self.circular_mean_of(i, cos, sin), count)
+    }
+
+    /// calculate all new opinions using the improved method using the tree
+    /// together with the number of agents within the confidence interval of every agent
+    /// every agent needs two binary searches in the cumulative sums of the tree, if they
+    /// are available, instead of a walk through its whole confidence interval
+    /// the previous content of `new_opinions` is replaced
+    /// without cumulative sums, the queries walk through the tree, but once clusters
+    /// formed, many agents share their opinion and confidences, such that their identical
+    /// queries are only answered once
+    /// the cumulative sums answer a query faster than it could be looked up
+    fn sync_new_opinions_tree(&self, new_opinions: &mut Vec<(Float, u32)>) {
+        match self.prefix_sums() {
+            Some(prefix) if self.coarsens() => self.coarse_new_opinions(&prefix, new_opinions),
+            None if self.opinion_set.len() * 2 < self.agents.len() => {
+                let mut known = HashMap::new();
+                new_opinions.clear();
+                new_opinions.extend(self.agents.iter().map(|i| {
+                    *known
+                        .entry(i.update_key())
+                        .or_insert_with(|| self.new_opinion_tree_with(i, None))
+                }));
+            }
+            prefix => self.fill_new_opinions_in_order(new_opinions, |i| {
+                self.new_opinion_tree_with(i, prefix.as_ref().map(|p| p as _))
+            }),
+        }
+    }
+
+    /// new opinions of all agents, which are calculated once per group of agents with the
+    /// same opinion, weight and media in sight, whose confidence intervals contain the same
+    /// entries of `prefix`, since they arrive at the same new opinion
+    /// the previous content of `new_opinions` is replaced
+    fn coarse_new_opinions(
+        &self,
+        prefix: &dyn CumulativeSums,
+        new_opinions: &mut Vec<(Float, u32)>,
+    ) {
+        let mut groups = HashMap::new();
+        new_opinions.clear();
+        new_opinions.extend(self.agents.iter().map(|i| {
+            let (left, right) = i.reach(self.kernel);
+            let (a, b) = prefix.bounds(i.opinion - left, i.opinion + right);
+            let media = self
+                .media
+                .is_some_and(|m| i.sees(self.kernel, self.topology, m.opinion));
+            *groups
+                .entry((to_key(i.opinion), to_key(i.mass()), a, b, media))
+                .or_insert_with(|| self.new_opinion_tree_with(i, Some(prefix)))
+        }));
+    }
+
+    /// like `sync_new_opinions_tree`, but `new_opinions` still holds the new opinions of the
+    /// last synchronous tree sweep, which are kept for every agent, whose key did not
+    /// change and whose reach does not contain any opinion changed since, as long as
+    /// `tracking` is valid, afterwards `tracking` refers to this sweep
+    fn sync_new_opinions_tracked(
+        &self,
+        new_opinions: &mut Vec<(Float, u32)>,
+        tracking: &mut ChangeTracking,
+    ) {
+        let n = self.agents.len();
+        let reuse = tracking.valid
+            && tracking.keys.len() == n
+            && new_opinions.len() == n
+            && tracking.media == self.media
+            && self.topology == OpinionSpace::Line;
+        if !reuse {
+            self.sync_new_opinions_tree(new_opinions);
+            tracking.keys = self.agents.iter().map(|i| i.update_key()).collect();
+        } else {
+            tracking.changed.sort_unstable_by_key(|&x| OrderedFloat(x));
+            // repelling agents beyond the confidence matter as well
+            let range = self.repulsion.map_or(0., |r| r.range);
+            let prefix = self.prefix_sums();
+            for (idx, i) in self.agents.iter().enumerate() {
+                let key = i.update_key();
+                let (left, right) = i.reach(self.kernel);
+                let (lower, upper) = (i.opinion - left.max(range), i.opinion + right.max(range));
+                if tracking.keys[idx] != key || tracking.touches(lower, upper) {
+                    new_opinions[idx] =
+                        self.new_opinion_tree_with(i, prefix.as_ref().map(|p| p as _));
+                    tracking.keys[idx] = key;
+                }
+            }
+        }
+        tracking.changed.clear();
+        tracking.media = self.media;
+        tracking.valid = true;
+    }
+
+    // perform a sweep (update every agent) with the tree-based method
+    // with rotation groups, only the agents of the current group update
+    pub fn sweep_tree(&mut self) {
+        self.rebuild_outdated_tree();
+        if self.rotation_groups > 1 {
+            let updated = self.next_rotation();
+            self.sweep_updating(&updated, true);
+            return;
+        }
+
+        self.refresh_order();
+        let mut new_opinions = std::mem::take(&mut self.new_opinions);
+        match self.change_tracking.take() {
+            Some(mut tracking) => {
+                self.sync_new_opinions_tracked(&mut new_opinions, &mut tracking);
+                self.change_tracking = Some(tracking);
+            }
+            None => self.sync_new_opinions_tree(&mut new_opinions),
+        }
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, true);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+        self.refresh_tree();
+    }
+
+    /// whether the sorted arrays of the opinions can answer all queries of a sweep, which
+    /// is the case for the plain mean with the hard kernel on the line
+    fn uses_sorted_arrays(&self) -> bool {
+        self.topology == OpinionSpace::Line
+            && self.rotation_groups == 1
+            && self.kernel == Kernel::Hard
+            && self.repulsion.is_none()
+            && self.aggregator == Aggregator::Mean
+    }
+
+    /// whether `sweep` uses the sorted arrays, the automatic choice prefers them wherever
+    /// they suffice, since they were at least as fast as the tree for all benchmarked numbers
+    /// of agents and confidences, unless the tree is needed to skip unchanged agents
+    fn uses_sorted_backend(&self) -> bool {
+        match self.backend {
+            Backend::Tree => false,
+            Backend::Sorted => true,
+            Backend::Auto => self.uses_sorted_arrays() && self.change_tracking.is_none(),
+        }
+    }
+
+    // perform a sweep (update every agent) with sorted arrays of the opinions and their
+    // cumulative sums, which are built from the agents at the start of the sweep
+    // like the naive method, the tree is not maintained
+    // where the arrays do not suffice, this is `sweep_naive`
+    pub fn sweep_sorted(&mut self) {
+        if !self.uses_sorted_arrays() {
+            self.sweep_naive();
+            return;
+        }
+
+        self.refresh_order();
+        // both buffers are moved out of the struct during the sweep
+        let mut prefix = std::mem::take(&mut self.sorted_sums);
+        prefix.fill_from_agents(&self.agents);
+        let mut new_opinions = self.take_new_opinions();
+        if self.coarsens() {
+            self.coarse_new_opinions(&prefix, &mut new_opinions);
+        } else {
+            self.fill_new_opinions_in_order(&mut new_opinions, |i| {
+                self.new_opinion_tree_with(i, Some(&prefix))
+            });
+        }
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+        self.sorted_sums = prefix;
+    }
+
+    /// whether the counts of the agents per grid value can replace the tree, which is
+    /// the case for quantized opinions on the line, as long as every agent counts once
+    /// and the hard kernel is used
+    fn uses_dense_levels(&self) -> bool {
+        self.quantization > 0
+            && self.topology == OpinionSpace::Line
+            && self.opinion_interval == (0., 1.)
+            && self.rotation_groups == 1
+            && self.kernel == Kernel::Hard
+            && self.weight_distribution == WeightDistribution::Unit
+            && self.leaders.is_empty()
+            && self.repulsion.is_none()
+            && self.aggregator == Aggregator::Mean
+    }
+
+    /// prefix sums of the number of agents and of their grid levels over the opinion grid,
+    /// entry `k` covers all grid values below the `k`-th one
+    fn level_prefix_sums(&self) -> (Vec<u32>, Vec<u64>) {
+        let levels = self.quantization as usize;
+        let spacing = (levels - 1) as Float;
+        let mut counts = vec![0u32; levels];
+        for i in self.agents.iter() {
+            counts[(i.opinion * spacing).round() as usize] += 1;
+        }
+
+        let mut count_prefix = vec![0; levels + 1];
+        let mut level_prefix = vec![0; levels + 1];
+        for (k, &c) in counts.iter().enumerate() {
+            count_prefix[k + 1] = count_prefix[k] + c;
+            level_prefix[k + 1] = level_prefix[k] + c as u64 * k as u64;
+        }
+        (count_prefix, level_prefix)
+    }
+
+    /// calculate the new opinion of a single agent from the prefix sums over the opinion
+    /// grid, the grid values within [x_i - left, x_i + right] are its neighbors like in
+    /// the tree-based method
+    fn new_opinion_dense(
+        &self,
+        i: &HKAgent,
+        count_prefix: &[u32],
+        level_prefix: &[u64],
+    ) -> (Float, u32) {
+        let last = self.quantization as usize - 1;
+        let spacing = last as Float;
+        let (left, right) = i.reach(self.kernel);
+        // the agent always sees itself, even if its bounds are rounded past its own level
+        let own = (i.opinion * spacing).round() as usize;
+        let lower = (((i.opinion - left) * spacing).ceil().max(0.) as usize).min(own);
+        let upper = (((i.opinion + right) * spacing).floor().max(0.) as usize).clamp(own, last);
+
+        let count = count_prefix[upper + 1] - count_prefix[lower];
+        let sum = (level_prefix[upper + 1] - level_prefix[lower]) as Float / spacing;
+        (self.neighborhood_mean(i, sum, count as Float), count)
+    }
+
+    // perform a sweep (update every agent) on quantized opinions, where the tree is
+    // replaced by prefix sums over the counts of the agents per grid value
+    // if those counts are not sufficient, e.g., for weighted agents, this is `sweep_tree`
+    pub fn sweep_quantized(&mut self) {
+        self.rebuild_outdated_tree();
+        if !self.uses_dense_levels() {
+            self.sweep_tree();
+            return;
+        }
+
+        let (count_prefix, level_prefix) = self.level_prefix_sums();
+        let mut new_opinions = self.take_new_opinions();
+        new_opinions.clear();
+        new_opinions.extend(
+            self.agents
+                .iter()
+                .map(|i| self.new_opinion_dense(i, &count_prefix, &level_prefix)),
+        );
+        self.reset_change();
+
+        // the tree has at most one entry per grid value, so keeping it is cheap
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, true);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+    }
+
+    /// calculate the new opinions of the agents marked in `updated` from the current
+    /// state of all agents, all other entries are left at the current opinion
+    fn partial_new_opinions(&self, updated: &[bool], tree: bool) -> Vec<(Float, u32)> {
+        let prefix = if tree { self.prefix_sums() } else { None };
+        self.agents
+            .iter()
+            .zip(updated)
+            .map(|(i, &u)| match (u, tree) {
+                (false, _) => (i.opinion, 0),
+                (true, false) => self.new_opinion_naive(i),
+                (true, true) => self.new_opinion_tree_with(i, prefix.as_ref().map(|p| p as _)),
+            })
+            .collect()
+    }
+
+    /// perform a partial sweep, in which only the randomly chosen `fraction` of the agents
+    /// updates synchronously, while all agents act as neighbors
+    fn sweep_partial_with(&mut self, fraction: Float, tree: bool) {
+        let updated = self.choose_agents(fraction.min(1.));
+        self.sweep_updating(&updated, tree);
+    }
 
-/// structure representing an agent
-#[derive(Clone, Debug)]
-struct HKAgent {
-    /// current opinion of the agent
-    opinion: f32,
-    /// idiosyncratic confidence of the agent
-    confidence: f32,
-}
+    /// perform a sweep, in which only the agents marked in `updated` update synchronously,
+    /// while all agents act as neighbors, only their changes are accumulated
+    fn sweep_updating(&mut self, updated: &[bool], tree: bool) {
+        let new_opinions = self.partial_new_opinions(updated, tree);
+        self.reset_change();
 
-impl HKAgent {
-    fn new(opinion: f32, confidence: f32) -> HKAgent {
-        HKAgent {
-            opinion,
-            confidence,
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            if updated[i] {
+                self.commit_opinion(i, new_opinion, tree);
+            }
+        }
+        self.adapt_confidences_of(&new_opinions, Some(updated));
+        if tree {
+            self.refresh_tree();
         }
     }
-}
 
-/// used for testing purposes
-impl PartialEq for HKAgent {
-    fn eq(&self, other: &HKAgent) -> bool {
-        (self.opinion - other.opinion).abs() < EPS
-            && (self.confidence - other.confidence).abs() < EPS
+    // perform a partial sweep with the naive method, where only a fraction `f` of the
+    // agents, drawn without replacement, updates its opinion
+    // for `f >= 1` this is `sweep_naive` and no random numbers are consumed
+    pub fn sweep_partial_naive(&mut self, f: Float) {
+        if f >= 1. {
+            self.sweep_naive();
+        } else {
+            self.sweep_partial_with(f, false);
+        }
     }
-}
-
-/// structure representing a realization of the HK model
-pub struct HegselmannKrause {
-    /// number of agents in the system
-    num_agents: u32,
-    /// vector of all agents constituting the system
-    agents: Vec<HKAgent>,
-    /// lower bound of the confidences of all agents
-    min_confidence: f32,
-    /// upper bound of the confidences of all agents
-    max_confidence: f32,
-
-    /// the tree structure used to efficiently update the system
-    opinion_set: BTreeMap<OrderedFloat<f32>, u32>,
-    /// total change of agents opinion during the last sweep
-    pub accumulated_change: f32,
 
-    /// we need many, good (but not crypto) random numbers
-    /// we will use here the pcg generator
-    rng: Pcg64,
-}
+    // perform a partial sweep with the tree-based method, where only a fraction `f` of the
+    // agents, drawn without replacement, updates its opinion
+    // for `f >= 1` this is `sweep_tree` and no random numbers are consumed
+    pub fn sweep_partial(&mut self, f: Float) {
+        self.rebuild_outdated_tree();
+        if f >= 1. {
+            self.sweep_tree();
+        } else {
+            self.sweep_partial_with(f, true);
+        }
+    }
 
-/// used for testing purposes
-impl PartialEq for HegselmannKrause {
-    fn eq(&self, other: &HegselmannKrause) -> bool {
-        self.agents == other.agents
+    /// draw the index of the next agent to update in a random sequential sweep
+    fn random_agent(&mut self) -> usize {
+        self.rng.gen_range(0, self.num_agents) as usize
     }
-}
 
-impl HegselmannKrause {
-    pub fn new(n: u32, min_confidence: f32, max_confidence: f32, seed: u64) -> HegselmannKrause {
-        let rng = Pcg64::seed_from_u64(seed);
-        let agents: Vec<HKAgent> = Vec::new();
+    // perform a random sequential sweep with the naive method
+    // `num_agents` times a random agent is chosen and updated, its new opinion
+    // is immediately visible to all following updates
+    pub fn sweep_async(&mut self) {
+        self.reset_change();
 
-        let opinion_set = BTreeMap::new();
+        for _ in 0..self.num_agents {
+            let idx = self.random_agent();
+            let (new_opinion, _) = self.new_opinion_naive(&self.agents[idx]);
+            self.commit_opinion(idx, new_opinion, false);
+        }
+    }
 
-        let mut hk = HegselmannKrause {
-            num_agents: n,
-            agents,
-            min_confidence,
-            max_confidence,
-            opinion_set,
-            accumulated_change: 0.,
-            rng,
-        };
+    // perform a random sequential sweep with the tree-based method
+    // the tree and its cumulative sums are updated after every single agent update, such
+    // that every update takes O(log n) with the hard kernel on the line
+    pub fn sweep_async_tree(&mut self) {
+        self.rebuild_outdated_tree();
+        self.reset_change();
 
-        hk.reset();
-        hk
+        for _ in 0..self.num_agents {
+            let idx = self.random_agent();
+            let (new_opinion, _) = self.new_opinion_tree(&self.agents[idx]);
+            self.commit_opinion(idx, new_opinion, true);
+        }
+        self.refresh_tree();
     }
 
-    /// reset the state of an HegselmannKrause struct
-    /// initialize the agents with random initial conditions
-    /// and prepare all internal datastructures
-    /// afterwards the object will be ready for a fresh simulation
-    pub fn reset(&mut self) {
-        /// helper function to scale a uniform[0,1] random number to a uniform[low, high]
-        fn scale(x: f32, low: f32, high: f32) -> f32 {
-            x * (high - low) + low
+    /// mean of the opinions `y`, each held by `c` agents, given as pairs `(y, c)`
+    /// on the circle this is the circular mean
+    fn mean_of_counted(&self, opinions: &[(Float, u32)]) -> Float {
+        match self.topology {
+            OpinionSpace::Line => {
+                let total: u32 = opinions.iter().map(|&(_, c)| c).sum();
+                opinions.iter().map(|&(y, c)| y * c as Float).sum::<Float>() / total as Float
+            }
+            OpinionSpace::Circle => {
+                let (cos, sin) = opinions
+                    .iter()
+                    .map(|&(y, c)| {
+                        let (cos, sin) = unit_vector(y);
+                        (cos * c as Float, sin * c as Float)
+                    })
+                    .fold((0., 0.), |(cos, sin), (c, s)| (cos + c, sin + s));
+                circular_mean(cos, sin)
+            }
         }
+    }
 
-        // initialize a vector of n agents with uniformly distributed opinions and confidences
-        self.agents = (0..self.num_agents)
-            .map(|_| {
-                HKAgent::new(
-                    self.rng.gen(),
-                    scale(self.rng.gen(), self.min_confidence, self.max_confidence),
-                )
+    /// mean of the `k` opinions nearest to the opinion of agent `i`, found by sorting all opinions
+    /// ties at equal distance are broken in favor of the opinion to the left, i.e., the smaller
+    /// one on the line and the one reached counterclockwise on the circle
+    /// if `within_confidence`, only opinions within the confidence interval of `i` (and all
+    /// equal to its own) are candidates, such that fewer than `k` may be averaged
+    fn new_opinion_knn_naive(&self, i: &HKAgent, k: usize, within_confidence: bool) -> Float {
+        let x = i.opinion;
+        let mut candidates: Vec<(Float, u8, Float)> = self
+            .agents
+            .iter()
+            .map(|j| {
+                let y = j.opinion;
+                let (left, right) = match self.topology {
+                    OpinionSpace::Line => (x - y, y - x),
+                    OpinionSpace::Circle => (wrap(x - y), wrap(y - x)),
+                };
+                if left >= 0. && (left <= right || right < 0.) {
+                    (left, 0, y)
+                } else {
+                    (right, 1, y)
+                }
+            })
+            .filter(|&(d, _, y)| {
+                !within_confidence || d <= 0. || i.sees(self.kernel, self.topology, y)
             })
             .collect();
+        candidates.sort_by(|a, b| (a.0, a.1).partial_cmp(&(b.0, b.1)).unwrap());
 
-        // initialize the tree of opinions with the initial conditions of the agents
-        self.opinion_set.clear();
-        for i in self.agents.iter() {
-            *self.opinion_set.entry(i.opinion.into()).or_insert(0) += 1;
+        let nearest: Vec<(Float, u32)> =
+            candidates.iter().take(k).map(|&(_, _, y)| (y, 1)).collect();
+        self.mean_of_counted(&nearest)
+    }
+
+    /// mean of the `k` opinions nearest to the opinion of agent `i`, found by walking
+    /// outwards from it through the tree, with the same tie-break as the naive method
+    /// every distinct opinion contributes as often as agents hold it
+    /// if `within_confidence`, the walk stops at the bounds of the confidence interval
+    fn new_opinion_knn_tree(&self, i: &HKAgent, k: usize, within_confidence: bool) -> Float {
+        let x = i.opinion;
+        let key = to_key(x);
+        let below = self.opinion_set.range(..key).rev();
+        let above = self.opinion_set.range((Excluded(key), Unbounded));
+        // on the circle the walks continue across the boundary, since k never exceeds
+        // the number of agents, they stop before taking any opinion twice
+        type Walk<'a> = Box<dyn Iterator<Item = (&'a Key, &'a OpinionEntry)> + 'a>;
+        let (left, right): (Walk, Walk) = match self.topology {
+            OpinionSpace::Line => (Box::new(below), Box::new(above)),
+            OpinionSpace::Circle => (
+                Box::new(below.chain(self.opinion_set.range((Excluded(key), Unbounded)).rev())),
+                Box::new(above.chain(self.opinion_set.range(..key))),
+            ),
+        };
+        let mut left = left.peekable();
+        let mut right = right.peekable();
+
+        let mut nearest: Vec<(Float, u32)> = Vec::new();
+        let own = self.opinion_set[&key].count.min(k as u32);
+        nearest.push((x, own));
+        let mut remaining = k as u32 - own;
+        let candidate =
+            |&y: &Key| !within_confidence || i.sees(self.kernel, self.topology, from_key(y));
+        while remaining > 0 {
+            let distance_left = left
+                .peek()
+                .filter(|(y, _)| candidate(y))
+                .map(|(&y, _)| match self.topology {
+                    OpinionSpace::Line => x - from_key(y),
+                    OpinionSpace::Circle => wrap(x - from_key(y)),
+                });
+            let distance_right =
+                right
+                    .peek()
+                    .filter(|(y, _)| candidate(y))
+                    .map(|(&y, _)| match self.topology {
+                        OpinionSpace::Line => from_key(y) - x,
+                        OpinionSpace::Circle => wrap(from_key(y) - x),
+                    });
+            let (y, entry) = match (distance_left, distance_right) {
+                (Some(l), Some(r)) if l <= r => left.next().unwrap(),
+                (Some(_), None) => left.next().unwrap(),
+                (_, Some(_)) => right.next().unwrap(),
+                (None, None) => {
+                    assert!(within_confidence, "fewer opinions than nearest neighbors");
+                    break;
+                }
+            };
+            let taken = entry.count.min(remaining);
+            nearest.push((from_key(*y), taken));
+            remaining -= taken;
         }
 
-        // assert that every agent has a corresponding opinion in the tree
-        assert!(self.opinion_set.iter().map(|(_, v)| v).sum::<u32>() == self.num_agents);
+        self.mean_of_counted(&nearest)
     }
 
-    /// calculate all new opinions using the naive method of iterating all agents
-    fn sync_new_opinions_naive(&self) -> Vec<f32> {
-        self.agents
+    // perform a sweep (update every agent) in which every agent averages over the `k`
+    // nearest opinions, found with the naive method, mainly as a reference for `sweep_knn`
+    pub fn sweep_knn_naive(&mut self, k: usize) {
+        assert!(k >= 1 && k <= self.num_agents as usize);
+        let mut new_opinions = std::mem::take(&mut self.nearest_opinions);
+        new_opinions.clear();
+        new_opinions.extend(
+            self.agents
+                .iter()
+                .map(|i| self.new_opinion_knn_naive(i, k, false)),
+        );
+        self.reset_change();
+
+        for (i, &new_opinion) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.nearest_opinions = new_opinions;
+    }
+
+    // perform a sweep (update every agent) in which every agent averages over the `k`
+    // nearest opinions, found by walking through the tree
+    pub fn sweep_knn(&mut self, k: usize) {
+        self.rebuild_outdated_tree();
+        assert!(k >= 1 && k <= self.num_agents as usize);
+        let mut new_opinions = std::mem::take(&mut self.nearest_opinions);
+        new_opinions.clear();
+        new_opinions.extend(
+            self.agents
+                .iter()
+                .map(|i| self.new_opinion_knn_tree(i, k, false)),
+        );
+        self.reset_change();
+
+        for (i, &new_opinion) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, true);
+        }
+        self.nearest_opinions = new_opinions;
+        self.refresh_tree();
+    }
+
+    /// mean of the opinion of agent `idx` and at most `c - 1` other agents drawn uniformly
+    /// without replacement from all within its confidence interval, if there are more
+    /// random numbers are only consumed if there are more than `c - 1` candidates
+    fn new_opinion_capped_random(&mut self, idx: usize, c: usize) -> Float {
+        let i = &self.agents[idx];
+        let others: Vec<Float> = self
+            .agents
             .iter()
-            .map(|i| {
-                let mut sum = 0.;
-                let mut count = 0;
-                for j in self
-                    .agents
+            .enumerate()
+            .filter(|&(k, j)| k != idx && i.sees(self.kernel, self.topology, j.opinion))
+            .map(|(_, j)| j.opinion)
+            .collect();
+
+        let mut chosen = vec![(i.opinion, 1)];
+        if others.len() < c {
+            chosen.extend(others.iter().map(|&y| (y, 1)));
+        } else {
+            chosen.extend(
+                rand::seq::index::sample(&mut self.rng, others.len(), c - 1)
+                    .into_iter()
+                    .map(|k| (others[k], 1)),
+            );
+        }
+        self.mean_of_counted(&chosen)
+    }
+
+    // perform a sweep (update every agent) in which every agent averages over at most `c`
+    // agents within its confidence interval, chosen according to `strategy` with the naive
+    // method, random neighbors are drawn from the random number generator of the model
+    pub fn sweep_capped_naive(&mut self, c: usize, strategy: CapStrategy) {
+        assert!(c >= 1);
+        let mut new_opinions = std::mem::take(&mut self.nearest_opinions);
+        new_opinions.clear();
+        match strategy {
+            CapStrategy::Closest => new_opinions.extend(
+                self.agents
                     .iter()
-                    .filter(|j| (i.opinion - j.opinion).abs() < i.confidence)
-                {
-                    sum += j.opinion;
-                    count += 1;
-                }
+                    .map(|i| self.new_opinion_knn_naive(i, c, true)),
+            ),
+            CapStrategy::Random => new_opinions
+                .extend((0..self.agents.len()).map(|idx| self.new_opinion_capped_random(idx, c))),
+        }
+        self.reset_change();
 
-                sum / count as f32
-            })
-            .collect()
+        for (i, &new_opinion) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.nearest_opinions = new_opinions;
     }
 
-    // perform a sweep (update every agent) with the naive method
-    pub fn sweep_naive(&mut self) {
-        let new_opinions = self.sync_new_opinions_naive();
-        self.accumulated_change = 0.;
+    // perform a sweep (update every agent) in which every agent averages over the at most
+    // `c` closest agents within its confidence interval, found by walking through the tree
+    pub fn sweep_capped(&mut self, c: usize) {
+        self.rebuild_outdated_tree();
+        assert!(c >= 1 && c <= self.num_agents as usize);
+        let mut new_opinions = std::mem::take(&mut self.nearest_opinions);
+        new_opinions.clear();
+        new_opinions.extend(
+            self.agents
+                .iter()
+                .map(|i| self.new_opinion_knn_tree(i, c, true)),
+        );
+        self.reset_change();
 
         for (i, &new_opinion) in new_opinions.iter().enumerate() {
-            self.accumulated_change += (self.agents[i].opinion - new_opinion).abs();
+            self.commit_opinion(i, new_opinion, true);
+        }
+        self.nearest_opinions = new_opinions;
+        self.refresh_tree();
+    }
 
-            self.agents[i].opinion = new_opinion;
+    /// calculate the new opinion of a single agent, which only sees its neighbors in the network
+    /// together with the number of agents it takes into account (including itself)
+    fn new_opinion_network(&self, idx: usize, neighbors: &[u32]) -> (Float, u32) {
+        let i = &self.agents[idx];
+        let mut sum = 0.;
+        let mut weight = 0.;
+        let mut count = 0;
+        // the agent itself is always included, at its position in the order of the
+        // naive method, such that a complete network yields identical sums
+        let position = neighbors.partition_point(|&j| (j as usize) < idx);
+        let (before, after) = neighbors.split_at(position);
+        for &j in before.iter().chain(&[idx as u32]).chain(after) {
+            let self_loop = j as usize == idx;
+            let j = &self.agents[j as usize];
+            if self_loop || i.sees(self.kernel, OpinionSpace::Line, j.opinion) {
+                let w = i.influence(self.kernel, OpinionSpace::Line, j.opinion) * j.mass();
+                sum += w * j.opinion;
+                weight += w;
+                count += 1;
+            }
         }
+
+        (self.neighborhood_mean(i, sum, weight), count)
     }
 
-    // we use float comparision to test if an entry did change during an iteration for performance
-    // false negatives do not lead to wrong results
-    #[allow(clippy::float_cmp)]
-    /// update the internal datastructure in case, any opinion was updated
-    fn update_entry(&mut self, old_opinion: f32, new_opinion: f32) {
-        // often, nothing changes -> optimize for this converged case
-        if old_opinion == new_opinion {
+    // perform a sweep (update every agent) restricted to the social network
+    // the tree is not used, since neighbors in opinion space are not neighbors in the network
+    pub fn sweep_network(&mut self) {
+        let mut new_opinions = self.take_new_opinions();
+        let network = self
+            .network
+            .as_ref()
+            .expect("sweep_network needs a model constructed with a network");
+        new_opinions.clear();
+        new_opinions.extend(
+            network
+                .iter()
+                .enumerate()
+                .map(|(idx, neighbors)| self.new_opinion_network(idx, neighbors)),
+        );
+        self.reset_change();
+
+        for (i, &(new_opinion, _)) in new_opinions.iter().enumerate() {
+            self.commit_opinion(i, new_opinion, false);
+        }
+        self.adapt_confidences(&new_opinions);
+        self.new_opinions = new_opinions;
+    }
+
+    /// replace `floor(turnover)` random agents and one more with the probability given by
+    /// the fractional part of `turnover`, keeping the tree consistent if `update_tree`
+    fn turn_over(&mut self, update_tree: bool) {
+        if self.turnover <= 0. {
             return;
         }
 
-        // if something changes, we have to update the tree
-        // decrease the counter of the old opinion and remove it, if the counter hits 0
-        *self
-            .opinion_set
-            .entry(old_opinion.into())
-            .or_insert_with(|| panic!("Removed opinion was not in the tree!")) -= 1;
-        if self.opinion_set[&old_opinion.into()] == 0 {
-            self.opinion_set.remove(&old_opinion.into());
+        let mut replacements = self.turnover.trunc() as u32;
+        if self.rng.gen::<Float>() < self.turnover.fract() {
+            replacements += 1;
+        }
+        for _ in 0..replacements {
+            let idx = self.random_agent();
+            let u = self.rng.gen();
+            let opinion = self.quantize(self.unit_to_opinion(u));
+            let (confidence, group) = self
+                .confidence_distribution
+                .sample_for_opinion(self.opinion_to_unit(opinion), &mut self.rng);
+            let confidence_right = match &self.confidence_distribution_right {
+                Some(distribution) => distribution.sample(&mut self.rng),
+                None => confidence,
+            };
+            let mut agent = HKAgent::asymmetric(opinion, confidence, confidence_right);
+            agent.group = group;
+            agent.weight = self.weight_distribution.sample(&mut self.rng);
+            agent.activity = self.activity_distribution.sample(&mut self.rng);
+            agent.zealot = self.agents[idx].zealot;
+            agent.multiplicity = self.agents[idx].multiplicity;
+            if let Some(groups) = &mut self.groups {
+                // the group is bound to the index, the trees of the groups are always updated,
+                // since only `sweep_groups_tree` is used with groups
+                let old = &self.agents[idx];
+                agent.group = old.group;
+                let tree = &mut groups.trees[old.group as usize];
+                let removed = remove_entry(tree, old.opinion, old.mass());
+                insert_entry(tree, agent.opinion, agent.mass());
+                if let Err(e) = removed {
+                    self.record_tree_error(e);
+                }
+            }
+            if self.memory > 1 {
+                let history = &mut self.histories[idx];
+                history.clear();
+                history.push_back(opinion);
+            }
+
+            if update_tree {
+                let old = &self.agents[idx];
+                let (old_opinion, old_weight) = (old.opinion, old.mass());
+                let removed = remove_entry(&mut self.opinion_set, old_opinion, old_weight);
+                self.opinion_sums.remove(old_opinion, old_weight);
+                insert_entry(&mut self.opinion_set, agent.opinion, agent.mass());
+                self.opinion_sums.insert(agent.opinion, agent.mass());
+                self.record_change(old_opinion, agent.opinion);
+                if let Err(e) = removed {
+                    self.record_tree_error(e);
+                }
+            } else {
+                self.invalidate_tracking();
+                self.tree_outdated = true;
+            }
+            self.initial_confidences[idx] = (confidence, confidence_right);
+            self.anchors[idx] = opinion;
+            if let Some(growth) = &mut self.isolation_growth {
+                growth.counters[idx] = 0;
+            }
+            self.agents[idx] = agent;
         }
-        // increase the counter of the new opinion or insert a new node for it
-        *self.opinion_set.entry(new_opinion.into()).or_insert(0) += 1;
     }
 
-    /// calculate all new opinions using the improved method using the tree
-    fn sync_new_opinions_tree(&self) -> Vec<f32> {
-        self.agents
-            .iter()
-            .map(|i| {
-                let (sum, count) = self
-                    .opinion_set
-                    // this method traverses the tree starting from i.opinion-i.confidence
-                    // up to i.opinion+i.confidence
-                    .range((
-                        Included(&OrderedFloat(i.opinion - i.confidence)),
-                        Included(&OrderedFloat(i.opinion + i.confidence)),
-                    ))
-                    // into_inner converts an `OrderedFloat` into a f32
-                    .map(|(x, ctr)| (x.into_inner(), ctr))
-                    .fold((0., 0), |(sum, count), (x, ctr)| {
-                        (sum + *ctr as f32 * x, count + ctr)
-                    });
+    /// perform a sweep with the fastest method supporting the configured dynamics
+    /// the tree is used, unless an extension prevents it: networks, diluted interactions
+    /// and perception noise change the set of neighbors per agent, such that a range
+    /// query over the globally shared tree of opinions does not yield them, e.g., with
+    /// perception noise every agent sees its own perturbed copy of all opinions
+    /// panics if the tree turns out to be inconsistent, see `try_sweep`
+    pub fn sweep(&mut self) {
+        if let Err(e) = self.try_sweep() {
+            panic!("{}", e);
+        }
+    }
+
+    /// like `sweep`, but an inconsistency between the tree and the agents is returned as
+    /// an error, after the sweep was finished without the tree, which is rebuilt before it
+    /// is used again
+    /// debug builds also compare the whole tree with the agents after every sweep, with the
+    /// `paranoid` feature every violated invariant panics, also in release builds
+    pub fn try_sweep(&mut self) -> Result<(), HkError> {
+        self.tree_error = None;
+        self.sweep_dispatch();
+        let sweep = self.sweeps;
+        let in_sweep = |e: HkError| match e {
+            HkError::InconsistentTree(reason) => {
+                HkError::InconsistentTree(format!("{} in sweep {}", reason, sweep))
+            }
+            e => e,
+        };
+        // a non-finite opinion is the likely cause of a broken tree, so it is reported first
+        if cfg!(debug_assertions) {
+            self.check_finite()?;
+        }
+        if let Some(e) = self.tree_error.take() {
+            return Err(in_sweep(e));
+        }
+        if cfg!(feature = "paranoid") {
+            if let Err(violation) = self.verify_invariants() {
+                panic!("invariant violated after sweep {}: {}", sweep, violation);
+            }
+        }
+        if cfg!(debug_assertions) {
+            self.verify_invariants()
+                .map_err(|v| in_sweep(HkError::from(v)))?;
+        }
+        Ok(())
+    }
+
+    /// the sweep of `sweep` without the checks of the tree
+    fn sweep_dispatch(&mut self) {
+        // every method resets the change itself, this covers all of them
+        self.reset_change();
+        self.redraw_confidences();
+        self.apply_confidence_schedule();
+        self.apply_confidence_function();
+        self.apply_media_schedule();
+        self.sweeps += 1;
+
+        // whether the chosen method keeps the tree consistent
+        let update_tree = if self.network.is_some() {
+            self.sweep_network();
+            false
+        } else if self.groups.is_some() {
+            self.sweep_groups_tree();
+            false
+        } else if let Some(k) = self.nearest_neighbors {
+            self.sweep_knn(k);
+            true
+        } else if let Some((c, strategy)) = self.neighbor_cap {
+            match strategy {
+                CapStrategy::Closest => self.sweep_capped(c),
+                CapStrategy::Random => self.sweep_capped_naive(c, strategy),
+            }
+            strategy == CapStrategy::Closest
+        } else if self.interaction_probability < 1. {
+            self.sweep_naive_diluted(self.interaction_probability);
+            false
+        } else if self.perception_noise > 0. {
+            self.sweep_naive_noisy_perception(self.perception_noise);
+            false
+        } else if self.update_fraction < 1. {
+            self.sweep_partial(self.update_fraction);
+            true
+        } else if self.uses_dense_levels() {
+            self.sweep_quantized();
+            true
+        } else if self.uses_sorted_backend() {
+            self.sweep_sorted();
+            false
+        } else {
+            // self.sweep_naive();
+            self.sweep_tree();
+            true
+        };
+        self.turn_over(update_tree);
+        if !update_tree {
+            self.tree_outdated = true;
+        }
+    }
+
+    /// iterator performing one sweep per step and yielding a `SweepReport`, e.g., the
+    /// sweeps until the total change drops below `threshold` are
+    /// `hk.sweeps().take_while(|r| r.accumulated_change >= threshold).count() + 1`
+    pub fn sweeps(&mut self) -> Sweeps<'_> {
+        Sweeps {
+            hk: self,
+            clusters: false,
+        }
+    }
+
+    /// total change of all opinions during the last sweep
+    pub fn last_change(&self) -> Float {
+        self.accumulated_change
+    }
+
+    /// largest change of a single opinion during the last sweep
+    pub fn last_max_change(&self) -> Float {
+        self.max_change
+    }
 
-                sum / count as f32
+    /// whether the total change of all opinions during the last sweep is below `threshold`
+    pub fn has_converged(&self, threshold: Float) -> bool {
+        self.accumulated_change < threshold
+    }
+
+    /// sweep until the `criterion` is met and return the number of sweeps, or `NotConverged`
+    /// if it is not met within `max_sweeps` sweeps, e.g., always for a threshold of 0
+    /// a system, which no sweep can change, e.g., a single agent, is converged from the
+    /// start and takes 0 sweeps, see `is_static`
+    /// panics if the tree turns out to be inconsistent, see `try_run_until_converged`
+    pub fn run_until_converged(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<usize, NotConverged> {
+        self.try_run_until_converged(criterion, max_sweeps)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// like `run_until_converged`, but returns the error of the first failed `try_sweep`
+    /// instead of panicking, the inner result tells whether the criterion was met
+    pub fn try_run_until_converged(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<Result<usize, NotConverged>, HkError> {
+        let (sweeps, converged) = self.sweep_until_converged(criterion, max_sweeps)?;
+        Ok(if converged {
+            Ok(sweeps)
+        } else {
+            Err(NotConverged {
+                sweeps,
+                last_change: self.accumulated_change,
             })
-            .collect()
+        })
     }
 
-    // perform a sweep (update every agent) with the tree-based method
-    pub fn sweep_tree(&mut self) {
-        let new_opinions = self.sync_new_opinions_tree();
-        self.accumulated_change = 0.;
+    /// sweep until the total change of all opinions drops below `threshold` or `max_sweeps`
+    /// sweeps are done and summarize the run, like `run_until_converged`
+    /// panics if the tree turns out to be inconsistent, see `try_run_to_convergence_with`
+    pub fn run_to_convergence(&mut self, threshold: Float, max_sweeps: usize) -> ConvergenceResult {
+        self.run_to_convergence_with(
+            ConvergenceCriterion::SumChange { threshold },
+            Some(max_sweeps),
+        )
+    }
 
-        for (i, &new_opinion) in new_opinions.iter().enumerate() {
-            let old_opinion = self.agents[i].opinion;
-            self.update_entry(old_opinion, new_opinion);
+    /// like `run_to_convergence`, but for any `criterion`
+    pub fn run_to_convergence_with(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> ConvergenceResult {
+        self.try_run_to_convergence_with(criterion, max_sweeps)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-            self.accumulated_change += (old_opinion - new_opinion).abs();
+    /// like `run_to_convergence_with`, but returns the error of the first failed `try_sweep`
+    /// instead of panicking
+    pub fn try_run_to_convergence_with(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<ConvergenceResult, HkError> {
+        let (sweeps, converged) = self.sweep_until_converged(criterion, max_sweeps)?;
+        Ok(ConvergenceResult::new(
+            sweeps,
+            converged,
+            &self.agents,
+            &self.list_clusters(),
+        ))
+    }
 
-            self.agents[i].opinion = new_opinion;
+    /// the number of sweeps until the `criterion` is met or `max_sweeps` sweeps are done,
+    /// and whether it is met
+    fn sweep_until_converged(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<(usize, bool), HkError> {
+        // decided before sweeping, such that 0 sweeps did not touch the state or the rng
+        if criterion.accepts_static() && self.is_static() {
+            return Ok((0, true));
         }
-    }
+        let mut check = ConvergenceCheck::new(criterion);
+        let mut sweeps = 0;
+        loop {
+            self.try_sweep()?;
+            sweeps += 1;
 
-    pub fn sweep(&mut self) {
-        // self.sweep_naive();
-        self.sweep_tree();
-    }
-
-    /// A cluster are agents whose distance is less than EPS
-    fn list_clusters(&self) -> Vec<Vec<HKAgent>> {
-        let mut clusters: Vec<Vec<HKAgent>> = Vec::new();
-        'agent: for i in &self.agents {
-            for c in &mut clusters {
-                if (i.opinion - c[0].opinion).abs() < EPS {
-                    c.push(i.clone());
-                    continue 'agent;
-                }
+            let converged = check.converged(self.accumulated_change, self.max_change, || {
+                self.cluster_sizes()
+            });
+            let capped = max_sweeps.is_some_and(|max| sweeps >= max);
+            if converged || capped {
+                return Ok((sweeps, converged));
             }
-            clusters.push(vec![i.clone(); 1])
         }
-        clusters
+    }
+
+    /// whether no sweep can change any opinion, since every agent only sees agents sharing
+    /// its opinion and nothing else moves or widens the view of the agents, e.g., for a
+    /// single agent or for agents out of reach of each other
+    /// in doubt, e.g., on the circle, the system is not static
+    pub fn is_static(&self) -> bool {
+        let undisturbed = self.noise == 0.
+            && self.drift == 0.
+            && self.jump_probability == 0.
+            && self.turnover == 0.
+            && self.perception_noise == 0.
+            && self.quantization == 0
+            && self.media.is_none()
+            && self.media_exposure.is_none()
+            && self.truth.is_none()
+            && self.anchoring.is_none()
+            && self.contrarians.is_none()
+            && self.repulsion.is_none()
+            && self.groups.is_none()
+            && self.nearest_neighbors.is_none()
+            && !self.annealed_confidences
+            && self.confidence_schedule == ConfidenceSchedule::Constant
+            && self.confidence_function == ConfidenceFunction::Constant
+            && self.adaptive_confidence.is_none()
+            && self.isolation_growth.is_none()
+            // remembered opinions pull even isolated agents back towards their past
+            && self.memory == 1;
+        undisturbed
+            && (self.agents.len() <= 1
+                || (self.topology == OpinionSpace::Line
+                    && sees_only_own_opinion(&self.agents, self.kernel)))
+    }
+
+    fn list_clusters(&self) -> Vec<Vec<usize>> {
+        list_clusters(&self.agents, self.topology, self.cluster_eps)
     }
 
     pub fn cluster_sizes(&self) -> Vec<usize> {
+        self.list_clusters().iter().map(|c| c.len()).collect()
+    }
+
+    /// indices of the agents of every cluster, in the same order as `cluster_sizes`
+    pub fn cluster_members(&self) -> Vec<Vec<usize>> {
+        self.list_clusters()
+    }
+
+    /// index of the cluster of the agent `agent` in the order of `cluster_sizes`
+    /// panics if there is no such agent
+    pub fn cluster_of(&self, agent: usize) -> usize {
+        assert!(agent < self.agents.len(), "there is no agent {}", agent);
+        self.list_clusters()
+            .iter()
+            .position(|c| c.contains(&agent))
+            .unwrap()
+    }
+
+    /// distance of every cluster to the truth, in the same order as `cluster_sizes`,
+    /// if a truth is set
+    pub fn cluster_truth_distances(&self) -> Option<Vec<Float>> {
+        self.truth.as_ref().map(|truth| {
+            self.list_clusters()
+                .iter()
+                .map(|c| {
+                    self.topology
+                        .distance(self.agents[c[0]].opinion, truth.value)
+                })
+                .collect()
+        })
+    }
+
+    /// write the distances of the clusters to the truth, if a truth is set
+    fn write_truth_distances<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        if let Some(distances) = self.cluster_truth_distances() {
+            writeln!(file, "# truth distances: {}", distances.iter().join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// write the current opinion of the media, if there is a media field
+    pub fn write_media_opinion<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        if let Some(media) = self.media {
+            writeln!(file, "# media: {}", media.opinion)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        self.write_truth_distances(file)?;
+        write_clusters(&self.agents, &self.list_clusters(), file)
+    }
+
+    /// number of agents of every group in every cluster, in the same order as `cluster_sizes`
+    pub fn cluster_composition(&self) -> Vec<Vec<usize>> {
+        let num_groups = self
+            .agents
+            .iter()
+            .map(|i| i.group as usize + 1)
+            .max()
+            .unwrap_or(0);
+        self.list_clusters()
+            .iter()
+            .map(|c| {
+                let mut composition = vec![0; num_groups];
+                for &i in c {
+                    composition[self.agents[i].group as usize] += 1;
+                }
+                composition
+            })
+            .collect()
+    }
+
+    /// number of extremists in every cluster, in the same order as `cluster_sizes`
+    pub fn cluster_extremists(&self) -> Vec<usize> {
         self.list_clusters()
             .iter()
-            .map(|c| c.len() as usize)
+            .map(|c| c.iter().filter(|&&i| self.agents[i].extremist).count())
             .collect()
     }
 
-    pub fn write_cluster_sizes(&self, file: &mut File) -> std::io::Result<()> {
+    /// like `write_cluster_sizes`, but instead of the size of every cluster, write
+    /// the number of moderates and extremists in it separated by a comma
+    pub fn write_cluster_extremists<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        self.write_truth_distances(file)?;
+        let clusters = self.list_clusters();
+
+        // write positions of the clusters
+        let string_list = clusters.iter().map(|c| self.agents[c[0]].opinion).join(" ");
+        writeln!(file, "# {}", string_list)?;
+
+        // write the number of moderates and extremists of the clusters
+        let string_list = clusters
+            .iter()
+            .map(|c| {
+                let extremists = c.iter().filter(|&&i| self.agents[i].extremist).count();
+                format!("{},{}", c.len() - extremists, extremists)
+            })
+            .join(" ");
+        writeln!(file, "{}", string_list)?;
+        Ok(())
+    }
+
+    /// like `write_cluster_sizes`, but instead of the size of every cluster, write
+    /// the number of agents of every group in it separated by commas
+    pub fn write_cluster_composition<W: Write + ?Sized>(
+        &self,
+        file: &mut W,
+    ) -> std::io::Result<()> {
+        self.write_truth_distances(file)?;
         let clusters = self.list_clusters();
 
         // write positions of the clusters
-        let string_list = clusters.iter().map(|c| c[0].opinion).join(" ");
+        let string_list = clusters.iter().map(|c| self.agents[c[0]].opinion).join(" ");
         writeln!(file, "# {}", string_list)?;
 
-        // write sizes of the clusters
-        let string_list = clusters.iter().map(|c| c.len().to_string()).join(" ");
+        // write composition of the clusters
+        let string_list = self
+            .cluster_composition()
+            .iter()
+            .map(|c| c.iter().join(","))
+            .join(" ");
         writeln!(file, "{}", string_list)?;
         Ok(())
     }
 }
+
+/// decrease the counter of the opinion in the tree and remove it, if the counter hits 0
+fn remove_entry(
+    tree: &mut BTreeMap<Key, OpinionEntry>,
+    opinion: Float,
+    weight: Float,
+) -> Result<(), HkError> {
+    let entry = tree.get_mut(&to_key(opinion)).ok_or_else(|| {
+        HkError::InconsistentTree(format!("removed opinion {} was not in the tree", opinion))
+    })?;
+    entry.count -= 1;
+    entry.weight -= weight as f64;
+    if entry.count == 0 {
+        tree.remove(&to_key(opinion));
+    }
+    Ok(())
+}
+
+/// increase the counter of the opinion in the tree or insert a new node for it
+fn insert_entry(tree: &mut BTreeMap<Key, OpinionEntry>, opinion: Float, weight: Float) {
+    let entry = tree.entry(to_key(opinion)).or_default();
+    entry.count += 1;
+    entry.weight += weight as f64;
+}
+
+/// whether every agent on the line sees only agents sharing its opinion with `kernel`,
+/// such that no interaction moves any agent
+pub(crate) fn sees_only_own_opinion(agents: &[HKAgent], kernel: Kernel) -> bool {
+    let mut prefix = PrefixSums::default();
+    prefix.fill_from_agents(agents);
+    agents.iter().all(|i| {
+        let (left, right) = i.reach(kernel);
+        let (a, b) = prefix.bounds(i.opinion - left, i.opinion + right);
+        b <= a + 1
+    })
+}
+
+/// compare the counts of the entries of the tree with the counts of the opinions of the agents
+/// `agents` are the indexed agents the tree should contain, `group` names the tree
+fn verify_tree<'a, I: Iterator<Item = (usize, &'a HKAgent)>>(
+    tree: &BTreeMap<Key, OpinionEntry>,
+    agents: I,
+    group: Option<usize>,
+) -> Result<(), InvariantViolation> {
+    if tree.keys().any(|&key| from_key(key).is_nan()) {
+        return Err(InvariantViolation::NanKey { group });
+    }
+    let mut counts: BTreeMap<Key, u32> = BTreeMap::new();
+    let mut num_agents = 0;
+    for (idx, i) in agents {
+        let key = to_key(i.opinion);
+        if !tree.contains_key(&key) {
+            return Err(InvariantViolation::MissingOpinion {
+                group,
+                agent: idx,
+                opinion: i.opinion,
+            });
+        }
+        *counts.entry(key).or_default() += 1;
+        num_agents += 1;
+    }
+    let sum: u64 = tree.values().map(|entry| entry.count as u64).sum();
+    if sum != num_agents as u64 {
+        return Err(InvariantViolation::CountSum {
+            group,
+            sum,
+            agents: num_agents,
+        });
+    }
+    for (&key, entry) in tree {
+        let expected = counts.get(&key).copied().unwrap_or(0);
+        if entry.count != expected {
+            return Err(InvariantViolation::WrongCount {
+                group,
+                opinion: from_key(key),
+                count: entry.count,
+                expected,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// a model needs at least one agent and finite bounds 0 <= min <= max of the confidences
+pub(crate) fn validate_model_parameters(
+    n: u32,
+    min_confidence: Float,
+    max_confidence: Float,
+) -> Result<(), HkError> {
+    if n == 0 {
+        return Err(HkError::InvalidParameter(
+            "a model needs at least one agent".to_string(),
+        ));
+    }
+    let invalid = |reason: String| Err(HkError::InvalidConfidenceDistribution(reason));
+    if !(min_confidence.is_finite() && max_confidence.is_finite()) {
+        return invalid(format!(
+            "bounds [{}, {}] of the confidences need to be finite",
+            min_confidence, max_confidence
+        ));
+    }
+    if min_confidence < 0. {
+        return invalid(format!(
+            "lower bound {} of the confidences needs to be non-negative",
+            min_confidence
+        ));
+    }
+    if min_confidence > max_confidence {
+        return invalid(format!(
+            "lower bound {} exceeds upper bound {}",
+            min_confidence, max_confidence
+        ));
+    }
+    Ok(())
+}
+
+/// the tolerance of the clustering needs to be positive and finite
+pub(crate) fn validate_cluster_eps(eps: Float) -> Result<(), HkError> {
+    if !(eps > 0. && eps.is_finite()) {
+        return Err(HkError::InvalidParameter(format!(
+            "cluster tolerance {} needs to be finite and positive",
+            eps
+        )));
+    }
+    Ok(())
+}
+
+/// A cluster are agents whose opinions are chained by distances (in the given opinion space)
+/// of less than `eps`, the clusters are given by the indices of their agents in ascending
+/// order of the opinions, such that they do not depend on the order of the agents
+pub(crate) fn list_clusters(
+    agents: &[HKAgent],
+    topology: OpinionSpace,
+    eps: Float,
+) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..agents.len()).collect();
+    order.sort_by_key(|&i| OrderedFloat(agents[i].opinion));
+
+    // a gap of at least eps between neighboring opinions separates two clusters
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut previous = None;
+    for i in order {
+        let x = agents[i].opinion;
+        match (previous, clusters.last_mut()) {
+            (Some(y), Some(c)) if x - y < eps => c.push(i),
+            _ => clusters.push(vec![i]),
+        }
+        previous = Some(x);
+    }
+
+    // on the circle, the clusters at both ends may be connected across 0
+    if topology == OpinionSpace::Circle && clusters.len() > 1 {
+        let lowest = agents[clusters[0][0]].opinion;
+        let highest = agents[*clusters[clusters.len() - 1].last().unwrap()].opinion;
+        if topology.distance(lowest, highest) < eps {
+            let mut wrapped = clusters.pop().unwrap();
+            wrapped.append(&mut clusters[0]);
+            clusters[0] = wrapped;
+        }
+    }
+    clusters
+}
+
+/// write the positions and sizes of the clusters of `agents` as a two line block
+pub(crate) fn write_clusters<W: Write + ?Sized>(
+    agents: &[HKAgent],
+    clusters: &[Vec<usize>],
+    file: &mut W,
+) -> std::io::Result<()> {
+    // write positions of the clusters
+    let string_list = clusters.iter().map(|c| agents[c[0]].opinion).join(" ");
+    writeln!(file, "# {}", string_list)?;
+
+    // write sizes of the clusters
+    let string_list = clusters.iter().map(|c| c.len().to_string()).join(" ");
+    writeln!(file, "{}", string_list)?;
+    Ok(())
+}
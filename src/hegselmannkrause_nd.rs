@@ -0,0 +1,283 @@
+/// This file implements the Hegselmann-Krause bounded confidence model with
+/// `D`-dimensional opinion vectors, heterogeneous confidences and synchronous update.
+/// Two agents are neighbors if the euclidean distance of their opinions is smaller
+/// than the confidence of the updating agent.
+/// Since the search tree of the one dimensional model does not transfer directly
+/// to multiple dimensions, only the naive method of iterating all agents is implemented.
+use std::io::prelude::*;
+
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::float::Float;
+use crate::hegselmannkrause::EPS;
+
+/// structure representing an agent with a `D`-dimensional opinion
+#[derive(Clone, Debug)]
+struct NDAgent<const D: usize> {
+    /// current opinion of the agent
+    opinion: [Float; D],
+    /// idiosyncratic confidence of the agent
+    confidence: Float,
+}
+
+impl<const D: usize> NDAgent<D> {
+    fn new(opinion: [Float; D], confidence: Float) -> NDAgent<D> {
+        NDAgent {
+            opinion,
+            confidence,
+        }
+    }
+
+    /// euclidean distance between the opinions of two agents
+    fn distance(&self, other: &NDAgent<D>) -> Float {
+        distance(&self.opinion, &other.opinion)
+    }
+}
+
+/// euclidean distance between two opinions
+fn distance<const D: usize>(a: &[Float; D], b: &[Float; D]) -> Float {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<Float>()
+        .sqrt()
+}
+
+/// used for testing purposes
+impl<const D: usize> PartialEq for NDAgent<D> {
+    fn eq(&self, other: &NDAgent<D>) -> bool {
+        self.opinion
+            .iter()
+            .zip(other.opinion.iter())
+            .all(|(a, b)| (a - b).abs() < EPS)
+            && (self.confidence - other.confidence).abs() < EPS
+    }
+}
+
+/// structure representing a realization of the `D`-dimensional HK model
+pub struct HegselmannKrauseND<const D: usize> {
+    /// number of agents in the system
+    num_agents: u32,
+    /// vector of all agents constituting the system
+    agents: Vec<NDAgent<D>>,
+    /// lower bound of the confidences of all agents
+    min_confidence: Float,
+    /// upper bound of the confidences of all agents
+    max_confidence: Float,
+
+    /// total change of agents opinion during the last sweep
+    pub accumulated_change: Float,
+
+    /// we need many, good (but not crypto) random numbers
+    /// we will use here the pcg generator
+    rng: Pcg64,
+}
+
+/// used for testing purposes
+impl<const D: usize> PartialEq for HegselmannKrauseND<D> {
+    fn eq(&self, other: &HegselmannKrauseND<D>) -> bool {
+        self.agents == other.agents
+    }
+}
+
+impl<const D: usize> HegselmannKrauseND<D> {
+    pub fn new(
+        n: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+    ) -> HegselmannKrauseND<D> {
+        let rng = Pcg64::seed_from_u64(seed);
+        let agents: Vec<NDAgent<D>> = Vec::new();
+
+        let mut hk = HegselmannKrauseND {
+            num_agents: n,
+            agents,
+            min_confidence,
+            max_confidence,
+            accumulated_change: 0.,
+            rng,
+        };
+
+        hk.reset();
+        hk
+    }
+
+    /// reset the state of an HegselmannKrauseND struct
+    /// initialize the agents with opinions uniformly distributed in the unit hypercube
+    /// and uniformly distributed confidences
+    pub fn reset(&mut self) {
+        /// helper function to scale a uniform[0,1] random number to a uniform[low, high]
+        fn scale(x: Float, low: Float, high: Float) -> Float {
+            x * (high - low) + low
+        }
+
+        let mut agents = Vec::with_capacity(self.num_agents as usize);
+        for _ in 0..self.num_agents {
+            let mut opinion = [0.; D];
+            for x in opinion.iter_mut() {
+                *x = self.rng.gen();
+            }
+            let confidence = scale(self.rng.gen(), self.min_confidence, self.max_confidence);
+            agents.push(NDAgent::new(opinion, confidence));
+        }
+        self.agents = agents;
+    }
+
+    /// overwrite the state of all agents with the given opinions and confidences
+    /// this is mainly useful to study specific configurations
+    pub fn set_state(&mut self, opinions: &[[Float; D]], confidences: &[Float]) {
+        assert_eq!(opinions.len(), confidences.len());
+        self.num_agents = opinions.len() as u32;
+        self.agents = opinions
+            .iter()
+            .zip(confidences.iter())
+            .map(|(&x, &c)| NDAgent::new(x, c))
+            .collect();
+        self.accumulated_change = 0.;
+    }
+
+    /// snapshot of the current opinions of all agents
+    pub fn opinions(&self) -> Vec<[Float; D]> {
+        self.agents.iter().map(|i| i.opinion).collect()
+    }
+
+    /// calculate all new opinions using the naive method of iterating all agents
+    fn sync_new_opinions_naive(&self) -> Vec<[Float; D]> {
+        self.agents
+            .iter()
+            .map(|i| {
+                let mut sum = [0.; D];
+                let mut count = 0;
+                for j in self.agents.iter().filter(|j| i.distance(j) < i.confidence) {
+                    for (s, x) in sum.iter_mut().zip(j.opinion.iter()) {
+                        *s += x;
+                    }
+                    count += 1;
+                }
+
+                for s in sum.iter_mut() {
+                    *s /= count as Float;
+                }
+                sum
+            })
+            .collect()
+    }
+
+    // perform a sweep (update every agent) with the naive method
+    pub fn sweep_naive(&mut self) {
+        let new_opinions = self.sync_new_opinions_naive();
+        self.accumulated_change = 0.;
+
+        for (i, new_opinion) in new_opinions.into_iter().enumerate() {
+            self.accumulated_change += distance(&self.agents[i].opinion, &new_opinion);
+
+            self.agents[i].opinion = new_opinion;
+        }
+    }
+
+    pub fn sweep(&mut self) {
+        self.sweep_naive();
+    }
+
+    fn list_clusters(&self) -> Vec<Vec<usize>> {
+        let opinions: Vec<[Float; D]> = self.agents.iter().map(|i| i.opinion).collect();
+        list_clusters(&opinions, EPS)
+    }
+
+    pub fn cluster_sizes(&self) -> Vec<usize> {
+        self.list_clusters().iter().map(|c| c.len()).collect()
+    }
+
+    /// the centroids of all clusters, in the same order as `cluster_sizes`
+    pub fn cluster_centroids(&self) -> Vec<[Float; D]> {
+        self.list_clusters()
+            .iter()
+            .map(|c| {
+                let mut centroid = [0.; D];
+                for &i in c {
+                    for (s, x) in centroid.iter_mut().zip(self.agents[i].opinion.iter()) {
+                        *s += x;
+                    }
+                }
+                for s in centroid.iter_mut() {
+                    *s /= c.len() as Float;
+                }
+                centroid
+            })
+            .collect()
+    }
+
+    pub fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        // write centroids of the clusters, coordinates separated by commas
+        let string_list = self
+            .cluster_centroids()
+            .iter()
+            .map(|c| c.iter().join(","))
+            .join(" ");
+        writeln!(file, "# {}", string_list)?;
+
+        // write sizes of the clusters
+        let string_list = self.cluster_sizes().iter().map(|c| c.to_string()).join(" ");
+        writeln!(file, "{}", string_list)?;
+        Ok(())
+    }
+}
+
+/// A cluster are agents whose opinions are chained by distances of less than `eps` in every
+/// coordinate (single linkage), the clusters are given by the indices of their agents in
+/// lexicographic order of the opinions, such that they do not depend on the order of the agents
+pub(crate) fn list_clusters<const D: usize>(
+    opinions: &[[Float; D]],
+    eps: Float,
+) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..opinions.len()).collect();
+    order.sort_by(|&i, &j| {
+        let key = |k: usize| opinions[k].map(OrderedFloat);
+        key(i).cmp(&key(j))
+    });
+
+    /// root of the tree of position `p` in the union-find forest, halving the paths
+    fn find(parent: &mut [usize], mut p: usize) -> usize {
+        while parent[p] != p {
+            parent[p] = parent[parent[p]];
+            p = parent[p];
+        }
+        p
+    }
+
+    // union-find over the positions in `order`, only the preceding opinions closer than
+    // eps in the first coordinate can be linked
+    let mut parent: Vec<usize> = (0..order.len()).collect();
+    for (p, &i) in order.iter().enumerate() {
+        for (q, &j) in order[..p].iter().enumerate().rev() {
+            if opinions[i][0] - opinions[j][0] >= eps {
+                break;
+            }
+            let linked = opinions[i]
+                .iter()
+                .zip(opinions[j].iter())
+                .all(|(a, b)| (a - b).abs() < eps);
+            if linked {
+                let (a, b) = (find(&mut parent, p), find(&mut parent, q));
+                // the root is the first position of a cluster, which orders the clusters
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut cluster_of_root = vec![usize::MAX; order.len()];
+    for (p, &i) in order.iter().enumerate() {
+        let root = find(&mut parent, p);
+        if cluster_of_root[root] == usize::MAX {
+            cluster_of_root[root] = clusters.len();
+            clusters.push(Vec::new());
+        }
+        clusters[cluster_of_root[root]].push(i);
+    }
+    clusters
+}
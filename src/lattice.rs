@@ -0,0 +1,216 @@
+/// This file implements the Hegselmann-Krause bounded confidence model on a square lattice
+/// with heterogeneous confidences and synchronous update.
+/// The agents sit on the sites of an `L x L` grid with open boundaries and only consider
+/// their 4 (von Neumann) or 8 (Moore) lattice neighbors, which are also within their
+/// confidence. Since the neighborhoods are fixed and tiny, no tree is needed.
+use std::io::prelude::*;
+
+use itertools::Itertools;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::float::Float;
+use crate::hegselmannkrause::{list_clusters, write_clusters, HKAgent, OpinionSpace, EPS};
+
+/// structure representing a realization of the HK model on a lattice
+pub struct HegselmannKrauseLattice {
+    /// linear size of the lattice
+    l: u32,
+    /// whether the diagonal neighbors are part of the neighborhood
+    moore: bool,
+    /// vector of all agents constituting the system, stored row-major
+    agents: Vec<HKAgent>,
+    /// lower bound of the confidences of all agents
+    min_confidence: Float,
+    /// upper bound of the confidences of all agents
+    max_confidence: Float,
+
+    /// total change of agents opinion during the last sweep
+    pub accumulated_change: Float,
+
+    /// we need many, good (but not crypto) random numbers
+    /// we will use here the pcg generator
+    rng: Pcg64,
+}
+
+/// used for testing purposes
+impl PartialEq for HegselmannKrauseLattice {
+    fn eq(&self, other: &HegselmannKrauseLattice) -> bool {
+        self.agents == other.agents
+    }
+}
+
+impl HegselmannKrauseLattice {
+    pub fn new(
+        l: u32,
+        min_confidence: Float,
+        max_confidence: Float,
+        seed: u64,
+        moore: bool,
+    ) -> HegselmannKrauseLattice {
+        let rng = Pcg64::seed_from_u64(seed);
+        let agents: Vec<HKAgent> = Vec::new();
+
+        let mut hk = HegselmannKrauseLattice {
+            l,
+            moore,
+            agents,
+            min_confidence,
+            max_confidence,
+            accumulated_change: 0.,
+            rng,
+        };
+
+        hk.reset();
+        hk
+    }
+
+    /// reset the state of an HegselmannKrauseLattice struct
+    /// initialize the agents with uniformly distributed opinions and confidences
+    pub fn reset(&mut self) {
+        /// helper function to scale a uniform[0,1] random number to a uniform[low, high]
+        fn scale(x: Float, low: Float, high: Float) -> Float {
+            x * (high - low) + low
+        }
+
+        self.agents = (0..self.l * self.l)
+            .map(|_| {
+                HKAgent::new(
+                    self.rng.gen(),
+                    scale(self.rng.gen(), self.min_confidence, self.max_confidence),
+                )
+            })
+            .collect();
+    }
+
+    /// overwrite the state of all agents with the given opinions and confidences,
+    /// both in row-major order of the sites
+    /// this is mainly useful to study specific configurations
+    pub fn set_state(&mut self, opinions: &[Float], confidences: &[Float]) {
+        assert_eq!(opinions.len(), confidences.len());
+        assert_eq!(opinions.len(), (self.l * self.l) as usize);
+        self.agents = opinions
+            .iter()
+            .zip(confidences.iter())
+            .map(|(&x, &c)| HKAgent::new(x, c))
+            .collect();
+        self.accumulated_change = 0.;
+    }
+
+    /// snapshot of the current opinions of all agents in row-major order
+    pub fn opinions(&self) -> Vec<Float> {
+        self.agents.iter().map(|i| i.opinion).collect()
+    }
+
+    /// indices of the lattice neighbors of the site `idx`, without the site itself
+    fn neighbors(&self, idx: usize) -> Vec<usize> {
+        let l = self.l as i64;
+        let (row, col) = (idx as i64 / l, idx as i64 % l);
+        let offsets: &[(i64, i64)] = if self.moore {
+            &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ]
+        } else {
+            &[(-1, 0), (0, -1), (0, 1), (1, 0)]
+        };
+
+        offsets
+            .iter()
+            .map(|(dr, dc)| (row + dr, col + dc))
+            .filter(|(r, c)| (0..l).contains(r) && (0..l).contains(c))
+            .map(|(r, c)| (r * l + c) as usize)
+            .collect()
+    }
+
+    /// calculate all new opinions as the mean of the own opinion and the opinions of
+    /// all lattice neighbors within the confidence
+    fn sync_new_opinions(&self) -> Vec<Float> {
+        self.agents
+            .iter()
+            .enumerate()
+            .map(|(idx, i)| {
+                let mut sum = i.opinion;
+                let mut count = 1;
+                for j in self.neighbors(idx) {
+                    let x = self.agents[j].opinion;
+                    if i.accepts(OpinionSpace::Line, x) {
+                        sum += x;
+                        count += 1;
+                    }
+                }
+
+                sum / count as Float
+            })
+            .collect()
+    }
+
+    // perform a sweep (update every agent synchronously)
+    pub fn sweep(&mut self) {
+        let new_opinions = self.sync_new_opinions();
+        self.accumulated_change = 0.;
+
+        for (i, new_opinion) in new_opinions.into_iter().enumerate() {
+            self.accumulated_change += (self.agents[i].opinion - new_opinion).abs();
+
+            self.agents[i].opinion = new_opinion;
+        }
+    }
+
+    /// sizes of the clusters in opinion space, regardless of the position of the agents
+    pub fn cluster_sizes(&self) -> Vec<usize> {
+        list_clusters(&self.agents, OpinionSpace::Line, EPS)
+            .iter()
+            .map(|c| c.len())
+            .collect()
+    }
+
+    /// sizes of the spatial domains, i.e., the connected components of lattice sites whose
+    /// opinions differ by less than EPS, ordered by their first site in row-major order
+    pub fn domain_sizes(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.agents.len()];
+        let mut sizes = Vec::new();
+        for start in 0..self.agents.len() {
+            if visited[start] {
+                continue;
+            }
+
+            // depth first search over the sites sharing the opinion
+            visited[start] = true;
+            let mut stack = vec![start];
+            let mut size = 0;
+            while let Some(idx) = stack.pop() {
+                size += 1;
+                for j in self.neighbors(idx) {
+                    if !visited[j]
+                        && (self.agents[j].opinion - self.agents[idx].opinion).abs() < EPS
+                    {
+                        visited[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        sizes
+    }
+
+    pub fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        write_clusters(
+            &self.agents,
+            &list_clusters(&self.agents, OpinionSpace::Line, EPS),
+            file,
+        )?;
+
+        // write sizes of the spatial domains
+        let string_list = self.domain_sizes().iter().map(|c| c.to_string()).join(" ");
+        writeln!(file, "# domains: {}", string_list)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,36 @@
+// the casts between f32 and f64 are only redundant, if the opinions are f64 anyway
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
 /// This is mostly boilerplate to enable the benchmark and test function
+pub mod coupled;
+pub mod deffuant;
+pub mod degroot;
+pub mod diagnostics;
+pub mod distributions;
+pub mod error;
+pub mod float;
 pub mod hegselmannkrause;
+pub mod hegselmannkrause_nd;
+pub mod lattice;
+pub mod network;
+pub mod output;
+pub mod schedule;
 
-pub use hegselmannkrause::HegselmannKrause;
+pub use coupled::CoupledHegselmannKrause;
+pub use deffuant::Deffuant;
+pub use degroot::DeGroot;
+pub use diagnostics::Diagnostics;
+pub use distributions::{
+    ActivityDistribution, ConfidenceDistribution, InitialOpinions, WeightDistribution,
+};
+pub use error::{HkError, InvariantViolation, NotConverged};
+pub use float::Float;
+pub use hegselmannkrause::{
+    Aggregator, Backend, Boundary, CapStrategy, ConvergenceCheck, ConvergenceCriterion,
+    ConvergenceResult, HegselmannKrause, HegselmannKrauseBuilder, Kernel, Media, OpinionSpace,
+    SweepReport, Sweeps,
+};
+pub use hegselmannkrause_nd::HegselmannKrauseND;
+pub use lattice::HegselmannKrauseLattice;
+pub use network::Network;
+pub use output::ClusterOutput;
+pub use schedule::{ConfidenceFunction, ConfidenceSchedule};
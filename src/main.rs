@@ -1,10 +1,188 @@
+// the casts between f32 and f64 are only redundant, if the opinions are f64 anyway
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
 /// This file implements a rudimentary command line interface and the main loop for simulations
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
+use structopt::clap;
 use structopt::StructOpt;
 
-use hk::HegselmannKrause;
+use hk::{
+    ActivityDistribution, Aggregator, Backend, Boundary, CapStrategy, ConfidenceDistribution,
+    ConfidenceFunction, ConfidenceSchedule, ConvergenceCheck, ConvergenceCriterion,
+    ConvergenceResult, Deffuant, Float, HegselmannKrause, HegselmannKrauseBuilder, HkError,
+    InitialOpinions, Kernel, Network, WeightDistribution,
+};
+
+/// maximum number of sweeps per sample, if none is given, such that systems whose
+/// change keeps jittering above the threshold, e.g., due to rounding, cannot run forever
+const DEFAULT_MAX_SWEEPS: u64 = 100_000;
+
+/// the bounded confidence models which can be simulated
+#[derive(Debug)]
+enum Model {
+    HegselmannKrause,
+    Deffuant,
+}
+
+impl std::str::FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hk" => Ok(Model::HegselmannKrause),
+            "deffuant" => Ok(Model::Deffuant),
+            _ => Err(format!("unknown model '{}', use 'hk' or 'deffuant'", s)),
+        }
+    }
+}
+
+/// the criteria for convergence which can be chosen on the command line
+#[derive(Debug)]
+enum Criterion {
+    Sum,
+    Max,
+    Clusters,
+}
+
+impl std::str::FromStr for Criterion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Criterion::Sum),
+            "max" => Ok(Criterion::Max),
+            "clusters" => Ok(Criterion::Clusters),
+            _ => Err(format!(
+                "unknown criterion '{}', use 'sum', 'max' or 'clusters'",
+                s
+            )),
+        }
+    }
+}
+
+/// a closed interval given as `<low>:<high>` on the command line
+#[derive(Clone, Copy, Debug)]
+struct Interval(Float, Float);
+
+impl std::str::FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |x: &str| {
+            x.parse::<Float>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        match s.split(':').collect::<Vec<_>>().as_slice() {
+            [low, high] => Ok(Interval(number(low)?, number(high)?)),
+            _ => Err(format!("invalid interval '{}', use '<low>:<high>'", s)),
+        }
+    }
+}
+
+/// the common interface of all models needed by the main loop
+trait Simulation {
+    fn reset_with_seed(&mut self, seed: u64);
+    fn last_change(&self) -> Float;
+    fn last_max_change(&self) -> Float;
+    /// whether no sweep can change the model anymore
+    fn is_static(&self) -> bool;
+    /// fails, if the model detects that its internal state got inconsistent, the summary
+    /// tells whether the criterion was met within `max_sweeps` sweeps
+    fn run_to_convergence(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<ConvergenceResult, HkError>;
+    fn write_cluster_sizes(&self, file: &mut dyn Write) -> std::io::Result<()>;
+    /// models without media do not write anything
+    fn write_media_opinion(&self, _file: &mut dyn Write) -> std::io::Result<()> {
+        Ok(())
+    }
+    /// models without groups of agents only write their cluster sizes
+    fn write_cluster_composition(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_cluster_sizes(file)
+    }
+    /// models without extremists only write their cluster sizes
+    fn write_cluster_extremists(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_cluster_sizes(file)
+    }
+    /// write the state, such that `--resume-from` continues the simulation exactly
+    fn save_checkpoint(&self, _file: &mut dyn Write) -> std::io::Result<()> {
+        Err(std::io::Error::other(
+            "checkpoints are only supported for the Hegselmann-Krause model with the serde feature",
+        ))
+    }
+}
+
+impl Simulation for HegselmannKrause {
+    fn reset_with_seed(&mut self, seed: u64) {
+        self.reset_with_seed(seed)
+    }
+    fn last_change(&self) -> Float {
+        self.last_change()
+    }
+    fn last_max_change(&self) -> Float {
+        self.last_max_change()
+    }
+    fn is_static(&self) -> bool {
+        self.is_static()
+    }
+    fn run_to_convergence(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<ConvergenceResult, HkError> {
+        self.try_run_to_convergence_with(criterion, max_sweeps)
+    }
+    fn write_cluster_sizes(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_cluster_sizes(file)
+    }
+    fn write_media_opinion(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_media_opinion(file)
+    }
+    fn write_cluster_composition(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_cluster_composition(file)
+    }
+    fn write_cluster_extremists(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_cluster_extremists(file)
+    }
+    #[cfg(feature = "serde")]
+    fn save_checkpoint(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.save_checkpoint(file)
+    }
+}
+
+impl Simulation for Deffuant {
+    fn reset_with_seed(&mut self, seed: u64) {
+        self.reset_with_seed(seed)
+    }
+    fn last_change(&self) -> Float {
+        self.last_change()
+    }
+    fn last_max_change(&self) -> Float {
+        self.last_max_change()
+    }
+    fn is_static(&self) -> bool {
+        self.is_static()
+    }
+    fn run_to_convergence(
+        &mut self,
+        criterion: ConvergenceCriterion,
+        max_sweeps: Option<usize>,
+    ) -> Result<ConvergenceResult, HkError> {
+        Ok(self.run_to_convergence_with(criterion, max_sweeps))
+    }
+    fn write_cluster_sizes(&self, file: &mut dyn Write) -> std::io::Result<()> {
+        self.write_cluster_sizes(file)
+    }
+}
 
 /// Simulate a  Hegselmann Krause model
 #[derive(StructOpt, Debug)]
@@ -21,8 +199,358 @@ struct Opt {
     /// maximum confidence of agents (uniformly distributed)
     max_confidence: f64,
 
+    #[structopt(long, default_value = "0.0", allow_hyphen_values = true)]
+    /// lower bound of the opinion interval, all initial distributions are scaled to it
+    /// (Hegselmann-Krause only)
+    min_opinion: f64,
+
+    #[structopt(long, default_value = "1.0", allow_hyphen_values = true)]
+    /// upper bound of the opinion interval (Hegselmann-Krause only)
+    max_opinion: f64,
+
+    #[structopt(long, default_value = "hk", possible_values = &["hk", "deffuant"])]
+    /// model to simulate: Hegselmann-Krause or Deffuant-Weisbuch
+    model: Model,
+
+    #[structopt(long)]
+    /// convergence parameter, i.e., the fraction of the way agents move towards the others
+    /// (default 0.5 for the Deffuant model and 1 for the Hegselmann-Krause model)
+    mu: Option<f64>,
+
+    #[structopt(long, default_value = "0.0")]
+    /// amplitude of the uniform noise added to the opinions every sweep (Hegselmann-Krause only)
+    noise: f64,
+
+    #[structopt(long)]
+    /// opinion of an external media field (Hegselmann-Krause only)
+    media_opinion: Option<f64>,
+
+    #[structopt(long, default_value = "0.0")]
+    /// amplitude of the uniform noise with which agents perceive the opinions of others,
+    /// values above 0 force the slower naive algorithm (Hegselmann-Krause only)
+    perception_noise: f64,
+
+    #[structopt(long, default_value = "1.0")]
+    /// weight of the media field in the average of the agents
+    media_weight: f64,
+
+    #[structopt(long, default_value = "0.0")]
+    /// probability of every agent per sweep to move towards the opinion of the media given
+    /// by --media-opinion instead of its usual update, regardless of its confidence
+    /// (Hegselmann-Krause only)
+    media_exposure: f64,
+
+    #[structopt(long, default_value = "0.5")]
+    /// fraction of the way towards the media an exposed agent moves
+    media_mu: f64,
+
+    #[structopt(long)]
+    /// amplitude of the oscillation of the media's opinion around 0.5, replaces
+    /// --media-opinion (Hegselmann-Krause only)
+    media_amplitude: Option<f64>,
+
+    #[structopt(long, default_value = "100.0")]
+    /// period in sweeps of the oscillation of the media's opinion
+    media_period: f64,
+
+    #[structopt(long, default_value = "1.0")]
+    /// weight of the own opinion in the average of an agent (Hegselmann-Krause only)
+    self_weight: f64,
+
+    #[structopt(long)]
+    /// shift of all opinions per sweep after the averaging step (Hegselmann-Krause only)
+    drift: Option<f64>,
+
+    #[structopt(
+        long,
+        default_value = "clamp",
+        possible_values = &["clamp", "reflect", "periodic", "open"]
+    )]
+    /// treatment of opinions leaving [0, 1], e.g., due to noise or drift, periodic simulates
+    /// opinions on a circle (Hegselmann-Krause only)
+    boundary: Boundary,
+
+    #[structopt(long)]
+    /// measure convergence on the changes without the drift
+    drift_corrected: bool,
+
+    #[structopt(long, default_value = "0.0")]
+    /// agents keep their opinion unless it would change by more than this threshold
+    /// (Hegselmann-Krause only)
+    update_threshold: f64,
+
+    #[structopt(long, default_value = "1e-5")]
+    /// opinions closer than this tolerance belong to the same cluster, should be looser than
+    /// the spread left by the convergence threshold
+    cluster_eps: f64,
+
+    #[structopt(long)]
+    /// redraw the confidences of all agents before every sweep (Hegselmann-Krause only)
+    annealed: bool,
+
+    #[structopt(long)]
+    /// compensate the rounding errors of the sums over many neighbors and of the change
+    /// per sweep, which is slower (Hegselmann-Krause only)
+    compensated: bool,
+
+    #[structopt(long)]
+    /// calculate the new opinion only once for agents, which share their opinion and the
+    /// opinions within their confidence intervals, e.g., in converged clusters, which
+    /// does not change the results (Hegselmann-Krause only)
+    coarsening: bool,
+
+    #[structopt(long, default_value = "0.0")]
+    /// expected number of agents replaced by newcomers after every sweep, the system is
+    /// then simulated for exactly max-sweeps sweeps (Hegselmann-Krause only)
+    turnover: f64,
+
+    #[structopt(long, default_value = "0.0")]
+    /// probability with which an agent jumps to a random opinion instead of updating,
+    /// the system is then simulated for exactly max-sweeps sweeps (Hegselmann-Krause only)
+    jump_probability: f64,
+
+    #[structopt(long)]
+    /// maximum number of sweeps per sample (default 100000), samples which do not converge
+    /// within it are marked in the output, required for noisy systems, which never converge
+    max_sweeps: Option<u64>,
+
+    #[structopt(long, default_value = "sum", possible_values = &["sum", "max", "clusters"])]
+    /// a sample is converged, when the total change of all opinions or the largest change
+    /// of a single opinion during a sweep drops below the threshold, or when the cluster
+    /// sizes did not change for stable-sweeps sweeps
+    criterion: Criterion,
+
+    #[structopt(long)]
+    /// threshold of the change during a sweep for the criteria sum and max (default 1e-4,
+    /// for the Hegselmann-Krause model scaled by mu)
+    threshold: Option<f64>,
+
+    #[structopt(long, default_value = "10")]
+    /// number of sweeps without a change of the cluster sizes for the criterion clusters
+    stable_sweeps: usize,
+
+    #[structopt(long)]
+    /// number of threads calculating the new opinions of synchronous sweeps, by default
+    /// one per core (needs the rayon feature), or with --ensemble the number of samples
+    /// simulated in parallel
+    threads: Option<usize>,
+
+    #[structopt(long)]
+    /// simulate the samples in parallel, every sample starts from a new model, the output is
+    /// the same as without --ensemble for any number of --threads
+    ensemble: bool,
+
+    #[structopt(long)]
+    /// social network restricting the interactions: complete, ring:<k> or er:<p>
+    /// (Hegselmann-Krause only)
+    network: Option<Network>,
+
+    #[structopt(long, default_value = "uniform")]
+    /// distribution of the initial opinions: uniform, gaussian:<mean>:<sd>,
+    /// beta:<alpha>:<beta> or extremists:<fraction>:<width>:<confidence>
+    /// (Hegselmann-Krause only)
+    initial: InitialOpinions,
+
+    #[structopt(long)]
+    /// split the agents into two blocks with separate opinion and confidence ranges,
+    /// this fraction of the agents forms block a, replaces --initial and the distribution
+    /// of the confidences (Hegselmann-Krause only)
+    block_split: Option<f64>,
+
+    #[structopt(long, default_value = "0:0.4")]
+    /// range <low>:<high> of the initial opinions of block a
+    block_a: Interval,
+
+    #[structopt(long, default_value = "0.6:1")]
+    /// range <low>:<high> of the initial opinions of block b
+    block_b: Interval,
+
+    #[structopt(long)]
+    /// range <low>:<high> of the confidences of block a, defaults to the range given by
+    /// --min-confidence and --max-confidence
+    block_a_confidence: Option<Interval>,
+
+    #[structopt(long)]
+    /// range <low>:<high> of the confidences of block b, defaults to the range given by
+    /// --min-confidence and --max-confidence
+    block_b_confidence: Option<Interval>,
+
+    #[structopt(
+        long,
+        default_value = "uniform",
+        possible_values = &["uniform", "powerlaw", "constant", "bimodal", "correlated"]
+    )]
+    /// distribution of the confidences within [min-confidence, max-confidence],
+    /// constant uses min-confidence for all agents, bimodal assigns max-confidence to
+    /// open-minded and min-confidence to closed-minded agents, correlated assigns
+    /// max-confidence to centrists and min-confidence to extremists (Hegselmann-Krause only)
+    confidence_distribution: String,
+
+    #[structopt(long, default_value = "2.5")]
+    /// exponent of the power law distribution of confidences
+    exponent: f64,
+
+    #[structopt(long, default_value = "1.0")]
+    /// exponent of the decay of the confidence towards extreme opinions for the correlated
+    /// distribution of confidences
+    gamma: f64,
+
+    #[structopt(long, default_value = "0.5")]
+    /// fraction of open-minded agents for the bimodal distribution of confidences
+    open_fraction: f64,
+
+    #[structopt(long, default_value = "hard", possible_values = &["hard", "gaussian", "linear"])]
+    /// influence of neighbors: hard cutoff at the confidence, a gaussian whose width is
+    /// the confidence, or linearly decaying to zero at the confidence (Hegselmann-Krause only)
+    kernel: String,
+
+    #[structopt(long, default_value = "auto", possible_values = &["tree", "sorted", "auto"])]
+    /// data structure for the range queries: a tree kept up to date, sorted arrays
+    /// rebuilt every sweep, which only help for the hard kernel on the line, or the
+    /// sorted arrays wherever they help and the tree otherwise (Hegselmann-Krause only)
+    backend: Backend,
+
+    #[structopt(long)]
+    /// true value attracting the truth seekers (Hegselmann-Krause only)
+    truth: Option<f64>,
+
+    #[structopt(long, default_value = "0.1")]
+    /// strength with which truth seekers are pulled towards the truth
+    truth_strength: f64,
+
+    #[structopt(long, default_value = "1.0")]
+    /// fraction of the agents seeking the truth
+    truth_fraction: f64,
+
+    #[structopt(long, default_value = "0.0")]
+    /// lower bound of the susceptibilities of the agents to their initial opinions
+    /// (Hegselmann-Krause only)
+    anchoring_min: f64,
+
+    #[structopt(long, default_value = "0.0")]
+    /// upper bound of the susceptibilities of the agents to their initial opinions,
+    /// 0 disables the anchoring (Hegselmann-Krause only)
+    anchoring_max: f64,
+
+    #[structopt(long)]
+    /// agents beyond the confidence, but closer than this distance, repel each other
+    /// (Hegselmann-Krause only)
+    repulsion_range: Option<f64>,
+
+    #[structopt(long, default_value = "0.5")]
+    /// fraction of the distance to the mean of the repelling agents an agent is pushed away
+    repulsion_strength: f64,
+
+    #[structopt(long, default_value = "1.0")]
+    /// probability with which a potential neighbor is considered in an update,
+    /// values below 1 force the slower naive algorithm (Hegselmann-Krause only)
+    interaction_probability: f64,
+
+    #[structopt(long, default_value = "1.0")]
+    /// fraction of the agents, drawn anew every sweep, which update their opinion
+    /// (Hegselmann-Krause only)
+    update_fraction: f64,
+
+    #[structopt(long, default_value = "1")]
+    /// number of groups of agents, which take turns to update their opinions
+    /// (Hegselmann-Krause only)
+    rotation_groups: u32,
+
+    #[structopt(long, default_value = "0")]
+    /// number of equally spaced values in [0, 1] the opinions are rounded to,
+    /// 0 for continuous opinions (Hegselmann-Krause only)
+    quantization: u32,
+
+    #[structopt(long, default_value = "unit")]
+    /// distribution of the influence weights of the agents: unit, uniform:<low>:<high>
+    /// or powerlaw:<exponent>:<min>:<max> (Hegselmann-Krause only)
+    weights: WeightDistribution,
+
+    #[structopt(long, default_value = "always")]
+    /// distribution of the probabilities with which agents update their opinion per sweep:
+    /// always, constant:<value> or uniform:<low>:<high> (Hegselmann-Krause only)
+    activity: ActivityDistribution,
+
+    #[structopt(long, default_value = "constant")]
+    /// dependence of the confidences on the current opinions: constant, tent:<eps0> or
+    /// table:<x>=<eps>,... (Hegselmann-Krause only)
+    confidence_profile: ConfidenceFunction,
+
+    #[structopt(long, default_value = "constant")]
+    /// decay of the confidences with time: constant, exp:<tau>:<floor> or
+    /// linear:<rate>:<floor> (Hegselmann-Krause only)
+    confidence_decay: ConfidenceSchedule,
+
+    #[structopt(long)]
+    /// after every sweep, increase the confidence of agents with neighbors by this amount
+    /// and decrease it for isolated agents, within [min-confidence, max-confidence]
+    /// (Hegselmann-Krause only)
+    adaptive_confidence: Option<f64>,
+
+    #[structopt(long)]
+    /// minimum confidence towards lower opinions, enables asymmetric confidence intervals
+    /// unset bounds default to min-confidence and max-confidence (Hegselmann-Krause only)
+    min_confidence_left: Option<f64>,
+
+    #[structopt(long)]
+    /// maximum confidence towards lower opinions
+    max_confidence_left: Option<f64>,
+
+    #[structopt(long)]
+    /// minimum confidence towards higher opinions
+    min_confidence_right: Option<f64>,
+
+    #[structopt(long)]
+    /// maximum confidence towards higher opinions
+    max_confidence_right: Option<f64>,
+
+    #[structopt(long)]
+    /// let agents average over their k nearest opinions instead of using their confidences
+    /// (Hegselmann-Krause only)
+    knn: Option<usize>,
+
+    #[structopt(long, default_value = "mean")]
+    /// statistic of the opinions of the neighbors an agent adopts: mean, median or
+    /// trimmed:<alpha> (Hegselmann-Krause only)
+    aggregator: Aggregator,
+
+    #[structopt(long)]
+    /// maximum number of agents within the confidence an agent averages over, including
+    /// itself (Hegselmann-Krause only)
+    neighbor_cap: Option<usize>,
+
+    #[structopt(long, default_value = "closest", possible_values = &["closest", "random"])]
+    /// which agents are averaged over, if more than the neighbor cap are within the
+    /// confidence, random forces the naive algorithm
+    cap_strategy: CapStrategy,
+
+    #[structopt(long)]
+    /// write the number of agents of every group per cluster instead of the cluster sizes
+    composition: bool,
+
+    #[structopt(long)]
+    /// write the number of moderates and extremists per cluster instead of the cluster sizes
+    extremists: bool,
+
+    #[structopt(long)]
+    /// write the media's opinion and the clusters after every sweep
+    trajectory: bool,
+
+    #[structopt(long)]
+    /// write a checkpoint every this many sweeps to the output file with the extension
+    /// .checkpoint appended, from which --resume-from continues the simulation exactly
+    /// (Hegselmann-Krause only, needs the serde feature)
+    checkpoint_every: Option<usize>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// continue the simulation from a checkpoint, whose model and parameters replace
+    /// those of the arguments, max-sweeps includes the sweeps before the checkpoint
+    /// (Hegselmann-Krause only, needs the serde feature)
+    resume_from: Option<PathBuf>,
+
     #[structopt(short, long, default_value = "1")]
-    /// seed to use for the simulation
+    /// seed to use for the simulation, sample i is simulated with the seed seed + i, such
+    /// that every sample can be reproduced on its own
     seed: u64,
 
     #[structopt(long, default_value = "1")]
@@ -34,36 +562,506 @@ struct Opt {
     outname: std::path::PathBuf,
 }
 
+/// abort with a human readable error message about invalid arguments
+fn invalid_arguments(description: &str) -> ! {
+    clap::Error::with_description(description, clap::ErrorKind::InvalidValue).exit()
+}
+
+/// construct the model described by the arguments with the given seed
+/// invalid arguments abort the program
+fn build_model(args: &Opt, seed: u64) -> Box<dyn Simulation> {
+    match args.model {
+        Model::HegselmannKrause => {
+            let confidence_distribution = match args.confidence_distribution.as_str() {
+                "powerlaw" => ConfidenceDistribution::PowerLaw {
+                    exponent: args.exponent as Float,
+                    min: args.min_confidence as Float,
+                    max: args.max_confidence as Float,
+                },
+                "constant" => ConfidenceDistribution::Constant {
+                    value: args.min_confidence as Float,
+                },
+                "bimodal" => ConfidenceDistribution::Bimodal {
+                    fraction: args.open_fraction as Float,
+                    open: args.max_confidence as Float,
+                    closed: args.min_confidence as Float,
+                },
+                "correlated" => ConfidenceDistribution::OpinionCorrelated {
+                    min: args.min_confidence as Float,
+                    max: args.max_confidence as Float,
+                    gamma: args.gamma as Float,
+                },
+                _ => ConfidenceDistribution::Uniform {
+                    low: args.min_confidence as Float,
+                    high: args.max_confidence as Float,
+                },
+            };
+            let initial_opinions = match args.block_split {
+                Some(split) => {
+                    let confidence =
+                        Interval(args.min_confidence as Float, args.max_confidence as Float);
+                    let Interval(a_low, a_high) = args.block_a_confidence.unwrap_or(confidence);
+                    let Interval(b_low, b_high) = args.block_b_confidence.unwrap_or(confidence);
+                    InitialOpinions::TwoBlocks {
+                        split_fraction: split as Float,
+                        range_a: (args.block_a.0, args.block_a.1),
+                        range_b: (args.block_b.0, args.block_b.1),
+                        confidence_a: (a_low, a_high),
+                        confidence_b: (b_low, b_high),
+                    }
+                }
+                None => args.initial.clone(),
+            };
+            let kernel = match args.kernel.as_str() {
+                "gaussian" => Kernel::Gaussian,
+                "linear" => Kernel::Linear,
+                _ => Kernel::Hard,
+            };
+            let mut builder = HegselmannKrauseBuilder::new()
+                .agents(args.num_agents)
+                .confidence_range(args.min_confidence as Float, args.max_confidence as Float)
+                .seed(seed)
+                .opinion_interval(args.min_opinion as Float, args.max_opinion as Float)
+                .initial_opinions(initial_opinions)
+                .confidence_distribution(confidence_distribution)
+                .boundary(args.boundary)
+                .kernel(kernel)
+                .aggregator(args.aggregator)
+                .noise(args.noise as Float)
+                .cluster_eps(args.cluster_eps as Float)
+                .backend(args.backend);
+            if let Some(network) = &args.network {
+                builder = builder.network(network.clone());
+            }
+            let mut hk = builder
+                .build()
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            if let Some(v) = args.drift {
+                hk.set_drift(v as Float, args.boundary)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            hk.set_drift_corrected_change(args.drift_corrected);
+            hk.set_annealed_confidences(args.annealed);
+            hk.set_compensated_summation(args.compensated);
+            hk.set_coarsening(args.coarsening);
+            hk.set_update_threshold(args.update_threshold as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_turnover(args.turnover as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_jump_probability(args.jump_probability as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            let asymmetric = [
+                args.min_confidence_left,
+                args.max_confidence_left,
+                args.min_confidence_right,
+                args.max_confidence_right,
+            ];
+            if asymmetric.iter().any(|bound| bound.is_some()) {
+                if args.confidence_distribution != "uniform" {
+                    invalid_arguments(
+                        "asymmetric confidences are only supported for uniform distributions",
+                    );
+                }
+                let min = |bound: Option<f64>| bound.unwrap_or(args.min_confidence) as Float;
+                let max = |bound: Option<f64>| bound.unwrap_or(args.max_confidence) as Float;
+                hk.set_asymmetric_confidences(
+                    min(args.min_confidence_left),
+                    max(args.max_confidence_left),
+                    min(args.min_confidence_right),
+                    max(args.max_confidence_right),
+                )
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if let Some(k) = args.knn {
+                hk.set_nearest_neighbors(k)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if let Some(c) = args.neighbor_cap {
+                hk.set_neighbor_cap(c, args.cap_strategy)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if let Some(mu) = args.mu {
+                hk.set_mu(mu as Float)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            hk.set_interaction_probability(args.interaction_probability as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_update_fraction(args.update_fraction as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_rotation_groups(args.rotation_groups)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_quantization(args.quantization)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_perception_noise(args.perception_noise as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_weight_distribution(args.weights.clone())
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_activity_distribution(args.activity.clone())
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_confidence_function(args.confidence_profile.clone())
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            hk.set_confidence_schedule(args.confidence_decay.clone())
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            if let Some(delta) = args.adaptive_confidence {
+                hk.enable_adaptive_confidence(
+                    delta as Float,
+                    args.min_confidence as Float,
+                    args.max_confidence as Float,
+                )
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            hk.set_self_weight(args.self_weight as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            if let Some(truth) = args.truth {
+                hk.set_truth(
+                    truth as Float,
+                    args.truth_strength as Float,
+                    args.truth_fraction as Float,
+                )
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if args.anchoring_max > 0. {
+                hk.set_anchoring(args.anchoring_min as Float, args.anchoring_max as Float)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if let Some(eps_rep) = args.repulsion_range {
+                hk.set_repulsion(eps_rep as Float, args.repulsion_strength as Float)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if let Some(media_opinion) = args.media_opinion {
+                hk.set_media(media_opinion as Float, args.media_weight as Float)
+                    .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if args.media_exposure > 0. {
+                let media_opinion = args.media_opinion.unwrap_or_else(|| {
+                    invalid_arguments("--media-exposure needs a --media-opinion")
+                });
+                hk.set_media_exposure(
+                    args.media_exposure as Float,
+                    args.media_mu as Float,
+                    media_opinion as Float,
+                )
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            if let Some(amplitude) = args.media_amplitude {
+                hk.set_media_schedule(
+                    amplitude as Float,
+                    args.media_period as Float,
+                    args.media_weight as Float,
+                )
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            }
+            Box::new(hk)
+        }
+        Model::Deffuant => {
+            let mut dw = Deffuant::try_new(
+                args.num_agents,
+                args.min_confidence as Float,
+                args.max_confidence as Float,
+                args.mu.unwrap_or(0.5) as Float,
+                seed,
+            )
+            .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            dw.set_cluster_eps(args.cluster_eps as Float)
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            Box::new(dw)
+        }
+    }
+}
+
 fn main() -> std::io::Result<()> {
     let args = Opt::from_args();
 
-    let mut hk = HegselmannKrause::new(
-        args.num_agents,
-        args.min_confidence as f32,
-        args.max_confidence as f32,
-        args.seed,
-    );
+    if let Some(threads) = args.threads {
+        if threads == 0 {
+            invalid_arguments("--threads must be at least 1");
+        }
+        // the samples of an ensemble are distributed over the threads instead
+        if !args.ensemble {
+            #[cfg(feature = "rayon")]
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+                .unwrap_or_else(|e| invalid_arguments(&e.to_string()));
+            #[cfg(not(feature = "rayon"))]
+            eprintln!("warning: --threads needs the rayon feature, running on one thread");
+        }
+    }
 
-    let mut output = File::create(&args.outname)?;
+    if args.noise > 0. && args.max_sweeps.is_none() {
+        clap::Error::with_description(
+            "a noisy system never converges, please specify --max-sweeps",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
+    if args.turnover > 0. && args.max_sweeps.is_none() {
+        clap::Error::with_description(
+            "an open system never converges, please specify --max-sweeps",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
+    if args.jump_probability > 0. && args.max_sweeps.is_none() {
+        clap::Error::with_description(
+            "a system with random jumps never converges, please specify --max-sweeps",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
+    let oscillating = args.media_amplitude.is_some_and(|a| a > 0.);
+    if oscillating && args.max_sweeps.is_none() {
+        clap::Error::with_description(
+            "a system following an oscillating media never converges, please specify --max-sweeps",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
+    let drifting = args.drift.is_some_and(|v| v != 0.);
+    if drifting && !args.drift_corrected && args.max_sweeps.is_none() {
+        clap::Error::with_description(
+            "a drifting system never converges, please specify --max-sweeps or --drift-corrected",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
 
-    for _ in 0..args.samples {
-        hk.reset();
+    if args.checkpoint_every.is_some() || args.resume_from.is_some() {
+        if cfg!(not(feature = "serde")) {
+            invalid_arguments("--checkpoint-every and --resume-from need the serde feature");
+        }
+        if !matches!(args.model, Model::HegselmannKrause) || args.samples != 1 || args.ensemble {
+            invalid_arguments(
+                "--checkpoint-every and --resume-from need a single sample of the Hegselmann-Krause model",
+            );
+        }
+        if args.checkpoint_every == Some(0) {
+            invalid_arguments("--checkpoint-every must be at least 1");
+        }
+    }
 
-        let mut ctr = 0;
-        loop {
-            ctr += 1;
+    if args.interaction_probability < 1. {
+        eprintln!("warning: --interaction-probability below 1 forces the naive algorithm");
+    }
+    if args.perception_noise > 0. {
+        eprintln!("warning: --perception-noise above 0 forces the naive algorithm");
+    }
 
-            hk.sweep();
+    // agents of the Hegselmann-Krause model with small mu move only a fraction of the way
+    // per sweep, such that the criterion for convergence has to be scaled accordingly
+    let threshold = match (args.threshold, &args.model) {
+        (Some(threshold), _) => threshold as Float,
+        (None, Model::HegselmannKrause) => 1e-4 * args.mu.unwrap_or(1.) as Float,
+        (None, Model::Deffuant) => 1e-4,
+    };
+    let criterion = match args.criterion {
+        Criterion::Sum => ConvergenceCriterion::SumChange { threshold },
+        Criterion::Max => ConvergenceCriterion::MaxChange { threshold },
+        Criterion::Clusters => ConvergenceCriterion::ClusterStability {
+            sweeps: args.stable_sweeps,
+        },
+    };
 
-            // test if we are converged
-            if hk.accumulated_change < 1e-4 {
-                writeln!(output, "# sweeps: {}", ctr)?;
-                break;
+    // open systems, systems with random jumps and systems following an oscillating media
+    // are simulated for a fixed number of sweeps
+    let fixed_sweeps = args.turnover > 0. || args.jump_probability > 0. || oscillating;
+
+    let mut output = BufWriter::new(File::create(&args.outname)?);
+
+    if args.ensemble {
+        let workers = args.threads.unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        });
+        run_ensemble(&args, workers, criterion, fixed_sweeps, &mut output)?;
+    } else {
+        let (mut model, resumed) = match &args.resume_from {
+            #[cfg(feature = "serde")]
+            Some(path) => {
+                let file = std::io::BufReader::new(File::open(path)?);
+                let hk = HegselmannKrause::load_checkpoint(file).map_err(|e| {
+                    std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+                })?;
+                let sweeps = hk.sweep_count();
+                (Box::new(hk) as Box<dyn Simulation>, Some(sweeps))
             }
-            hk.accumulated_change = 0.;
+            _ => (build_model(&args, args.seed), None),
+        };
+        for sample in 0..args.samples {
+            run_sample(
+                model.as_mut(),
+                &args,
+                sample,
+                resumed,
+                criterion,
+                fixed_sweeps,
+                &mut output,
+            )?;
         }
-        hk.write_cluster_sizes(&mut output)?;
     }
 
-    Ok(())
+    output.flush()
+}
+
+/// the seed of sample `sample`, which is independent of the samples before
+fn sample_seed(args: &Opt, sample: u32) -> u64 {
+    args.seed.wrapping_add(sample as u64)
+}
+
+/// the file to which the checkpoints of a run writing to `outname` are written
+fn checkpoint_path(outname: &Path) -> PathBuf {
+    let mut name = outname.as_os_str().to_owned();
+    name.push(".checkpoint");
+    PathBuf::from(name)
+}
+
+/// write a checkpoint of `model` via a temporary file, such that a run killed while
+/// writing keeps its previous checkpoint
+fn write_checkpoint(model: &dyn Simulation, outname: &Path) -> std::io::Result<()> {
+    let path = checkpoint_path(outname);
+    let mut tmp = path.clone().into_os_string();
+    tmp.push(".tmp");
+    let mut file = BufWriter::new(File::create(&tmp)?);
+    model.save_checkpoint(&mut file)?;
+    file.flush()?;
+    drop(file);
+    std::fs::rename(&tmp, &path)
+}
+
+/// simulate one sample from a fresh initial state until convergence or the maximum number
+/// of sweeps and write its block of output, which starts with the seed of the sample
+/// a model `resumed` from a checkpoint after the given number of sweeps is not reset
+/// a failure of the model is reported as an error naming the sample and the sweep, the
+/// output written so far is kept
+fn run_sample(
+    model: &mut dyn Simulation,
+    args: &Opt,
+    sample: u32,
+    resumed: Option<usize>,
+    criterion: ConvergenceCriterion,
+    fixed_sweeps: bool,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    let seed = sample_seed(args, sample);
+    if resumed.is_none() {
+        model.reset_with_seed(seed);
+    }
+    writeln!(output, "# seed: {}", seed)?;
+
+    // the change never drops below 0, such that exactly max-sweeps sweeps are done
+    let criterion = if fixed_sweeps {
+        ConvergenceCriterion::SumChange { threshold: 0. }
+    } else {
+        criterion
+    };
+    let max_sweeps = Some(args.max_sweeps.unwrap_or(DEFAULT_MAX_SWEEPS) as usize);
+    let failed = |e: HkError| std::io::Error::other(format!("sample {}: {}", sample, e));
+    let (sweeps, converged) = if criterion.accepts_static() && model.is_static() {
+        // a system, which no sweep can change, is converged without another sweep in
+        // both loops, like in `run_to_convergence`
+        (resumed.unwrap_or(0), true)
+    } else if args.trajectory || args.checkpoint_every.is_some() || resumed.is_some() {
+        // sweep by sweep, to write the state or a checkpoint after every sweep
+        // a resumed criterion of cluster stability starts counting anew
+        let mut check = ConvergenceCheck::new(criterion);
+        let single_sweep = ConvergenceCriterion::SumChange { threshold: 0. };
+        let mut ctr = resumed.unwrap_or(0);
+        loop {
+            let result = model
+                .run_to_convergence(single_sweep, Some(1))
+                .map_err(failed)?;
+            ctr += result.sweeps;
+            if args.trajectory {
+                model.write_media_opinion(output)?;
+                model.write_cluster_sizes(output)?;
+            }
+            if args.checkpoint_every.is_some_and(|every| ctr % every == 0) {
+                write_checkpoint(model, &args.outname)?;
+            }
+
+            // test if we are converged or reached the maximum number of sweeps
+            let capped = max_sweeps.is_some_and(|max| ctr >= max);
+            let converged = check.converged(model.last_change(), model.last_max_change(), || {
+                result.cluster_sizes
+            });
+            if converged || capped {
+                break (ctr, converged);
+            }
+        }
+    } else {
+        let result = model
+            .run_to_convergence(criterion, max_sweeps)
+            .map_err(failed)?;
+        (result.sweeps, result.converged)
+    };
+    // systems with a fixed number of sweeps are not expected to converge
+    if !converged && !fixed_sweeps {
+        writeln!(output, "# NOT CONVERGED after {} sweeps", sweeps)?;
+    }
+    writeln!(output, "# sweeps: {}", sweeps)?;
+    if args.composition {
+        model.write_cluster_composition(output)
+    } else if args.extremists {
+        model.write_cluster_extremists(output)
+    } else {
+        model.write_cluster_sizes(output)
+    }
+}
+
+/// simulate the samples on `workers` threads, where every sample is a model of its own
+/// constructed with the seed of the sample, and write their blocks in the order of the
+/// samples, such that the output does not depend on the number of workers
+fn run_ensemble(
+    args: &Opt,
+    workers: usize,
+    criterion: ConvergenceCriterion,
+    fixed_sweeps: bool,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    let next = AtomicU32::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..workers.min(args.samples as usize) {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let sample = next.fetch_add(1, Ordering::Relaxed);
+                if sample >= args.samples {
+                    break;
+                }
+                let mut model = build_model(args, sample_seed(args, sample));
+                let mut block = Vec::new();
+                // writing to memory cannot fail, only the model can
+                let result = run_sample(
+                    model.as_mut(),
+                    args,
+                    sample,
+                    None,
+                    criterion,
+                    fixed_sweeps,
+                    &mut block,
+                );
+                // the writer is gone, if it failed
+                if sender.send((sample, result.map(|_| block))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // blocks of later samples wait until all earlier ones are written, a failed sample
+        // stops the output after the blocks of all samples before it
+        let mut pending = BTreeMap::new();
+        let mut written = 0;
+        for (sample, block) in receiver {
+            pending.insert(sample, block);
+            while let Some(block) = pending.remove(&written) {
+                output.write_all(&block?)?;
+                written += 1;
+            }
+        }
+        Ok(())
+    })
 }
@@ -0,0 +1,89 @@
+/// This file implements generators for social networks, which restrict the interactions
+/// of the agents. A network is represented as adjacency lists, i.e., for every agent
+/// the sorted list of indices of all other agents it can see.
+use rand::Rng;
+
+/// standard graphs, which can be used as social networks
+#[derive(Clone, Debug, PartialEq)]
+pub enum Network {
+    /// every agent is connected to every other agent
+    Complete,
+    /// agents are arranged on a ring and connected to their `k` nearest neighbors on each side
+    Ring { k: u32 },
+    /// every pair of agents is connected with probability `p`
+    ErdosRenyi { p: f64 },
+}
+
+impl Network {
+    /// generate the adjacency lists of this network for `n` agents
+    /// random networks draw from the given random number generator
+    pub fn edges<R: Rng>(&self, n: u32, rng: &mut R) -> Vec<Vec<u32>> {
+        match *self {
+            Network::Complete => complete(n),
+            Network::Ring { k } => ring(n, k),
+            Network::ErdosRenyi { p } => erdos_renyi(n, p, rng),
+        }
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = String;
+
+    /// parse networks of the form `complete`, `ring:<k>` or `er:<p>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let parameter = parts.next();
+        match (name, parameter) {
+            ("complete", None) => Ok(Network::Complete),
+            ("ring", Some(k)) => k
+                .parse()
+                .map(|k| Network::Ring { k })
+                .map_err(|e| format!("invalid k '{}': {}", k, e)),
+            ("er", Some(p)) => p
+                .parse()
+                .map(|p| Network::ErdosRenyi { p })
+                .map_err(|e| format!("invalid p '{}': {}", p, e)),
+            _ => Err(format!(
+                "unknown network '{}', use 'complete', 'ring:<k>' or 'er:<p>'",
+                s
+            )),
+        }
+    }
+}
+
+/// complete graph of `n` nodes
+pub fn complete(n: u32) -> Vec<Vec<u32>> {
+    (0..n)
+        .map(|i| (0..n).filter(|&j| j != i).collect())
+        .collect()
+}
+
+/// ring of `n` nodes, each connected to its `k` nearest neighbors on either side
+pub fn ring(n: u32, k: u32) -> Vec<Vec<u32>> {
+    (0..n)
+        .map(|i| {
+            let mut neighbors: Vec<u32> = (1..=k)
+                .flat_map(|d| vec![(i + d) % n, (i + n - d % n) % n])
+                .filter(|&j| j != i)
+                .collect();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            neighbors
+        })
+        .collect()
+}
+
+/// Erdős–Rényi random graph of `n` nodes, where every edge exists with probability `p`
+pub fn erdos_renyi<R: Rng>(n: u32, p: f64, rng: &mut R) -> Vec<Vec<u32>> {
+    let mut edges = vec![Vec::new(); n as usize];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if rng.gen::<f64>() < p {
+                edges[i as usize].push(j);
+                edges[j as usize].push(i);
+            }
+        }
+    }
+    edges
+}
@@ -0,0 +1,60 @@
+//! the output of the clusters shared by all models, every model only needs to write its
+//! clusters in the format of the output files
+use std::io::Write;
+
+use crate::coupled::CoupledHegselmannKrause;
+use crate::deffuant::Deffuant;
+use crate::degroot::DeGroot;
+use crate::hegselmannkrause::HegselmannKrause;
+use crate::hegselmannkrause_nd::HegselmannKrauseND;
+use crate::lattice::HegselmannKrauseLattice;
+
+/// models, which write the positions and sizes of their clusters
+pub trait ClusterOutput {
+    /// write the positions and sizes of the clusters in the format of the output files
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()>;
+
+    /// the output of `write_cluster_sizes` as a string, e.g., for a quick look at the clusters
+    fn cluster_sizes_string(&self) -> String {
+        let mut buffer = Vec::new();
+        // writing to memory cannot fail
+        self.write_cluster_sizes(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl ClusterOutput for HegselmannKrause {
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        HegselmannKrause::write_cluster_sizes(self, file)
+    }
+}
+
+impl ClusterOutput for Deffuant {
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        Deffuant::write_cluster_sizes(self, file)
+    }
+}
+
+impl ClusterOutput for DeGroot {
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        DeGroot::write_cluster_sizes(self, file)
+    }
+}
+
+impl ClusterOutput for HegselmannKrauseLattice {
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        HegselmannKrauseLattice::write_cluster_sizes(self, file)
+    }
+}
+
+impl ClusterOutput for CoupledHegselmannKrause {
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        CoupledHegselmannKrause::write_cluster_sizes(self, file)
+    }
+}
+
+impl<const D: usize> ClusterOutput for HegselmannKrauseND<D> {
+    fn write_cluster_sizes<W: Write + ?Sized>(&self, file: &mut W) -> std::io::Result<()> {
+        HegselmannKrauseND::write_cluster_sizes(self, file)
+    }
+}
@@ -0,0 +1,185 @@
+/// This file implements schedules, which change the confidences of the agents over time,
+/// e.g., to model opinions hardening with age, and profiles, which make the confidences
+/// depend on the current opinions, e.g., to model open-minded moderates
+use crate::error::HkError;
+use crate::float::Float;
+
+/// time dependence of the confidences of all agents
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfidenceSchedule {
+    /// the confidences never change
+    Constant,
+    /// confidences decay as `eps(t) = eps(0) exp(-t / tau)`, but not below `floor`
+    ExponentialDecay { tau: Float, floor: Float },
+    /// confidences decay as `eps(t) = eps(0) - rate t`, but not below `floor`
+    LinearDecay { rate: Float, floor: Float },
+}
+
+impl ConfidenceSchedule {
+    /// check that the parameters describe a proper schedule
+    pub fn validate(&self) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidConfidenceSchedule(reason));
+        match *self {
+            ConfidenceSchedule::Constant => {}
+            ConfidenceSchedule::ExponentialDecay { tau, floor } => {
+                if tau.is_nan() || tau <= 0. {
+                    return invalid(format!("decay time {} needs to be positive", tau));
+                }
+                if !(floor.is_finite() && floor >= 0.) {
+                    return invalid(format!("floor {} needs to be non-negative", floor));
+                }
+            }
+            ConfidenceSchedule::LinearDecay { rate, floor } => {
+                if !(rate.is_finite() && rate >= 0.) {
+                    return invalid(format!("rate {} needs to be non-negative", rate));
+                }
+                if !(floor.is_finite() && floor >= 0.) {
+                    return invalid(format!("floor {} needs to be non-negative", floor));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// confidence after `t` sweeps of an agent with the initial confidence `initial`
+    /// an initial confidence below the floor is never raised
+    pub fn confidence(&self, initial: Float, t: u64) -> Float {
+        match *self {
+            ConfidenceSchedule::Constant => initial,
+            ConfidenceSchedule::ExponentialDecay { tau, floor } => {
+                (initial * (-(t as Float) / tau).exp()).max(floor.min(initial))
+            }
+            ConfidenceSchedule::LinearDecay { rate, floor } => {
+                (initial - rate * t as Float).max(floor.min(initial))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ConfidenceSchedule {
+    type Err = String;
+
+    /// parse schedules of the form `constant`, `exp:<tau>:<floor>` or `linear:<rate>:<floor>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |x: &str| {
+            x.parse::<Float>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        match parts.as_slice() {
+            ["constant"] => Ok(ConfidenceSchedule::Constant),
+            ["exp", tau, floor] => Ok(ConfidenceSchedule::ExponentialDecay {
+                tau: number(tau)?,
+                floor: number(floor)?,
+            }),
+            ["linear", rate, floor] => Ok(ConfidenceSchedule::LinearDecay {
+                rate: number(rate)?,
+                floor: number(floor)?,
+            }),
+            _ => Err(format!(
+                "unknown confidence schedule '{}', use 'constant', 'exp:<tau>:<floor>' or 'linear:<rate>:<floor>'",
+                s
+            )),
+        }
+    }
+}
+
+/// dependence of the confidence of an agent on its current opinion
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConfidenceFunction {
+    /// the confidences do not depend on the opinions
+    Constant,
+    /// `eps(x) = eps0 (1 - |2x - 1|)`, i.e., moderates are the most open-minded
+    Tent { eps0: Float },
+    /// piecewise linear interpolation between the points `(x, eps)`, sorted by `x`,
+    /// constant beyond the first and last point
+    Table(Vec<(Float, Float)>),
+}
+
+impl ConfidenceFunction {
+    /// check that the parameters describe a proper profile
+    pub fn validate(&self) -> Result<(), HkError> {
+        let invalid = |reason: String| Err(HkError::InvalidConfidenceProfile(reason));
+        match self {
+            ConfidenceFunction::Constant => {}
+            ConfidenceFunction::Tent { eps0 } => {
+                if !(eps0.is_finite() && *eps0 >= 0.) {
+                    return invalid(format!("confidence {} needs to be non-negative", eps0));
+                }
+            }
+            ConfidenceFunction::Table(points) => {
+                if points.is_empty() {
+                    return invalid("the table needs at least one point".to_string());
+                }
+                if let Some((x, eps)) = points
+                    .iter()
+                    .find(|(x, eps)| !(x.is_finite() && eps.is_finite() && *eps >= 0.))
+                {
+                    return invalid(format!(
+                        "point ({}, {}) needs a finite position and non-negative confidence",
+                        x, eps
+                    ));
+                }
+                if points.windows(2).any(|w| w[0].0 >= w[1].0) {
+                    return invalid("the points need to be sorted by their position".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// confidence of an agent with opinion `x`, `None` if it does not depend on `x`
+    pub fn confidence(&self, x: Float) -> Option<Float> {
+        match self {
+            ConfidenceFunction::Constant => None,
+            ConfidenceFunction::Tent { eps0 } => Some(eps0 * (1. - (2. * x - 1.).abs())),
+            ConfidenceFunction::Table(points) => {
+                let first = points[0];
+                let last = points[points.len() - 1];
+                if x <= first.0 {
+                    return Some(first.1);
+                }
+                if x >= last.0 {
+                    return Some(last.1);
+                }
+                let k = points.partition_point(|p| p.0 <= x);
+                let (x0, e0) = points[k - 1];
+                let (x1, e1) = points[k];
+                Some(e0 + (x - x0) / (x1 - x0) * (e1 - e0))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ConfidenceFunction {
+    type Err = String;
+
+    /// parse profiles of the form `constant`, `tent:<eps0>` or `table:<x>=<eps>,<x>=<eps>,...`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |x: &str| {
+            x.parse::<Float>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        let parts: Vec<&str> = s.splitn(2, ':').collect();
+        match parts.as_slice() {
+            ["constant"] => Ok(ConfidenceFunction::Constant),
+            ["tent", eps0] => Ok(ConfidenceFunction::Tent {
+                eps0: number(eps0)?,
+            }),
+            ["table", points] => points
+                .split(',')
+                .map(|point| match point.split_once('=') {
+                    Some((x, eps)) => Ok((number(x)?, number(eps)?)),
+                    None => Err(format!("invalid point '{}', use '<x>=<eps>'", point)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(ConfidenceFunction::Table),
+            _ => Err(format!(
+                "unknown confidence profile '{}', use 'constant', 'tent:<eps0>' or 'table:<x>=<eps>,...'",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+extern crate hk;
+use hk::{ActivityDistribution, Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_always_active_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_activity_distribution(ActivityDistribution::Always)
+            .unwrap();
+        hk1.reset();
+        hk2.reset();
+        assert_eq!(hk1.opinions(), hk2.opinions());
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_activity_distribution(ActivityDistribution::Constant { value: 1. })
+            .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_inactive_agents_keep_opinion() {
+        let mut hk = HegselmannKrause::new(100, 0., 1., 13);
+        hk.set_activity_distribution(ActivityDistribution::Constant { value: 0. })
+            .unwrap();
+        hk.reset();
+        let initial = hk.opinions();
+
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), initial);
+        assert_eq!(hk.last_change(), 0.);
+    }
+
+    #[test]
+    fn test_inactive_agent_influences_others() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_activity_distribution(ActivityDistribution::Uniform { low: 0., high: 1. })
+            .unwrap();
+        hk.reset();
+        let activities = hk.activities();
+        assert!(activities.iter().all(|&a| (0. ..=1.).contains(&a)));
+
+        // repeat until exactly one agent moved, it has to move to the mean of both
+        loop {
+            hk.set_state(&[0.25, 0.75], &[1., 1.]).unwrap();
+            hk.sweep_tree();
+            let opinions = hk.opinions();
+            let moved = opinions.iter().filter(|&&x| x == 0.5).count();
+            if moved == 1 {
+                assert!(opinions.contains(&0.25) || opinions.contains(&0.75));
+                assert_eq!(hk.last_change(), 0.25);
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_activity_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        let activity = ActivityDistribution::Uniform {
+            low: 0.2,
+            high: 0.8,
+        };
+        hk1.set_activity_distribution(activity.clone()).unwrap();
+        hk2.set_activity_distribution(activity).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_activities() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk
+            .set_activity_distribution(ActivityDistribution::Constant { value: 1.5 })
+            .is_err());
+        assert!(hk
+            .set_activity_distribution(ActivityDistribution::Uniform {
+                low: 0.8,
+                high: 0.2
+            })
+            .is_err());
+        assert!(hk
+            .set_activity_distribution(ActivityDistribution::Constant { value: Float::NAN })
+            .is_err());
+        assert_eq!(
+            "uniform:0.1:0.9".parse::<ActivityDistribution>(),
+            Ok(ActivityDistribution::Uniform {
+                low: 0.1,
+                high: 0.9
+            })
+        );
+        assert!("sometimes".parse::<ActivityDistribution>().is_err());
+    }
+}
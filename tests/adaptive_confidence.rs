@@ -0,0 +1,91 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_adaptation_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.enable_adaptive_confidence(0.01, 0., 1.).unwrap();
+        hk2.disable_adaptive_confidence();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.confidences(), hk2.confidences());
+        }
+    }
+
+    #[test]
+    fn test_isolated_agents_reach_cluster() {
+        // a cluster of five agents and an isolated pair far away
+        let mut hk = HegselmannKrause::new(7, 0., 1., 13);
+        let opinions = [0.2, 0.2, 0.2, 0.2, 0.2, 0.7, 0.72];
+        hk.set_state(&opinions, &[0.05; 7]).unwrap();
+        hk.enable_adaptive_confidence(0.05, 0.01, 1.).unwrap();
+
+        // the pair sees each other, such that its confidence grows in every sweep
+        // until it reaches the cluster
+        let mut confidence = hk.confidences()[6];
+        let mut sweeps = 0;
+        while hk.cluster_sizes().len() > 1 {
+            hk.sweep_tree();
+            sweeps += 1;
+            let c = hk.confidences()[6];
+            assert!(c > confidence);
+            confidence = c;
+            assert!(sweeps < 20);
+        }
+        assert!(confidence > 0.45);
+        assert_eq!(hk.cluster_sizes(), vec![7]);
+    }
+
+    #[test]
+    fn test_single_agent_shrinks() {
+        let mut hk = HegselmannKrause::new(1, 0., 1., 13);
+        hk.set_state(&[0.5], &[0.3]).unwrap();
+        hk.enable_adaptive_confidence(0.1, 0.05, 1.).unwrap();
+
+        // without anybody else, the confidence decreases down to its lower bound
+        hk.sweep_naive();
+        assert!((hk.confidences()[0] - 0.2).abs() < 1e-6);
+        for _ in 0..5 {
+            hk.sweep_naive();
+        }
+        assert_eq!(hk.confidences(), vec![0.05]);
+    }
+
+    #[test]
+    fn test_cmp_adaptive_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.1, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.1, 13);
+        hk1.enable_adaptive_confidence(0.01, 0.01, 0.3).unwrap();
+        hk2.enable_adaptive_confidence(0.01, 0.01, 0.3).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_adaptive_confidence() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.enable_adaptive_confidence(-0.1, 0., 1.).is_err());
+        assert!(hk.enable_adaptive_confidence(Float::NAN, 0., 1.).is_err());
+        assert!(hk.enable_adaptive_confidence(0.1, 0.5, 0.2).is_err());
+        assert!(hk.enable_adaptive_confidence(0.1, -0.1, 0.2).is_err());
+        assert!(hk
+            .enable_adaptive_confidence(0.1, 0., Float::INFINITY)
+            .is_err());
+        // a failed call keeps the confidences constant
+        let confidences = hk.confidences();
+        hk.sweep();
+        assert_eq!(hk.confidences(), confidences);
+    }
+}
@@ -0,0 +1,190 @@
+extern crate hk;
+use hk::{Aggregator, Float, HegselmannKrause, OpinionSpace, WeightDistribution};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_aggregator(Aggregator::Median).unwrap();
+        hk2.set_aggregator(Aggregator::Mean).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        for &naive in &[true, false] {
+            for &(opinions, expected) in &[
+                (&[0.1, 0.2, 0.9][..], 0.2),
+                (&[0.1, 0.2, 0.3, 0.9][..], 0.25),
+            ] {
+                let n = opinions.len();
+                let mut hk = HegselmannKrause::new(n as u32, 0., 1., 13);
+                hk.set_state(opinions, &vec![1.; n]).unwrap();
+                hk.set_aggregator(Aggregator::Median).unwrap();
+                if naive {
+                    hk.sweep_naive();
+                } else {
+                    hk.sweep_tree();
+                }
+                for x in hk.opinions() {
+                    assert!((x - expected).abs() < 1e-6, "{} != {}", x, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_trimmed_mean() {
+        for &naive in &[true, false] {
+            for &(alpha, expected) in &[(0.2, 0.2), (0.1, 0.275), (0., 0.32)] {
+                let mut hk = HegselmannKrause::new(5, 0., 1., 13);
+                hk.set_state(&[0., 0.1, 0.2, 0.3, 1.], &[2.; 5]).unwrap();
+                hk.set_aggregator(Aggregator::TrimmedMean { alpha })
+                    .unwrap();
+                if naive {
+                    hk.sweep_naive();
+                } else {
+                    hk.sweep_tree();
+                }
+                for x in hk.opinions() {
+                    assert!((x - expected).abs() < 1e-6, "{} != {}", x, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_aggregator_naive_tree() {
+        for &aggregator in &[Aggregator::Median, Aggregator::TrimmedMean { alpha: 0.25 }] {
+            let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+            let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+            hk1.set_aggregator(aggregator).unwrap();
+            hk2.set_aggregator(aggregator).unwrap();
+
+            for _ in 0..50 {
+                hk1.sweep_naive();
+                hk2.sweep();
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_aggregator() {
+        assert_eq!("mean".parse::<Aggregator>(), Ok(Aggregator::Mean));
+        assert_eq!("median".parse::<Aggregator>(), Ok(Aggregator::Median));
+        assert_eq!(
+            "trimmed:0.1".parse::<Aggregator>(),
+            Ok(Aggregator::TrimmedMean { alpha: 0.1 })
+        );
+        assert!("trimmed".parse::<Aggregator>().is_err());
+        assert!("mode".parse::<Aggregator>().is_err());
+    }
+
+    #[test]
+    fn test_invalid_aggregator() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        assert!(hk
+            .set_aggregator(Aggregator::TrimmedMean { alpha: 0.5 })
+            .is_err());
+        assert!(hk
+            .set_aggregator(Aggregator::TrimmedMean { alpha: -0.1 })
+            .is_err());
+        assert!(hk
+            .set_aggregator(Aggregator::TrimmedMean { alpha: 0.4 })
+            .is_ok());
+
+        let mut hk = HegselmannKrause::with_topology(10, 0.1, 0.3, 13, OpinionSpace::Circle);
+        assert!(hk.set_aggregator(Aggregator::Median).is_err());
+        assert!(hk.set_aggregator(Aggregator::Mean).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod trimmed_mean_tests {
+    use super::*;
+
+    #[test]
+    fn test_untrimmed_equals_mean() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk3 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_aggregator(Aggregator::TrimmedMean { alpha: 0. })
+            .unwrap();
+        hk3.set_aggregator(Aggregator::TrimmedMean { alpha: 0. })
+            .unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            hk3.sweep_naive();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+            assert!(hk1.approx_eq(&hk3, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_trimmed_naive_tree() {
+        for &alpha in &[0., 0.05, 0.1, 0.2, 0.3, 0.45] {
+            let mut hk1 = HegselmannKrause::new(200, 0.05, 0.4, 17);
+            let mut hk2 = HegselmannKrause::new(200, 0.05, 0.4, 17);
+            hk1.set_aggregator(Aggregator::TrimmedMean { alpha })
+                .unwrap();
+            hk2.set_aggregator(Aggregator::TrimmedMean { alpha })
+                .unwrap();
+
+            for _ in 0..50 {
+                hk1.sweep_naive();
+                hk2.sweep_tree();
+                assert!(hk1.approx_eq(&hk2, 1e-5), "alpha = {}", alpha);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trimmed_within_single_entry() {
+        // all weight but the outliers sits on one opinion, which is cut from both ends
+        for &naive in &[true, false] {
+            let mut hk = HegselmannKrause::new(6, 0., 1., 13);
+            hk.set_state(&[0., 0.5, 0.5, 0.5, 0.5, 1.], &[2.; 6])
+                .unwrap();
+            hk.set_aggregator(Aggregator::TrimmedMean { alpha: 0.3 })
+                .unwrap();
+            if naive {
+                hk.sweep_naive();
+            } else {
+                hk.sweep_tree();
+            }
+            for x in hk.opinions() {
+                assert!((x - 0.5).abs() < 1e-6, "{}", x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trimmed_almost_everything() {
+        // the cumulative sums of non-unit weights differ from the walked weights by rounding
+        // errors, which must neither trim away the whole range nor leave a negative weight
+        let alpha = 0.5 - Float::EPSILON;
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.4, 17);
+        hk.set_weight_distribution(WeightDistribution::Uniform { low: 0.1, high: 3. })
+            .unwrap();
+        hk.set_aggregator(Aggregator::TrimmedMean { alpha })
+            .unwrap();
+        for _ in 0..20 {
+            hk.sweep_tree();
+            for x in hk.opinions() {
+                assert!((0. ..=1.).contains(&x), "{}", x);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_anchoring_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_anchoring(0., 0.).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_full_anchoring_freezes() {
+        for &naive in &[true, false] {
+            let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+            hk.set_anchoring(1., 1.).unwrap();
+            // the anchors are captured at the reset
+            hk.reset();
+            let initial = hk.opinions();
+
+            for _ in 0..3 {
+                if naive {
+                    hk.sweep_naive();
+                } else {
+                    hk.sweep();
+                }
+                assert_eq!(hk.opinions(), initial);
+                assert_eq!(hk.last_change(), 0.);
+            }
+        }
+    }
+
+    #[test]
+    fn test_anchoring_prevents_consensus() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.2, 0.4], &[0.5, 0.5]).unwrap();
+        hk.set_anchoring(0.5, 0.5).unwrap();
+
+        // both agents settle halfway between the mean 0.3 and their initial opinions
+        for _ in 0..10 {
+            hk.sweep();
+        }
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.25).abs() < 1e-6);
+        assert!((opinions[1] - 0.35).abs() < 1e-6);
+        assert_eq!(hk.cluster_sizes(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_anchoring_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk1.set_anchoring(0., 0.5).unwrap();
+        hk2.set_anchoring(0., 0.5).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_anchoring() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        assert!(hk.set_anchoring(-0.1, 0.5).is_err());
+        assert!(hk.set_anchoring(0.5, 0.1).is_err());
+        assert!(hk.set_anchoring(0.5, 1.1).is_err());
+        assert!(hk.set_anchoring(Float::NAN, 0.5).is_err());
+        assert!(hk.set_anchoring(0.1, 0.5).is_ok());
+    }
+}
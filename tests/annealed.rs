@@ -0,0 +1,70 @@
+extern crate hk;
+use hk::{ConfidenceDistribution, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_annealed_equals_quenched() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        for hk in [&mut hk1, &mut hk2].iter_mut() {
+            hk.set_confidence_distribution(ConfidenceDistribution::Constant { value: 0.15 })
+                .unwrap();
+            hk.reset();
+        }
+        hk2.set_annealed_confidences(true);
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.confidences(), hk2.confidences());
+        }
+    }
+
+    #[test]
+    fn test_confidences_redrawn() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.set_annealed_confidences(true);
+
+        let mut previous = hk.confidences();
+        for _ in 0..10 {
+            hk.sweep();
+            let confidences = hk.confidences();
+            assert_ne!(confidences, previous);
+            assert!(confidences.iter().all(|&c| (0.1..=0.3).contains(&c)));
+            previous = confidences;
+        }
+    }
+
+    #[test]
+    fn test_annealed_reproducible() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk1.set_annealed_confidences(true);
+        hk2.set_annealed_confidences(true);
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.confidences(), hk2.confidences());
+        }
+    }
+
+    #[test]
+    fn test_disabling_restores_quenched() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_annealed_confidences(true);
+        hk2.set_annealed_confidences(false);
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+}
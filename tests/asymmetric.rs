@@ -0,0 +1,90 @@
+extern crate hk;
+use hk::{HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_sides_reproduce_symmetric() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+
+        // explicitly set both sides to the same values
+        let opinions = hk1.opinions();
+        let confidences = hk1.confidences();
+        hk2.set_state_asymmetric(&opinions, &confidences, &confidences)
+            .unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_state_asymmetric(&opinions, &confidences, &confidences)
+            .unwrap();
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_asymmetric_pair() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        // the first agent only looks up, the second only looks down
+        hk.set_state_asymmetric(&[0.4, 0.6], &[0.01, 0.3], &[0.3, 0.01])
+            .unwrap();
+        hk.sweep_naive();
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.5).abs() < 1e-6);
+        assert!((opinions[1] - 0.5).abs() < 1e-6);
+
+        // looking the other way, nobody moves
+        hk.set_state_asymmetric(&[0.4, 0.6], &[0.3, 0.01], &[0.01, 0.3])
+            .unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), vec![0.4, 0.6]);
+    }
+
+    #[test]
+    fn test_cmp_asymmetric_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_asymmetric_confidences(0., 0.1, 0.1, 0.4).unwrap();
+        hk2.set_asymmetric_confidences(0., 0.1, 0.1, 0.4).unwrap();
+        hk1.reset();
+        hk2.reset();
+        assert!(hk1
+            .confidences()
+            .iter()
+            .zip(hk1.confidences_right().iter())
+            .all(|(l, r)| l <= r));
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_asymmetric_circle() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, OpinionSpace::Circle);
+        hk1.set_asymmetric_confidences(0., 0.1, 0.2, 0.4).unwrap();
+        hk2.set_asymmetric_confidences(0., 0.1, 0.2, 0.4).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+extern crate hk;
+use hk::{Boundary, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp() {
+        assert_eq!(Boundary::Clamp.apply(-0.5), 0.);
+        assert_eq!(Boundary::Clamp.apply(1.5), 1.);
+        assert_eq!(Boundary::Clamp.apply(0.25), 0.25);
+    }
+
+    #[test]
+    fn test_reflect() {
+        assert_eq!(Boundary::Reflect.apply(1.25), 0.75);
+        assert_eq!(Boundary::Reflect.apply(-0.25), 0.25);
+        // beyond 2 and below -1, the opinion is folded multiple times
+        assert_eq!(Boundary::Reflect.apply(2.5), 0.5);
+        assert_eq!(Boundary::Reflect.apply(3.25), 0.75);
+        assert_eq!(Boundary::Reflect.apply(-1.5), 0.5);
+        assert_eq!(Boundary::Reflect.apply(-2.75), 0.75);
+        assert_eq!(Boundary::Reflect.apply(0.25), 0.25);
+        assert_eq!(Boundary::Reflect.apply(1.), 1.);
+    }
+
+    #[test]
+    fn test_periodic() {
+        assert_eq!(Boundary::Periodic.apply(1.25), 0.25);
+        assert_eq!(Boundary::Periodic.apply(-0.25), 0.75);
+        assert_eq!(Boundary::Periodic.apply(2.5), 0.5);
+        assert_eq!(Boundary::Periodic.apply(1.), 0.);
+        assert_eq!(Boundary::Periodic.apply(0.25), 0.25);
+    }
+
+    #[test]
+    fn test_open() {
+        assert_eq!(Boundary::Open.apply(1.5), 1.5);
+        assert_eq!(Boundary::Open.apply(-0.5), -0.5);
+    }
+
+    #[test]
+    fn test_open_drift_leaves_interval() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.875, 0.125], &[0.1, 0.1]).unwrap();
+        hk.set_drift(0.25, Boundary::Open).unwrap();
+        hk.sweep_tree();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), vec![1.375, 0.625]);
+    }
+
+    #[test]
+    fn test_periodic_neighbors_across_boundary() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_boundary(Boundary::Periodic).unwrap();
+        hk.set_state(&[0.99, 0.01], &[0.05, 0.05]).unwrap();
+        hk.sweep_tree();
+
+        // both agents see each other across the boundary and meet at 0
+        let opinions = hk.opinions();
+        for x in opinions {
+            assert!(!(1e-6..=1. - 1e-6).contains(&x));
+        }
+
+        hk.set_boundary(Boundary::Clamp).unwrap();
+        assert_eq!(hk.boundary(), Boundary::Clamp);
+        hk.set_state(&[0.99, 0.01], &[0.05, 0.05]).unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), vec![0.99, 0.01]);
+    }
+
+    #[test]
+    fn test_cmp_boundaries_naive_tree() {
+        for &boundary in &[
+            Boundary::Clamp,
+            Boundary::Reflect,
+            Boundary::Periodic,
+            Boundary::Open,
+        ] {
+            let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+            let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+            hk1.set_drift(0.02, boundary).unwrap();
+            hk2.set_drift(0.02, boundary).unwrap();
+
+            for _ in 0..50 {
+                hk1.sweep_naive();
+                hk2.sweep_tree();
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+extern crate hk;
+use hk::{
+    Aggregator, Boundary, ConfidenceDistribution, Float, HegselmannKrause, HegselmannKrauseBuilder,
+    HkError, InitialOpinions, Kernel, Network,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_invalid_parameter(result: Result<HegselmannKrause, HkError>) -> bool {
+        matches!(result, Err(HkError::InvalidParameter(_)))
+    }
+
+    #[test]
+    fn test_defaults() {
+        // confidences in [0, 1] and seed 1, like the command line
+        let hk = HegselmannKrauseBuilder::new().agents(100).build().unwrap();
+        assert!(hk == HegselmannKrause::new(100, 0., 1., 1));
+        assert_eq!(hk.boundary(), Boundary::Clamp);
+        assert!(hk.opinions().iter().all(|&x| (0. ..=1.).contains(&x)));
+        assert!(hk.confidences().iter().all(|&c| (0. ..=1.).contains(&c)));
+    }
+
+    #[test]
+    fn test_like_constructors() {
+        let hk = HegselmannKrauseBuilder::new()
+            .agents(100)
+            .confidence_range(0.1, 0.3)
+            .seed(13)
+            .build()
+            .unwrap();
+        assert!(hk == HegselmannKrause::new(100, 0.1, 0.3, 13));
+
+        let hk = HegselmannKrauseBuilder::new()
+            .agents(100)
+            .confidence_range(0.2, 0.5)
+            .seed(13)
+            .opinion_interval(-1., 1.)
+            .build()
+            .unwrap();
+        let expected = HegselmannKrause::with_opinion_interval(100, 0.2, 0.5, 13, -1., 1.).unwrap();
+        assert!(hk == expected);
+    }
+
+    #[test]
+    fn test_network_like_constructor() {
+        let network = Network::Ring { k: 2 };
+        let mut hk1 = HegselmannKrauseBuilder::new()
+            .agents(100)
+            .confidence_range(0.1, 0.3)
+            .seed(13)
+            .network(network.clone())
+            .build()
+            .unwrap();
+        let mut hk2 = HegselmannKrause::with_generated_network(100, 0.1, 0.3, 13, &network);
+        assert!(hk1 == hk2);
+        // the neighbors are the same, too
+        for _ in 0..10 {
+            hk1.sweep();
+            hk2.sweep();
+        }
+        assert!(hk1 == hk2);
+    }
+
+    #[test]
+    fn test_like_setters() {
+        let initial_opinions = InitialOpinions::Gaussian { mean: 0.5, sd: 0.1 };
+        let distribution = ConfidenceDistribution::Constant { value: 0.2 };
+        let mut built = HegselmannKrauseBuilder::new()
+            .agents(100)
+            .seed(13)
+            .initial_opinions(initial_opinions.clone())
+            .confidence_distribution(distribution.clone())
+            .kernel(Kernel::Linear)
+            .aggregator(Aggregator::Median)
+            .cluster_eps(1e-3)
+            .build()
+            .unwrap();
+        assert!(built.confidences().iter().all(|&c| c == 0.2));
+
+        let mut configured = HegselmannKrause::new(100, 0., 1., 13);
+        configured.set_initial_opinions(initial_opinions).unwrap();
+        configured
+            .set_confidence_distribution(distribution)
+            .unwrap();
+        configured.set_kernel(Kernel::Linear);
+        configured.set_aggregator(Aggregator::Median).unwrap();
+        configured.set_cluster_eps(1e-3).unwrap();
+
+        built.reset_with_seed(7);
+        configured.reset_with_seed(7);
+        assert!(built == configured);
+        for _ in 0..10 {
+            built.sweep();
+            configured.sweep();
+        }
+        assert!(built == configured);
+        assert_eq!(built.cluster_sizes(), configured.cluster_sizes());
+    }
+
+    #[test]
+    fn test_periodic_boundary() {
+        let hk = HegselmannKrauseBuilder::new()
+            .agents(100)
+            .boundary(Boundary::Periodic)
+            .build()
+            .unwrap();
+        assert_eq!(hk.boundary(), Boundary::Periodic);
+        hk.verify_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_missing_agents() {
+        assert!(is_invalid_parameter(HegselmannKrauseBuilder::new().build()));
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let builder = HegselmannKrauseBuilder::new().agents(10);
+        assert!(matches!(
+            builder.clone().confidence_range(0.3, 0.1).build(),
+            Err(HkError::InvalidConfidenceDistribution(_))
+        ));
+        assert!(is_invalid_parameter(builder.clone().noise(-0.1).build()));
+        assert!(is_invalid_parameter(
+            builder.clone().noise(Float::NAN).build()
+        ));
+        assert!(is_invalid_parameter(
+            builder.clone().cluster_eps(-1.).build()
+        ));
+        assert!(matches!(
+            builder
+                .initial_opinions(InitialOpinions::Custom(vec![0.5; 3]))
+                .build(),
+            Err(HkError::InvalidInitialOpinions(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_combinations() {
+        let builder = HegselmannKrauseBuilder::new()
+            .agents(10)
+            .boundary(Boundary::Periodic);
+        // the circle is the interval [0, 1)
+        assert!(is_invalid_parameter(
+            builder.clone().opinion_interval(-1., 1.).build()
+        ));
+        // statistics other than the mean are only defined on the line
+        assert!(is_invalid_parameter(
+            builder.aggregator(Aggregator::Median).build()
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+// checkpoints only exist with the serde feature
+#![cfg(feature = "serde")]
+extern crate hk;
+use hk::HegselmannKrause;
+
+/// continue `hk` from a checkpoint in memory
+fn roundtrip(hk: &HegselmannKrause) -> HegselmannKrause {
+    let mut buffer = Vec::new();
+    hk.save_checkpoint(&mut buffer).unwrap();
+    HegselmannKrause::load_checkpoint(buffer.as_slice()).unwrap()
+}
+
+/// a run of 100 sweeps and one interrupted by a checkpoint after 50 sweeps end bitwise
+/// identical, the noise needs the state of the random number generator
+fn assert_resumes_exactly<F: Fn(&mut HegselmannKrause)>(setup: F) {
+    let mut uninterrupted = HegselmannKrause::new(300, 0.05, 0.2, 13);
+    setup(&mut uninterrupted);
+    let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+    setup(&mut hk);
+    for _ in 0..50 {
+        uninterrupted.sweep();
+        hk.sweep();
+    }
+    let mut resumed = roundtrip(&hk);
+    drop(hk);
+    assert_eq!(resumed.sweep_count(), 50);
+    for _ in 0..50 {
+        uninterrupted.sweep();
+        resumed.sweep();
+    }
+    assert_eq!(
+        resumed.state_fingerprint(),
+        uninterrupted.state_fingerprint()
+    );
+    assert!(resumed == uninterrupted);
+    assert_eq!(resumed.last_change(), uninterrupted.last_change());
+    resumed.verify_invariants().unwrap();
+}
+
+/// run the command line interface with the given arguments writing to `outname`
+fn run_cli(args: &[&str], outname: &std::path::Path) {
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_hk"))
+        .args(args)
+        .arg("--outname")
+        .arg(outname)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_exactly() {
+        assert_resumes_exactly(|_| {});
+        assert_resumes_exactly(|hk| hk.set_noise(0.01).unwrap());
+        assert_resumes_exactly(|hk| hk.set_turnover(3.).unwrap());
+    }
+
+    #[test]
+    fn test_resume_groups_and_memory() {
+        assert_resumes_exactly(|hk| {
+            let assignments: Vec<u8> = (0..300).map(|i| (i % 2) as u8).collect();
+            hk.set_groups(&assignments, &[vec![0.2, 0.05], vec![0.1, 0.3]])
+                .unwrap();
+        });
+        assert_resumes_exactly(|hk| hk.enable_memory(3).unwrap());
+        assert_resumes_exactly(|hk| hk.set_skip_unchanged(true));
+    }
+
+    #[test]
+    fn test_parameters_restored() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.set_mu(0.3).unwrap();
+        hk.sweep();
+        let resumed = roundtrip(&hk);
+        assert_eq!(resumed.opinions(), hk.opinions());
+        assert_eq!(resumed.confidences(), hk.confidences());
+        assert_eq!(resumed.cluster_sizes(), hk.cluster_sizes());
+
+        // the initial state is part of the checkpoint and can be replayed
+        let mut replayed = roundtrip(&hk);
+        replayed.reset_to_initial();
+        hk.reset_to_initial();
+        assert!(replayed == hk);
+    }
+
+    #[test]
+    fn test_invalid_checkpoint() {
+        let err = HegselmannKrause::load_checkpoint(&[0xff, 0x00, 0x13][..])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut buffer = Vec::new();
+        HegselmannKrause::new(10, 0.1, 0.3, 13)
+            .save_checkpoint(&mut buffer)
+            .unwrap();
+        buffer.truncate(buffer.len() / 2);
+        assert!(HegselmannKrause::load_checkpoint(buffer.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_header_mismatch() {
+        let mut buffer = Vec::new();
+        HegselmannKrause::new(10, 0.1, 0.3, 13)
+            .save_checkpoint(&mut buffer)
+            .unwrap();
+        assert_eq!(&buffer[..4], b"HKCP");
+
+        // another version of the format
+        let mut other = buffer.clone();
+        other[4] += 1;
+        let err = HegselmannKrause::load_checkpoint(other.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // another precision of the opinions
+        let mut other = buffer.clone();
+        other[5] = if other[5] == 4 { 8 } else { 4 };
+        let err = HegselmannKrause::load_checkpoint(other.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // the encoded model without any header
+        let err = HegselmannKrause::load_checkpoint(&buffer[6..])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_resume_in_new_process() {
+        let dir = std::env::temp_dir();
+        let name = |s: &str| dir.join(format!("hk_checkpoint_{}_{}", std::process::id(), s));
+        let args = [
+            "-n", "300", "-l", "0.05", "-u", "0.2", "--noise", "0.01", "--seed", "13",
+        ];
+
+        run_cli(
+            &[&args[..], &["--max-sweeps", "100"]].concat(),
+            &name("full"),
+        );
+        run_cli(
+            &[
+                &args[..],
+                &["--max-sweeps", "50", "--checkpoint-every", "50"],
+            ]
+            .concat(),
+            &name("first"),
+        );
+        let checkpoint = name("first.checkpoint");
+        run_cli(
+            &[
+                &args[..],
+                &["--max-sweeps", "100", "--resume-from"],
+                &[checkpoint.to_str().unwrap()],
+            ]
+            .concat(),
+            &name("second"),
+        );
+
+        let full = std::fs::read_to_string(name("full")).unwrap();
+        let second = std::fs::read_to_string(name("second")).unwrap();
+        assert!(full.contains("# sweeps: 100\n"));
+        assert_eq!(second, full);
+        for s in &["full", "first", "first.checkpoint", "second"] {
+            std::fs::remove_file(name(s)).unwrap();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// distance of two opinions on the circle
+    fn circle_distance(a: Float, b: Float) -> Float {
+        let d = (a - b).abs();
+        d.min(1. - d)
+    }
+
+    #[test]
+    fn test_wrap_around_interaction() {
+        for &tree in &[false, true] {
+            let mut hk = HegselmannKrause::with_topology(2, 0., 1., 13, OpinionSpace::Circle);
+            hk.set_state(&[0.99, 0.01], &[0.05, 0.05]).unwrap();
+            if tree {
+                hk.sweep_tree();
+            } else {
+                hk.sweep_naive();
+            }
+
+            // both agents see each other across the boundary and meet at 0
+            let opinions = hk.opinions();
+            assert!(circle_distance(opinions[0], 0.) < 1e-6);
+            assert!(circle_distance(opinions[1], 0.) < 1e-6);
+            assert!(opinions.iter().all(|&x| (0. ..1.).contains(&x)));
+            assert_eq!(hk.cluster_sizes(), vec![2]);
+        }
+    }
+
+    #[test]
+    fn test_line_does_not_wrap() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.99, 0.01], &[0.05, 0.05]).unwrap();
+        hk.sweep_tree();
+
+        assert_eq!(hk.opinions(), vec![0.99, 0.01]);
+        assert_eq!(hk.cluster_sizes(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_cluster_straddling_boundary() {
+        let mut hk = HegselmannKrause::with_topology(3, 0., 1., 13, OpinionSpace::Circle);
+        hk.set_state(&[0.999_999, 0.000_001, 0.5], &[0.1, 0.1, 0.1])
+            .unwrap();
+
+        assert_eq!(hk.cluster_sizes(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_cmp_circle_naive_tree() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.6, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.6, 13, OpinionSpace::Circle);
+
+        // test that the two methods will yield identical results for 100 sweeps
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+        assert!(hk2.opinions().iter().all(|&x| (0. ..1.).contains(&x)));
+    }
+}
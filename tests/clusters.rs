@@ -0,0 +1,249 @@
+extern crate hk;
+use hk::{ConvergenceCriterion, Deffuant, Float, HegselmannKrause, HkError, OpinionSpace};
+
+/// a system of agents with the given opinions, which do not interact
+#[cfg(test)]
+fn frozen(opinions: &[Float], topology: OpinionSpace) -> HegselmannKrause {
+    let n = opinions.len() as u32;
+    let mut hk = HegselmannKrause::with_topology(n, 0., 0., 13, topology);
+    hk.set_state(opinions, &vec![0.; opinions.len()]).unwrap();
+    hk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_sizes_sum_to_num_agents() {
+        for &topology in &[OpinionSpace::Line, OpinionSpace::Circle] {
+            let mut hk = HegselmannKrause::with_topology(1000, 0.01, 0.1, 13, topology);
+            for _ in 0..20 {
+                let sizes = hk.cluster_sizes();
+                assert_eq!(sizes.iter().sum::<usize>(), 1000);
+                hk.sweep();
+            }
+        }
+    }
+
+    #[test]
+    fn test_clusters_in_ascending_order() {
+        let hk = frozen(&[0.9, 0.1, 0.5, 0.1, 0.9, 0.9], OpinionSpace::Line);
+        assert_eq!(hk.cluster_sizes(), vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn test_clusters_independent_of_order() {
+        // a chain of opinions closer than 1e-5 to their neighbors forms a single cluster,
+        // although its ends are further apart
+        let chain = [0.3, 0.300_006, 0.300_012, 0.300_018, 0.7, 0.700_006];
+        let mut reversed = chain;
+        reversed.reverse();
+        let shuffled = [0.300_012, 0.7, 0.3, 0.700_006, 0.300_018, 0.300_006];
+
+        for opinions in [chain, reversed, shuffled].iter() {
+            let hk = frozen(opinions, OpinionSpace::Line);
+            assert_eq!(hk.cluster_sizes(), vec![4, 2]);
+        }
+    }
+
+    #[test]
+    fn test_cluster_across_zero() {
+        let hk = frozen(
+            &[0.999_999, 0.5, 0.000_001, 0.000_002],
+            OpinionSpace::Circle,
+        );
+        assert_eq!(hk.cluster_sizes(), vec![3, 1]);
+
+        let hk = frozen(&[0.999_999, 0.5, 0.000_001, 0.000_002], OpinionSpace::Line);
+        assert_eq!(hk.cluster_sizes(), vec![2, 1, 1]);
+    }
+}
+
+#[cfg(test)]
+mod members {
+    use super::*;
+
+    fn converged(topology: OpinionSpace) -> HegselmannKrause {
+        let mut hk = HegselmannKrause::with_topology(500, 0.05, 0.15, 13, topology);
+        for _ in 0..100 {
+            hk.sweep();
+        }
+        hk
+    }
+
+    #[test]
+    fn test_members_partition_agents() {
+        for &topology in &[OpinionSpace::Line, OpinionSpace::Circle] {
+            let hk = converged(topology);
+            let members = hk.cluster_members();
+            let mut all: Vec<usize> = members.iter().flatten().cloned().collect();
+            all.sort_unstable();
+            assert_eq!(all, (0..500).collect::<Vec<_>>());
+
+            let sizes: Vec<usize> = members.iter().map(|c| c.len()).collect();
+            assert_eq!(sizes, hk.cluster_sizes());
+        }
+    }
+
+    #[test]
+    fn test_cluster_of() {
+        let hk = converged(OpinionSpace::Line);
+        let opinions = hk.opinions();
+        for (n, c) in hk.cluster_members().iter().enumerate() {
+            for &i in c {
+                assert_eq!(hk.cluster_of(i), n);
+                assert!((opinions[i] - opinions[c[0]]).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cluster_of_unknown_agent() {
+        let hk = converged(OpinionSpace::Line);
+        hk.cluster_of(500);
+    }
+
+    #[test]
+    fn test_cluster_sizes_regression() {
+        // the cluster sizes did not change, when clusters became lists of indices
+        let hk = converged(OpinionSpace::Line);
+        // the outcome depends on the random numbers, which differ for f64
+        if cfg!(not(feature = "f64")) {
+            assert_eq!(hk.cluster_sizes(), vec![267, 233]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tolerance {
+    use super::*;
+
+    #[test]
+    fn test_loose_threshold_needs_loose_eps() {
+        // the large self weight lets the consensus contract only slowly, such that a loose
+        // convergence threshold leaves a spread far above the default tolerance
+        let mut hk = HegselmannKrause::new(100, 0.5, 0.5, 13);
+        hk.set_self_weight(1000.).unwrap();
+        hk.run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-2 }, None)
+            .unwrap();
+        assert!(hk.cluster_sizes().len() > 50);
+
+        hk.set_cluster_eps(1e-2).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![100]);
+    }
+
+    #[test]
+    fn test_eps_merges_close_clusters() {
+        let opinions = [0.1, 0.1005, 0.101, 0.5, 0.5005];
+        let mut hk = HegselmannKrause::new(5, 0., 0., 13);
+        hk.set_state(&opinions, &[0.; 5]).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![1, 1, 1, 1, 1]);
+        hk.set_cluster_eps(1e-3).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![3, 2]);
+        hk.set_cluster_eps(0.5).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![5]);
+    }
+
+    #[test]
+    fn test_deffuant_eps() {
+        let mut dw = Deffuant::new(100, 0.5, 0.5, 0.01, 13);
+        dw.run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-1 }, None)
+            .unwrap();
+        let strict = dw.cluster_sizes().len();
+        dw.set_cluster_eps(0.1).unwrap();
+        let loose = dw.cluster_sizes();
+        assert!(loose.len() <= strict);
+        assert_eq!(loose.iter().sum::<usize>(), 100);
+    }
+
+    #[test]
+    fn test_invalid_eps() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.2, 13);
+        for &eps in &[0., -1e-3, Float::NAN, Float::INFINITY] {
+            match hk.set_cluster_eps(eps) {
+                Err(HkError::InvalidParameter(_)) => {}
+                other => panic!("tolerance {} accepted: {:?}", eps, other),
+            }
+        }
+        let mut dw = Deffuant::new(10, 0.1, 0.2, 0.5, 13);
+        assert!(dw.set_cluster_eps(0.).is_err());
+    }
+
+    #[test]
+    fn test_equality_ignores_eps() {
+        // the tolerance of the clusters does not loosen the comparison of the models
+        let mut a = HegselmannKrause::new(2, 0., 0., 13);
+        let mut b = HegselmannKrause::new(2, 0., 0., 13);
+        a.set_state(&[0.2, 0.6], &[0.; 2]).unwrap();
+        b.set_state(&[0.2005, 0.6], &[0.; 2]).unwrap();
+        a.set_cluster_eps(1e-3).unwrap();
+        assert!(a != b);
+        assert!(!a.approx_eq(&b, 1e-5));
+        assert!(a.approx_eq(&b, 1e-3));
+    }
+}
+
+#[cfg(test)]
+mod chains {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    /// permutations of `opinions`, whose first one is the sorted order
+    fn permutations(opinions: &[Float]) -> Vec<Vec<Float>> {
+        let mut rng = Pcg64::seed_from_u64(13);
+        let mut sorted = opinions.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut reversed = sorted.clone();
+        reversed.reverse();
+        let mut result = vec![sorted, reversed];
+        for _ in 0..20 {
+            let mut shuffled = opinions.to_vec();
+            shuffled.shuffle(&mut rng);
+            result.push(shuffled);
+        }
+        result
+    }
+
+    #[test]
+    fn test_dense_chain_is_one_cluster() {
+        // a greedy assignment to the first cluster with a representative closer than 1e-5
+        // splits this chain into pieces, which depend on the order of the agents
+        let chain: Vec<Float> = (0..200).map(|i| 0.2 + i as Float * 0.5e-5).collect();
+        for opinions in permutations(&chain) {
+            let hk = frozen(&opinions, OpinionSpace::Line);
+            assert_eq!(hk.cluster_sizes(), vec![200]);
+        }
+    }
+
+    #[test]
+    fn test_middle_agent_links_distant_agents() {
+        // both outer agents are further apart than 1e-5, but the middle agent links them
+        let opinions = [0.5, 0.500_009_5, 0.500_019];
+        for opinions in permutations(&opinions) {
+            let hk = frozen(&opinions, OpinionSpace::Line);
+            assert_eq!(hk.cluster_sizes(), vec![3]);
+        }
+        // without it, they are separate clusters
+        let hk = frozen(&[0.5, 0.500_019], OpinionSpace::Line);
+        assert_eq!(hk.cluster_sizes(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_chains_with_gaps() {
+        // three chains, separated by gaps larger than 1e-5, in every order of the agents
+        let mut opinions: Vec<Float> = Vec::new();
+        for (start, len) in [(0.1, 30), (0.100_4, 50), (0.7, 20)].iter() {
+            opinions.extend((0..*len).map(|i| start + i as Float * 0.6e-5));
+        }
+        for opinions in permutations(&opinions) {
+            let hk = frozen(&opinions, OpinionSpace::Line);
+            let sizes = hk.cluster_sizes();
+            assert_eq!(sizes, vec![30, 50, 20]);
+            assert_eq!(sizes.iter().sum::<usize>(), opinions.len());
+        }
+    }
+}
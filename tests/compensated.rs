@@ -0,0 +1,93 @@
+// the casts between f32 and f64 are only redundant, if the opinions are f64 anyway
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a spread out system of `n` agents, which all see each other
+    fn everybody_sees_everybody(n: u32, compensated: bool) -> HegselmannKrause {
+        let mut hk = HegselmannKrause::new(n, 1., 1., 13);
+        hk.set_compensated_summation(compensated);
+        hk
+    }
+
+    /// sum in f64 with Neumaier's compensation, precise enough as a reference for f64
+    fn exact_sum(values: impl Iterator<Item = f64>) -> f64 {
+        let mut sum = 0.;
+        let mut compensation = 0.;
+        for x in values {
+            let t: f64 = sum + x;
+            if sum.abs() >= x.abs() {
+                compensation += (sum - t) + x;
+            } else {
+                compensation += (x - t) + sum;
+            }
+            sum = t;
+        }
+        sum + compensation
+    }
+
+    /// mean of the opinions of all agents
+    fn exact_mean(hk: &HegselmannKrause) -> f64 {
+        let opinions = hk.opinions();
+        exact_sum(opinions.iter().map(|&x| x as f64)) / opinions.len() as f64
+    }
+
+    #[test]
+    fn test_compensated_mean() {
+        let mut plain = everybody_sees_everybody(5000, false);
+        let mut naive = everybody_sees_everybody(5000, true);
+        let mut tree = everybody_sees_everybody(5000, true);
+        let mean = exact_mean(&plain);
+        plain.sweep_naive();
+        naive.sweep_naive();
+        tree.sweep_tree();
+
+        // every agent arrives at the mean of all opinions
+        let error = |hk: &HegselmannKrause| (hk.opinions()[0] as f64 - mean).abs();
+        // f32 loses several digits summing up 5000 opinions
+        if cfg!(not(feature = "f64")) {
+            assert!(error(&plain) > 10. * Float::EPSILON as f64);
+        }
+        assert!(error(&naive) < Float::EPSILON as f64);
+        // the tree uses cumulative sums in f64 here, which are not compensated, but for
+        // f32 much more precise than necessary
+        assert!(error(&tree) < 4. * Float::EPSILON as f64);
+        for (x, y) in naive.opinions().iter().zip(tree.opinions()) {
+            assert!((x - y).abs() <= 4. * Float::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_compensated_accumulated_change() {
+        let mut hk = everybody_sees_everybody(5000, true);
+        let before = hk.opinions();
+        hk.sweep_tree();
+        let exact = exact_sum(
+            before
+                .iter()
+                .zip(hk.opinions())
+                .map(|(&x, y)| (x as f64 - y as f64).abs()),
+        );
+
+        assert!((hk.last_change() as f64 - exact).abs() < exact * Float::EPSILON as f64);
+    }
+
+    #[test]
+    fn test_plain_summation_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_compensated_summation(true);
+        hk2.set_compensated_summation(false);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+}
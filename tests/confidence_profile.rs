@@ -0,0 +1,65 @@
+extern crate hk;
+use hk::{ConfidenceFunction, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_profile_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.set_confidence_function(ConfidenceFunction::Constant)
+            .unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.confidences(), hk2.confidences());
+        }
+    }
+
+    #[test]
+    fn test_tent_profile() {
+        let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+        // the initial confidences are replaced by the profile
+        hk.set_state(&[0.5, 0.6, 0.9], &[1., 1., 1.]).unwrap();
+        hk.set_confidence_function(ConfidenceFunction::Tent { eps0: 0.3 })
+            .unwrap();
+        hk.sweep();
+
+        // eps(0.5) = 0.3, eps(0.6) = 0.24, eps(0.9) = 0.06
+        let confidences = hk.confidences();
+        assert!((confidences[0] - 0.3).abs() < 1e-6);
+        assert!((confidences[1] - 0.24).abs() < 1e-6);
+        assert!((confidences[2] - 0.06).abs() < 1e-6);
+
+        // the moderates meet in the middle, the extreme agent sees nobody
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.55).abs() < 1e-6);
+        assert!((opinions[1] - 0.55).abs() < 1e-6);
+        assert!((opinions[2] - 0.9).abs() < 1e-6);
+
+        hk.sweep();
+        assert!((hk.confidences()[0] - 0.27).abs() < 1e-6);
+        assert_eq!(hk.cluster_sizes(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_table_profile() {
+        let table = ConfidenceFunction::Table(vec![(0.2, 0.1), (0.6, 0.3)]);
+        assert_eq!(table.confidence(0.), Some(0.1));
+        assert!((table.confidence(0.4).unwrap() - 0.2).abs() < 1e-6);
+        assert_eq!(table.confidence(0.9), Some(0.3));
+        assert_eq!("table:0.2=0.1,0.6=0.3".parse(), Ok(table));
+        assert_eq!(
+            "tent:0.3".parse(),
+            Ok(ConfidenceFunction::Tent { eps0: 0.3 })
+        );
+
+        let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+        let unsorted = ConfidenceFunction::Table(vec![(0.6, 0.3), (0.2, 0.1)]);
+        assert!(hk.set_confidence_function(unsorted).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+// the casts between f32 and f64 are only redundant, if the opinions are f64 anyway
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+extern crate hk;
+use hk::{ConfidenceDistribution, Float, HegselmannKrause, HkError};
+
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_law_mean() {
+        let (exponent, min, max) = (2.5, 0.05, 0.5);
+        let distribution = ConfidenceDistribution::PowerLaw { exponent, min, max };
+        assert!(distribution.validate().is_ok());
+
+        let mut rng = Pcg64::seed_from_u64(13);
+        let n = 100_000;
+        let samples: Vec<Float> = (0..n).map(|_| distribution.sample(&mut rng)).collect();
+        assert!(samples.iter().all(|&x| x >= min && x <= max));
+
+        // analytic mean of a truncated power law
+        let (a, b, e) = (min as f64, max as f64, exponent as f64);
+        let expected = (1. - e) / (2. - e) * (b.powf(2. - e) - a.powf(2. - e))
+            / (b.powf(1. - e) - a.powf(1. - e));
+        let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / n as f64;
+        assert!((mean - expected).abs() < 1e-3, "{} != {}", mean, expected);
+    }
+
+    #[test]
+    fn test_invalid_power_law() {
+        for &exponent in &[1., 0.5, -2., Float::NAN] {
+            let distribution = ConfidenceDistribution::PowerLaw {
+                exponent,
+                min: 0.1,
+                max: 0.5,
+            };
+            assert!(matches!(
+                distribution.validate(),
+                Err(HkError::InvalidConfidenceDistribution(_))
+            ));
+        }
+
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        let distribution = ConfidenceDistribution::PowerLaw {
+            exponent: 2.,
+            min: 0.,
+            max: 0.5,
+        };
+        assert!(hk.set_confidence_distribution(distribution).is_err());
+    }
+
+    #[test]
+    fn test_uniform_distribution_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.3, 0.4, 13);
+        hk2.set_confidence_distribution(ConfidenceDistribution::Uniform { low: 0., high: 1. })
+            .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_constant_equals_degenerate_uniform() {
+        let mut hk1 = HegselmannKrause::new(100, 0.2, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.2, 0.2, 13);
+        hk2.set_confidence_distribution(ConfidenceDistribution::Constant { value: 0.2 })
+            .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+}
+
+#[cfg(test)]
+mod bimodal_tests {
+    use super::*;
+
+    #[test]
+    fn test_bimodal_full_fraction_equals_constant() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_confidence_distribution(ConfidenceDistribution::Constant { value: 0.15 })
+            .unwrap();
+        hk2.set_confidence_distribution(ConfidenceDistribution::Bimodal {
+            fraction: 1.,
+            open: 0.15,
+            closed: 0.01,
+        })
+        .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+        assert_eq!(hk1.cluster_sizes(), hk2.cluster_sizes());
+        // everybody is open-minded
+        for (composition, size) in hk2.cluster_composition().iter().zip(hk2.cluster_sizes()) {
+            assert_eq!(composition, &vec![size]);
+        }
+    }
+
+    #[test]
+    fn test_bimodal_composition() {
+        let mut hk = HegselmannKrause::new(1000, 0., 1., 13);
+        hk.set_confidence_distribution(ConfidenceDistribution::Bimodal {
+            fraction: 0.3,
+            open: 0.3,
+            closed: 0.05,
+        })
+        .unwrap();
+        hk.reset();
+        for _ in 0..50 {
+            hk.sweep();
+        }
+
+        let composition = hk.cluster_composition();
+        assert!(composition.iter().all(|c| c.len() == 2));
+        let open: usize = composition.iter().map(|c| c[0]).sum();
+        let closed: usize = composition.iter().map(|c| c[1]).sum();
+        assert_eq!(open + closed, 1000);
+        // roughly the configured fraction is open-minded
+        assert!((open as f64 / 1000. - 0.3).abs() < 0.05);
+
+        // the composition adds up to the cluster sizes
+        for (c, size) in composition.iter().zip(hk.cluster_sizes()) {
+            assert_eq!(c.iter().sum::<usize>(), size);
+        }
+    }
+
+    #[test]
+    fn test_invalid_bimodal() {
+        let distribution = ConfidenceDistribution::Bimodal {
+            fraction: 1.5,
+            open: 0.3,
+            closed: 0.05,
+        };
+        assert!(distribution.validate().is_err());
+    }
+
+    #[test]
+    fn test_opinion_correlated_confidence() {
+        let distribution = ConfidenceDistribution::OpinionCorrelated {
+            min: 0.125,
+            max: 0.375,
+            gamma: 2.,
+        };
+        assert!(distribution.validate().is_ok());
+
+        let mut rng = Pcg64::seed_from_u64(13);
+        // centrists are most open-minded, extremists most closed-minded
+        assert_eq!(distribution.sample_for_opinion(0.5, &mut rng), (0.375, 0));
+        assert_eq!(distribution.sample_for_opinion(0., &mut rng), (0.125, 0));
+        assert_eq!(distribution.sample_for_opinion(1., &mut rng), (0.125, 0));
+        assert_eq!(distribution.sample_for_opinion(0.25, &mut rng), (0.1875, 0));
+        assert_eq!(distribution.sample_for_opinion(0.75, &mut rng), (0.1875, 0));
+    }
+
+    #[test]
+    fn test_opinion_correlated_reset() {
+        let mut hk = HegselmannKrause::new(100, 0., 1., 13);
+        let (min, max, gamma) = (0.05, 0.3, 1.5);
+        hk.set_confidence_distribution(ConfidenceDistribution::OpinionCorrelated {
+            min,
+            max,
+            gamma,
+        })
+        .unwrap();
+        hk.reset();
+
+        for (x, c) in hk.opinions().iter().zip(hk.confidences()) {
+            let expected = min + (max - min) * (1. - (2. * x - 1.).abs()).powf(gamma);
+            assert!((c - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_invalid_opinion_correlated() {
+        for &gamma in &[-1., Float::NAN, Float::INFINITY] {
+            let distribution = ConfidenceDistribution::OpinionCorrelated {
+                min: 0.1,
+                max: 0.3,
+                gamma,
+            };
+            assert!(distribution.validate().is_err());
+        }
+        let distribution = ConfidenceDistribution::OpinionCorrelated {
+            min: 0.3,
+            max: 0.1,
+            gamma: 1.,
+        };
+        assert!(distribution.validate().is_err());
+        let distribution = ConfidenceDistribution::OpinionCorrelated {
+            min: 0.1,
+            max: 0.3,
+            gamma: 0.,
+        };
+        assert!(distribution.validate().is_ok());
+    }
+}
+
+#[cfg(test)]
+mod non_negative {
+    use super::*;
+
+    #[test]
+    fn test_negative_distributions_rejected() {
+        let distributions = [
+            ConfidenceDistribution::Uniform {
+                low: -0.5,
+                high: 0.2,
+            },
+            ConfidenceDistribution::Constant { value: -0.1 },
+            ConfidenceDistribution::Bimodal {
+                fraction: 0.5,
+                open: 0.3,
+                closed: -0.05,
+            },
+            ConfidenceDistribution::OpinionCorrelated {
+                min: -0.1,
+                max: 0.3,
+                gamma: 1.,
+            },
+        ];
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        for distribution in distributions.iter() {
+            assert!(
+                matches!(
+                    hk.set_confidence_distribution(distribution.clone()),
+                    Err(HkError::InvalidConfidenceDistribution(_))
+                ),
+                "{:?} accepted",
+                distribution
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_confidence_sees_itself() {
+        // without confidence every agent is its only neighbor, so nothing moves
+        let sweeps: [fn(&mut HegselmannKrause); 5] = [
+            HegselmannKrause::sweep_naive,
+            HegselmannKrause::sweep_tree,
+            HegselmannKrause::sweep_sorted,
+            HegselmannKrause::sweep_async,
+            HegselmannKrause::sweep,
+        ];
+        for sweep in sweeps.iter() {
+            let mut hk = HegselmannKrause::new(100, 0., 0., 13);
+            let initial = hk.opinions();
+            sweep(&mut hk);
+            assert_eq!(hk.opinions(), initial);
+            assert_eq!(hk.last_change(), 0.);
+        }
+    }
+
+    #[test]
+    fn test_zero_constant_confidence() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.set_confidence_distribution(ConfidenceDistribution::Constant { value: 0. })
+            .unwrap();
+        hk.reset();
+        let initial = hk.opinions();
+        hk.sweep();
+        assert_eq!(hk.opinions(), initial);
+        assert!(hk.opinions().iter().all(|x| x.is_finite()));
+    }
+}
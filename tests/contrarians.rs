@@ -0,0 +1,73 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_contrarians_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_contrarians(0., 0.5).unwrap();
+        assert!(hk2.contrarians().is_empty());
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_contrarian_between_clusters() {
+        let n = 21;
+        let mut hk = HegselmannKrause::new(n, 0., 1., 13);
+        hk.set_contrarians(1. / n as Float, 0.5).unwrap();
+        let contrarians = hk.contrarians();
+        assert_eq!(contrarians.len(), 1);
+        let c = contrarians[0];
+
+        // two big clusters, which do not see the contrarian, and a contrarian
+        // slightly below the middle, which sees everybody
+        let mut opinions: Vec<Float> = (0..n as usize)
+            .map(|i| if i % 2 == 0 { 0.2 } else { 0.8 })
+            .collect();
+        let mut confidences = vec![0.05; n as usize];
+        opinions[c] = 0.45;
+        confidences[c] = 1.;
+        hk.set_state(&opinions, &confidences).unwrap();
+
+        for _ in 0..50 {
+            hk.sweep_tree();
+        }
+
+        // it is repelled from the mean and ends at the boundary
+        let opinions = hk.opinions();
+        assert_eq!(opinions[c], 0.);
+        assert!(opinions.iter().all(|x| (0. ..=1.).contains(x)));
+
+        // the tree is consistent with the clamped opinions
+        for _ in 0..10 {
+            hk.sweep_tree();
+        }
+        assert_eq!(hk.cluster_sizes().len(), 3);
+    }
+
+    #[test]
+    fn test_cmp_contrarians_naive_tree() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Line);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Line);
+        hk1.set_contrarians(0.1, 0.3).unwrap();
+        hk2.set_contrarians(0.1, 0.3).unwrap();
+        assert_eq!(hk1.contrarians(), hk2.contrarians());
+
+        // contrarians amplify the different rounding errors of both methods,
+        // such that the trajectories only agree for a limited time
+        for _ in 0..20 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+}
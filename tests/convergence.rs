@@ -0,0 +1,346 @@
+extern crate hk;
+use hk::{ClusterOutput, ConvergenceCriterion, Deffuant, Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// the loop of the main program before it used `run_until_converged`
+    fn manual_loop(hk: &mut HegselmannKrause, threshold: Float, max_sweeps: Option<u64>) -> u64 {
+        let mut ctr = 0;
+        loop {
+            ctr += 1;
+            hk.sweep();
+
+            let capped = max_sweeps.is_some_and(|max| ctr >= max);
+            let converged = hk.last_change() < threshold;
+            if converged || capped {
+                return ctr;
+            }
+        }
+    }
+
+    fn cmp_manual_loop<F: Fn(&mut HegselmannKrause)>(
+        setup: F,
+        threshold: Float,
+        max_sweeps: Option<u64>,
+    ) {
+        let mut hk1 = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        setup(&mut hk1);
+        setup(&mut hk2);
+
+        let sweeps = manual_loop(&mut hk1, threshold, max_sweeps);
+        let max_sweeps = max_sweeps.map(|max| max as usize);
+        assert_eq!(
+            hk2.run_until_converged(ConvergenceCriterion::SumChange { threshold }, max_sweeps)
+                .unwrap_or_else(|e| e.sweeps) as u64,
+            sweeps
+        );
+        assert_eq!(hk1.opinions(), hk2.opinions());
+    }
+
+    #[test]
+    fn test_converged_like_manual_loop() {
+        cmp_manual_loop(|_| {}, 1e-4, None);
+        cmp_manual_loop(|hk| hk.set_mu(0.3).unwrap(), 0.3e-4, Some(10000));
+    }
+
+    #[test]
+    fn test_capped_like_manual_loop() {
+        cmp_manual_loop(|_| {}, 1e-4, Some(3));
+        // open systems never converge and run for exactly max_sweeps sweeps
+        cmp_manual_loop(|hk| hk.set_turnover(2.).unwrap(), 0., Some(50));
+
+        let mut hk = HegselmannKrause::new(100, 0., 1., 13);
+        hk.set_turnover(2.).unwrap();
+        let criterion = ConvergenceCriterion::SumChange { threshold: 0. };
+        assert_eq!(
+            hk.run_until_converged(criterion, Some(50))
+                .unwrap_err()
+                .sweeps,
+            50
+        );
+    }
+
+    #[test]
+    fn test_summary_like_manual_loop() {
+        let mut hk1 = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        let sweeps = manual_loop(&mut hk1, 1e-4, Some(10000));
+        let result = hk2.run_to_convergence(1e-4, 10000);
+        assert_eq!(result.sweeps as u64, sweeps);
+        assert!(result.converged);
+        assert_eq!(result.cluster_sizes, hk1.cluster_sizes());
+        assert_eq!(result.cluster_sizes.iter().sum::<usize>(), 500);
+        // the positions of the clusters are those of the output
+        let positions = hk1.cluster_sizes_string();
+        let positions: Vec<Float> = positions.lines().next().unwrap()[1..]
+            .split_whitespace()
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert_eq!(result.cluster_positions, positions);
+        assert!(hk1 == hk2);
+    }
+
+    #[test]
+    fn test_summary_of_cap() {
+        let mut hk1 = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        assert_eq!(manual_loop(&mut hk1, 1e-4, Some(3)), 3);
+        let result = hk2.run_to_convergence(1e-4, 3);
+        assert_eq!(result.sweeps, 3);
+        assert!(!result.converged);
+        assert_eq!(result.cluster_sizes, hk1.cluster_sizes());
+
+        // reaching the threshold in the last allowed sweep counts as converged
+        let mut hk = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        let result = hk.run_to_convergence(1e-4, sweeps_until(1e-4));
+        assert!(result.converged);
+        assert_eq!(result.sweeps, sweeps_until(1e-4));
+    }
+
+    #[test]
+    fn test_deffuant_summary() {
+        let criterion = ConvergenceCriterion::SumChange { threshold: 1e-3 };
+        let mut dw1 = Deffuant::new(200, 0.1, 0.3, 0.5, 13);
+        let mut dw2 = Deffuant::new(200, 0.1, 0.3, 0.5, 13);
+        let sweeps = dw1.run_until_converged(criterion, Some(10000)).unwrap();
+        let result = dw2.run_to_convergence(1e-3, 10000);
+        assert_eq!(result.sweeps, sweeps);
+        assert!(result.converged);
+        assert_eq!(result.cluster_sizes, dw1.cluster_sizes());
+
+        let result = dw2.run_to_convergence(0., 2);
+        assert_eq!((result.sweeps, result.converged), (2, false));
+    }
+
+    /// sweeps until the total change of the system of the tests drops below `threshold`
+    fn sweeps_until(threshold: Float) -> usize {
+        let mut hk = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        manual_loop(&mut hk, threshold, None) as usize
+    }
+
+    #[test]
+    fn test_sweeps_like_main() {
+        let args = ["-n", "300", "-l", "0.05", "-u", "0.3", "--seed", "5"];
+        let outname = std::env::temp_dir().join(format!("hk_convergence_{}", std::process::id()));
+        let status = Command::new(env!("CARGO_BIN_EXE_hk"))
+            .args(args)
+            .arg("--outname")
+            .arg(&outname)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let output = std::fs::read_to_string(&outname).unwrap();
+        std::fs::remove_file(&outname).unwrap();
+
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.3, 5);
+        hk.reset_with_seed(5);
+        let sweeps = hk
+            .run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-4 }, None)
+            .unwrap();
+        assert!(output.contains(&format!("# sweeps: {}\n", sweeps)));
+    }
+}
+
+#[cfg(test)]
+mod criteria {
+    use super::*;
+
+    fn sweeps(criterion: ConvergenceCriterion) -> (usize, Vec<usize>) {
+        let mut hk = HegselmannKrause::new(1000, 0.05, 0.2, 13);
+        let sweeps = hk.run_until_converged(criterion, Some(10000)).unwrap();
+        (sweeps, hk.cluster_sizes())
+    }
+
+    #[test]
+    fn test_max_change_not_earlier_than_sum_change() {
+        // the total change is at most N times the largest change
+        for &threshold in &[1e-3, 1e-5, 1e-7] {
+            let (sum_sweeps, _) = sweeps(ConvergenceCriterion::SumChange {
+                threshold: 1000. * threshold,
+            });
+            let (max_sweeps, _) = sweeps(ConvergenceCriterion::MaxChange { threshold });
+            assert!(max_sweeps >= sum_sweeps);
+        }
+    }
+
+    #[test]
+    fn test_max_change_tracked() {
+        let mut hk = HegselmannKrause::new(500, 0.05, 0.2, 13);
+        let before = hk.opinions();
+        hk.sweep();
+        let largest = before
+            .iter()
+            .zip(hk.opinions())
+            .map(|(x, y)| (x - y).abs())
+            .fold(0., Float::max);
+        assert_eq!(hk.last_max_change(), largest);
+        assert!(hk.last_max_change() <= hk.last_change());
+    }
+
+    #[test]
+    fn test_cluster_stability() {
+        let (stable, sizes) = sweeps(ConvergenceCriterion::ClusterStability { sweeps: 5 });
+        let (converged, converged_sizes) =
+            sweeps(ConvergenceCriterion::SumChange { threshold: 1e-4 });
+        // the sizes stay the same after convergence
+        assert!(stable > 5);
+        assert!(stable <= converged + 5);
+        assert_eq!(sizes, converged_sizes);
+    }
+
+    #[test]
+    fn test_cluster_stability_of_stable_state() {
+        // agents which do not see each other never change
+        let mut hk = HegselmannKrause::new(10, 0., 0., 13);
+        let criterion = ConvergenceCriterion::ClusterStability { sweeps: 3 };
+        assert_eq!(hk.run_until_converged(criterion, None), Ok(4));
+    }
+
+    #[test]
+    fn test_deffuant_criteria() {
+        let run = |criterion| {
+            let mut dw = Deffuant::new(500, 0.1, 0.3, 0.5, 13);
+            dw.run_until_converged(criterion, Some(100000)).unwrap()
+        };
+        let sum = run(ConvergenceCriterion::SumChange { threshold: 500e-6 });
+        let max = run(ConvergenceCriterion::MaxChange { threshold: 1e-6 });
+        assert!(max >= sum);
+        assert!(run(ConvergenceCriterion::ClusterStability { sweeps: 5 }) > 5);
+    }
+}
+
+#[cfg(test)]
+mod not_converged {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_cap_reported() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let criterion = ConvergenceCriterion::SumChange { threshold: 0. };
+        let err = hk.run_until_converged(criterion, Some(3)).unwrap_err();
+        assert_eq!(err.sweeps, 3);
+        assert_eq!(err.last_change, hk.last_change());
+        assert!(err.last_change > 0.);
+        assert!(err.to_string().contains("within 3 sweeps"));
+
+        // the fallible variant reports the cap in its inner result
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        assert_eq!(hk.try_run_until_converged(criterion, Some(3)), Ok(Err(err)));
+    }
+
+    #[test]
+    fn test_has_converged() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.sweep();
+        let change = hk.last_change();
+        assert!(change > 0.);
+        assert!(!hk.has_converged(change));
+        assert!(hk.has_converged(change * 2.));
+
+        // every sweep starts counting from zero
+        let mut hk = HegselmannKrause::new(10, 0., 0., 13);
+        hk.sweep();
+        hk.sweep();
+        assert_eq!(hk.last_change(), 0.);
+        assert!(hk.has_converged(1e-10));
+    }
+
+    #[test]
+    fn test_noise_reaches_cap() {
+        // the noise keeps the change far above the threshold
+        let mut hk = HegselmannKrause::new(200, 0.05, 0.2, 13);
+        hk.set_noise(0.1).unwrap();
+        let criterion = ConvergenceCriterion::SumChange { threshold: 1e-4 };
+        let err = hk.run_until_converged(criterion, Some(30)).unwrap_err();
+        assert_eq!(err.sweeps, 30);
+        assert!(err.last_change > 1e-4);
+    }
+
+    /// run the command line interface with the given arguments and return its output
+    fn run_cli(name: &str, args: &[&str]) -> String {
+        let outname =
+            std::env::temp_dir().join(format!("hk_not_converged_{}_{}", std::process::id(), name));
+        let status = Command::new(env!("CARGO_BIN_EXE_hk"))
+            .args(args)
+            .arg("--outname")
+            .arg(&outname)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let output = std::fs::read_to_string(&outname).unwrap();
+        std::fs::remove_file(&outname).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_static_trajectory_like_plain() {
+        // a single agent and agents out of reach of each other are converged without any
+        // sweep, also if the state is written after every sweep
+        let systems: [&[&str]; 3] = [
+            &["-n", "1", "-l", "0.1", "-u", "0.2"],
+            &["-n", "2", "-l", "0", "-u", "0"],
+            &["--model", "deffuant", "-n", "1", "-l", "0.1", "-u", "0.2"],
+        ];
+        for (k, args) in systems.iter().enumerate() {
+            let plain = run_cli(&format!("static_plain_{}", k), args);
+            let trajectory = [args, &["--trajectory"][..]].concat();
+            let trajectory = run_cli(&format!("static_trajectory_{}", k), &trajectory);
+            assert!(plain.contains("# sweeps: 0\n"));
+            assert_eq!(trajectory, plain);
+        }
+    }
+
+    #[test]
+    fn test_main_marks_cap() {
+        let args = ["-n", "200", "-l", "0.05", "-u", "0.2", "--samples", "2"];
+        let noisy = [&args[..], &["--noise", "0.1", "--max-sweeps", "20"]].concat();
+        let output = run_cli("noisy", &noisy);
+        assert_eq!(
+            output
+                .matches("# NOT CONVERGED after 20 sweeps\n# sweeps: 20\n")
+                .count(),
+            2
+        );
+
+        // converged samples and systems with a fixed number of sweeps are not marked
+        assert!(!run_cli("converged", &args).contains("NOT CONVERGED"));
+        let open = [&args[..], &["--turnover", "1", "--max-sweeps", "20"]].concat();
+        let output = run_cli("open", &open);
+        assert!(output.contains("# sweeps: 20\n"));
+        assert!(!output.contains("NOT CONVERGED"));
+    }
+
+    #[test]
+    fn test_main_marks_cap_of_trajectory() {
+        let args = [
+            "-n",
+            "100",
+            "-l",
+            "0.05",
+            "-u",
+            "0.2",
+            "--noise",
+            "0.1",
+            "--max-sweeps",
+            "5",
+            "--trajectory",
+        ];
+        let output = run_cli("trajectory", &args);
+        assert!(output.contains("# NOT CONVERGED after 5 sweeps\n# sweeps: 5\n"));
+    }
+
+    #[test]
+    fn test_deffuant_cap_reported() {
+        let mut dw = Deffuant::new(100, 0.1, 0.3, 0.5, 13);
+        let criterion = ConvergenceCriterion::SumChange { threshold: 0. };
+        let err = dw.run_until_converged(criterion, Some(4)).unwrap_err();
+        assert_eq!(err.sweeps, 4);
+        assert_eq!(err.last_change, dw.last_change());
+        assert!(!dw.has_converged(0.));
+    }
+}
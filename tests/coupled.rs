@@ -0,0 +1,128 @@
+extern crate hk;
+use hk::{CoupledHegselmannKrause, Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: (Float, Float), b: (Float, Float)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn test_three_agents_coupled() {
+        let mut hk = CoupledHegselmannKrause::new(3, 0., 1., 13);
+        // agent 1 is close to both others on topic A, but far from them on topic B
+        hk.set_state(
+            &[(0., 0.), (0.25, 0.75), (0.25, 0.)],
+            &[(0.5, 0.5), (0.5, 0.5), (0.5, 0.5)],
+        );
+        hk.sweep();
+
+        let opinions = hk.opinions();
+        assert_close(opinions[0], (0.125, 0.));
+        assert_close(opinions[1], (0.25, 0.75));
+        assert_close(opinions[2], (0.125, 0.));
+        assert!((hk.accumulated_change - 0.25).abs() < 1e-6);
+        assert_eq!(hk.cluster_sizes(), vec![2, 1]);
+        assert_eq!(hk.cluster_centroids(), vec![(0.125, 0.), (0.25, 0.75)]);
+    }
+
+    #[test]
+    fn test_four_agents_different_confidences() {
+        let mut hk = CoupledHegselmannKrause::new(4, 0., 1., 13);
+        hk.set_state(
+            &[(0., 0.), (0.5, 0.25), (0.5, 0.5), (1., 0.5)],
+            &[(0.75, 0.375), (0.75, 0.375), (0.125, 1.), (0.75, 0.375)],
+        );
+        hk.sweep();
+
+        let opinions = hk.opinions();
+        // agent 0 sees agent 1
+        assert_close(opinions[0], (0.25, 0.125));
+        // agent 1 sees all agents
+        assert_close(opinions[1], (0.5, 0.3125));
+        // agent 2 is narrow-minded on topic A, but open on topic B and sees agent 1
+        assert_close(opinions[2], (0.5, 0.375));
+        // agent 3 sees agent 1 and 2
+        assert_close(opinions[3], (2. / 3., 1.25 / 3.));
+    }
+
+    #[test]
+    fn test_decoupled_topic_a_is_standard_model() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut coupled = CoupledHegselmannKrause::new(100, 0., 1., 13);
+
+        // topic B is arbitrary, but can never prevent an interaction
+        let opinions: Vec<(Float, Float)> = hk
+            .opinions()
+            .iter()
+            .enumerate()
+            .map(|(n, &x)| (x, (n % 7) as Float / 7.))
+            .collect();
+        let confidences: Vec<(Float, Float)> = hk
+            .confidences()
+            .iter()
+            .map(|&c| (c, Float::INFINITY))
+            .collect();
+        coupled.set_state(&opinions, &confidences);
+
+        for _ in 0..50 {
+            hk.sweep_naive();
+            coupled.sweep_naive();
+            for (x, (a, _)) in hk.opinions().iter().zip(coupled.opinions()) {
+                assert!((x - a).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_converges() {
+        let mut hk = CoupledHegselmannKrause::new(100, 0.2, 0.4, 13);
+        let mut sweeps = 0;
+        loop {
+            sweeps += 1;
+            hk.sweep();
+            if hk.accumulated_change < 1e-4 {
+                break;
+            }
+        }
+        assert!(sweeps < 1000);
+        assert_eq!(hk.cluster_sizes().iter().sum::<usize>(), 100);
+        assert_eq!(hk.cluster_centroids().len(), hk.cluster_sizes().len());
+    }
+}
+
+#[cfg(test)]
+mod chains {
+    use super::*;
+
+    #[test]
+    fn test_chain_independent_of_order() {
+        // two chains on topic A, which are separated on topic B, and a chain with a gap
+        let mut opinions: Vec<(Float, Float)> = Vec::new();
+        opinions.extend((0..30).map(|i| (0.2 + i as Float * 0.7e-5, 0.2)));
+        opinions.extend((0..30).map(|i| (0.2 + i as Float * 0.7e-5, 0.8)));
+        opinions.extend((0..10).map(|i| (0.6, 0.6 + i as Float * 0.7e-5)));
+        opinions.extend((0..5).map(|i| (0.6, 0.601 + i as Float * 0.7e-5)));
+
+        let mut reversed = opinions.clone();
+        reversed.reverse();
+        let mut interleaved: Vec<(Float, Float)> = opinions.iter().step_by(2).cloned().collect();
+        interleaved.extend(opinions.iter().skip(1).step_by(2));
+
+        for opinions in [opinions, reversed, interleaved].iter() {
+            let mut hk = CoupledHegselmannKrause::new(opinions.len() as u32, 0., 0., 13);
+            hk.set_state(opinions, &vec![(0., 0.); opinions.len()]);
+            let sizes = hk.cluster_sizes();
+            // ordered by the opinions on topic A and then B
+            assert_eq!(sizes, vec![30, 30, 10, 5]);
+            assert_eq!(sizes.iter().sum::<usize>(), 75);
+        }
+    }
+}
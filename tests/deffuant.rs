@@ -0,0 +1,46 @@
+extern crate hk;
+use hk::Deffuant;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// run a Deffuant model until no opinion changes noticeably anymore
+    fn converge(dw: &mut Deffuant) -> u32 {
+        let mut ctr = 0;
+        loop {
+            ctr += 1;
+            dw.sweep();
+            if dw.last_change() < 1e-4 {
+                return ctr;
+            }
+        }
+    }
+
+    #[test]
+    fn test_deffuant_reproducible() {
+        let mut dw1 = Deffuant::new(50, 0.1, 0.3, 0.5, 42);
+        let mut dw2 = Deffuant::new(50, 0.1, 0.3, 0.5, 42);
+
+        // identical seeds have to lead to identical trajectories
+        for _ in 0..100 {
+            dw1.sweep();
+            dw2.sweep();
+            assert_eq!(dw1.last_change(), dw2.last_change());
+        }
+        assert_eq!(dw1.cluster_sizes(), dw2.cluster_sizes());
+    }
+
+    #[test]
+    fn test_deffuant_regression() {
+        let mut dw = Deffuant::new(10, 0.2, 0.4, 0.5, 13);
+        let sweeps = converge(&mut dw);
+
+        // pin the outcome of a small system, such that changes to the dynamics are noticed
+        // the outcome depends on the random numbers, which differ for f64
+        if cfg!(not(feature = "f64")) {
+            assert_eq!(sweeps, 26);
+            assert_eq!(dw.cluster_sizes(), vec![2, 7, 1]);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+extern crate hk;
+use hk::{Backend, ClusterOutput, ConvergenceCriterion, Deffuant, Float, HegselmannKrause};
+
+const CRITERION: ConvergenceCriterion = ConvergenceCriterion::SumChange { threshold: 1e-4 };
+
+/// the state after one sweep of every implementation of a synchronous sweep
+fn one_sweep(opinions: &[Float], left: &[Float], right: &[Float]) -> Vec<Vec<Float>> {
+    let sweeps: [fn(&mut HegselmannKrause); 6] = [
+        HegselmannKrause::sweep_naive,
+        HegselmannKrause::sweep_tree,
+        HegselmannKrause::sweep_sorted,
+        |hk| {
+            hk.set_backend(Backend::Tree);
+            hk.sweep()
+        },
+        |hk| {
+            hk.set_backend(Backend::Sorted);
+            hk.sweep()
+        },
+        |hk| {
+            hk.set_backend(Backend::Auto);
+            hk.sweep()
+        },
+    ];
+    sweeps
+        .iter()
+        .map(|sweep| {
+            let mut hk = HegselmannKrause::new(opinions.len() as u32, 0., 0., 13);
+            hk.set_state_asymmetric(opinions, left, right).unwrap();
+            sweep(&mut hk);
+            hk.opinions()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod single_agent {
+    use super::*;
+
+    #[test]
+    fn test_converged_from_the_start() {
+        let mut hk = HegselmannKrause::new(1, 0.1, 0.2, 13);
+        let initial = hk.opinions();
+        assert_eq!(hk.run_until_converged(CRITERION, None), Ok(0));
+        assert_eq!(hk.opinions(), initial);
+        // no sweep was done at all
+        assert_eq!(hk.sweep_count(), 0);
+
+        let mut dw = Deffuant::new(1, 0.1, 0.2, 0.5, 13);
+        assert_eq!(dw.run_until_converged(CRITERION, None), Ok(0));
+    }
+
+    #[test]
+    fn test_not_static() {
+        // noise moves even a single agent
+        let mut hk = HegselmannKrause::new(1, 0.1, 0.2, 13);
+        assert!(hk.is_static());
+        hk.set_noise(0.01).unwrap();
+        assert!(!hk.is_static());
+
+        // a threshold of 0 sweeps a fixed number of times, even if nothing moves
+        let mut hk = HegselmannKrause::new(1, 0.1, 0.2, 13);
+        let criterion = ConvergenceCriterion::SumChange { threshold: 0. };
+        assert_eq!(
+            hk.run_until_converged(criterion, Some(5))
+                .unwrap_err()
+                .sweeps,
+            5
+        );
+        assert_eq!(hk.sweep_count(), 5);
+    }
+
+    #[test]
+    fn test_media_exposure_not_static() {
+        // the exposure pulls the agent towards the media regardless of its confidence
+        let mut hk = HegselmannKrause::new(1, 0., 0., 13);
+        hk.set_state(&[0.25], &[0.]).unwrap();
+        hk.set_media_exposure(1., 0.5, 0.75).unwrap();
+        assert!(!hk.is_static());
+        let sweeps = hk.run_until_converged(CRITERION, Some(100)).unwrap();
+        assert!(sweeps >= 1);
+        assert!(hk.sweep_count() >= 1);
+        assert!((hk.opinions()[0] - 0.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_never_moves() {
+        for result in one_sweep(&[0.25], &[0.], &[0.5]) {
+            assert_eq!(result, vec![0.25]);
+        }
+    }
+
+    #[test]
+    fn test_cluster_output() {
+        let mut hk = HegselmannKrause::new(1, 0.1, 0.2, 13);
+        hk.set_state(&[0.25], &[0.1]).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![1]);
+        assert_eq!(hk.cluster_sizes_string(), "# 0.25\n1\n");
+    }
+}
+
+#[cfg(test)]
+mod two_agents {
+    use super::*;
+
+    #[test]
+    fn test_memory_not_static() {
+        // the agents approach each other and then lose sight of each other, but they still
+        // remember their past opinions and keep moving towards them
+        let mut hk = HegselmannKrause::new(2, 0., 0., 13);
+        hk.set_state(&[0.25, 0.5], &[0.25, 0.25]).unwrap();
+        hk.enable_memory(2).unwrap();
+        hk.sweep();
+        assert_eq!(hk.opinions(), vec![0.3125, 0.4375]);
+        hk.set_confidence(0, 0.).unwrap();
+        hk.set_confidence(1, 0.).unwrap();
+        assert!(!hk.is_static());
+
+        let sweeps = hk.run_until_converged(CRITERION, None).unwrap();
+        assert!(sweeps >= 1);
+        assert_eq!(hk.sweep_count(), 1 + sweeps);
+        // the history [x, y] settles at (x + 2y) / 3
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 1. / 3.).abs() < 1e-3, "{}", opinions[0]);
+        assert!((opinions[1] - 5. / 12.).abs() < 1e-3, "{}", opinions[1]);
+    }
+
+    #[test]
+    fn test_mutual_merge_at_midpoint() {
+        for result in one_sweep(&[0.25, 0.5], &[0.25, 0.25], &[0.25, 0.25]) {
+            assert_eq!(result, vec![0.375, 0.375]);
+        }
+
+        // one sweep to merge and one sweep to see that nothing moves anymore
+        let mut hk = HegselmannKrause::new(2, 0., 0., 13);
+        hk.set_state(&[0.25, 0.5], &[0.25, 0.25]).unwrap();
+        assert_eq!(hk.run_until_converged(CRITERION, None), Ok(2));
+        assert_eq!(hk.cluster_sizes_string(), "# 0.375\n2\n");
+    }
+
+    #[test]
+    fn test_out_of_range_never_move() {
+        for result in one_sweep(&[0.25, 0.5], &[0.125, 0.125], &[0.125, 0.125]) {
+            assert_eq!(result, vec![0.25, 0.5]);
+        }
+
+        let mut hk = HegselmannKrause::new(2, 0., 0., 13);
+        hk.set_state(&[0.25, 0.5], &[0.125, 0.125]).unwrap();
+        assert_eq!(hk.run_until_converged(CRITERION, None), Ok(0));
+        assert_eq!(hk.cluster_sizes_string(), "# 0.25 0.5\n1 1\n");
+    }
+
+    #[test]
+    fn test_only_one_sees_the_other() {
+        // the lower agent reaches up to the higher one, but not vice versa
+        for result in one_sweep(&[0.25, 0.5], &[0., 0.125], &[0.25, 0.]) {
+            assert_eq!(result, vec![0.375, 0.5]);
+        }
+        // the same with the higher agent reaching down
+        for result in one_sweep(&[0.25, 0.5], &[0., 0.25], &[0.125, 0.]) {
+            assert_eq!(result, vec![0.25, 0.375]);
+        }
+    }
+
+    #[test]
+    fn test_one_sided_approach_converges() {
+        // the lower agent halves its distance in every sweep, until it is close enough
+        // for the higher agent to see it
+        let mut hk = HegselmannKrause::new(2, 0., 0., 13);
+        hk.set_state_asymmetric(&[0.25, 0.5], &[0., 0.0625], &[0.25, 0.])
+            .unwrap();
+        let sweeps = hk.run_until_converged(CRITERION, None).unwrap();
+        assert!(sweeps > 1);
+        assert_eq!(hk.cluster_sizes(), vec![2]);
+    }
+}
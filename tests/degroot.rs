@@ -0,0 +1,92 @@
+extern crate hk;
+use hk::{DeGroot, Float};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(n: usize) -> Vec<Vec<Float>> {
+        (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1. } else { 0. }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_identity_unchanged() {
+        let mut dg = DeGroot::new(&identity(10), 13).unwrap();
+        let initial = dg.opinions();
+        dg.sweep();
+
+        assert_eq!(dg.opinions(), initial);
+        assert_eq!(dg.accumulated_change, 0.);
+        assert_eq!(dg.cluster_sizes().len(), 10);
+    }
+
+    #[test]
+    fn test_complete_averaging() {
+        let n = 4;
+        let mut dg = DeGroot::new(&vec![vec![0.25; n]; n], 13).unwrap();
+        dg.set_state(&[0.1, 0.2, 0.3, 0.8]).unwrap();
+        dg.sweep();
+
+        for x in dg.opinions() {
+            assert!((x - 0.35).abs() < 1e-6);
+        }
+        assert_eq!(dg.cluster_sizes(), vec![4]);
+
+        // the consensus is a fixed point
+        dg.sweep();
+        assert!(dg.accumulated_change < 1e-6);
+    }
+
+    #[test]
+    fn test_triplets() {
+        // a chain, in which every agent moves halfway to its successor, the last one is stubborn
+        let triplets = [
+            (0, 0, 0.5),
+            (0, 1, 0.5),
+            (1, 1, 0.5),
+            (1, 2, 0.5),
+            (2, 2, 1.),
+        ];
+        let mut sparse = DeGroot::from_triplets(3, &triplets, 13).unwrap();
+        let mut dense = DeGroot::new(
+            &[vec![0.5, 0.5, 0.], vec![0., 0.5, 0.5], vec![0., 0., 1.]],
+            13,
+        )
+        .unwrap();
+        assert_eq!(sparse.opinions(), dense.opinions());
+
+        let mut sweeps = 0;
+        loop {
+            sweeps += 1;
+            sparse.sweep();
+            dense.sweep();
+            assert_eq!(sparse.opinions(), dense.opinions());
+            if sparse.accumulated_change < 1e-6 {
+                break;
+            }
+        }
+        assert!(sweeps < 100);
+        // everybody converges to the stubborn agent
+        assert_eq!(sparse.cluster_sizes(), vec![3]);
+    }
+
+    #[test]
+    fn test_invalid_weights() {
+        // not row-stochastic
+        assert!(DeGroot::new(&[vec![0.5, 0.4], vec![0.5, 0.5]], 13).is_err());
+        // negative entries
+        assert!(DeGroot::new(&[vec![1.5, -0.5], vec![0.5, 0.5]], 13).is_err());
+        // not square
+        assert!(DeGroot::new(&[vec![0.5, 0.5], vec![1.]], 13).is_err());
+        assert!(DeGroot::new(&[vec![0.5, 0.5]], 13).is_err());
+        // out of bounds
+        assert!(DeGroot::from_triplets(2, &[(0, 0, 1.), (1, 2, 1.)], 13).is_err());
+        // within the tolerance
+        assert!(DeGroot::new(&[vec![0.5, 0.50001], vec![0.5, 0.5]], 13).is_ok());
+
+        let mut dg = DeGroot::new(&identity(3), 13).unwrap();
+        assert!(dg.set_state(&[0.1, 0.2]).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! the exact results of short simulations are pinned bit by bit, such that a platform,
+//! a compiler or a refactoring, which changes the rounding of the dynamics, is noticed
+//!
+//! only the plain model on the line is pinned, since it needs nothing but exactly rounded
+//! arithmetic, see the section on reproducibility in the readme
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+/// a system of `n` agents after `sweeps` sweeps
+fn simulate(n: u32, min: Float, max: Float, seed: u64, sweeps: usize) -> HegselmannKrause {
+    let mut hk = HegselmannKrause::new(n, min, max, seed);
+    for _ in 0..sweeps {
+        hk.sweep();
+    }
+    hk
+}
+
+/// the bit patterns of the opinions of the first, the middle and the last agent
+fn opinion_bits(hk: &HegselmannKrause) -> [u64; 3] {
+    let opinions = hk.opinions();
+    let n = opinions.len();
+    // the cast is only needed for f32
+    #[allow(clippy::unnecessary_cast)]
+    let bits = |x: Float| x.to_bits() as u64;
+    [
+        bits(opinions[0]),
+        bits(opinions[n / 2]),
+        bits(opinions[n - 1]),
+    ]
+}
+
+/// (n, min, max, seed, sweeps, fingerprint of the initial state, fingerprint after the sweeps,
+/// opinion bits after the sweeps)
+type Case = (u32, Float, Float, u64, usize, u64, u64, [u64; 3]);
+
+#[cfg(not(feature = "f64"))]
+const CASES: [Case; 3] = [
+    (
+        100,
+        0.1,
+        0.1,
+        1,
+        5,
+        0xcd36_e987_eb79_2e1a,
+        0x9331_f855_4c45_ffb7,
+        [0x3e02_4d70, 0x3f1a_70fa, 0x3f55_8d52],
+    ),
+    (
+        200,
+        0.05,
+        0.2,
+        13,
+        10,
+        0xc539_7903_a3d5_2b86,
+        0x1816_bb5d_2cc7_affd,
+        [0x3f45_6118, 0x3f44_d101, 0x3e9f_1079],
+    ),
+    (
+        500,
+        0.2,
+        0.3,
+        42,
+        20,
+        0xf6b9_6d0a_9bdb_121f,
+        0xeafa_d59e_33c4_1275,
+        [0x3f21_3254, 0x3f21_3254, 0x3e99_545a],
+    ),
+];
+
+#[cfg(feature = "f64")]
+const CASES: [Case; 3] = [
+    (
+        100,
+        0.1,
+        0.1,
+        1,
+        5,
+        0x185f_740b_667d_29f8,
+        0xa082_45d0_8c91_61e5,
+        [
+            0x3fea_f0fd_82e1_0321,
+            0x3fea_f0fd_82e1_0321,
+            0x3fdc_fbe9_259c_0a4f,
+        ],
+    ),
+    (
+        200,
+        0.05,
+        0.2,
+        13,
+        10,
+        0x6b47_26c2_a9d7_4e55,
+        0x4bed_c13a_b149_c4c3,
+        [
+            0x3fca_bc3b_a42a_983e,
+            0x3fde_2814_de4e_29dc,
+            0x3fea_e1c2_589e_7a3c,
+        ],
+    ),
+    (
+        500,
+        0.2,
+        0.3,
+        42,
+        20,
+        0x1e0c_d7c8_0c54_c598,
+        0x10ca_04a7_9d0e_bf76,
+        [
+            0x3fdf_ee04_092f_154f,
+            0x3fdf_ee04_092f_154f,
+            0x3fdf_ee04_092f_154f,
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_initial_states() {
+        for &(n, min, max, seed, _, initial, _, _) in &CASES {
+            let hk = simulate(n, min, max, seed, 0);
+            assert_eq!(
+                hk.state_fingerprint(),
+                initial,
+                "initial state of n = {}, confidences [{}, {}], seed {}",
+                n,
+                min,
+                max,
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn test_pinned_final_states() {
+        // this also holds with the rayon feature, since the new opinions of all agents are
+        // calculated independently of each other
+        for &(n, min, max, seed, sweeps, _, fingerprint, bits) in &CASES {
+            let hk = simulate(n, min, max, seed, sweeps);
+            let case = format!(
+                "n = {}, confidences [{}, {}], seed {}, {} sweeps",
+                n, min, max, seed, sweeps
+            );
+            assert_eq!(opinion_bits(&hk), bits, "{}", case);
+            assert_eq!(hk.state_fingerprint(), fingerprint, "{}", case);
+        }
+    }
+
+    #[test]
+    fn test_repeated_runs() {
+        let hk1 = simulate(300, 0.1, 0.2, 7, 15);
+        let hk2 = simulate(300, 0.1, 0.2, 7, 15);
+        assert!(hk1 == hk2);
+        assert_eq!(hk1.state_fingerprint(), hk2.state_fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_detects_single_bit() {
+        let mut hk = HegselmannKrause::new(3, 0., 0., 13);
+        hk.set_state(&[0.25, 0.5, 0.75], &[0.1, 0.1, 0.1]).unwrap();
+        let fingerprint = hk.state_fingerprint();
+
+        // the next representable opinion
+        let half: Float = 0.5;
+        let next = Float::from_bits(half.to_bits() + 1);
+        hk.set_state(&[0.25, next, 0.75], &[0.1, 0.1, 0.1]).unwrap();
+        assert_ne!(hk.state_fingerprint(), fingerprint);
+
+        // the confidences are part of the state
+        hk.set_state(&[0.25, 0.5, 0.75], &[0.1, 0.1, 0.2]).unwrap();
+        assert_ne!(hk.state_fingerprint(), fingerprint);
+
+        hk.set_state(&[0.25, 0.5, 0.75], &[0.1, 0.1, 0.1]).unwrap();
+        assert_eq!(hk.state_fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_fingerprint_depends_on_order() {
+        let mut hk = HegselmannKrause::new(2, 0., 0., 13);
+        hk.set_state(&[0.25, 0.75], &[0.1, 0.1]).unwrap();
+        let fingerprint = hk.state_fingerprint();
+        hk.set_state(&[0.75, 0.25], &[0.1, 0.1]).unwrap();
+        assert_ne!(hk.state_fingerprint(), fingerprint);
+    }
+}
@@ -0,0 +1,73 @@
+extern crate hk;
+use hk::{DeGroot, Deffuant, Diagnostics, Float, HegselmannKrause, HegselmannKrauseLattice};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_conserved_if_everyone_sees_everyone() {
+        // every agent averages over all agents, so all adopt the mean in the first sweep
+        let mut hk = HegselmannKrause::new(500, 1., 1., 13);
+        let mean = hk.mean_opinion();
+        for _ in 0..100 {
+            hk.sweep();
+            assert!((hk.mean_opinion() - mean).abs() < 1e-5);
+        }
+        assert_eq!(hk.cluster_sizes(), vec![500]);
+    }
+
+    #[test]
+    fn test_bounds_never_widen() {
+        let mut hk = HegselmannKrause::new(500, 0.05, 0.3, 13);
+        let (mut lo, mut hi) = hk.opinion_bounds();
+        assert!(0. <= lo && hi <= 1.);
+        for _ in 0..100 {
+            hk.sweep();
+            let (new_lo, new_hi) = hk.opinion_bounds();
+            assert!(lo <= new_lo && new_hi <= hi);
+            lo = new_lo;
+            hi = new_hi;
+        }
+    }
+
+    #[test]
+    fn test_known_state() {
+        let mut hk = HegselmannKrause::new(4, 0., 0., 13);
+        hk.set_state(&[0.25, 0.75, 0.25, 0.5], &[0.; 4]).unwrap();
+        assert_eq!(hk.mean_opinion(), 0.4375);
+        assert_eq!(hk.opinion_bounds(), (0.25, 0.75));
+        assert_eq!(hk.total_agents_in_clusters(), 4);
+    }
+
+    #[test]
+    fn test_all_models() {
+        fn check(model: &dyn Diagnostics, n: usize) {
+            assert_eq!(model.total_agents_in_clusters(), n);
+            let (lo, hi) = model.opinion_bounds();
+            assert!(0. <= lo && lo <= hi && hi <= 1.);
+            // the mean of a consensus may be rounded past the common opinion
+            let mean = model.mean_opinion();
+            assert!(lo - 1e-6 <= mean && mean <= hi + 1e-6);
+        }
+
+        let mut dw = Deffuant::new(200, 0.1, 0.3, 0.5, 13);
+        for _ in 0..20 {
+            dw.sweep();
+            check(&dw, 200);
+        }
+
+        let weights: Vec<Vec<Float>> = vec![vec![0.1; 10]; 10];
+        let mut dg = DeGroot::new(&weights, 13).unwrap();
+        for _ in 0..20 {
+            dg.sweep();
+            check(&dg, 10);
+        }
+
+        let mut lattice = HegselmannKrauseLattice::new(10, 0.1, 0.3, 13, false);
+        for _ in 0..20 {
+            lattice.sweep();
+            check(&lattice, 100);
+        }
+    }
+}
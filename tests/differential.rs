@@ -0,0 +1,197 @@
+//! differential tests of all sweep implementations on small systems, which are decoded
+//! from bytes and injected with `set_state_asymmetric`, such that a failing input is
+//! independent of the random number generator and can be minimized agent by agent
+extern crate hk;
+use hk::{Backend, Float, HegselmannKrause};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/// opinions and confidences towards lower and higher opinions of every agent
+#[derive(Clone, Debug)]
+struct State {
+    opinions: Vec<Float>,
+    left: Vec<Float>,
+    right: Vec<Float>,
+}
+
+/// decode `bytes` into a system of at most 32 agents
+/// the first byte determines the number of agents and whether the values lie on a grid
+/// of binary fractions, where many agents are exactly at the boundary of the intervals
+/// of others, every agent consumes three bytes, missing bytes are zero
+fn decode(bytes: &[u8]) -> State {
+    let (&head, rest) = bytes.split_first().unwrap_or((&0, &[]));
+    let n = (head % 32) as usize + 1;
+    let grid = head & 0x80 != 0;
+    let byte = |k: usize| rest.get(k).copied().unwrap_or(0);
+    let opinion = |b: u8| {
+        if grid {
+            (b % 65) as Float / 64.
+        } else {
+            b as Float / 255.
+        }
+    };
+    let confidence = |b: u8| {
+        if grid {
+            (b % 17) as Float / 64.
+        } else {
+            b as Float / 510.
+        }
+    };
+    State {
+        opinions: (0..n).map(|i| opinion(byte(3 * i))).collect(),
+        left: (0..n).map(|i| confidence(byte(3 * i + 1))).collect(),
+        right: (0..n).map(|i| confidence(byte(3 * i + 2))).collect(),
+    }
+}
+
+/// a model in the given state
+fn model(state: &State) -> HegselmannKrause {
+    let mut hk = HegselmannKrause::new(state.opinions.len() as u32, 0., 0., 13);
+    hk.set_state_asymmetric(&state.opinions, &state.left, &state.right)
+        .unwrap();
+    hk
+}
+
+/// a named implementation of a synchronous sweep
+type Sweep = (&'static str, fn(&mut HegselmannKrause));
+
+/// all implementations of a synchronous sweep, compared to the naive one
+fn backends() -> Vec<Sweep> {
+    vec![
+        ("tree", HegselmannKrause::sweep_tree),
+        ("sorted", HegselmannKrause::sweep_sorted),
+        ("auto", |hk| {
+            hk.set_backend(Backend::Auto);
+            hk.sweep()
+        }),
+        ("dispatched tree", |hk| {
+            hk.set_backend(Backend::Tree);
+            hk.sweep()
+        }),
+        ("dispatched sorted", |hk| {
+            hk.set_backend(Backend::Sorted);
+            hk.sweep()
+        }),
+    ]
+}
+
+/// run a few sweeps of every implementation from the same state and describe the first
+/// disagreement with the naive sweep or the first violated invariant
+/// the rounding differences of the sums can flip agents close to a boundary, which grows
+/// into large deviations, so every sweep starts from the state of the naive sweep
+fn check(state: &State) -> Result<(), String> {
+    let mut state = state.clone();
+    for sweep in 0..5 {
+        let mut reference = model(&state);
+        reference.sweep_naive();
+        let expected = reference.opinions();
+        for (name, backend) in backends() {
+            let mut hk = model(&state);
+            backend(&mut hk);
+            hk.verify_invariants()
+                .map_err(|e| format!("{} after sweep {}: {}", name, sweep, e))?;
+            // NaN is never close to anything
+            let close = |x: Float, y: Float| (x - y).abs() < 1e-4;
+            let opinions = hk.opinions();
+            if let Some(idx) = (0..expected.len()).find(|&i| !close(expected[i], opinions[i])) {
+                return Err(format!(
+                    "{} after sweep {}: agent {} has opinion {}, naive {}",
+                    name, sweep, idx, opinions[idx], expected[idx]
+                ));
+            }
+        }
+        state.opinions = expected;
+    }
+    Ok(())
+}
+
+/// remove agents from a state failing `check` as long as it keeps failing
+fn minimize<F>(mut state: State, check: F) -> (State, String)
+where
+    F: Fn(&State) -> Result<(), String>,
+{
+    let mut reason = check(&state).unwrap_err();
+    let mut idx = 0;
+    while idx < state.opinions.len() && state.opinions.len() > 1 {
+        let mut smaller = state.clone();
+        smaller.opinions.remove(idx);
+        smaller.left.remove(idx);
+        smaller.right.remove(idx);
+        match check(&smaller) {
+            Err(r) => {
+                state = smaller;
+                reason = r;
+            }
+            Ok(()) => idx += 1,
+        }
+    }
+    (state, reason)
+}
+
+/// check the state decoded from `bytes` and report a minimized failure
+fn assert_agree(bytes: &[u8]) {
+    let state = decode(bytes);
+    if check(&state).is_err() {
+        let (minimal, reason) = minimize(state, check);
+        panic!(
+            "sweeps disagree for input {:02x?}\n{}\nminimal state: {:?}",
+            bytes, reason, minimal
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let state = decode(&[0x82, 64, 4, 8, 32]);
+        assert_eq!(state.opinions, vec![1., 0.5, 0.]);
+        assert_eq!(state.left, vec![0.0625, 0., 0.]);
+        assert_eq!(state.right, vec![0.125, 0., 0.]);
+        assert_eq!(decode(&[]).opinions, vec![0.]);
+    }
+
+    #[test]
+    fn test_random_bytes() {
+        let mut rng = Pcg64::seed_from_u64(13);
+        for _ in 0..1000 {
+            let len = rng.gen_range(1, 100);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            assert_agree(&bytes);
+        }
+    }
+
+    #[test]
+    fn test_all_pairs_on_grid() {
+        // two agents at all combinations of opinions and symmetric confidences on a coarse
+        // grid, which covers every way two intervals can touch
+        for a in (0..65).step_by(4) {
+            for b in (0..65).step_by(4) {
+                for ca in 0..17 {
+                    for cb in (0..17).step_by(2) {
+                        assert_agree(&[0x81, a, ca, ca, b, cb, cb]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        // a state, which fails only because of one agent, is minimized to it
+        let state = decode(&[0x83, 0, 1, 0, 64, 2, 0, 32, 3, 0, 16, 4, 0]);
+        let check = |s: &State| {
+            if s.opinions.contains(&0.5) {
+                Err("contains 0.5".to_string())
+            } else {
+                Ok(())
+            }
+        };
+        let (minimal, reason) = minimize(state, check);
+        assert_eq!(minimal.opinions, vec![0.5]);
+        assert_eq!(minimal.left, vec![3. / 64.]);
+        assert_eq!(reason, "contains 0.5");
+    }
+}
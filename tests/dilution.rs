@@ -0,0 +1,51 @@
+extern crate hk;
+use hk::{HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_probability_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive_diluted(1.);
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_diluted_reproducible() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk1.set_interaction_probability(0.5).unwrap();
+        hk2.set_interaction_probability(0.5).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep_naive_diluted(0.5);
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_dilution_changes_dynamics() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Circle);
+        hk2.set_interaction_probability(0.5).unwrap();
+        hk1.sweep_naive();
+        hk2.sweep();
+        assert_ne!(hk1.opinions(), hk2.opinions());
+        assert!(hk2.opinions().iter().all(|x| (0. ..1.).contains(x)));
+    }
+
+    #[test]
+    fn test_invalid_probability() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_interaction_probability(0.).is_err());
+        assert!(hk.set_interaction_probability(1.1).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+extern crate hk;
+use hk::{Boundary, Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_drift_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_drift(0., Boundary::Clamp).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_drift(0., Boundary::Reflect).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_drift_after_averaging() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.25, 0.75], &[1., 1.]).unwrap();
+        hk.set_drift(0.125, Boundary::Clamp).unwrap();
+        hk.sweep_tree();
+
+        assert_eq!(hk.opinions(), vec![0.625, 0.625]);
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.875, 0.125], &[0.1, 0.1]).unwrap();
+        hk.set_drift(0.25, Boundary::Clamp).unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), vec![1., 0.375]);
+
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.875, 0.125], &[0.1, 0.1]).unwrap();
+        hk.set_drift(-0.25, Boundary::Reflect).unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), vec![0.625, 0.125]);
+
+        let mut hk = HegselmannKrause::with_topology(2, 0., 1., 13, OpinionSpace::Circle);
+        hk.set_state(&[0.875, 0.125], &[0.1, 0.1]).unwrap();
+        hk.set_drift(0.25, Boundary::Periodic).unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), vec![0.125, 0.375]);
+    }
+
+    #[test]
+    fn test_cmp_drift_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_drift(0.01, Boundary::Reflect).unwrap();
+        hk2.set_drift(0.01, Boundary::Reflect).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, OpinionSpace::Circle);
+        hk1.set_drift(0.03, Boundary::Periodic).unwrap();
+        hk2.set_drift(0.03, Boundary::Periodic).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_drift_corrected_change() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.25, 0.25], &[0.1, 0.1]).unwrap();
+        hk.set_drift(0.125, Boundary::Clamp).unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.last_change(), 0.25);
+
+        hk.set_drift_corrected_change(true);
+        hk.sweep_tree();
+        assert_eq!(hk.last_change(), 0.);
+        assert_eq!(hk.opinions(), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_invalid_drift() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_drift(Float::NAN, Boundary::Clamp).is_err());
+        assert!(hk.set_drift(Float::INFINITY, Boundary::Periodic).is_err());
+        assert!(hk.set_drift(0.1, Boundary::Periodic).is_ok());
+    }
+}
@@ -0,0 +1,111 @@
+extern crate hk;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    /// run the command line interface with the given arguments and return its output file
+    fn run(name: &str, args: &[&str]) -> Vec<u8> {
+        let outname: PathBuf =
+            std::env::temp_dir().join(format!("hk_ensemble_{}_{}", std::process::id(), name));
+        let status = Command::new(env!("CARGO_BIN_EXE_hk"))
+            .args(args)
+            .arg("--outname")
+            .arg(&outname)
+            .status()
+            .expect("the binary could not be run");
+        assert!(status.success());
+        let output = fs::read(&outname).unwrap();
+        fs::remove_file(&outname).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_ensemble_independent_of_threads() {
+        let args = [
+            "-n",
+            "200",
+            "-l",
+            "0.05",
+            "-u",
+            "0.3",
+            "--samples",
+            "12",
+            "--ensemble",
+        ];
+        let sequential = run("sequential", &[&args[..], &["--threads", "1"]].concat());
+        let parallel = run("parallel", &[&args[..], &["--threads", "4"]].concat());
+        assert!(!sequential.is_empty());
+        assert_eq!(sequential, parallel);
+    }
+
+    #[test]
+    fn test_ensemble_samples_seeded_individually() {
+        let args = ["-n", "100", "-l", "0.1", "-u", "0.2", "--seed", "7"];
+        let ensemble = run(
+            "ensemble",
+            &[
+                &args[..],
+                &["--samples", "3", "--ensemble", "--threads", "3"],
+            ]
+            .concat(),
+        );
+
+        // sample i of the ensemble is a single sample with the seed 7 + i
+        let single: Vec<u8> = (7..10)
+            .flat_map(|seed| {
+                let seed = seed.to_string();
+                let args = ["-n", "100", "-l", "0.1", "-u", "0.2", "--seed", &seed];
+                run(&format!("single_{}", seed), &args)
+            })
+            .collect();
+        assert_eq!(ensemble, single);
+    }
+
+    #[test]
+    fn test_ensemble_trajectory() {
+        let args = [
+            "-n",
+            "50",
+            "--samples",
+            "5",
+            "--ensemble",
+            "--trajectory",
+            "--max-sweeps",
+            "20",
+        ];
+        let sequential = run("trajectory_1", &[&args[..], &["--threads", "1"]].concat());
+        let parallel = run("trajectory_3", &[&args[..], &["--threads", "3"]].concat());
+        assert_eq!(sequential, parallel);
+    }
+
+    #[test]
+    fn test_samples_reproducible_on_their_own() {
+        let args = ["-n", "100", "-l", "0.1", "-u", "0.2"];
+        let together = run(
+            "together",
+            &[&args[..], &["--seed", "7", "--samples", "3"]].concat(),
+        );
+        assert!(String::from_utf8_lossy(&together).starts_with("# seed: 7\n"));
+
+        // sample i of a run with the seed 7 is the only sample of a run with the seed 7 + i
+        let alone: Vec<u8> = (7..10)
+            .flat_map(|seed| {
+                let seed = seed.to_string();
+                run(
+                    &format!("alone_{}", seed),
+                    &[&args[..], &["--seed", &seed]].concat(),
+                )
+            })
+            .collect();
+        assert_eq!(together, alone);
+
+        let ensemble = run(
+            "together_ensemble",
+            &[&args[..], &["--seed", "7", "--samples", "3", "--ensemble"]].concat(),
+        );
+        assert_eq!(together, ensemble);
+    }
+}
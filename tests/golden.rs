@@ -0,0 +1,143 @@
+//! the output of complete simulations is compared to the golden files in `tests/golden`,
+//! such that every change of the output format, which the analysis scripts parse, is noticed
+//!
+//! after an intended change of the format or of the dynamics, regenerate the golden files with
+//!
+//!     HK_UPDATE_GOLDEN=1 cargo test --test golden
+//!     HK_UPDATE_GOLDEN=1 cargo test --test golden --features f64
+//!
+//! and commit them together with the change, the random numbers and the printed opinions
+//! differ for f64, such that both precisions have golden files of their own
+extern crate hk;
+use hk::{ConvergenceCriterion, Deffuant, Float, HegselmannKrause};
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// environment variable, which lets the tests write the golden files instead of comparing
+const UPDATE: &str = "HK_UPDATE_GOLDEN";
+
+fn golden_path(name: &str) -> PathBuf {
+    let precision = if cfg!(feature = "f64") { "f64" } else { "f32" };
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.{}.txt", name, precision))
+}
+
+/// compare `output` to the golden file `name` or replace the golden file by it
+fn check_golden(name: &str, output: &[u8]) {
+    let path = golden_path(name);
+    if std::env::var_os(UPDATE).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, output).unwrap();
+        return;
+    }
+    let expected = fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "golden file {} could not be read ({}), create it with {}=1",
+            path.display(),
+            e,
+            UPDATE
+        )
+    });
+    assert!(
+        output == expected.as_slice(),
+        "output differs from {}, regenerate it with {}=1 if the change is intended\n\
+         expected:\n{}\nfound:\n{}",
+        path.display(),
+        UPDATE,
+        String::from_utf8_lossy(&expected),
+        String::from_utf8_lossy(output)
+    );
+}
+
+/// the block the main program writes for a single sample with the default arguments
+fn hk_block(n: u32, min: Float, max: Float, seed: u64) -> Vec<u8> {
+    let mut hk = HegselmannKrause::new(n, min, max, seed);
+    hk.reset_with_seed(seed);
+    let sweeps = hk
+        .run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-4 }, None)
+        .unwrap();
+    let mut output = Vec::new();
+    writeln!(output, "# seed: {}", seed).unwrap();
+    writeln!(output, "# sweeps: {}", sweeps).unwrap();
+    hk.write_cluster_sizes(&mut output).unwrap();
+    output
+}
+
+/// like `hk_block`, but for the Deffuant model with the default mu
+fn deffuant_block(n: u32, min: Float, max: Float, seed: u64) -> Vec<u8> {
+    let mut dw = Deffuant::new(n, min, max, 0.5, seed);
+    dw.reset_with_seed(seed);
+    let sweeps = dw
+        .run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-4 }, None)
+        .unwrap();
+    let mut output = Vec::new();
+    writeln!(output, "# seed: {}", seed).unwrap();
+    writeln!(output, "# sweeps: {}", sweeps).unwrap();
+    dw.write_cluster_sizes(&mut output).unwrap();
+    output
+}
+
+/// run the command line interface for a single sample and return its output file
+fn run_cli(name: &str, model: &str, n: u32, min: Float, max: Float, seed: u64) -> Vec<u8> {
+    let outname = std::env::temp_dir().join(format!("hk_golden_{}_{}", std::process::id(), name));
+    let status = Command::new(env!("CARGO_BIN_EXE_hk"))
+        .args(["--model", model, "-n", &n.to_string()])
+        .args(["-l", &min.to_string(), "-u", &max.to_string()])
+        .args(["--seed", &seed.to_string()])
+        .arg("--outname")
+        .arg(&outname)
+        .status()
+        .expect("the binary could not be run");
+    assert!(status.success());
+    let output = fs::read(&outname).unwrap();
+    fs::remove_file(&outname).unwrap();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hk_seed_13() {
+        check_golden("hk_seed13", &hk_block(200, 0.05, 0.2, 13));
+    }
+
+    #[test]
+    fn test_hk_seed_42() {
+        check_golden("hk_seed42", &hk_block(500, 0.1, 0.3, 42));
+    }
+
+    #[test]
+    fn test_deffuant_seed_7() {
+        check_golden("deffuant_seed7", &deffuant_block(200, 0.1, 0.3, 7));
+    }
+
+    #[test]
+    fn test_hk_single_agent() {
+        // a single agent is converged without any sweep
+        check_golden("hk_single", &hk_block(1, 0.1, 0.2, 1));
+    }
+
+    #[test]
+    fn test_cli_like_library() {
+        // the main program writes exactly the blocks of the library pipeline
+        assert_eq!(
+            run_cli("hk", "hk", 200, 0.05, 0.2, 13),
+            hk_block(200, 0.05, 0.2, 13)
+        );
+        assert_eq!(
+            run_cli("deffuant", "deffuant", 200, 0.1, 0.3, 7),
+            deffuant_block(200, 0.1, 0.3, 7)
+        );
+        assert_eq!(
+            run_cli("single", "hk", 1, 0.1, 0.2, 1),
+            hk_block(1, 0.1, 0.2, 1)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+extern crate hk;
+use hk::{ConfidenceDistribution, HegselmannKrause, HkError, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_three_agents_two_groups() {
+        for &tree in &[false, true] {
+            let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+            hk.set_state(&[0.25, 0.5, 0.625], &[0.; 3]).unwrap();
+            hk.set_groups(&[0, 0, 1], &[vec![0.3, 0.1], vec![0.5, 0.5]])
+                .unwrap();
+            if tree {
+                hk.sweep_groups_tree();
+            } else {
+                hk.sweep_groups();
+            }
+
+            let opinions = hk.opinions();
+            // group 0 is tolerant within its group only
+            assert!((opinions[0] - 0.375).abs() < 1e-6);
+            assert!((opinions[1] - 0.375).abs() < 1e-6);
+            // group 1 is tolerant towards everybody
+            assert!((opinions[2] - 1.375 / 3.).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_single_group_is_standard_model() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        let constant = ConfidenceDistribution::Constant { value: 0.2 };
+        hk1.set_confidence_distribution(constant.clone()).unwrap();
+        hk2.set_confidence_distribution(constant).unwrap();
+        hk1.reset();
+        hk2.reset();
+        hk2.set_groups(&[0; 100], &[vec![0.2]]).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep_naive();
+            hk2.sweep_groups();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_cmp_groups_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        let assignments: Vec<u8> = (0..100).map(|i| (i % 3 == 0) as u8).collect();
+        let matrix = vec![vec![0.2, 0.05], vec![0.1, 0.3]];
+        hk1.set_groups(&assignments, &matrix).unwrap();
+        hk2.set_groups(&assignments, &matrix).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep_groups();
+            hk2.sweep_groups_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_groups_survive_reset() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        let assignments = [0, 1, 2, 0, 1, 2, 0, 1, 2, 0];
+        let matrix = vec![vec![0.1; 3]; 3];
+        hk.set_groups(&assignments, &matrix).unwrap();
+        hk.reset();
+        for _ in 0..100 {
+            hk.sweep();
+        }
+
+        // every cluster reports its members per group
+        let composition = hk.cluster_composition();
+        assert_eq!(composition.len(), hk.cluster_sizes().len());
+        let per_group: Vec<usize> = (0..3)
+            .map(|g| composition.iter().map(|c| c[g]).sum())
+            .collect();
+        assert_eq!(per_group, vec![4, 3, 3]);
+    }
+
+    #[test]
+    fn test_invalid_groups() {
+        let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+        let matrix = vec![vec![0.1, 0.2], vec![0.2, 0.1]];
+        let invalid = |r: Result<(), HkError>| matches!(r, Err(HkError::InvalidGroups(_)));
+        assert!(invalid(hk.set_groups(&[0, 1], &matrix)));
+        assert!(invalid(hk.set_groups(&[0, 1, 2], &matrix)));
+        assert!(invalid(hk.set_groups(&[0, 1, 1], &[vec![0.1, 0.2]])));
+        assert!(invalid(hk.set_groups(&[0, 0, 0], &[])));
+        assert!(invalid(hk.set_groups(&[0, 0, 0], &[vec![-0.1]])));
+        assert!(hk.set_groups(&[0, 1, 1], &matrix).is_ok());
+
+        let mut hk = HegselmannKrause::with_topology(3, 0., 1., 13, OpinionSpace::Circle);
+        assert!(invalid(hk.set_groups(&[0, 1, 1], &matrix)));
+    }
+}
@@ -0,0 +1,122 @@
+extern crate hk;
+use hk::{Float, HegselmannKrauseND};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close<const D: usize>(a: [Float; D], b: [Float; D]) {
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_two_agents_merge() {
+        let mut hk = HegselmannKrauseND::<2>::new(2, 0., 1., 13);
+        // distance is 0.5, both see each other
+        hk.set_state(&[[0.1, 0.2], [0.4, 0.6]], &[0.6, 0.6]);
+        hk.sweep();
+
+        let opinions = hk.opinions();
+        assert_close(opinions[0], [0.25, 0.4]);
+        assert_close(opinions[1], [0.25, 0.4]);
+        assert!((hk.accumulated_change - 0.5).abs() < 1e-6);
+        assert_eq!(hk.cluster_sizes(), vec![2]);
+    }
+
+    #[test]
+    fn test_three_agents_asymmetric() {
+        let mut hk = HegselmannKrauseND::<2>::new(3, 0., 1., 13);
+        // agent 0 and 1 have distance 0.5, agent 1 and 2 have distance 0.3,
+        // agent 0 and 2 have distance 0.8
+        hk.set_state(&[[0.0, 0.0], [0.3, 0.4], [0.3, 0.7]], &[0.55, 0.1, 0.35]);
+        hk.sweep();
+
+        let opinions = hk.opinions();
+        // agent 0 sees agent 1
+        assert_close(opinions[0], [0.15, 0.2]);
+        // agent 1 sees only itself
+        assert_close(opinions[1], [0.3, 0.4]);
+        // agent 2 sees agent 1
+        assert_close(opinions[2], [0.3, 0.55]);
+    }
+
+    #[test]
+    fn test_far_agents_do_not_interact() {
+        let mut hk = HegselmannKrauseND::<3>::new(2, 0., 1., 13);
+        // in every single coordinate, they are within range, but not in euclidean distance
+        hk.set_state(&[[0.0, 0.0, 0.0], [0.2, 0.2, 0.2]], &[0.3, 0.3]);
+        hk.sweep();
+
+        assert_eq!(hk.accumulated_change, 0.);
+        assert_eq!(hk.cluster_sizes(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_converges() {
+        let mut hk = HegselmannKrauseND::<2>::new(100, 0.2, 0.4, 13);
+        let mut sweeps = 0;
+        loop {
+            sweeps += 1;
+            hk.sweep();
+            if hk.accumulated_change < 1e-4 {
+                break;
+            }
+        }
+        assert!(sweeps < 1000);
+        assert_eq!(hk.cluster_sizes().iter().sum::<usize>(), 100);
+        assert_eq!(hk.cluster_centroids().len(), hk.cluster_sizes().len());
+    }
+}
+
+#[cfg(test)]
+mod chains {
+    use super::*;
+
+    /// frozen agents with the given opinions in the given order and in reverse
+    fn both_orders(opinions: &[[Float; 2]]) -> Vec<HegselmannKrauseND<2>> {
+        let mut reversed = opinions.to_vec();
+        reversed.reverse();
+        [opinions.to_vec(), reversed]
+            .iter()
+            .map(|opinions| {
+                let mut hk = HegselmannKrauseND::<2>::new(opinions.len() as u32, 0., 0., 13);
+                hk.set_state(opinions, &vec![0.; opinions.len()]);
+                hk
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diagonal_chain_is_one_cluster() {
+        // every agent is closer than 1e-5 to the next one in both coordinates, a greedy
+        // assignment to the first representative splits the chain into short pieces
+        let chain: Vec<[Float; 2]> = (0..100)
+            .map(|i| {
+                let x = 0.3 + i as Float * 0.5e-5;
+                [x, x]
+            })
+            .collect();
+        for hk in both_orders(&chain) {
+            assert_eq!(hk.cluster_sizes(), vec![100]);
+            let centroid = hk.cluster_centroids()[0];
+            assert!((centroid[0] - (0.3 + 49.5 * 0.5e-5)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_bent_chain_with_gap() {
+        // a chain along the second coordinate, which bends into the first one, and a
+        // separate agent, which is close in the first coordinate only
+        let mut opinions: Vec<[Float; 2]> =
+            (0..40).map(|i| [0.5, 0.5 + i as Float * 0.6e-5]).collect();
+        opinions.extend((1..40).map(|i| [0.5 + i as Float * 0.6e-5, 0.5]));
+        opinions.push([0.5, 0.6]);
+        for hk in both_orders(&opinions) {
+            let sizes = hk.cluster_sizes();
+            assert_eq!(sizes, vec![79, 1]);
+            assert_eq!(sizes.iter().sum::<usize>(), opinions.len());
+        }
+    }
+}
@@ -0,0 +1,389 @@
+// the casts between f32 and f64 are only redundant, if the opinions are f64 anyway
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+extern crate hk;
+use hk::{Float, HegselmannKrause, HkError, InitialOpinions};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// mean and variance of a sample
+    fn moments(x: &[Float]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let var = x.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / n;
+        (mean, var)
+    }
+
+    #[test]
+    fn test_gaussian_moments() {
+        let mut hk = HegselmannKrause::new(100_000, 0., 1., 13);
+        hk.set_initial_opinions(InitialOpinions::Gaussian { mean: 0.4, sd: 0.1 })
+            .unwrap();
+        hk.reset();
+
+        let opinions = hk.opinions();
+        assert!(opinions.iter().all(|&x| (0. ..=1.).contains(&x)));
+        let (mean, var) = moments(&opinions);
+        assert!((mean - 0.4).abs() < 1e-3, "mean {}", mean);
+        assert!((var - 0.01).abs() < 1e-3, "variance {}", var);
+    }
+
+    #[test]
+    fn test_truncated_gaussian() {
+        // a wide distribution centered at the boundary is strongly truncated
+        let mut hk = HegselmannKrause::new(100_000, 0., 1., 13);
+        hk.set_initial_opinions(InitialOpinions::Gaussian { mean: 0., sd: 1. })
+            .unwrap();
+        hk.reset();
+
+        let opinions = hk.opinions();
+        assert!(opinions.iter().all(|&x| (0. ..=1.).contains(&x)));
+        // mean of a standard normal truncated to [0, 1]
+        let (mean, _) = moments(&opinions);
+        assert!((mean - 0.459_862).abs() < 2e-3, "mean {}", mean);
+    }
+
+    #[test]
+    fn test_draws_independent_of_distribution() {
+        let mut hk1 = HegselmannKrause::new(1000, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(1000, 0., 1., 13);
+        hk2.set_initial_opinions(InitialOpinions::Gaussian { mean: 0.5, sd: 0.1 })
+            .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        // the confidences are the same, whichever distribution of opinions is used
+        assert_eq!(hk1.confidences(), hk2.confidences());
+        assert_ne!(hk1.opinions(), hk2.opinions());
+
+        // also for all following realizations
+        hk1.reset();
+        hk2.reset();
+        assert_eq!(hk1.confidences(), hk2.confidences());
+    }
+
+    #[test]
+    fn test_custom_opinions() {
+        let opinions: Vec<Float> = (0..10).map(|i| i as Float / 10.).collect();
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        hk.set_initial_opinions(InitialOpinions::Custom(opinions.clone()))
+            .unwrap();
+        hk.reset();
+        assert_eq!(hk.opinions(), opinions);
+
+        assert!(matches!(
+            hk.set_initial_opinions(InitialOpinions::Custom(vec![0.5; 3])),
+            Err(HkError::InvalidInitialOpinions(_))
+        ));
+        assert!(hk
+            .set_initial_opinions(InitialOpinions::Custom(vec![1.5; 10]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_initial_opinions() {
+        assert_eq!("uniform".parse(), Ok(InitialOpinions::Uniform));
+        assert_eq!(
+            "gaussian:0.5:0.1".parse(),
+            Ok(InitialOpinions::Gaussian { mean: 0.5, sd: 0.1 })
+        );
+        assert!("gaussian:0.5".parse::<InitialOpinions>().is_err());
+        assert!("gaussian:a:0.1".parse::<InitialOpinions>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod extremist_tests {
+    use super::*;
+
+    fn extremists(fraction: Float) -> InitialOpinions {
+        InitialOpinions::Extremists {
+            fraction,
+            width: 0.05,
+            extremist_confidence: 0.01,
+        }
+    }
+
+    #[test]
+    fn test_no_extremists_is_default() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_initial_opinions(extremists(0.)).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        assert_eq!(hk1.opinions(), hk2.opinions());
+        assert_eq!(hk1.confidences(), hk2.confidences());
+        assert!(hk2.cluster_extremists().iter().all(|&e| e == 0));
+    }
+
+    #[test]
+    fn test_extremists_at_boundaries() {
+        let mut hk = HegselmannKrause::new(10_000, 0.2, 0.3, 13);
+        hk.set_initial_opinions(extremists(0.2)).unwrap();
+        hk.reset();
+
+        let opinions = hk.opinions();
+        let confidences = hk.confidences();
+        let num_extremists = confidences.iter().filter(|&&c| c == 0.01).count();
+        assert!((num_extremists as Float / 10_000. - 0.2).abs() < 0.02);
+        for (x, c) in opinions.iter().zip(confidences.iter()) {
+            if *c == 0.01 {
+                assert!(*x <= 0.05 || *x >= 0.95);
+            }
+        }
+        assert_eq!(
+            hk.cluster_extremists().iter().sum::<usize>(),
+            num_extremists
+        );
+
+        // the same seed yields the same extremists
+        let mut hk2 = HegselmannKrause::new(10_000, 0.2, 0.3, 13);
+        hk2.set_initial_opinions(extremists(0.2)).unwrap();
+        hk2.reset();
+        assert_eq!(confidences, hk2.confidences());
+    }
+
+    #[test]
+    fn test_invalid_extremists() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        let invalid = InitialOpinions::Extremists {
+            fraction: 0.1,
+            width: 0.7,
+            extremist_confidence: 0.01,
+        };
+        assert!(matches!(
+            hk.set_initial_opinions(invalid),
+            Err(HkError::InvalidInitialOpinions(_))
+        ));
+        assert_eq!("extremists:0.1:0.05:0.01".parse(), Ok(extremists(0.1)));
+    }
+}
+
+#[cfg(test)]
+mod beta_tests {
+    use super::*;
+
+    /// mean and variance of a sample
+    fn moments(x: &[Float]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let var = x.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / n;
+        (mean, var)
+    }
+
+    #[test]
+    fn test_beta_moments() {
+        for &(alpha, beta) in &[(2., 5.), (0.5, 0.5), (3., 3.)] {
+            let mut hk = HegselmannKrause::new(100_000, 0., 1., 13);
+            hk.set_initial_opinions(InitialOpinions::Beta { alpha, beta })
+                .unwrap();
+            hk.reset();
+
+            let opinions = hk.opinions();
+            assert!(opinions.iter().all(|&x| (0. ..=1.).contains(&x)));
+            let (mean, var) = moments(&opinions);
+            let (a, b) = (alpha as f64, beta as f64);
+            let expected_mean = a / (a + b);
+            let expected_var = a * b / ((a + b).powi(2) * (a + b + 1.));
+            assert!((mean - expected_mean).abs() < 3e-3, "mean {}", mean);
+            assert!((var - expected_var).abs() < 3e-3, "variance {}", var);
+        }
+    }
+
+    #[test]
+    fn test_beta_one_one_is_uniform() {
+        let mut hk1 = HegselmannKrause::new(1000, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(1000, 0., 1., 13);
+        hk2.set_initial_opinions(InitialOpinions::Beta {
+            alpha: 1.,
+            beta: 1.,
+        })
+        .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        #[cfg(not(feature = "f64"))]
+        assert_eq!(hk1.opinions(), hk2.opinions());
+        // the quantile is found numerically, which is only exact up to f32
+        #[cfg(feature = "f64")]
+        for (x, y) in hk1.opinions().iter().zip(hk2.opinions()) {
+            assert!((x - y).abs() < 1e-12);
+        }
+        assert_eq!(hk1.confidences(), hk2.confidences());
+    }
+
+    #[test]
+    fn test_parse_beta() {
+        assert_eq!(
+            "beta:2:5".parse(),
+            Ok(InitialOpinions::Beta {
+                alpha: 2.,
+                beta: 5.
+            })
+        );
+        assert!("beta:2".parse::<InitialOpinions>().is_err());
+
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        for &(alpha, beta) in &[(0., 1.), (1., -1.), (Float::INFINITY, 1.)] {
+            assert!(matches!(
+                hk.set_initial_opinions(InitialOpinions::Beta { alpha, beta }),
+                Err(HkError::InvalidInitialOpinions(_))
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod block_tests {
+    use super::*;
+
+    fn blocks(split_fraction: Float) -> InitialOpinions {
+        InitialOpinions::TwoBlocks {
+            split_fraction,
+            range_a: (0., 0.4),
+            range_b: (0.6, 1.),
+            confidence_a: (0.01, 0.02),
+            confidence_b: (0.1, 0.2),
+        }
+    }
+
+    /// mean and variance of a sample
+    fn moments(x: &[Float]) -> (f64, f64) {
+        let n = x.len() as f64;
+        let mean = x.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let var = x.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / n;
+        (mean, var)
+    }
+
+    #[test]
+    fn test_identical_blocks_like_uniform() {
+        let mut hk1 = HegselmannKrause::new(100_000, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100_000, 0.1, 0.3, 17);
+        hk2.set_initial_opinions(InitialOpinions::TwoBlocks {
+            split_fraction: 0.3,
+            range_a: (0., 1.),
+            range_b: (0., 1.),
+            confidence_a: (0.1, 0.3),
+            confidence_b: (0.1, 0.3),
+        })
+        .unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for (x, y) in [
+            (hk1.opinions(), hk2.opinions()),
+            (hk1.confidences(), hk2.confidences()),
+        ]
+        .iter()
+        {
+            let ((mean1, var1), (mean2, var2)) = (moments(x), moments(y));
+            assert!((mean1 - mean2).abs() < 3e-3, "{} != {}", mean1, mean2);
+            assert!((var1 - var2).abs() < 1e-3, "{} != {}", var1, var2);
+
+            // compare the deciles of both samples
+            let mut x = x.clone();
+            let mut y = y.clone();
+            x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            y.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for k in 1..10 {
+                let idx = k * x.len() / 10;
+                assert!((x[idx] - y[idx]).abs() < 5e-3, "{} != {}", x[idx], y[idx]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocks_within_ranges() {
+        let mut hk = HegselmannKrause::new(10_000, 0., 1., 13);
+        hk.set_initial_opinions(blocks(0.25)).unwrap();
+        hk.reset();
+
+        let mut in_a = 0;
+        for (x, c) in hk.opinions().iter().zip(hk.confidences()) {
+            if *x <= 0.4 {
+                in_a += 1;
+                assert!((0.01..=0.02).contains(&c));
+            } else {
+                assert!((0.6..=1.).contains(x));
+                assert!((0.1..=0.2).contains(&c));
+            }
+        }
+        assert!((in_a as Float / 10_000. - 0.25).abs() < 0.02, "{}", in_a);
+    }
+
+    #[test]
+    fn test_blocks_deterministic() {
+        let mut hk1 = HegselmannKrause::new(1000, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(1000, 0., 1., 13);
+        hk1.set_initial_opinions(blocks(0.5)).unwrap();
+        hk2.set_initial_opinions(blocks(0.5)).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        assert_eq!(hk1.opinions(), hk2.opinions());
+        assert_eq!(hk1.confidences(), hk2.confidences());
+    }
+
+    #[test]
+    fn test_block_composition() {
+        let mut hk = HegselmannKrause::new(1000, 0., 1., 13);
+        hk.set_initial_opinions(blocks(0.5)).unwrap();
+        hk.reset();
+        for _ in 0..100 {
+            hk.sweep();
+        }
+
+        // the blocks are too far apart to interact, every cluster is pure
+        let composition = hk.cluster_composition();
+        for (c, size) in composition.iter().zip(hk.cluster_sizes()) {
+            assert!(c.contains(&size), "{:?}", c);
+        }
+        let total_a: usize = composition.iter().map(|c| c[0]).sum();
+        assert!((400..600).contains(&total_a), "{}", total_a);
+    }
+
+    #[test]
+    fn test_invalid_blocks() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        let valid = blocks(0.5);
+        assert!(hk.set_initial_opinions(valid.clone()).is_ok());
+        let invalid = [
+            InitialOpinions::TwoBlocks {
+                split_fraction: 1.5,
+                range_a: (0., 0.4),
+                range_b: (0.6, 1.),
+                confidence_a: (0.1, 0.2),
+                confidence_b: (0.1, 0.2),
+            },
+            InitialOpinions::TwoBlocks {
+                split_fraction: 0.5,
+                range_a: (0.4, 0.),
+                range_b: (0.6, 1.),
+                confidence_a: (0.1, 0.2),
+                confidence_b: (0.1, 0.2),
+            },
+            InitialOpinions::TwoBlocks {
+                split_fraction: 0.5,
+                range_a: (0., 0.4),
+                range_b: (0.6, 1.2),
+                confidence_a: (0.1, 0.2),
+                confidence_b: (0.1, 0.2),
+            },
+            InitialOpinions::TwoBlocks {
+                split_fraction: 0.5,
+                range_a: (0., 0.4),
+                range_b: (0.6, 1.),
+                confidence_a: (-0.1, 0.2),
+                confidence_b: (0.1, 0.2),
+            },
+        ];
+        for initial in invalid.iter() {
+            assert!(matches!(
+                hk.set_initial_opinions(initial.clone()),
+                Err(HkError::InvalidInitialOpinions(_))
+            ));
+        }
+    }
+}
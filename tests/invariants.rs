@@ -0,0 +1,258 @@
+extern crate hk;
+use hk::{
+    Backend, Boundary, ConvergenceCriterion, Float, HegselmannKrause, HkError, InvariantViolation,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consistent_after_sweeps() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        assert_eq!(hk.verify_invariants(), Ok(()));
+        for _ in 0..20 {
+            hk.sweep_tree();
+            assert_eq!(hk.verify_invariants(), Ok(()));
+        }
+        hk.set_turnover(1.).unwrap();
+        for _ in 0..20 {
+            hk.sweep();
+            assert_eq!(hk.verify_invariants(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_rebuild_repairs_desynced_tree() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        hk.sweep_tree();
+        hk.desync_tree();
+        assert_eq!(
+            hk.verify_invariants(),
+            Err(InvariantViolation::CountSum {
+                group: None,
+                sum: 301,
+                agents: 300
+            })
+        );
+
+        hk.rebuild_tree();
+        assert_eq!(hk.verify_invariants(), Ok(()));
+
+        // the repaired tree yields the same dynamics as an untouched one
+        let mut reference = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        reference.sweep_tree();
+        for _ in 0..20 {
+            hk.sweep_tree();
+            reference.sweep_tree();
+        }
+        assert_eq!(hk.opinions(), reference.opinions());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the counters of the tree sum to 301")]
+    fn test_sweep_detects_desynced_tree() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        hk.set_backend(Backend::Tree);
+        hk.desync_tree();
+        hk.sweep();
+    }
+
+    #[test]
+    fn test_try_sweep_reports_missing_opinion() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        hk.set_backend(Backend::Tree);
+        hk.sweep();
+        hk.desync_tree_removing();
+        match hk.try_sweep() {
+            Err(HkError::InconsistentTree(reason)) => assert!(reason.contains("sweep 2")),
+            other => panic!("missing opinion not reported: {:?}", other),
+        }
+
+        // the tree is rebuilt, such that the simulation can go on
+        assert_eq!(hk.try_sweep(), Ok(()));
+        assert_eq!(hk.verify_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_try_run_until_converged_reports_missing_opinion() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        hk.set_backend(Backend::Tree);
+        hk.desync_tree_removing();
+        let result = hk.try_run_until_converged(
+            ConvergenceCriterion::SumChange { threshold: 1e-4 },
+            Some(100),
+        );
+        match result {
+            Err(HkError::InconsistentTree(_)) => {}
+            other => panic!("missing opinion not reported: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reset_consistent() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        for _ in 0..10 {
+            hk.sweep();
+        }
+        hk.desync_tree();
+        hk.reset();
+        assert_eq!(hk.verify_invariants(), Ok(()));
+        assert_eq!(hk.opinions().len(), 300);
+        assert_eq!(hk.confidences().len(), 300);
+    }
+}
+
+#[cfg(test)]
+mod violations {
+    use super::*;
+
+    #[test]
+    fn test_non_finite_opinion() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.inject_opinion(17, Float::NAN);
+        match hk.verify_invariants() {
+            Err(InvariantViolation::NonFiniteOpinion { agent: 17, opinion }) => {
+                assert!(opinion.is_nan())
+            }
+            other => panic!("NaN opinion not detected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_outside_interval() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.inject_opinion(3, 1.5);
+        let violation = hk.verify_invariants().unwrap_err();
+        assert_eq!(
+            violation,
+            InvariantViolation::OutsideInterval {
+                agent: 3,
+                opinion: 1.5,
+                interval: (0., 1.)
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "agent 3 has the opinion 1.5 outside of the opinion interval [0, 1]"
+        );
+
+        // open boundaries allow opinions to leave the interval
+        hk.set_boundary(Boundary::Open).unwrap();
+        assert_eq!(hk.verify_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_nan_key() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.set_backend(Backend::Tree);
+        hk.desync_tree_nan();
+        let violation = hk.verify_invariants().unwrap_err();
+        assert_eq!(violation, InvariantViolation::NanKey { group: None });
+        assert_eq!(violation.to_string(), "the tree contains a NaN key");
+    }
+
+    #[test]
+    fn test_missing_opinion() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let opinion = hk.opinions()[0];
+        hk.desync_tree_removing();
+        let violation = hk.verify_invariants().unwrap_err();
+        assert_eq!(
+            violation,
+            InvariantViolation::MissingOpinion {
+                group: None,
+                agent: 0,
+                opinion
+            }
+        );
+        assert!(violation
+            .to_string()
+            .contains("of agent 0 is missing in the tree"));
+    }
+
+    #[test]
+    fn test_count_sum() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.desync_tree_count(5, -1);
+        let violation = hk.verify_invariants().unwrap_err();
+        assert_eq!(
+            violation,
+            InvariantViolation::CountSum {
+                group: None,
+                sum: 99,
+                agents: 100
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "the counters of the tree sum to 99, but it contains 100 agents"
+        );
+    }
+
+    #[test]
+    fn test_wrong_count() {
+        // move one count from the opinion of agent 0 to the one of agent 1
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let opinions = hk.opinions();
+        assert_ne!(opinions[0], opinions[1]);
+        hk.desync_tree_count(0, -1);
+        hk.desync_tree_count(1, 1);
+        // the lower of both opinions is reported
+        let (opinion, count) = if opinions[0] < opinions[1] {
+            (opinions[0], 0)
+        } else {
+            (opinions[1], 2)
+        };
+        assert_eq!(
+            hk.verify_invariants(),
+            Err(InvariantViolation::WrongCount {
+                group: None,
+                opinion,
+                count,
+                expected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_group_trees_consistent() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let assignments: Vec<u8> = (0..100).map(|i| (i % 2) as u8).collect();
+        hk.set_groups(&assignments, &[vec![0.1, 0.05], vec![0.05, 0.1]])
+            .unwrap();
+        assert_eq!(hk.verify_invariants(), Ok(()));
+        hk.sweep();
+        assert_eq!(hk.verify_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_converts_to_inconsistent_tree() {
+        let violation = InvariantViolation::NanKey { group: Some(2) };
+        assert_eq!(
+            HkError::from(violation),
+            HkError::InconsistentTree("the tree of group 2 contains a NaN key".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "paranoid"))]
+    #[should_panic(expected = "the tree contains a NaN key")]
+    fn test_sweep_detects_violation() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.set_backend(Backend::Tree);
+        hk.desync_tree_nan();
+        hk.sweep();
+    }
+
+    #[test]
+    #[cfg(feature = "paranoid")]
+    #[should_panic(expected = "invariant violated after sweep 1: the counters of the tree")]
+    fn test_paranoid_panics_in_try_sweep() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.set_backend(Backend::Tree);
+        hk.desync_tree_count(0, 1);
+        let _ = hk.try_sweep();
+    }
+}
@@ -0,0 +1,123 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a cluster of 20 agents around 0.3 and a single agent at 0.9 with tiny confidence
+    fn loner() -> HegselmannKrause {
+        let mut opinions: Vec<Float> = (0..20).map(|k| 0.3 + k as Float * 5e-4).collect();
+        let mut confidences = vec![0.05; 20];
+        opinions.push(0.9);
+        confidences.push(0.01);
+
+        let mut hk = HegselmannKrause::new(21, 0., 1., 13);
+        hk.set_state(&opinions, &confidences).unwrap();
+        hk
+    }
+
+    #[test]
+    fn test_loner_joins_cluster() {
+        let mut hk = loner();
+        hk.enable_isolation_growth(2, 2., 1.).unwrap();
+
+        for _ in 0..100 {
+            hk.sweep();
+        }
+        assert_eq!(hk.cluster_sizes(), vec![21]);
+        // the confidences of the agents in the cluster never grew
+        assert!(hk.confidences()[..20].iter().all(|&c| c == 0.05));
+        // 0.01 * 2^6 is the first confidence reaching the cluster
+        assert_eq!(hk.confidences()[20], 0.64);
+    }
+
+    #[test]
+    fn test_loner_stays_without_growth() {
+        let mut hk = loner();
+
+        for _ in 0..100 {
+            hk.sweep();
+        }
+        assert_eq!(hk.cluster_sizes(), vec![20, 1]);
+        assert_eq!(hk.opinions()[20], 0.9);
+        assert_eq!(hk.confidences()[20], 0.01);
+    }
+
+    #[test]
+    fn test_growth_capped() {
+        let mut hk = loner();
+        hk.enable_isolation_growth(1, 2., 0.3).unwrap();
+
+        for _ in 0..100 {
+            hk.sweep();
+        }
+        assert_eq!(hk.cluster_sizes(), vec![20, 1]);
+        assert_eq!(hk.confidences()[20], 0.3);
+    }
+
+    #[test]
+    fn test_set_state_clears_counters() {
+        let mut hk = loner();
+        hk.enable_isolation_growth(3, 2., 1.).unwrap();
+
+        // two isolated sweeps are not enough
+        hk.sweep();
+        hk.sweep();
+        assert_eq!(hk.confidences()[20], 0.01);
+
+        // and they are forgotten by setting a new state
+        let (opinions, confidences) = (hk.opinions(), hk.confidences());
+        hk.set_state(&opinions, &confidences).unwrap();
+        hk.sweep();
+        hk.sweep();
+        assert_eq!(hk.confidences()[20], 0.01);
+        hk.sweep();
+        assert_eq!(hk.confidences()[20], 0.02);
+    }
+
+    #[test]
+    fn test_reset_clears_counters() {
+        // the sweeps do not consume random numbers, such that both reset to the same state
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.01, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.01, 13);
+        hk1.enable_isolation_growth(3, 2., 1.).unwrap();
+        for _ in 0..2 {
+            hk1.sweep();
+            hk2.sweep();
+        }
+        hk2.enable_isolation_growth(3, 2., 1.).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..20 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.confidences(), hk2.confidences());
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_naive_equals_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.05, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.05, 13);
+        hk1.enable_isolation_growth(2, 1.5, 0.3).unwrap();
+        hk2.enable_isolation_growth(2, 1.5, 0.3).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_isolation_growth() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.enable_isolation_growth(0, 2., 1.).is_err());
+        assert!(hk.enable_isolation_growth(2, 1., 1.).is_err());
+        assert!(hk.enable_isolation_growth(2, 2., -1.).is_err());
+        assert!(hk.enable_isolation_growth(2, 2., 1.).is_ok());
+    }
+}
@@ -0,0 +1,75 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_jump_probability_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_jump_probability(0.).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_jumps_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk1.set_jump_probability(0.05).unwrap();
+        hk2.set_jump_probability(0.05).unwrap();
+
+        // the decisions are drawn in the order of the agents by both methods
+        for _ in 0..20 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_jumps_prevent_convergence() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk1.set_jump_probability(0.1).unwrap();
+        hk2.set_jump_probability(0.1).unwrap();
+
+        for _ in 0..500 {
+            hk1.sweep();
+            hk2.sweep();
+            assert!(hk1.opinions().iter().all(|x| (0. ..=1.).contains(x)));
+        }
+        // the jumps are reproducible from the seed
+        assert_eq!(hk1.opinions(), hk2.opinions());
+        assert!(hk1.last_change() > 1e-4);
+    }
+
+    #[test]
+    fn test_certain_jumps() {
+        // with p = 1 every agent jumps, regardless of its neighbors
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.5, 0.5], &[1., 1.]).unwrap();
+        hk.set_jump_probability(1.).unwrap();
+        hk.sweep();
+
+        let opinions = hk.opinions();
+        assert_ne!(opinions[0], 0.5);
+        assert_ne!(opinions[0], opinions[1]);
+    }
+
+    #[test]
+    fn test_invalid_jump_probability() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        assert!(hk.set_jump_probability(-0.1).is_err());
+        assert!(hk.set_jump_probability(1.1).is_err());
+        assert!(hk.set_jump_probability(Float::NAN).is_err());
+        assert!(hk.set_jump_probability(0.5).is_ok());
+    }
+}
@@ -0,0 +1,168 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, Kernel, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_narrow_gaussian_approaches_hard() {
+        let opinions = [0.3, 0.3001, 0.3002, 0.7, 0.7001];
+        let confidences = [0.01; 5];
+
+        let mut hard = HegselmannKrause::new(5, 0., 1., 13);
+        hard.set_state(&opinions, &confidences).unwrap();
+        let mut gaussian = HegselmannKrause::new(5, 0., 1., 13);
+        gaussian.set_kernel(Kernel::Gaussian);
+        gaussian.set_state(&opinions, &confidences).unwrap();
+
+        for _ in 0..3 {
+            hard.sweep_tree();
+            gaussian.sweep_tree();
+        }
+        assert_eq!(hard.cluster_sizes(), vec![3, 2]);
+        assert_eq!(gaussian.cluster_sizes(), vec![3, 2]);
+        for (x, y) in hard.opinions().iter().zip(gaussian.opinions().iter()) {
+            assert!((x - y).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_hard_kernel_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_kernel(Kernel::Hard);
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_cmp_gaussian_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.1, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.1, 13);
+        hk1.set_kernel(Kernel::Gaussian);
+        hk2.set_kernel(Kernel::Gaussian);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_gaussian_naive_tree_clusters() {
+        // few distinct opinions held by many agents, whose queries the tree shares
+        let opinions: Vec<Float> = (0..300).map(|k| (k % 7) as Float / 10.).collect();
+        let confidences: Vec<Float> = (0..300).map(|k| 0.05 + (k % 2) as Float * 0.1).collect();
+        let mut hk1 = HegselmannKrause::new(300, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(300, 0., 1., 13);
+        for hk in [&mut hk1, &mut hk2].iter_mut() {
+            hk.set_kernel(Kernel::Gaussian);
+            hk.set_state(&opinions, &confidences).unwrap();
+        }
+
+        for _ in 0..20 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_gaussian_circle() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.1, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.1, 13, OpinionSpace::Circle);
+        hk1.set_kernel(Kernel::Gaussian);
+        hk2.set_kernel(Kernel::Gaussian);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+}
+
+#[cfg(test)]
+mod linear_tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_weighted_mean() {
+        for &naive in &[true, false] {
+            let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+            hk.set_kernel(Kernel::Linear);
+            // the third agent is beyond the reach of both others
+            hk.set_state(&[0.4, 0.5, 0.9], &[0.2, 0.2, 0.2]).unwrap();
+            if naive {
+                hk.sweep_naive();
+            } else {
+                hk.sweep_tree();
+            }
+
+            // the neighbor at distance 0.1 has half the weight of the own opinion
+            let expected = [0.65 / 1.5, 0.7 / 1.5, 0.9];
+            for (x, y) in hk.opinions().iter().zip(expected.iter()) {
+                assert!((x - y).abs() < 1e-6, "{} != {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_confidence_linear_frozen() {
+        let opinions = [0.1, 0.2, 0.3];
+        let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+        hk.set_kernel(Kernel::Linear);
+        hk.set_state(&opinions, &[0.; 3]).unwrap();
+        hk.sweep_tree();
+        assert_eq!(hk.opinions(), opinions.to_vec());
+    }
+
+    #[test]
+    fn test_cmp_linear_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk1.set_kernel(Kernel::Linear);
+        hk2.set_kernel(Kernel::Linear);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_linear_circle() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.1, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.1, 13, OpinionSpace::Circle);
+        hk1.set_kernel(Kernel::Linear);
+        hk2.set_kernel(Kernel::Linear);
+
+        // the order of the summation differs, such that rounding errors grow slowly
+        for _ in 0..50 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_hard_naive_tree_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.set_kernel(Kernel::Linear);
+        hk2.set_kernel(Kernel::Hard);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+}
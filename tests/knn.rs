@@ -0,0 +1,81 @@
+extern crate hk;
+use hk::{HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmp_knn_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+
+        for _ in 0..50 {
+            hk1.sweep_knn_naive(10);
+            hk2.sweep_knn(10);
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_knn_naive_tree_circle() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, OpinionSpace::Circle);
+
+        for _ in 0..50 {
+            hk1.sweep_knn_naive(10);
+            hk2.sweep_knn(10);
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_knn_tie_break() {
+        for &tree in &[false, true] {
+            let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+            hk.set_state(&[0.25, 0.5, 0.75], &[0.1, 0.1, 0.1]).unwrap();
+            if tree {
+                hk.sweep_knn(2);
+            } else {
+                hk.sweep_knn_naive(2);
+            }
+
+            // the middle agent has two neighbors at equal distance and takes the left one
+            assert_eq!(hk.opinions(), vec![0.375, 0.375, 0.625]);
+        }
+    }
+
+    #[test]
+    fn test_knn_duplicates() {
+        for &tree in &[false, true] {
+            let mut hk = HegselmannKrause::new(4, 0., 1., 13);
+            hk.set_state(&[0.25, 0.75, 0.25, 0.25], &[0.1; 4]).unwrap();
+            if tree {
+                hk.sweep_knn(2);
+            } else {
+                hk.sweep_knn_naive(2);
+            }
+
+            // only one of the three agents sharing an opinion is counted for the outlier
+            assert_eq!(hk.opinions(), vec![0.25, 0.5, 0.25, 0.25]);
+        }
+    }
+
+    #[test]
+    fn test_knn_all_agents() {
+        let mut hk = HegselmannKrause::new(4, 0., 1., 13);
+        hk.set_state(&[0.25, 0.75, 0.25, 0.25], &[0.1; 4]).unwrap();
+        hk.set_nearest_neighbors(4).unwrap();
+        hk.sweep();
+
+        assert_eq!(hk.opinions(), vec![0.375; 4]);
+    }
+
+    #[test]
+    fn test_invalid_knn() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_nearest_neighbors(0).is_err());
+        assert!(hk.set_nearest_neighbors(11).is_err());
+        assert!(hk.set_nearest_neighbors(10).is_ok());
+    }
+}
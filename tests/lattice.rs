@@ -0,0 +1,107 @@
+extern crate hk;
+use hk::{Diagnostics, Float, HegselmannKrauseLattice};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[Float], b: &[Float]) {
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_update_and_domains() {
+        let mut hk = HegselmannKrauseLattice::new(3, 0., 1., 13, false);
+        #[rustfmt::skip]
+        hk.set_state(
+            &[
+                0.1, 0.1, 0.9,
+                0.1, 0.35, 0.9,
+                0.9, 0.9, 0.9,
+            ],
+            &[
+                0.2, 0.3, 0.2,
+                0.2, 0.3, 0.2,
+                0.2, 0.2, 0.2,
+            ],
+        );
+        assert_eq!(hk.domain_sizes(), vec![3, 5, 1]);
+        hk.sweep();
+
+        // the center and its upper neighbor see each other and the two sites with 0.1
+        let x = (0.1 + 0.1 + 0.35) / 3.;
+        #[rustfmt::skip]
+        assert_close(
+            &hk.opinions(),
+            &[
+                0.1, x, 0.9,
+                0.1, x, 0.9,
+                0.9, 0.9, 0.9,
+            ],
+        );
+        assert!((hk.accumulated_change - (0.35 - x) - (x - 0.1)).abs() < 1e-6);
+        assert_eq!(hk.cluster_sizes(), vec![2, 2, 5]);
+        assert_eq!(hk.domain_sizes(), vec![2, 2, 5]);
+    }
+
+    #[test]
+    fn test_domains_differ_from_clusters() {
+        for &moore in &[false, true] {
+            let mut hk = HegselmannKrauseLattice::new(3, 0., 1., 13, moore);
+            #[rustfmt::skip]
+            hk.set_state(
+                &[
+                    0.1, 0.9, 0.1,
+                    0.9, 0.9, 0.9,
+                    0.1, 0.9, 0.1,
+                ],
+                &[0.1; 9],
+            );
+            hk.sweep();
+
+            assert_eq!(hk.accumulated_change, 0.);
+            assert_eq!(hk.cluster_sizes(), vec![4, 5]);
+            // the corners only touch each other diagonally via the center
+            assert_eq!(hk.domain_sizes(), vec![1, 5, 1, 1, 1]);
+        }
+    }
+
+    #[test]
+    fn test_moore_neighborhood() {
+        let opinions = [0.2, 0.5, 0.2, 0.5, 0.5, 0.5, 0.2, 0.5, 0.2];
+        let confidences = [0.; 9];
+        let mut center = confidences;
+        center[4] = 0.5;
+
+        let mut hk = HegselmannKrauseLattice::new(3, 0., 1., 13, false);
+        hk.set_state(&opinions, &center);
+        hk.sweep();
+        assert!((hk.opinions()[4] - 0.5).abs() < 1e-6);
+
+        let mut hk = HegselmannKrauseLattice::new(3, 0., 1., 13, true);
+        hk.set_state(&opinions, &center);
+        hk.sweep();
+        // the diagonal neighbors pull the center down
+        assert!((hk.opinions()[4] - (5. * 0.5 + 4. * 0.2) / 9.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_converges() {
+        for &moore in &[false, true] {
+            let mut hk = HegselmannKrauseLattice::new(10, 0.2, 0.4, 13, moore);
+            let mut sweeps = 0;
+            loop {
+                sweeps += 1;
+                hk.sweep();
+                if hk.accumulated_change < 1e-4 {
+                    break;
+                }
+            }
+            assert!(sweeps < 10000);
+            assert_eq!(hk.total_agents_in_clusters(), 100);
+            assert_eq!(hk.domain_sizes().iter().sum::<usize>(), 100);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+extern crate hk;
+use hk::{HegselmannKrause, HkError};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_leader_weight_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_leaders(&[3, 14, 15], 1).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_leaders(&[3, 14, 15], 1).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_leader_counts_as_copies() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.25, 0.75], &[1., 1.]).unwrap();
+        hk.set_leaders(&[1], 3).unwrap();
+        hk.sweep_tree();
+
+        // the leader counts three times in both averages
+        assert_eq!(hk.opinions(), vec![0.625, 0.625]);
+    }
+
+    #[test]
+    fn test_strong_leader_attracts_neighbors() {
+        let mut hk = HegselmannKrause::new(100, 0.2, 0.2, 13);
+        let leader = 0;
+        hk.set_leaders(&[leader], 1_000_000).unwrap();
+        let opinions = hk.opinions();
+        let x = opinions[leader];
+        hk.sweep_tree();
+
+        // everybody who sees the leader jumps onto it within one sweep
+        for (&before, &after) in opinions.iter().zip(hk.opinions().iter()) {
+            if (before - x).abs() < 0.2 {
+                assert!((after - x).abs() < 1e-3, "{} != {}", after, x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_leaders_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_leaders(&[1, 2, 3], 20).unwrap();
+        hk2.set_leaders(&[1, 2, 3], 20).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_leaders() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        hk.set_leaders(&[1], 3).unwrap();
+        let before = hk.opinions();
+        for (indices, weight) in [(&[10][..], 3), (&[1][..], 0)] {
+            assert!(matches!(
+                hk.set_leaders(indices, weight),
+                Err(HkError::InvalidParameter(_))
+            ));
+        }
+
+        // the previous leader is kept
+        let mut expected = HegselmannKrause::new(10, 0., 1., 13);
+        expected.set_leaders(&[1], 3).unwrap();
+        assert_eq!(hk.opinions(), before);
+        hk.sweep_naive();
+        expected.sweep_naive();
+        assert_eq!(hk.opinions(), expected.opinions());
+    }
+}
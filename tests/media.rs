@@ -0,0 +1,243 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_weight_media_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_media(0.3, 0.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_strong_media_attracts() {
+        let media = 0.3;
+        let mut hk = HegselmannKrause::new(100, 0., 0.2, 13);
+        hk.set_media(media, 1e9).unwrap();
+        let opinions = hk.opinions();
+        let confidences = hk.confidences();
+        hk.sweep_tree();
+
+        // everybody who sees the media adopts its opinion in a single sweep
+        let reached = opinions
+            .iter()
+            .zip(confidences.iter())
+            .map(|(x, c)| (x - media).abs() < *c)
+            .collect::<Vec<_>>();
+        assert!(reached.iter().any(|&r| r));
+        assert!(reached.iter().any(|&r| !r));
+        for (x, &r) in hk.opinions().iter().zip(reached.iter()) {
+            if r {
+                assert!((x - media).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_media_single_agent() {
+        let mut hk = HegselmannKrause::new(1, 0., 1., 13);
+        hk.set_state(&[0.5], &[0.3]).unwrap();
+        hk.set_media(0.7, 3.).unwrap();
+        hk.sweep_naive();
+
+        // (0.5 + 3 * 0.7) / (1 + 3)
+        assert!((hk.opinions()[0] - 0.65).abs() < 1e-6);
+
+        // out of reach, the media has no influence
+        hk.set_state(&[0.1], &[0.3]).unwrap();
+        hk.sweep_naive();
+        assert_eq!(hk.opinions(), vec![0.1]);
+    }
+
+    #[test]
+    fn test_cmp_media_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_media(0.8, 5.).unwrap();
+        hk2.set_media(0.8, 5.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_amplitude_equals_static_media() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk1.set_media(0.5, 2.).unwrap();
+        hk2.set_media_schedule(0., 10., 2.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk2.media_opinion(), Some(0.5));
+        }
+    }
+
+    #[test]
+    fn test_media_oscillates() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk.set_media_schedule(0.2, 8., 1.).unwrap();
+        assert_eq!(hk.media_opinion(), Some(0.5));
+
+        // the sweep `t` uses the media opinion after `t` completed sweeps
+        let mut seen = Vec::new();
+        for _ in 0..8 {
+            hk.sweep();
+            seen.push(hk.media_opinion().unwrap());
+        }
+        assert!((seen[2] - 0.7).abs() < 1e-6);
+        assert!((seen[6] - 0.3).abs() < 1e-6);
+        assert!(seen.iter().all(|m| (0.3 - 1e-6..=0.7 + 1e-6).contains(m)));
+    }
+
+    #[test]
+    fn test_counter_restarts_after_reset() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk.set_media_schedule(0.3, 7., 1.).unwrap();
+
+        let mut first = Vec::new();
+        for _ in 0..20 {
+            hk.sweep();
+            first.push(hk.media_opinion().unwrap());
+        }
+        assert_eq!(hk.sweep_count(), 20);
+
+        hk.reset();
+        assert_eq!(hk.sweep_count(), 0);
+        assert_eq!(hk.media_opinion(), Some(0.5));
+        let mut second = Vec::new();
+        for _ in 0..20 {
+            hk.sweep();
+            second.push(hk.media_opinion().unwrap());
+        }
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_static_media_replaces_schedule() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk.set_media_schedule(0.3, 7., 1.).unwrap();
+        hk.set_media(0.2, 1.).unwrap();
+        for _ in 0..10 {
+            hk.sweep();
+            assert_eq!(hk.media_opinion(), Some(0.2));
+        }
+        hk.remove_media();
+        assert_eq!(hk.media_opinion(), None);
+    }
+
+    #[test]
+    fn test_invalid_media_schedule() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        assert!(hk.set_media_schedule(-0.1, 10., 1.).is_err());
+        assert!(hk.set_media_schedule(0.6, 10., 1.).is_err());
+        assert!(hk.set_media_schedule(0.2, 0., 1.).is_err());
+        assert!(hk.set_media_schedule(0.2, Float::INFINITY, 1.).is_err());
+        assert_eq!(hk.media_opinion(), None);
+    }
+}
+
+#[cfg(test)]
+mod exposure_tests {
+    use super::*;
+
+    #[test]
+    fn test_no_exposure_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.set_media_exposure(0., 0.5, 0.2).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_full_exposure_collapses() {
+        for &naive in &[true, false] {
+            let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+            hk.set_media_exposure(1., 1., 0.2).unwrap();
+            if naive {
+                hk.sweep_naive();
+            } else {
+                hk.sweep_tree();
+            }
+            assert!(hk.opinions().iter().all(|&x| x == 0.2));
+            assert_eq!(hk.cluster_sizes(), vec![100]);
+
+            // the tree is consistent with the new opinions
+            if !naive {
+                hk.remove_media_exposure();
+                hk.sweep_tree();
+                assert!(hk.opinions().iter().all(|&x| x == 0.2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_exposed_fraction() {
+        // without confidence, only the exposed agents move
+        let mut hk = HegselmannKrause::new(10_000, 0., 0., 13);
+        hk.set_media_exposure(0.3, 1., 0.5).unwrap();
+        hk.sweep();
+        let exposed = hk.opinions().iter().filter(|&&x| x == 0.5).count();
+        assert!((2800..3200).contains(&exposed), "{}", exposed);
+    }
+
+    #[test]
+    fn test_exposure_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.2, 13);
+        hk1.set_media_exposure(0.1, 0.3, 0.8).unwrap();
+        hk2.set_media_exposure(0.1, 0.3, 0.8).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_exposure() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.set_media_exposure(-0.1, 0.5, 0.5).is_err());
+        assert!(hk.set_media_exposure(1.1, 0.5, 0.5).is_err());
+        assert!(hk.set_media_exposure(0.5, 1.5, 0.5).is_err());
+        assert!(hk.set_media_exposure(0.5, 0.5, 2.).is_err());
+    }
+
+    #[test]
+    fn test_invalid_media() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.set_media(0.5, -1.).is_err());
+        assert!(hk.set_media(0.5, Float::NAN).is_err());
+        assert!(hk.set_media(0.5, Float::INFINITY).is_err());
+        assert!(hk.set_media(1.5, 1.).is_err());
+        assert!(hk.set_media(Float::NAN, 1.).is_err());
+        assert_eq!(hk.media_opinion(), None);
+        assert!(hk.set_media_schedule(0.2, 10., -1.).is_err());
+        assert_eq!(hk.media_opinion(), None);
+    }
+}
@@ -0,0 +1,95 @@
+extern crate hk;
+use hk::{HegselmannKrause, HkError, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_memory_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.enable_memory(1).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.enable_memory(1).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_memory_pair() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.4, 0.6], &[0.5, 0.5]).unwrap();
+        hk.enable_memory(2).unwrap();
+
+        // both agents adopt 0.5, but remember their initial opinions
+        hk.sweep_tree();
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.45).abs() < 1e-6);
+        assert!((opinions[1] - 0.55).abs() < 1e-6);
+
+        // the mean of the effective opinions is again 0.5, the old opinions are forgotten
+        hk.sweep_tree();
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.5).abs() < 1e-6);
+        assert!((opinions[1] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cmp_memory_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.enable_memory(3).unwrap();
+        hk2.enable_memory(3).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_memory_circle_across_boundary() {
+        let mut hk = HegselmannKrause::with_topology(2, 0., 1., 13, OpinionSpace::Circle);
+        hk.set_state(&[0.05, 0.95], &[0.2, 0.2]).unwrap();
+        hk.enable_memory(2).unwrap();
+        hk.sweep_naive();
+
+        // the remembered opinions are averaged along the shorter arc
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.025).abs() < 1e-5);
+        assert!((opinions[1] - 0.975).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_empty_memory_rejected() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.4, 0.6], &[0.5, 0.5]).unwrap();
+        hk.enable_memory(2).unwrap();
+        assert!(matches!(
+            hk.enable_memory(0),
+            Err(HkError::InvalidParameter(_))
+        ));
+
+        // the memory is unchanged, so the initial opinions are still remembered
+        hk.sweep_tree();
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.45).abs() < 1e-6);
+        assert!((opinions[1] - 0.55).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,80 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_mu_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_mu(1.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_mu(1.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_mu_pair() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.4, 0.6], &[0.5, 0.5]).unwrap();
+        hk.set_mu(0.5).unwrap();
+        hk.sweep_tree();
+
+        // both agents move half way towards the mean 0.5
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.45).abs() < 1e-6);
+        assert!((opinions[1] - 0.55).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mu_circle_shorter_arc() {
+        let mut hk = HegselmannKrause::with_topology(2, 0., 1., 13, OpinionSpace::Circle);
+        hk.set_state(&[0.05, 0.95], &[0.2, 0.2]).unwrap();
+        hk.set_mu(0.5).unwrap();
+        hk.sweep_naive();
+
+        // the mean is 0, which is approached across the boundary
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.025).abs() < 1e-6);
+        assert!((opinions[1] - 0.975).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cmp_mu_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_mu(0.3).unwrap();
+        hk2.set_mu(0.3).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_mu() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_mu(0.).is_err());
+        assert!(hk.set_mu(1.5).is_err());
+        assert!(hk.set_mu(Float::NAN).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/// the largest difference between the opinions of the same agent in both models
+fn max_deviation(hk1: &HegselmannKrause, hk2: &HegselmannKrause) -> Float {
+    hk1.opinions()
+        .iter()
+        .zip(hk2.opinions())
+        .map(|(x, y)| (x - y).abs())
+        .fold(0., Float::max)
+}
+
+/// a random range of confidences, including ranges of a single value and tiny confidences
+fn random_confidences(rng: &mut Pcg64) -> (Float, Float) {
+    match rng.gen_range(0, 4) {
+        0 => {
+            let eps = rng.gen_range(0., 0.5);
+            (eps, eps)
+        }
+        1 => {
+            let eps = rng.gen_range(0., 1e-5);
+            (eps, 2. * eps)
+        }
+        _ => {
+            let a: Float = rng.gen_range(0., 0.5);
+            let b = rng.gen_range(0., 0.5);
+            (a.min(b), a.max(b))
+        }
+    }
+}
+
+#[cfg(test)]
+mod properties {
+    use super::*;
+
+    /// run both methods side by side and compare them after every sweep
+    /// the rounding differences of the sums flip agents close to a boundary, which grows
+    /// into large deviations, so every sweep starts from the same state
+    fn cmp_sweeps(hk1: &mut HegselmannKrause, hk2: &mut HegselmannKrause, case: &str) {
+        for sweep in 0..50 {
+            hk2.set_state(&hk1.opinions(), &hk1.confidences()).unwrap();
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            let deviation = max_deviation(hk1, hk2);
+            assert!(
+                deviation < 1e-4,
+                "{}: deviation {} after sweep {}",
+                case,
+                deviation,
+                sweep
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_systems() {
+        let mut rng = Pcg64::seed_from_u64(13);
+        for _ in 0..20 {
+            let n = rng.gen_range(2, 501);
+            let (min, max) = random_confidences(&mut rng);
+            let seed = rng.gen();
+            let case = format!("n = {}, confidences [{}, {}], seed {}", n, min, max, seed);
+
+            let mut hk1 = HegselmannKrause::new(n, min, max, seed);
+            let mut hk2 = HegselmannKrause::new(n, min, max, seed);
+            cmp_sweeps(&mut hk1, &mut hk2, &case);
+        }
+    }
+
+    #[test]
+    fn test_random_grids() {
+        // opinions and confidences on a grid of exact binary fractions, such that many
+        // agents are exactly at the boundary of the confidence intervals of others
+        let mut rng = Pcg64::seed_from_u64(13);
+        for _ in 0..20 {
+            let n = rng.gen_range(2, 501);
+            let levels = 1 << rng.gen_range(2, 7);
+            let grid = |k: u32| k as Float / levels as Float;
+            let opinions: Vec<Float> = (0..n).map(|_| grid(rng.gen_range(0, levels + 1))).collect();
+            let confidences: Vec<Float> = (0..n).map(|_| grid(rng.gen_range(0, 5))).collect();
+            let case = format!("n = {}, grid of {} levels", n, levels);
+
+            let mut hk1 = HegselmannKrause::new(n, 0., 0., 13);
+            let mut hk2 = HegselmannKrause::new(n, 0., 0., 13);
+            hk1.set_state(&opinions, &confidences).unwrap();
+            hk2.set_state(&opinions, &confidences).unwrap();
+            cmp_sweeps(&mut hk1, &mut hk2, &case);
+        }
+    }
+}
+
+#[cfg(test)]
+mod boundary {
+    use super::*;
+
+    /// the opinions after one sweep of every method from the same state
+    fn one_sweep(opinions: &[Float], confidences: &[Float]) -> Vec<Vec<Float>> {
+        let sweeps: [fn(&mut HegselmannKrause); 4] = [
+            HegselmannKrause::sweep_naive,
+            HegselmannKrause::sweep_tree,
+            HegselmannKrause::sweep_sorted,
+            HegselmannKrause::sweep,
+        ];
+        sweeps
+            .iter()
+            .map(|sweep| {
+                let mut hk = HegselmannKrause::new(opinions.len() as u32, 0., 0., 13);
+                hk.set_state(opinions, confidences).unwrap();
+                sweep(&mut hk);
+                hk.opinions()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exactly_eps_apart() {
+        // the confidence interval is closed, so agents exactly eps apart see each other
+        for result in one_sweep(&[0.25, 0.5], &[0.25, 0.25]) {
+            assert_eq!(result, vec![0.375, 0.375]);
+        }
+    }
+
+    #[test]
+    fn test_only_one_reaches_the_other() {
+        for result in one_sweep(&[0.25, 0.5], &[0.25, 0.125]) {
+            assert_eq!(result, vec![0.375, 0.5]);
+        }
+    }
+
+    #[test]
+    fn test_zero_confidence() {
+        // an agent without confidence sees exactly the agents sharing its opinion
+        for result in one_sweep(&[0.25, 0.25, 0.5], &[0., 0., 0.25]) {
+            assert_eq!(result, vec![0.25, 0.25, 1. / 3.]);
+        }
+    }
+}
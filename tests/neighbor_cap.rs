@@ -0,0 +1,112 @@
+extern crate hk;
+use hk::{CapStrategy, Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_neighbor_cap(10, CapStrategy::Closest).unwrap();
+        hk2.remove_neighbor_cap();
+
+        for _ in 0..50 {
+            hk1.sweep_tree();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_cap_above_neighbors() {
+        // if the cap is never reached, every agent averages over its whole interval
+        for &strategy in &[CapStrategy::Closest, CapStrategy::Random] {
+            let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+            let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+            for _ in 0..20 {
+                hk1.sweep_naive();
+                hk2.sweep_capped_naive(100, strategy);
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_closest_naive_tree() {
+        for &topology in &[OpinionSpace::Line, OpinionSpace::Circle] {
+            let mut hk1 = HegselmannKrause::with_topology(100, 0.1, 0.3, 13, topology);
+            let mut hk2 = HegselmannKrause::with_topology(100, 0.1, 0.3, 13, topology);
+            hk2.set_neighbor_cap(5, CapStrategy::Closest).unwrap();
+
+            for _ in 0..50 {
+                hk1.sweep_capped_naive(5, CapStrategy::Closest);
+                hk2.sweep();
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_closest_tie_break() {
+        // at equal distance the lower opinion is preferred, duplicates of the own opinion
+        // are closest and duplicates of other opinions are interchangeable
+        let cases: &[(&[Float], &[Float], usize, Float)] = &[
+            (&[0.25, 0.5, 0.75], &[0., 0.3, 0.], 2, 0.375),
+            (&[0.25, 0.5, 0.75, 0.75], &[0., 0.3, 0., 0.], 3, 0.5),
+            (&[0.25, 0.5, 0.5], &[0., 0.3, 0.], 2, 0.5),
+            (&[0.25, 0.25, 0.5, 0.75], &[0., 0., 0.3, 0.], 3, 1. / 3.),
+        ];
+        for &(opinions, confidences, c, expected) in cases {
+            let idx = confidences.iter().position(|&x| x > 0.).unwrap();
+            for &naive in &[true, false] {
+                let mut hk = HegselmannKrause::new(opinions.len() as u32, 0., 1., 13);
+                hk.set_state(opinions, confidences).unwrap();
+                if naive {
+                    hk.sweep_capped_naive(c, CapStrategy::Closest);
+                } else {
+                    hk.sweep_capped(c);
+                }
+                let x = hk.opinions()[idx];
+                assert!((x - expected).abs() < 1e-6, "{} != {}", x, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_closest_respects_confidence() {
+        // the cap never adds agents beyond the confidence
+        let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+        hk.set_state(&[0.25, 0.5, 0.75], &[0.1, 0.1, 0.1]).unwrap();
+        hk.sweep_capped(3);
+        assert_eq!(hk.opinions(), vec![0.25, 0.5, 0.75]);
+        assert_eq!(hk.last_change(), 0.);
+    }
+
+    #[test]
+    fn test_random_reproducible() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk3 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk1.set_neighbor_cap(3, CapStrategy::Random).unwrap();
+        hk2.set_neighbor_cap(3, CapStrategy::Random).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep();
+            hk2.sweep();
+            hk3.sweep_capped_naive(3, CapStrategy::Closest);
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+        assert_ne!(hk1.opinions(), hk3.opinions());
+    }
+
+    #[test]
+    fn test_invalid_neighbor_cap() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        assert!(hk.set_neighbor_cap(0, CapStrategy::Closest).is_err());
+        assert!(hk.set_neighbor_cap(11, CapStrategy::Random).is_err());
+        assert!(hk.set_neighbor_cap(10, CapStrategy::Random).is_ok());
+        assert!(hk.set_neighbor_cap(1, CapStrategy::Closest).is_ok());
+    }
+}
@@ -0,0 +1,85 @@
+extern crate hk;
+use hk::network::{complete, erdos_renyi, ring};
+use hk::{HegselmannKrause, Network};
+
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_network_equals_naive() {
+        let mut hk1 = HegselmannKrause::new(100, 0.01, 1., 13);
+        let mut hk2 = HegselmannKrause::with_network(100, 0.01, 1., 13, complete(100));
+
+        // on a complete network, the result has to be bit-identical to the naive method
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_network();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_empty_network_frozen() {
+        let mut hk = HegselmannKrause::with_network(50, 0., 1., 13, vec![Vec::new(); 50]);
+        let initial = hk.opinions();
+        hk.sweep();
+
+        assert_eq!(hk.opinions(), initial);
+        assert_eq!(hk.last_change(), 0.);
+    }
+
+    #[test]
+    fn test_ring() {
+        let edges = ring(10, 2);
+        assert!(edges.iter().all(|e| e.len() == 4));
+        assert_eq!(edges[0], vec![1, 2, 8, 9]);
+
+        // more neighbors than agents results in a complete network
+        assert_eq!(ring(4, 3), complete(4));
+    }
+
+    #[test]
+    fn test_erdos_renyi() {
+        let mut rng = Pcg64::seed_from_u64(13);
+        let edges = erdos_renyi(200, 0.1, &mut rng);
+
+        // undirected graph
+        for (i, neighbors) in edges.iter().enumerate() {
+            for &j in neighbors {
+                assert!(edges[j as usize].contains(&(i as u32)));
+            }
+        }
+
+        // roughly the expected number of edges
+        let num_edges = edges.iter().map(|e| e.len()).sum::<usize>() / 2;
+        let expected = 0.1 * (200. * 199. / 2.);
+        assert!((num_edges as f64 - expected).abs() < 0.1 * expected);
+    }
+
+    #[test]
+    fn test_generated_network_reproducible() {
+        let network = Network::ErdosRenyi { p: 0.05 };
+        let mut hk1 = HegselmannKrause::with_generated_network(100, 0., 1., 13, &network);
+        let mut hk2 = HegselmannKrause::with_generated_network(100, 0., 1., 13, &network);
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_parse_network() {
+        assert_eq!("complete".parse(), Ok(Network::Complete));
+        assert_eq!("ring:3".parse(), Ok(Network::Ring { k: 3 }));
+        assert_eq!("er:0.5".parse(), Ok(Network::ErdosRenyi { p: 0.5 }));
+        assert!("ring".parse::<Network>().is_err());
+        assert!("star:3".parse::<Network>().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+extern crate hk;
+use hk::{Aggregator, HegselmannKrause, Kernel, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 100 agents, of which the first has a confidence of 0, which excludes even its own
+    /// opinion from its interval
+    fn closed_minded(topology: OpinionSpace) -> HegselmannKrause {
+        let mut hk = HegselmannKrause::with_topology(100, 0.1, 0.3, 13, topology);
+        let mut confidences = hk.confidences();
+        confidences[0] = 0.;
+        let opinions = hk.opinions();
+        hk.set_state(&opinions, &confidences).unwrap();
+        hk
+    }
+
+    fn check_sweeps<F: Fn(&mut HegselmannKrause)>(topology: OpinionSpace, setup: F) {
+        let sweeps: [fn(&mut HegselmannKrause); 3] = [
+            HegselmannKrause::sweep_naive,
+            HegselmannKrause::sweep_tree,
+            HegselmannKrause::sweep_sorted,
+        ];
+        for sweep in sweeps.iter() {
+            let mut hk = closed_minded(topology);
+            setup(&mut hk);
+            let x = hk.opinions()[0];
+            for _ in 0..20 {
+                sweep(&mut hk);
+                assert!(hk.opinions().iter().all(|x| x.is_finite()));
+                assert!(hk.last_change().is_finite());
+                // the direction of a single opinion on the circle is only exact up to rounding
+                assert!((hk.opinions()[0] - x).abs() < 1e-5);
+                assert_eq!(hk.verify_invariants(), Ok(()));
+            }
+            hk.rebuild_tree();
+            assert_eq!(hk.verify_invariants(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_zero_confidence_keeps_opinion() {
+        check_sweeps(OpinionSpace::Line, |_| {});
+    }
+
+    #[test]
+    fn test_zero_confidence_kernels() {
+        check_sweeps(OpinionSpace::Line, |hk| hk.set_kernel(Kernel::Gaussian));
+        check_sweeps(OpinionSpace::Line, |hk| hk.set_kernel(Kernel::Linear));
+    }
+
+    #[test]
+    fn test_zero_confidence_aggregators() {
+        check_sweeps(OpinionSpace::Line, |hk| {
+            hk.set_aggregator(Aggregator::Median).unwrap()
+        });
+        check_sweeps(OpinionSpace::Line, |hk| {
+            hk.set_aggregator(Aggregator::TrimmedMean { alpha: 0.1 })
+                .unwrap()
+        });
+    }
+
+    #[test]
+    fn test_zero_confidence_circle() {
+        check_sweeps(OpinionSpace::Circle, |_| {});
+    }
+
+    #[test]
+    fn test_zero_confidence_async() {
+        let mut hk = closed_minded(OpinionSpace::Line);
+        let x = hk.opinions()[0];
+        for _ in 0..20 {
+            hk.sweep_async();
+            hk.sweep_async_tree();
+            assert!(hk.opinions().iter().all(|x| x.is_finite()));
+            assert_eq!(hk.opinions()[0], x);
+        }
+        assert_eq!(hk.verify_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_all_zero_confidences() {
+        let mut hk = HegselmannKrause::new(50, 0., 0., 13);
+        let opinions = hk.opinions();
+        for _ in 0..5 {
+            hk.sweep_naive();
+            assert_eq!(hk.opinions(), opinions);
+            assert_eq!(hk.last_change(), 0.);
+        }
+    }
+}
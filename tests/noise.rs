@@ -0,0 +1,73 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_noise_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_noise(0.).unwrap();
+
+        // without noise, the results have to be bit-identical to the plain model
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_noise(0.).unwrap();
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_noise_within_bounds() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.2, 13);
+        hk.set_noise(0.3).unwrap();
+
+        for _ in 0..100 {
+            hk.sweep_tree();
+            assert!(hk.opinions().iter().all(|&x| (0. ..=1.).contains(&x)));
+            // noise prevents convergence
+            assert!(hk.last_change() > 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_cmp_noisy_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_noise(0.01).unwrap();
+        hk2.set_noise(0.01).unwrap();
+
+        // the noise is drawn in the same order, so both methods have to agree
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_noise() {
+        let mut hk = HegselmannKrause::new(100, 0., 1., 13);
+        hk.set_noise(0.1).unwrap();
+        for &eta in &[-0.1, Float::NAN, Float::INFINITY] {
+            assert!(hk.set_noise(eta).is_err());
+        }
+        // the noise of the failed calls is not applied
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_noise(0.1).unwrap();
+        hk.sweep();
+        hk2.sweep();
+        assert_eq!(hk.opinions(), hk2.opinions());
+    }
+}
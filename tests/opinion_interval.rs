@@ -0,0 +1,129 @@
+extern crate hk;
+use hk::{Boundary, Float, HegselmannKrause, InitialOpinions, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_interval_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::with_opinion_interval(100, 0., 0.3, 13, 0., 1.).unwrap();
+        assert_eq!(hk1.opinions(), hk2.opinions());
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_affine_transformation() {
+        // the same run on [-1, 1] with confidences scaled by the width of the interval
+        let mut hk1 = HegselmannKrause::new(200, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::with_opinion_interval(200, 0.1, 0.4, 13, -1., 1.).unwrap();
+        let rescale = |x: Float| 2. * x - 1.;
+
+        for _ in 0..50 {
+            for (x, y) in hk1.opinions().iter().zip(hk2.opinions()) {
+                assert!((rescale(*x) - y).abs() < 1e-4, "{} != {}", rescale(*x), y);
+            }
+            hk1.sweep();
+            hk2.sweep();
+        }
+        assert!(hk2.opinions().iter().any(|&y| y < 0.));
+        assert_eq!(hk1.cluster_sizes(), hk2.cluster_sizes());
+    }
+
+    #[test]
+    fn test_initial_distribution_scaled() {
+        let mut hk = HegselmannKrause::with_opinion_interval(10_000, 0., 0.1, 13, -1., 1.).unwrap();
+        hk.set_initial_opinions(InitialOpinions::Gaussian {
+            mean: 0.75,
+            sd: 0.05,
+        })
+        .unwrap();
+        hk.reset();
+        let opinions = hk.opinions();
+        let mean = opinions.iter().sum::<Float>() / opinions.len() as Float;
+        assert!((mean - 0.5).abs() < 1e-2, "{}", mean);
+    }
+
+    #[test]
+    fn test_boundary_within_interval() {
+        let mut hk = HegselmannKrause::with_opinion_interval(100, 0., 0.5, 13, -1., 1.).unwrap();
+        hk.set_noise(0.5).unwrap();
+        for &boundary in &[Boundary::Clamp, Boundary::Reflect] {
+            hk.set_boundary(boundary).unwrap();
+            for _ in 0..20 {
+                hk.sweep();
+                assert!(hk.opinions().iter().all(|x| (-1. ..=1.).contains(x)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_jumps_within_interval() {
+        let mut hk = HegselmannKrause::with_opinion_interval(1000, 0., 0.1, 13, 2., 3.).unwrap();
+        hk.set_jump_probability(1.).unwrap();
+        hk.sweep();
+        assert!(hk.opinions().iter().all(|x| (2. ..=3.).contains(x)));
+    }
+
+    #[test]
+    fn test_set_state_within_interval() {
+        let mut hk = HegselmannKrause::with_opinion_interval(2, 0., 0.1, 13, -1., 1.).unwrap();
+        assert!(hk.set_state(&[-0.5, 0.5], &[0.1, 0.1]).is_ok());
+        assert!(hk.set_state(&[-1.5, 0.5], &[0.1, 0.1]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_interval() {
+        assert!(HegselmannKrause::with_opinion_interval(10, 0., 0.1, 13, 1., 1.).is_err());
+        assert!(HegselmannKrause::with_opinion_interval(10, 0., 0.1, 13, 1., -1.).is_err());
+        assert!(
+            HegselmannKrause::with_opinion_interval(10, 0., 0.1, 13, 0., Float::INFINITY).is_err()
+        );
+
+        let mut hk = HegselmannKrause::with_topology(10, 0., 0.1, 13, OpinionSpace::Circle);
+        assert!(hk.set_opinion_interval(-1., 1.).is_err());
+
+        let mut hk = HegselmannKrause::with_opinion_interval(10, 0., 0.1, 13, -1., 1.).unwrap();
+        assert!(hk.set_boundary(Boundary::Periodic).is_err());
+        assert_eq!(hk.opinion_interval(), (-1., 1.));
+    }
+
+    #[test]
+    fn test_cmp_naive_tree_negative_opinions() {
+        // the tree needs to order negative opinions below positive ones
+        for &sweep_async in &[false, true] {
+            let mut hk1 =
+                HegselmannKrause::with_opinion_interval(200, 0.05, 0.3, 13, -1., 1.).unwrap();
+            let mut hk2 =
+                HegselmannKrause::with_opinion_interval(200, 0.05, 0.3, 13, -1., 1.).unwrap();
+
+            for _ in 0..50 {
+                if sweep_async {
+                    hk1.sweep_async();
+                    hk2.sweep_async_tree();
+                } else {
+                    hk1.sweep_naive();
+                    hk2.sweep_tree();
+                }
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_negative_zero_in_tree() {
+        // -0 and 0 are the same opinion
+        let mut hk = HegselmannKrause::with_opinion_interval(4, 0., 0.1, 13, -1., 1.).unwrap();
+        hk.set_state(&[-0., 0., -0.5, 0.5], &[0.1; 4]).unwrap();
+        hk.sweep_tree();
+        hk.sweep_async_tree();
+        assert_eq!(hk.cluster_sizes(), vec![1, 2, 1]);
+        assert_eq!(hk.opinions()[2..], [-0.5, 0.5]);
+    }
+}
@@ -0,0 +1,98 @@
+extern crate hk;
+use hk::{
+    ClusterOutput, ConvergenceCriterion, CoupledHegselmannKrause, Deffuant, HegselmannKrause,
+    HegselmannKrauseLattice, HegselmannKrauseND,
+};
+
+/// the string of the clusters of any model is exactly what it writes
+fn assert_string_like_writer<M: ClusterOutput>(model: &M) {
+    let mut output = Vec::new();
+    model.write_cluster_sizes(&mut output).unwrap();
+    assert_eq!(model.cluster_sizes_string().into_bytes(), output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converged_hk() -> HegselmannKrause {
+        let mut hk = HegselmannKrause::new(50, 0.02, 0.1, 13);
+        hk.run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-4 }, None)
+            .unwrap();
+        hk
+    }
+
+    #[test]
+    fn test_cluster_sizes_bytes() {
+        let hk = converged_hk();
+        let mut output = Vec::new();
+        hk.write_cluster_sizes(&mut output).unwrap();
+        hk.write_cluster_composition(&mut output).unwrap();
+        hk.write_cluster_extremists(&mut output).unwrap();
+        // the outcome depends on the random numbers, which differ for f64
+        if cfg!(not(feature = "f64")) {
+            let positions =
+                "# 0.15209804 0.28704298 0.4569438 0.6831374 0.8140229 0.8688182 0.92361355\n";
+            let expected = format!(
+                "{p}11 4 13 19 1 1 1\n{p}11 4 13 19 1 1 1\n{p}11,0 4,0 13,0 19,0 1,0 1,0 1,0\n",
+                p = positions
+            );
+            assert_eq!(String::from_utf8(output).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_deffuant_cluster_sizes_bytes() {
+        let mut dw = Deffuant::new(50, 0.1, 0.3, 0.5, 13);
+        dw.run_until_converged(ConvergenceCriterion::SumChange { threshold: 1e-4 }, None)
+            .unwrap();
+        let mut output = Vec::new();
+        dw.write_cluster_sizes(&mut output).unwrap();
+        if cfg!(not(feature = "f64")) {
+            assert_eq!(
+                output,
+                b"# 0.28180423 0.2818427 0.62486947 0.9507788\n17 2 30 1\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_exact_state_bytes() {
+        // opinions which are exact in both precisions
+        let mut hk = HegselmannKrause::new(3, 0., 0., 13);
+        hk.set_state(&[0.25, 0.75, 0.25], &[0.1, 0.1, 0.1]).unwrap();
+        assert_eq!(hk.cluster_sizes_string(), "# 0.25 0.75\n2 1\n");
+        hk.sweep();
+        assert_eq!(hk.cluster_sizes_string(), "# 0.25 0.75\n2 1\n");
+    }
+
+    #[test]
+    fn test_string_like_writer() {
+        let hk = converged_hk();
+        let mut output = Vec::new();
+        hk.write_cluster_sizes(&mut output).unwrap();
+        assert_eq!(hk.cluster_sizes_string().into_bytes(), output);
+
+        // also through a trait object, like the main program does
+        let mut output = Vec::new();
+        let writer: &mut dyn std::io::Write = &mut output;
+        hk.write_cluster_sizes(writer).unwrap();
+        assert_eq!(hk.cluster_sizes_string().into_bytes(), output);
+    }
+
+    #[test]
+    fn test_media_opinion_bytes() {
+        let mut output = Vec::new();
+        converged_hk().write_media_opinion(&mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_string_like_writer_all_models() {
+        assert_string_like_writer(&converged_hk());
+        assert_string_like_writer(&Deffuant::new(50, 0.1, 0.3, 0.5, 13));
+        assert_string_like_writer(&HegselmannKrauseLattice::new(5, 0.1, 0.3, 13, false));
+        assert_string_like_writer(&HegselmannKrauseND::<2>::new(50, 0.1, 0.3, 13));
+        assert_string_like_writer(&CoupledHegselmannKrause::new(50, 0.1, 0.3, 13));
+    }
+}
@@ -0,0 +1,43 @@
+// the parallel sweeps only exist with the rayon feature
+#![cfg(feature = "rayon")]
+extern crate hk;
+use hk::HegselmannKrause;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// run `f` within a pool of `threads` threads
+    fn with_threads<F: FnOnce() + Send>(threads: usize, f: F) {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(f);
+    }
+
+    #[test]
+    fn test_parallel_naive_bit_identical() {
+        let mut hk1 = HegselmannKrause::new(1000, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(1000, 0., 0.3, 13);
+
+        for _ in 0..20 {
+            with_threads(1, || hk1.sweep_naive());
+            with_threads(4, || hk2.sweep_naive());
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_parallel_tree_bit_identical() {
+        let mut hk1 = HegselmannKrause::new(1000, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(1000, 0., 0.3, 13);
+
+        for _ in 0..20 {
+            with_threads(1, || hk1.sweep_tree());
+            with_threads(4, || hk2.sweep_tree());
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+}
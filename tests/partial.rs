@@ -0,0 +1,104 @@
+extern crate hk;
+use hk::{Diagnostics, Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_partial_sweep_unchanged() {
+        for &topology in &[OpinionSpace::Line, OpinionSpace::Circle] {
+            let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+            let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+
+            for _ in 0..100 {
+                hk1.sweep_tree();
+                hk2.sweep_partial(1.);
+                assert_eq!(hk1.opinions(), hk2.opinions());
+                assert_eq!(hk1.last_change(), hk2.last_change());
+            }
+
+            let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+            let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+            for _ in 0..100 {
+                hk1.sweep_naive();
+                hk2.sweep_partial_naive(1.);
+                assert_eq!(hk1.opinions(), hk2.opinions());
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_naive_tree() {
+        for &topology in &[OpinionSpace::Line, OpinionSpace::Circle] {
+            let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+            let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+
+            for _ in 0..100 {
+                hk1.sweep_partial_naive(0.3);
+                hk2.sweep_partial(0.3);
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_updates_only_fraction() {
+        let mut hk = HegselmannKrause::new(100, 0., 1., 13);
+        let before = hk.opinions();
+        hk.sweep_partial(0.1);
+        let after = hk.opinions();
+
+        let changed = before.iter().zip(&after).filter(|(a, b)| a != b).count();
+        assert!(changed > 0);
+        assert!(changed <= 10);
+
+        let change: Float = before.iter().zip(&after).map(|(a, b)| (a - b).abs()).sum();
+        assert!((hk.last_change() - change).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_partial_reproducible() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_update_fraction(0.5).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep_partial(0.5);
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_partial_converges() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.set_update_fraction(0.2).unwrap();
+        let mut quiet = 0;
+        for _ in 0..10000 {
+            hk.sweep();
+            // a single quiet partial sweep does not imply convergence
+            if hk.last_change() < 1e-4 {
+                quiet += 1;
+                if quiet > 20 {
+                    break;
+                }
+            } else {
+                quiet = 0;
+            }
+        }
+        assert!(quiet > 20);
+        assert_eq!(hk.total_agents_in_clusters(), 100);
+    }
+
+    #[test]
+    fn test_invalid_update_fraction() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_update_fraction(0.).is_err());
+        assert!(hk.set_update_fraction(-0.5).is_err());
+        assert!(hk.set_update_fraction(1.5).is_err());
+        assert!(hk.set_update_fraction(Float::NAN).is_err());
+        assert!(hk.set_update_fraction(0.5).is_ok());
+        assert!(hk.set_update_fraction(1.).is_ok());
+    }
+}
@@ -0,0 +1,65 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_perception_noise_unchanged() {
+        for &topology in &[OpinionSpace::Line, OpinionSpace::Circle] {
+            let mut hk1 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+            let mut hk2 = HegselmannKrause::with_topology(100, 0., 1., 13, topology);
+
+            for _ in 0..100 {
+                hk1.sweep_naive();
+                hk2.sweep_naive_noisy_perception(0.);
+                assert_eq!(hk1.opinions(), hk2.opinions());
+                assert_eq!(hk1.last_change(), hk2.last_change());
+            }
+        }
+    }
+
+    #[test]
+    fn test_perception_noise_reproducible() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_perception_noise(0.05).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep_naive_noisy_perception(0.05);
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_perception_noise_changes_dynamics() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.sweep_naive();
+        hk2.sweep_naive_noisy_perception(0.05);
+
+        assert_ne!(hk1.opinions(), hk2.opinions());
+        assert!(hk2.opinions().iter().all(|x| (0. ..=1.).contains(x)));
+    }
+
+    #[test]
+    fn test_perceived_opinion_stays_close() {
+        // two agents far apart never see each other, regardless of the noise
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.1, 0.9], &[0.2, 0.2]).unwrap();
+        for _ in 0..10 {
+            hk.sweep_naive_noisy_perception(0.1);
+            assert_eq!(hk.opinions(), vec![0.1, 0.9]);
+        }
+    }
+
+    #[test]
+    fn test_invalid_perception_noise() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_perception_noise(-0.1).is_err());
+        assert!(hk.set_perception_noise(Float::NAN).is_err());
+        assert!(hk.set_perception_noise(0.).is_ok());
+    }
+}
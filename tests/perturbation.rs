@@ -0,0 +1,106 @@
+extern crate hk;
+use hk::{ConvergenceCriterion, HegselmannKrause, HkError};
+
+const CRITERION: ConvergenceCriterion = ConvergenceCriterion::SumChange { threshold: 1e-4 };
+
+/// a converged system of several clusters
+fn converged() -> HegselmannKrause {
+    let mut hk = HegselmannKrause::new(200, 0.05, 0.1, 13);
+    hk.run_until_converged(CRITERION, None).unwrap();
+    hk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_consistent_after_kick() {
+        let mut hk = converged();
+        hk.set_opinion(3, 0.5).unwrap();
+        hk.set_opinion(4, 0.).unwrap();
+        hk.set_opinion(5, 1.).unwrap();
+        hk.verify_invariants().unwrap();
+        assert_eq!(hk.agent(3).unwrap().0, 0.5);
+        assert_eq!(hk.opinions()[4], 0.);
+    }
+
+    #[test]
+    fn test_tree_like_naive_after_kick() {
+        let mut hk1 = converged();
+        for (idx, &x) in [0.5, 0.51, 0.02, 0.97].iter().enumerate() {
+            hk1.set_opinion(10 * idx, x).unwrap();
+        }
+        hk1.set_confidence(7, 0.3).unwrap();
+        let mut hk2 = HegselmannKrause::new(200, 0.05, 0.1, 13);
+        hk2.set_state(&hk1.opinions(), &hk1.confidences()).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep_tree();
+            hk2.sweep_naive();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+            hk1.verify_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_kick_with_skipped_agents() {
+        // the agents, whose neighborhood did not change, are not recalculated, so the
+        // perturbations need to be noticed
+        let mut hk1 = converged();
+        let mut hk2 = converged();
+        hk1.set_skip_unchanged(true);
+        let kicks: [fn(&mut HegselmannKrause); 2] = [
+            |hk| hk.set_confidence(1, 0.5).unwrap(),
+            |hk| hk.set_opinion(0, 0.5).unwrap(),
+        ];
+        for kick in kicks.iter() {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            kick(&mut hk1);
+            kick(&mut hk2);
+            for _ in 0..20 {
+                hk1.sweep_tree();
+                hk2.sweep_tree();
+                assert!(hk1 == hk2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clusters_rearrange() {
+        // two clusters, which do not see each other
+        let mut hk = HegselmannKrause::new(100, 0., 0., 13);
+        let opinions: Vec<_> = (0..100).map(|i| if i < 50 { 0.25 } else { 0.75 }).collect();
+        hk.set_state(&opinions, &[0.125; 100]).unwrap();
+        assert_eq!(hk.run_until_converged(CRITERION, None), Ok(0));
+
+        // a lonely agent far away from both stays a cluster of its own
+        hk.set_opinion(0, 0.).unwrap();
+        hk.run_until_converged(CRITERION, None).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![1, 49, 50]);
+
+        // an open-minded agent between the clusters is pulled to their mean, while
+        // both clusters ignore it
+        hk.set_opinion(99, 0.5).unwrap();
+        hk.set_confidence(99, 0.5).unwrap();
+        hk.run_until_converged(CRITERION, None).unwrap();
+        assert_eq!(hk.cluster_sizes(), vec![1, 49, 1, 49]);
+    }
+
+    #[test]
+    fn test_invalid_perturbations() {
+        let mut hk = converged();
+        let state = hk.opinions();
+        let invalid = |r: Result<(), HkError>| matches!(r, Err(HkError::InvalidState(_)));
+        assert!(invalid(hk.set_opinion(200, 0.5)));
+        assert!(invalid(hk.set_opinion(0, 1.5)));
+        assert!(invalid(hk.set_opinion(0, -0.1)));
+        assert!(invalid(hk.set_opinion(0, hk::Float::NAN)));
+        assert!(invalid(hk.set_confidence(200, 0.1)));
+        assert!(invalid(hk.set_confidence(0, -0.1)));
+        assert!(invalid(hk.set_confidence(0, hk::Float::INFINITY)));
+        assert_eq!(hk.opinions(), state);
+        hk.verify_invariants().unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// difference of the largest and smallest opinion
+    fn spread(hk: &HegselmannKrause) -> Float {
+        let opinions = hk.opinions();
+        let max = opinions
+            .iter()
+            .cloned()
+            .fold(Float::NEG_INFINITY, Float::max);
+        let min = opinions.iter().cloned().fold(Float::INFINITY, Float::min);
+        max - min
+    }
+
+    #[test]
+    fn test_consensus_resolution() {
+        // everybody sees everybody, such that the opinions approach their consensus
+        // geometrically with mu < 1, until rounding stops them
+        let mut hk = HegselmannKrause::new(10, 1., 1., 13);
+        hk.set_mu(0.3).unwrap();
+        for _ in 0..200 {
+            hk.sweep();
+        }
+
+        // f32 gets stuck a unit in the last place apart, f64 resolves the consensus
+        if cfg!(feature = "f64") {
+            assert!(spread(&hk) < 1e-10);
+        } else {
+            assert!(spread(&hk) > 1e-10);
+        }
+        assert_eq!(hk.last_change(), 0.);
+    }
+}
+
+#[cfg(test)]
+mod large_counts {
+    use super::*;
+    use hk::Backend;
+
+    #[test]
+    fn test_weights_beyond_f32_integers() {
+        // a leader counting as 2^24 agents shares its opinion with 1000 others, beyond
+        // 2^24 an f32 cannot count single agents anymore
+        let n = 1002;
+        for &backend in &[Backend::Tree, Backend::Sorted] {
+            let mut hk = HegselmannKrause::new(n, 1., 1., 13);
+            let mut opinions = vec![0.; n as usize];
+            opinions[n as usize - 1] = 0.1;
+            hk.set_state(&opinions, &vec![1.; n as usize]).unwrap();
+            hk.set_leaders(&[0], 1 << 24).unwrap();
+            hk.set_backend(backend);
+            hk.sweep();
+
+            let expected: Float = 0.1 / (16777216. + 1001.);
+            let x = hk.opinions()[1];
+            assert!(
+                ((x - expected) / expected).abs() < 1e-6,
+                "{:?}: {} instead of {}",
+                backend,
+                x,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,97 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on_grid(opinions: &[Float], spacing: Float) -> bool {
+        opinions
+            .iter()
+            .all(|x| ((x * spacing).round() - x * spacing).abs() < 1e-3)
+    }
+
+    #[test]
+    fn test_quantized_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk3 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        for hk in [&mut hk1, &mut hk2, &mut hk3].iter_mut() {
+            hk.set_quantization(11).unwrap();
+        }
+
+        for _ in 0..50 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            hk3.sweep_quantized();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+            assert!(hk2.approx_eq(&hk3, 1e-5));
+            assert!(on_grid(&hk3.opinions(), 10.));
+        }
+    }
+
+    #[test]
+    fn test_quantized_circle() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0.1, 0.3, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0.1, 0.3, 13, OpinionSpace::Circle);
+        hk1.set_quantization(10).unwrap();
+        hk2.set_quantization(10).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep_naive();
+            hk2.sweep();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+            assert!(on_grid(&hk2.opinions(), 10.));
+            assert!(hk2.opinions().iter().all(|&x| (0. ..1.).contains(&x)));
+        }
+    }
+
+    #[test]
+    fn test_quantization_rounds() {
+        let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+        hk.set_state(&[0.1, 0.2, 0.26], &[0.5, 0.05, 0.05]).unwrap();
+        hk.set_quantization(5).unwrap();
+        assert_eq!(hk.opinions(), vec![0., 0.25, 0.25]);
+
+        hk.sweep();
+        // the mean 1/6 of the first agent is rounded to the grid
+        assert_eq!(hk.opinions(), vec![0.25, 0.25, 0.25]);
+        assert_eq!(hk.cluster_sizes(), vec![3]);
+    }
+
+    #[test]
+    fn test_fine_grid_approaches_continuous() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_quantization(1_000_001).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep();
+            hk2.sweep();
+        }
+        for (x, y) in hk1.opinions().iter().zip(hk2.opinions().iter()) {
+            assert!((x - y).abs() < 1e-4, "{} != {}", x, y);
+        }
+    }
+
+    #[test]
+    fn test_zero_quantization_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk2.set_quantization(0).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_invalid_quantization() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        assert!(hk.set_quantization(1).is_err());
+        assert!(hk.set_quantization(2).is_ok());
+        assert!(hk.set_quantization(0).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trajectory(hk: &mut HegselmannKrause, sweeps: usize) -> Vec<Vec<Float>> {
+        (0..sweeps)
+            .map(|_| {
+                hk.sweep();
+                hk.opinions()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_after_using_rng() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        let (opinions, confidences) = hk.initial_state();
+        assert_eq!(hk.opinions(), opinions);
+        let first = trajectory(&mut hk, 30);
+
+        // noisy sweeps consume random numbers in between
+        hk.set_noise(0.1).unwrap();
+        trajectory(&mut hk, 10);
+        hk.set_noise(0.).unwrap();
+
+        hk.reset_to_initial();
+        assert_eq!(hk.opinions(), opinions);
+        assert_eq!(hk.confidences(), confidences);
+        assert_eq!(trajectory(&mut hk, 30), first);
+    }
+
+    #[test]
+    fn test_replay_with_other_parameters() {
+        let mut hk = HegselmannKrause::new(300, 0.05, 0.2, 13);
+        let initial = hk.opinions();
+        let deterministic = trajectory(&mut hk, 20);
+
+        hk.set_noise(0.01).unwrap();
+        hk.reset_to_initial();
+        assert_eq!(hk.opinions(), initial);
+        let noisy = trajectory(&mut hk, 20);
+        assert_ne!(noisy, deterministic);
+    }
+
+    #[test]
+    fn test_replay_does_not_use_rng() {
+        let mut hk1 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        // right after the construction, the initial conditions are the constructed ones
+        hk1.reset_to_initial();
+        assert!(hk1 == hk2);
+
+        hk1.reset();
+        hk2.reset();
+        assert_eq!(hk1.opinions(), hk2.opinions());
+    }
+
+    #[test]
+    fn test_reset_replaces_initial_state() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let (first, _) = hk.initial_state();
+        hk.reset();
+        let (second, _) = hk.initial_state();
+        assert_ne!(first, second);
+        assert_eq!(hk.opinions(), second);
+
+        trajectory(&mut hk, 10);
+        hk.reset_to_initial();
+        assert_eq!(hk.opinions(), second);
+    }
+
+    #[test]
+    fn test_replay_set_state() {
+        let mut hk = HegselmannKrause::new(4, 0., 0., 13);
+        let opinions = [0.1, 0.2, 0.6, 0.65];
+        let confidences = [0.15, 0.15, 0.1, 0.1];
+        hk.set_state(&opinions, &confidences).unwrap();
+        assert_eq!(
+            hk.initial_state(),
+            (opinions.to_vec(), confidences.to_vec())
+        );
+
+        trajectory(&mut hk, 5);
+        assert_ne!(hk.opinions(), opinions.to_vec());
+        hk.reset_to_initial();
+        assert_eq!(hk.opinions(), opinions.to_vec());
+        assert_eq!(hk.verify_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_replay_restarts_time() {
+        let mut hk = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk.set_turnover(0.5).unwrap();
+        let initial = hk.opinions();
+        trajectory(&mut hk, 10);
+        assert_eq!(hk.sweep_count(), 10);
+
+        hk.reset_to_initial();
+        assert_eq!(hk.sweep_count(), 0);
+        assert_eq!(hk.opinions(), initial);
+        assert_eq!(hk.verify_invariants(), Ok(()));
+    }
+}
@@ -0,0 +1,97 @@
+extern crate hk;
+use hk::{Boundary, Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repulsion_drives_to_boundaries() {
+        for &naive in &[true, false] {
+            let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+            hk.set_state(&[0.4, 0.6], &[0.1, 0.1]).unwrap();
+            hk.set_repulsion(1., 0.5).unwrap();
+
+            let expected = [[0.3, 0.7], [0.1, 0.9], [0., 1.], [0., 1.]];
+            for e in expected.iter() {
+                if naive {
+                    hk.sweep_naive();
+                } else {
+                    hk.sweep_tree();
+                }
+                let opinions = hk.opinions();
+                assert!((opinions[0] - e[0]).abs() < 1e-6, "{:?}", opinions);
+                assert!((opinions[1] - e[1]).abs() < 1e-6, "{:?}", opinions);
+            }
+            assert_eq!(hk.last_change(), 0.);
+        }
+    }
+
+    #[test]
+    fn test_repulsion_respects_boundary() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_boundary(Boundary::Open).unwrap();
+        hk.set_state(&[0.4, 0.6], &[0.1, 0.1]).unwrap();
+        hk.set_repulsion(1., 0.5).unwrap();
+        for _ in 0..3 {
+            hk.sweep();
+        }
+
+        let opinions = hk.opinions();
+        assert!((opinions[0] + 0.3).abs() < 1e-6, "{:?}", opinions);
+        assert!((opinions[1] - 1.3).abs() < 1e-6, "{:?}", opinions);
+    }
+
+    #[test]
+    fn test_repulsion_out_of_range() {
+        // agents farther apart than the repulsion range do not interact at all
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.2, 0.8], &[0.1, 0.1]).unwrap();
+        hk.set_repulsion(0.5, 0.5).unwrap();
+        hk.sweep();
+        assert_eq!(hk.opinions(), vec![0.2, 0.8]);
+        assert_eq!(hk.last_change(), 0.);
+    }
+
+    #[test]
+    fn test_repulsion_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        hk1.set_repulsion(0.3, 0.1).unwrap();
+        hk2.set_repulsion(0.3, 0.1).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_zero_repulsion_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        hk2.set_repulsion(0.3, 0.).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_invalid_repulsion() {
+        let mut hk = HegselmannKrause::new(2, 0.1, 0.2, 13);
+        hk.set_state(&[0.3, 0.7], &[0.1, 0.2]).unwrap();
+        assert!(hk.set_repulsion(0.15, 0.5).is_err());
+        assert!(hk.set_repulsion(0.2, 0.5).is_err());
+        assert!(hk.set_repulsion(0.3, -0.5).is_err());
+        assert!(hk.set_repulsion(0.3, Float::NAN).is_err());
+        assert!(hk.set_repulsion(Float::INFINITY, 0.5).is_err());
+        assert!(hk.set_repulsion(0.3, 0.5).is_ok());
+
+        let mut hk = HegselmannKrause::with_topology(10, 0.1, 0.2, 13, OpinionSpace::Circle);
+        assert!(hk.set_repulsion(0.3, 0.5).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+extern crate hk;
+use hk::HegselmannKrause;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_group_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.set_rotation_groups(1).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_two_groups_by_hand() {
+        for &naive in &[true, false] {
+            let mut hk = HegselmannKrause::new(3, 0., 1., 13);
+            hk.set_state(&[0.1, 0.2, 0.4], &[0.15; 3]).unwrap();
+            hk.set_rotation_groups(2).unwrap();
+            let sweep = |hk: &mut HegselmannKrause| {
+                if naive {
+                    hk.sweep_naive()
+                } else {
+                    hk.sweep_tree()
+                }
+            };
+
+            // agents 0 and 2 update, agent 2 is alone
+            sweep(&mut hk);
+            let expected = [0.15, 0.2, 0.4];
+            for (x, y) in hk.opinions().iter().zip(expected.iter()) {
+                assert!((x - y).abs() < 1e-6, "{} != {}", x, y);
+            }
+            assert!((hk.last_change() - 0.05).abs() < 1e-6);
+
+            // agent 1 updates and sees the new opinion of agent 0
+            sweep(&mut hk);
+            let expected = [0.15, 0.175, 0.4];
+            for (x, y) in hk.opinions().iter().zip(expected.iter()) {
+                assert!((x - y).abs() < 1e-6, "{} != {}", x, y);
+            }
+            assert!((hk.last_change() - 0.025).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_rotation_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk1.set_rotation_groups(3).unwrap();
+        hk2.set_rotation_groups(3).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_rotation_restarts_with_new_state() {
+        let mut hk1 = HegselmannKrause::new(3, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(3, 0., 1., 13);
+        for hk in [&mut hk1, &mut hk2].iter_mut() {
+            hk.set_rotation_groups(2).unwrap();
+        }
+        // one sweep shifts the rotation of the first model
+        hk1.sweep();
+        for hk in [&mut hk1, &mut hk2].iter_mut() {
+            hk.set_state(&[0.1, 0.2, 0.4], &[0.15; 3]).unwrap();
+        }
+
+        for _ in 0..4 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_invalid_rotation_groups() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.set_rotation_groups(0).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+extern crate hk;
+use hk::{ConfidenceSchedule, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_schedule_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.set_confidence_schedule(ConfidenceSchedule::Constant)
+            .unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.confidences(), hk2.confidences());
+        }
+    }
+
+    #[test]
+    fn test_fast_decay_freezes() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.set_confidence_schedule(ConfidenceSchedule::ExponentialDecay {
+            tau: 1e-3,
+            floor: 0.,
+        })
+        .unwrap();
+
+        // the first sweep still uses the initial confidences
+        hk.sweep();
+        assert!(hk.last_change() > 0.);
+
+        // afterwards only agents with identical opinions see each other,
+        // such that the system is converged immediately
+        let frozen = hk.opinions();
+        for _ in 0..10 {
+            hk.sweep();
+            assert!(hk.last_change() < 1e-4);
+        }
+        for (x, y) in hk.opinions().iter().zip(frozen.iter()) {
+            assert!((x - y).abs() < 1e-6);
+        }
+        assert!(hk.confidences().iter().all(|&c| c == 0.));
+    }
+
+    #[test]
+    fn test_linear_decay_floor() {
+        let mut hk = HegselmannKrause::new(100, 0.2, 0.3, 13);
+        let initial = hk.confidences();
+        hk.set_confidence_schedule(ConfidenceSchedule::LinearDecay {
+            rate: 0.01,
+            floor: 0.05,
+        })
+        .unwrap();
+
+        for _ in 0..50 {
+            hk.sweep();
+        }
+        // the confidences of the last sweep belong to t = 49
+        for (c, c0) in hk.confidences().iter().zip(initial.iter()) {
+            assert!((c - (c0 - 0.49).max(0.05)).abs() < 1e-6);
+        }
+
+        // a reset draws fresh, undecayed confidences
+        hk.reset();
+        assert!(hk.confidences().iter().all(|&c| c >= 0.2));
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        assert!(hk
+            .set_confidence_schedule(ConfidenceSchedule::ExponentialDecay { tau: 0., floor: 0. })
+            .is_err());
+        assert_eq!(
+            "linear:0.1:0".parse(),
+            Ok(ConfidenceSchedule::LinearDecay {
+                rate: 0.1,
+                floor: 0.
+            })
+        );
+    }
+}
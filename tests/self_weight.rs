@@ -0,0 +1,115 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, OpinionSpace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_self_weight_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_self_weight(1.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_self_weight(1.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_unit_self_weight_regression() {
+        let mut hk = HegselmannKrause::new(10, 0.1, 0.3, 13);
+        hk.set_self_weight(1.).unwrap();
+        let mut sweeps = 0;
+        loop {
+            sweeps += 1;
+            hk.sweep_tree();
+            if hk.last_change() < 1e-4 {
+                break;
+            }
+        }
+
+        // pin the outcome of the classic model, such that changes to the dynamics are noticed
+        // the outcome depends on the random numbers, which differ for f64
+        if cfg!(not(feature = "f64")) {
+            assert_eq!(sweeps, 4);
+            assert_eq!(hk.cluster_sizes(), vec![2, 1, 7]);
+            assert_eq!(hk.opinions()[0], 0.82024115);
+            assert_eq!(hk.opinions()[4], 0.44853222);
+            assert_eq!(hk.opinions()[5], 0.11117309);
+        }
+    }
+
+    #[test]
+    fn test_self_weight_pair() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_state(&[0.4, 0.6], &[0.5, 0.1]).unwrap();
+        hk.set_self_weight(3.).unwrap();
+        hk.sweep_naive();
+
+        // (3 * 0.4 + 0.6) / 4, the second agent does not see the first
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.45).abs() < 1e-6);
+        assert!((opinions[1] - 0.6).abs() < 1e-6);
+
+        // without self weight the first agent adopts the opinion of the second
+        // the isolated second agent keeps its opinion
+        hk.set_state(&[0.4, 0.6], &[0.5, 0.1]).unwrap();
+        hk.set_self_weight(0.).unwrap();
+        hk.sweep_tree();
+        let opinions = hk.opinions();
+        assert!((opinions[0] - 0.6).abs() < 1e-6);
+        assert!((opinions[1] - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cmp_self_weight_naive_tree() {
+        for &s in &[0., 0.5, 4.] {
+            let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+            let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+            hk1.set_self_weight(s).unwrap();
+            hk2.set_self_weight(s).unwrap();
+
+            for _ in 0..100 {
+                hk1.sweep_naive();
+                hk2.sweep_tree();
+                assert!(hk1.approx_eq(&hk2, 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_self_weight_circle() {
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Circle);
+        hk1.set_self_weight(2.).unwrap();
+        hk2.set_self_weight(2.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_self_weight() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.set_self_weight(-1.).is_err());
+        assert!(hk.set_self_weight(Float::NAN).is_err());
+        assert!(hk.set_self_weight(Float::INFINITY).is_err());
+        hk.set_self_weight(0.).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause, HkError};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_state_sweep() {
+        let mut hk = HegselmannKrause::new(4, 0., 1., 13);
+        hk.set_state(&[0.1, 0.2, 0.4, 0.9], &[0.15, 0.25, 0.25, 0.05])
+            .unwrap();
+        assert_eq!(hk.last_change(), 0.);
+        hk.sweep_naive();
+
+        // agent 0 sees agent 1, agent 1 sees agents 0 and 2,
+        // agent 2 sees agent 1, agent 3 is alone
+        let expected = [0.15, 0.7 / 3., 0.3, 0.9];
+        for (x, e) in hk.opinions().iter().zip(expected.iter()) {
+            assert!((x - e).abs() < 1e-6, "{} != {}", x, e);
+        }
+
+        // the tree has to be consistent with the injected state
+        let mut hk2 = HegselmannKrause::new(4, 0., 1., 13);
+        hk2.set_state(&[0.1, 0.2, 0.4, 0.9], &[0.15, 0.25, 0.25, 0.05])
+            .unwrap();
+        hk2.sweep_tree();
+        assert!(hk.approx_eq(&hk2, 1e-5));
+    }
+
+    #[test]
+    fn test_symmetric_clusters_never_merge() {
+        let mut opinions = vec![0.3; 50];
+        opinions.extend(vec![0.7; 50]);
+        let confidences = vec![0.35; 100];
+
+        let mut hk = HegselmannKrause::new(100, 0., 1., 13);
+        hk.set_state(&opinions, &confidences).unwrap();
+        for _ in 0..100 {
+            hk.sweep_tree();
+        }
+
+        assert_eq!(hk.opinions(), opinions);
+        assert_eq!(hk.cluster_sizes(), vec![50, 50]);
+    }
+
+    #[test]
+    fn test_set_state_invalid() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+
+        // wrong number of agents
+        assert!(matches!(
+            hk.set_state(&[0.5], &[0.1, 0.1]),
+            Err(HkError::InvalidState(_))
+        ));
+        assert!(hk.set_state(&[0.5, 0.5], &[0.1]).is_err());
+        // opinions outside of [0, 1]
+        assert!(hk.set_state(&[0.5, 1.5], &[0.1, 0.1]).is_err());
+        assert!(hk.set_state(&[Float::NAN, 0.5], &[0.1, 0.1]).is_err());
+        // negative or undefined confidences
+        assert!(hk.set_state(&[0.5, 0.5], &[-0.1, 0.1]).is_err());
+        assert!(hk.set_state(&[0.5, 0.5], &[0.1, Float::NAN]).is_err());
+
+        // a rejected state leaves the model untouched
+        let before = hk.opinions();
+        assert!(hk.set_state(&[0.1, 0.2], &[0.1, -1.]).is_err());
+        assert_eq!(hk.opinions(), before);
+    }
+}
+
+#[cfg(test)]
+mod non_finite {
+    use super::*;
+    use hk::DeGroot;
+
+    fn invalid_state_message(result: Result<(), HkError>) -> String {
+        match result {
+            Err(HkError::InvalidState(reason)) => reason,
+            other => panic!("non-finite state accepted: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_state_rejects_non_finite() {
+        let mut hk = HegselmannKrause::new(3, 0.1, 0.2, 13);
+        let before = hk.opinions();
+
+        let reason = invalid_state_message(hk.set_state(&[0.5, Float::NAN, 0.5], &[0.1; 3]));
+        assert!(reason.contains("agent 1"));
+        assert!(reason.contains("NaN"));
+
+        let reason = invalid_state_message(hk.set_state(&[0.5; 3], &[0.1, 0.1, Float::INFINITY]));
+        assert!(reason.contains("agent 2"));
+        assert!(reason.contains("inf"));
+
+        let reason = invalid_state_message(hk.set_state_asymmetric(
+            &[0.5; 3],
+            &[0.1; 3],
+            &[Float::NAN, 0.1, 0.1],
+        ));
+        assert!(reason.contains("agent 0"));
+
+        // nothing was changed by the rejected states
+        assert_eq!(hk.opinions(), before);
+        assert_eq!(hk.check_finite(), Ok(()));
+    }
+
+    #[test]
+    fn test_degroot_rejects_non_finite() {
+        let weights = vec![vec![0.5, 0.5], vec![0.5, 0.5]];
+        let mut dg = DeGroot::new(&weights, 13).unwrap();
+        let reason = invalid_state_message(dg.set_state(&[0.5, Float::NEG_INFINITY]));
+        assert!(reason.contains("agent 1"));
+    }
+
+    #[test]
+    fn test_check_finite_reports_agent() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        for _ in 0..3 {
+            hk.sweep();
+        }
+        assert_eq!(hk.check_finite(), Ok(()));
+
+        hk.inject_opinion(42, Float::NAN);
+        match hk.check_finite() {
+            Err(HkError::NonFinite {
+                agent,
+                opinion,
+                sweep,
+                ..
+            }) => {
+                assert_eq!(agent, 42);
+                assert!(opinion.is_nan());
+                assert_eq!(sweep, 3);
+            }
+            other => panic!("NaN not detected: {:?}", other),
+        }
+        let message = hk.check_finite().unwrap_err().to_string();
+        assert!(message.contains("agent 42"));
+        assert!(message.contains("after sweep 3"));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_try_sweep_detects_nan() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        hk.inject_opinion(7, Float::NAN);
+        match hk.try_sweep() {
+            Err(HkError::NonFinite { sweep, .. }) => assert_eq!(sweep, 1),
+            other => panic!("NaN not detected after the sweep: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "non-finite state")]
+    fn test_sweep_panics_on_nan() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.2, 13);
+        hk.inject_opinion(7, Float::NAN);
+        hk.sweep();
+    }
+}
@@ -1,5 +1,5 @@
 extern crate hk;
-use hk::HegselmannKrause;
+use hk::{Backend, Float, HegselmannKrause, Kernel};
 
 #[cfg(test)]
 mod tests {
@@ -14,7 +14,524 @@ mod tests {
         for _ in 0..100 {
             hk1.sweep_naive();
             hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_naive_tree_many_agents() {
+        let mut hk1 = HegselmannKrause::new(2000, 0.01, 0.1, 13);
+        let mut hk2 = HegselmannKrause::new(2000, 0.01, 0.1, 13);
+
+        // many distinct opinions in the early sweeps, where the cumulative sums matter
+        // the rounding differences grow quickly with so many agents, so every sweep
+        // starts from the same state
+        for _ in 0..10 {
+            hk2.set_state(&hk1.opinions(), &hk1.confidences()).unwrap();
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_naive_sorted() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_sorted();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_tree_sorted_duplicates() {
+        // few distinct opinions, held by many agents each
+        let opinions: Vec<Float> = (0..300).map(|k| (k % 7) as Float / 10.).collect();
+        let confidences = vec![0.15; 300];
+        let mut hk1 = HegselmannKrause::new(300, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(300, 0., 1., 13);
+        hk1.set_state(&opinions, &confidences).unwrap();
+        hk2.set_state(&opinions, &confidences).unwrap();
+
+        for _ in 0..20 {
+            hk1.sweep_tree();
+            hk2.sweep_sorted();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_sorted_backend_sweep() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_backend(Backend::Sorted);
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+
+        // switching back rebuilds the tree
+        hk2.set_backend(Backend::Tree);
+        hk2.sweep();
+    }
+
+    /// run two copies of the same system for 200 synchronous tree sweeps, the second one
+    /// skipping the agents whose neighborhood did not change, and compare them bit by bit
+    fn cmp_skip_unchanged<F: Fn(&mut HegselmannKrause)>(setup: F) {
+        let mut hk1 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        setup(&mut hk1);
+        setup(&mut hk2);
+        hk2.set_skip_unchanged(true);
+
+        for _ in 0..200 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_skip_unchanged_plain() {
+        cmp_skip_unchanged(|_| {});
+    }
+
+    #[test]
+    fn test_skip_unchanged_gaussian() {
+        cmp_skip_unchanged(|hk| hk.set_kernel(Kernel::Gaussian));
+    }
+
+    #[test]
+    fn test_skip_unchanged_mu() {
+        cmp_skip_unchanged(|hk| hk.set_mu(0.3).unwrap());
+    }
+
+    #[test]
+    fn test_skip_unchanged_repulsion() {
+        cmp_skip_unchanged(|hk| hk.set_repulsion(0.3, 0.1).unwrap());
+    }
+
+    #[test]
+    fn test_skip_unchanged_turnover() {
+        cmp_skip_unchanged(|hk| hk.set_turnover(0.5).unwrap());
+    }
+
+    #[test]
+    fn test_skip_unchanged_media_schedule() {
+        cmp_skip_unchanged(|hk| hk.set_media_schedule(0.3, 50., 5.).unwrap());
+    }
+
+    #[test]
+    fn test_skip_unchanged_after_set_state() {
+        let mut hk1 = HegselmannKrause::new(100, 0.01, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.01, 0.2, 13);
+        hk2.set_skip_unchanged(true);
+        for _ in 0..50 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+        }
+
+        let opinions: Vec<Float> = (0..100).map(|k| k as Float / 100.).collect();
+        let confidences = vec![0.05; 100];
+        hk1.set_state(&opinions, &confidences).unwrap();
+        hk2.set_state(&opinions, &confidences).unwrap();
+        for _ in 0..50 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_cmp_async_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+
+        // test that the two methods will yield identical results for 100 random sequential sweeps
+        for _ in 0..100 {
+            hk1.sweep_async();
+            hk2.sweep_async_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_async_tree_sums_like_rebuilt() {
+        let mut hk1 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        for _ in 0..20 {
+            hk1.sweep_async_tree();
+            hk2.sweep_async_tree();
+        }
+
+        // the cumulative sums maintained by the random sequential sweeps do not depend on
+        // the order of the updates, which led to them
+        hk2.rebuild_tree();
+        for _ in 0..20 {
+            hk1.sweep_async_tree();
+            hk2.sweep_async_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+}
+
+#[cfg(test)]
+mod auto_backend {
+    use super::*;
+
+    #[test]
+    fn test_auto_backend_sweep() {
+        let mut hk1 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        hk1.set_backend(Backend::Tree);
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+
+        // the sweeps on the tree rebuild it, after the sorted arrays were used
+        for _ in 0..5 {
+            hk1.sweep_async_tree();
+            hk2.sweep_async_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+        hk2.sweep();
+        hk2.sweep_tree();
+    }
+
+    #[test]
+    fn test_auto_backend_uses_tree() {
+        // with the gaussian kernel, the sorted arrays do not suffice and the tree is used
+        let mut hk1 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+        hk1.set_kernel(Kernel::Gaussian);
+        hk2.set_kernel(Kernel::Gaussian);
+        hk1.set_backend(Backend::Tree);
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!("auto".parse::<Backend>(), Ok(Backend::Auto));
+        assert!("fast".parse::<Backend>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod coarsening {
+    use super::*;
+    use hk::{Aggregator, WeightDistribution};
+
+    #[test]
+    fn test_cmp_naive_tree_coarsening() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_coarsening(true);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    /// run copies of the same system for 100 synchronous sweeps on the tree and on the
+    /// sorted arrays, with and without coarsening, and compare them bit by bit
+    fn cmp_coarsening<F: Fn(&mut HegselmannKrause)>(setup: F) {
+        for &sorted in &[false, true] {
+            let mut hk1 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+            let mut hk2 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+            for hk in [&mut hk1, &mut hk2].iter_mut() {
+                setup(hk);
+                hk.reset();
+            }
+            hk2.set_coarsening(true);
+
+            for _ in 0..100 {
+                if sorted {
+                    hk1.sweep_sorted();
+                    hk2.sweep_sorted();
+                } else {
+                    hk1.sweep_tree();
+                    hk2.sweep_tree();
+                }
+                assert_eq!(hk1.opinions(), hk2.opinions());
+            }
+        }
+    }
+
+    #[test]
+    fn test_coarsening_plain() {
+        cmp_coarsening(|_| {});
+    }
+
+    #[test]
+    fn test_coarsening_media() {
+        cmp_coarsening(|hk| hk.set_media(0.3, 5.).unwrap());
+    }
+
+    #[test]
+    fn test_coarsening_self_weight() {
+        cmp_coarsening(|hk| hk.set_self_weight(3.).unwrap());
+    }
+
+    #[test]
+    fn test_coarsening_weights() {
+        cmp_coarsening(|hk| {
+            hk.set_weight_distribution(WeightDistribution::Uniform { low: 1., high: 2. })
+                .unwrap()
+        });
+    }
+
+    #[test]
+    fn test_coarsening_median() {
+        cmp_coarsening(|hk| hk.set_aggregator(Aggregator::Median).unwrap());
+    }
+
+    #[test]
+    fn test_coarsening_mu() {
+        cmp_coarsening(|hk| hk.set_mu(0.4).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod sorted_iteration {
+    use super::*;
+    use hk::WeightDistribution;
+
+    /// run copies of the same system for 100 synchronous sweeps on the tree and on the
+    /// sorted arrays, visiting the agents in the order of their opinions or of their
+    /// indices, and compare them bit by bit
+    fn cmp_order<F: Fn(&mut HegselmannKrause)>(setup: F) {
+        for &sorted in &[false, true] {
+            let mut hk1 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+            let mut hk2 = HegselmannKrause::new(300, 0.01, 0.2, 13);
+            for hk in [&mut hk1, &mut hk2].iter_mut() {
+                setup(hk);
+                hk.reset();
+            }
+            hk1.set_sorted_iteration(false);
+            hk2.set_sorted_iteration(true);
+
+            for _ in 0..100 {
+                if sorted {
+                    hk1.sweep_sorted();
+                    hk2.sweep_sorted();
+                } else {
+                    hk1.sweep_tree();
+                    hk2.sweep_tree();
+                }
+                assert_eq!(hk1.opinions(), hk2.opinions());
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_naive_tree_sorted_iteration() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_sorted_iteration(true);
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_order_plain() {
+        cmp_order(|_| {});
+    }
+
+    #[test]
+    fn test_order_media() {
+        cmp_order(|hk| hk.set_media(0.3, 5.).unwrap());
+    }
+
+    #[test]
+    fn test_order_weights() {
+        cmp_order(|hk| {
+            hk.set_weight_distribution(WeightDistribution::Uniform { low: 1., high: 2. })
+                .unwrap()
+        });
+    }
+
+    #[test]
+    fn test_order_gaussian() {
+        cmp_order(|hk| hk.set_kernel(Kernel::Gaussian));
+    }
+
+    #[test]
+    fn test_order_skip_unchanged() {
+        cmp_order(|hk| hk.set_skip_unchanged(true));
+    }
+
+    #[test]
+    fn test_order_mu() {
+        cmp_order(|hk| hk.set_mu(0.4).unwrap());
+    }
+
+    #[test]
+    fn test_order_self_weight() {
+        cmp_order(|hk| hk.set_self_weight(3.).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod reseeding {
+    use super::*;
+    use hk::Deffuant;
+
+    #[test]
+    fn test_reset_with_seed_independent_of_history() {
+        let mut fresh = HegselmannKrause::new(300, 0.05, 0.2, 1);
+        fresh.reset_with_seed(57);
+        let initial = fresh.opinions();
+
+        // sweeps and samples before do not change the sample of a seed
+        let mut used = HegselmannKrause::new(300, 0.05, 0.2, 2);
+        for _ in 0..3 {
+            for _ in 0..10 {
+                used.sweep();
+            }
+            used.reset();
+        }
+        used.reset_with_seed(57);
+        assert_eq!(used.opinions(), initial);
+        assert_eq!(used.confidences(), fresh.confidences());
+
+        for _ in 0..20 {
+            fresh.sweep();
+            used.sweep();
+        }
+        assert_eq!(used.opinions(), fresh.opinions());
+    }
+
+    #[test]
+    fn test_deffuant_reset_with_seed() {
+        let mut a = Deffuant::new(100, 0.1, 0.3, 0.5, 1);
+        let mut b = Deffuant::new(100, 0.1, 0.3, 0.5, 2);
+        b.sweep();
+        a.reset_with_seed(57);
+        b.reset_with_seed(57);
+        for _ in 0..10 {
+            a.sweep();
+            b.sweep();
+        }
+        assert_eq!(a.cluster_sizes(), b.cluster_sizes());
+        assert_eq!(a.last_change(), b.last_change());
+    }
+}
+
+#[cfg(test)]
+mod comparison {
+    use super::*;
+
+    #[test]
+    fn test_strict_equality() {
+        let mut hk1 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        for _ in 0..10 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
             assert!(hk1 == hk2);
+            assert_eq!(hk1.first_difference(&hk2), None);
+        }
+    }
+
+    #[test]
+    fn test_perturbed_copy() {
+        let hk1 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let x = hk1.opinions()[42];
+        hk2.inject_opinion(42, x + 1e-3);
+
+        assert!(hk1 != hk2);
+        assert!(!hk1.approx_eq(&hk2, 1e-4));
+        assert!(hk1.approx_eq(&hk2, 2e-3));
+        assert_eq!(
+            hk1.first_difference(&hk2),
+            Some((42, Some(x), Some(x + 1e-3)))
+        );
+        assert_eq!(
+            hk2.first_difference(&hk1),
+            Some((42, Some(x + 1e-3), Some(x)))
+        );
+    }
+
+    #[test]
+    fn test_first_of_several_differences() {
+        let hk1 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0.05, 0.2, 13);
+        hk2.inject_opinion(70, 0.5);
+        hk2.inject_opinion(7, 0.5);
+        let (idx, a, b) = hk1.first_difference(&hk2).unwrap();
+        assert_eq!((idx, b), (7, Some(0.5)));
+        assert_eq!(a, Some(hk1.opinions()[7]));
+    }
+
+    #[test]
+    fn test_tolerance_is_not_transitive() {
+        // the reason why the tolerant comparison is no implementation of `PartialEq`
+        let mut models: Vec<HegselmannKrause> = (0..3)
+            .map(|_| HegselmannKrause::new(1, 0., 0., 13))
+            .collect();
+        for (k, hk) in models.iter_mut().enumerate() {
+            hk.set_state(&[0.5 + k as Float * 0.75e-5], &[0.]).unwrap();
         }
+        assert!(models[0].approx_eq(&models[1], 1e-5));
+        assert!(models[1].approx_eq(&models[2], 1e-5));
+        assert!(!models[0].approx_eq(&models[2], 1e-5));
+    }
+
+    #[test]
+    fn test_different_numbers_of_agents() {
+        let hk1 = HegselmannKrause::new(10, 0.05, 0.2, 13);
+        let hk2 = HegselmannKrause::new(11, 0.05, 0.2, 13);
+        assert!(hk1 != hk2);
+        assert!(!hk1.approx_eq(&hk2, 1.));
+        let (idx, a, b) = hk1.first_difference(&hk2).unwrap();
+        assert!(idx <= 10);
+        assert!(idx < 10 || (a.is_none() && b.is_some()));
+
+        // the missing agent is reported, even if all others are identical
+        let hk1 = HegselmannKrause::new(1, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(2, 0.05, 0.2, 13);
+        let x = hk1.opinions()[0];
+        hk2.inject_opinion(0, x);
+        assert_eq!(
+            hk1.first_difference(&hk2),
+            Some((1, None, Some(hk2.opinions()[1])))
+        );
+        assert_eq!(
+            hk2.first_difference(&hk1),
+            Some((1, Some(hk2.opinions()[1]), None))
+        );
+    }
+
+    #[test]
+    fn test_equal_nan_no_difference() {
+        let mut hk1 = HegselmannKrause::new(10, 0.05, 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(10, 0.05, 0.2, 13);
+        hk1.inject_opinion(3, Float::NAN);
+        hk2.inject_opinion(3, Float::NAN);
+        assert_eq!(hk1.first_difference(&hk2), None);
+
+        hk2.inject_opinion(5, 0.5);
+        assert_eq!(hk1.first_difference(&hk2).map(|(idx, _, _)| idx), Some(5));
     }
 }
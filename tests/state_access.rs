@@ -0,0 +1,82 @@
+extern crate hk;
+use hk::{ClusterOutput, Deffuant, Float, HegselmannKrause};
+
+/// the positions of the clusters, which the output writes in its comment line
+fn cluster_positions(output: &str) -> Vec<Float> {
+    output
+        .lines()
+        .next()
+        .unwrap()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .map(|x| x.parse().unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lengths() {
+        let mut hk = HegselmannKrause::new(123, 0.1, 0.3, 13);
+        for seed in 0..3 {
+            hk.reset_with_seed(seed);
+            assert_eq!(hk.num_agents(), 123);
+            assert_eq!(hk.opinions().len(), 123);
+            assert_eq!(hk.confidences().len(), 123);
+            assert_eq!(hk.iter_opinions().count(), 123);
+        }
+
+        let dw = Deffuant::new(77, 0.1, 0.3, 0.5, 13);
+        assert_eq!(dw.num_agents(), 77);
+        assert_eq!(dw.opinions().len(), 77);
+        assert_eq!(dw.confidences().len(), 77);
+    }
+
+    #[test]
+    fn test_single_agents() {
+        let hk = HegselmannKrause::new(50, 0.1, 0.3, 13);
+        let (opinions, confidences) = (hk.opinions(), hk.confidences());
+        for idx in 0..50 {
+            assert_eq!(hk.agent(idx), Some((opinions[idx], confidences[idx])));
+        }
+        assert_eq!(hk.agent(50), None);
+        assert!(hk.iter_opinions().eq(opinions.iter().copied()));
+
+        let dw = Deffuant::new(50, 0.1, 0.3, 0.5, 13);
+        assert_eq!(dw.agent(0), Some((dw.opinions()[0], dw.confidences()[0])));
+        assert_eq!(dw.agent(50), None);
+    }
+
+    #[test]
+    fn test_snapshot_of_reset() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.reset_with_seed(42);
+        let opinions = hk.opinions();
+        assert!(opinions.iter().all(|x| (0. ..=1.).contains(x)));
+        assert!(hk.confidences().iter().all(|c| (0.1..=0.3).contains(c)));
+
+        // the snapshot does not change with the model
+        hk.sweep();
+        assert_ne!(hk.opinions(), opinions);
+        hk.reset_with_seed(42);
+        assert_eq!(hk.opinions(), opinions);
+
+        // both models draw the same initial state for the same seed
+        let dw = Deffuant::new(100, 0.1, 0.3, 0.5, 42);
+        assert_eq!(dw.opinions(), opinions);
+        assert_eq!(dw.confidences(), hk.confidences());
+    }
+
+    #[test]
+    fn test_opinions_like_cluster_positions() {
+        // with a tiny cluster tolerance every distinct opinion is a cluster of its own
+        let mut hk = HegselmannKrause::new(30, 0.1, 0.3, 13);
+        hk.set_cluster_eps(1e-9).unwrap();
+        hk.reset_with_seed(7);
+        let mut opinions = hk.opinions();
+        opinions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(cluster_positions(&hk.cluster_sizes_string()), opinions);
+    }
+}
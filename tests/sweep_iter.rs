@@ -0,0 +1,71 @@
+extern crate hk;
+use hk::{ConvergenceCriterion, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_convergence_loop() {
+        // the iterator replaces the loop sweeping until the change drops below the threshold
+        let threshold = 1e-4;
+        for seed in 0..5 {
+            let mut manual = HegselmannKrause::new(200, 0.05, 0.2, seed);
+            let mut ctr = 0;
+            loop {
+                manual.sweep();
+                ctr += 1;
+                if manual.last_change() < threshold {
+                    break;
+                }
+            }
+
+            let mut hk = HegselmannKrause::new(200, 0.05, 0.2, seed);
+            let sweeps = hk
+                .sweeps()
+                .take_while(|r| r.accumulated_change >= threshold)
+                .count()
+                + 1;
+            assert_eq!(sweeps, ctr);
+            assert!(hk == manual);
+
+            let mut reference = HegselmannKrause::new(200, 0.05, 0.2, seed);
+            let criterion = ConvergenceCriterion::SumChange { threshold };
+            assert_eq!(reference.run_until_converged(criterion, None), Ok(ctr));
+            assert!(hk == reference);
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        let reports: Vec<_> = hk.sweeps().take(3).collect();
+        assert_eq!(
+            reports.iter().map(|r| r.sweep).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(reports.iter().all(|r| r.num_clusters.is_none()));
+        let last = reports.last().unwrap();
+        assert_eq!(last.accumulated_change, hk.last_change());
+        assert_eq!(last.max_change, hk.last_max_change());
+        assert!(last.max_change <= last.accumulated_change);
+
+        let report = hk.sweeps().with_clusters().next().unwrap();
+        assert_eq!(report.sweep, 4);
+        assert_eq!(report.num_clusters, Some(hk.cluster_sizes().len()));
+    }
+
+    #[test]
+    fn test_find_converged() {
+        // a consensus is a single cluster, which stops moving
+        let mut hk = HegselmannKrause::new(100, 0.4, 0.4, 13);
+        let report = hk
+            .sweeps()
+            .with_clusters()
+            .take(1000)
+            .find(|r| r.max_change == 0.)
+            .unwrap();
+        assert_eq!(report.num_clusters, Some(1));
+        assert_eq!(report.sweep, hk.sweep_count());
+    }
+}
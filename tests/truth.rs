@@ -0,0 +1,76 @@
+extern crate hk;
+use hk::HegselmannKrause;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_seekers_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_truth(0.3, 0.5, 0.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_naive();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_truth(0.3, 0.5, 0.).unwrap();
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_full_strength_jumps_to_truth() {
+        let truth = 0.3;
+        let mut hk = HegselmannKrause::new(100, 0., 0.2, 13);
+        hk.set_truth(truth, 1., 0.5).unwrap();
+        let before = hk.opinions();
+        hk.sweep_tree();
+
+        // exactly half of the agents are seekers, which are all at the truth now
+        let at_truth = hk
+            .opinions()
+            .iter()
+            .filter(|&&x| (x - truth).abs() < 1e-6)
+            .count();
+        let initially_at_truth = before.iter().filter(|&&x| (x - truth).abs() < 1e-6).count();
+        assert!(at_truth >= 50);
+        assert!(at_truth <= 50 + initially_at_truth + 5);
+    }
+
+    #[test]
+    fn test_seekers_reproducible() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.2, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.2, 13);
+        hk1.set_truth(0.7, 0.1, 0.3).unwrap();
+        hk2.set_truth(0.7, 0.1, 0.3).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+
+        // the truth attracts the consensus
+        let distances = hk2.cluster_truth_distances().unwrap();
+        assert_eq!(distances.len(), hk2.cluster_sizes().len());
+        assert!(distances.iter().any(|&d| d < 0.05));
+    }
+
+    #[test]
+    fn test_invalid_truth() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_truth(1.5, 0.1, 0.5).is_err());
+        assert!(hk.set_truth(0.5, -0.1, 0.5).is_err());
+        assert!(hk.set_truth(0.5, 0.1, 2.).is_err());
+        assert_eq!(hk.cluster_truth_distances(), None);
+    }
+}
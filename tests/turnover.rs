@@ -0,0 +1,61 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_turnover_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_turnover(0.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+            assert_eq!(hk1.last_change(), hk2.last_change());
+        }
+    }
+
+    #[test]
+    fn test_turnover_replaces_agents() {
+        let mut hk = HegselmannKrause::new(100, 0.2, 0.3, 13);
+        hk.set_turnover(2.5).unwrap();
+        for _ in 0..1000 {
+            hk.sweep();
+        }
+
+        // newcomers keep the system from converging to a few clusters
+        assert_eq!(hk.opinions().len(), 100);
+        assert!(hk.cluster_sizes().len() > 5);
+        assert!(hk.confidences().iter().all(|c| (0.2..=0.3).contains(c)));
+    }
+
+    #[test]
+    fn test_turnover_keeps_tree_consistent() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_turnover(3.).unwrap();
+        hk2.set_turnover(3.).unwrap();
+
+        for _ in 0..50 {
+            hk1.sweep();
+            hk2.sweep();
+        }
+
+        // the tree of hk1 only yields the same result as the naive method,
+        // if it contains exactly the opinions of all current agents
+        hk1.sweep_tree();
+        hk2.sweep_naive();
+        assert!(hk1.approx_eq(&hk2, 1e-5));
+    }
+
+    #[test]
+    fn test_invalid_turnover() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk.set_turnover(-1.).is_err());
+        assert!(hk.set_turnover(Float::INFINITY).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+extern crate hk;
+use hk::{Float, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_threshold_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk2.set_update_threshold(0.).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep();
+            hk2.sweep();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_naive_equals_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk1.set_update_threshold(0.01).unwrap();
+        hk2.set_update_threshold(0.01).unwrap();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    /// two agents at distance `d` which see each other both move by `d / 2`
+    fn two_agents(delta: Float) -> [HegselmannKrause; 2] {
+        let mut models = [
+            HegselmannKrause::new(2, 0., 1., 13),
+            HegselmannKrause::new(2, 0., 1., 13),
+        ];
+        for hk in models.iter_mut() {
+            hk.set_state(&[0.4, 0.6], &[0.5, 0.5]).unwrap();
+            hk.set_update_threshold(delta).unwrap();
+        }
+        models
+    }
+
+    #[test]
+    fn test_two_agents_freeze() {
+        let [mut naive, mut tree] = two_agents(0.11);
+        naive.sweep_naive();
+        tree.sweep_tree();
+        for hk in [naive, tree].iter() {
+            assert_eq!(hk.opinions(), vec![0.4, 0.6]);
+            assert_eq!(hk.last_change(), 0.);
+        }
+    }
+
+    #[test]
+    fn test_two_agents_move() {
+        let [mut naive, mut tree] = two_agents(0.09);
+        naive.sweep_naive();
+        tree.sweep_tree();
+        for hk in [naive, tree].iter() {
+            for x in hk.opinions() {
+                assert!((x - 0.5).abs() < 1e-6);
+            }
+            assert!((hk.last_change() - 0.2).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_freeze_with_disagreement() {
+        let mut hk = HegselmannKrause::new(100, 0.1, 0.3, 13);
+        hk.set_update_threshold(0.01).unwrap();
+        for _ in 0..1000 {
+            hk.sweep();
+            if hk.last_change() == 0. {
+                break;
+            }
+        }
+        assert_eq!(hk.last_change(), 0.);
+
+        let frozen = hk.opinions();
+        hk.sweep();
+        assert_eq!(hk.opinions(), frozen);
+    }
+
+    #[test]
+    fn test_invalid_threshold() {
+        let mut hk = HegselmannKrause::new(10, 0., 0.3, 13);
+        assert!(hk.set_update_threshold(-0.1).is_err());
+        assert!(hk.set_update_threshold(Float::NAN).is_err());
+    }
+}
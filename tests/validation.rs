@@ -0,0 +1,103 @@
+extern crate hk;
+use hk::{Deffuant, Float, HegselmannKrause, HkError, Network};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_invalid_parameter(result: Result<HegselmannKrause, HkError>) -> bool {
+        matches!(result, Err(HkError::InvalidParameter(_)))
+    }
+
+    fn is_invalid_confidences(result: Result<HegselmannKrause, HkError>) -> bool {
+        matches!(result, Err(HkError::InvalidConfidenceDistribution(_)))
+    }
+
+    #[test]
+    fn test_valid_parameters() {
+        assert!(HegselmannKrause::try_new(1, 0., 0., 13).is_ok());
+        let hk = HegselmannKrause::try_new(100, 0.1, 0.3, 13).unwrap();
+        assert!(hk == HegselmannKrause::new(100, 0.1, 0.3, 13));
+    }
+
+    #[test]
+    fn test_no_agents() {
+        assert!(is_invalid_parameter(HegselmannKrause::try_new(
+            0, 0.1, 0.3, 13
+        )));
+    }
+
+    #[test]
+    fn test_min_exceeds_max() {
+        assert!(is_invalid_confidences(HegselmannKrause::try_new(
+            10, 0.3, 0.1, 13
+        )));
+    }
+
+    #[test]
+    fn test_negative_confidence() {
+        assert!(is_invalid_confidences(HegselmannKrause::try_new(
+            10, -0.1, 0.3, 13
+        )));
+    }
+
+    #[test]
+    fn test_nan_bounds() {
+        assert!(is_invalid_confidences(HegselmannKrause::try_new(
+            10,
+            Float::NAN,
+            0.3,
+            13
+        )));
+        assert!(is_invalid_confidences(HegselmannKrause::try_new(
+            10,
+            0.1,
+            Float::NAN,
+            13
+        )));
+    }
+
+    #[test]
+    fn test_infinite_bound() {
+        assert!(is_invalid_confidences(HegselmannKrause::try_new(
+            10,
+            0.1,
+            Float::INFINITY,
+            13
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one agent")]
+    fn test_new_panics() {
+        HegselmannKrause::new(0, 0.1, 0.3, 13);
+    }
+
+    #[test]
+    fn test_other_constructors() {
+        assert!(is_invalid_confidences(
+            HegselmannKrause::with_opinion_interval(10, 0.3, 0.1, 13, -1., 1.)
+        ));
+        let network = Network::Ring { k: 2 };
+        assert!(is_invalid_parameter(
+            HegselmannKrause::try_with_generated_network(0, 0.1, 0.3, 13, &network)
+        ));
+    }
+
+    #[test]
+    fn test_deffuant() {
+        assert!(Deffuant::try_new(10, 0.1, 0.3, 0.5, 13).is_ok());
+        for &(n, min, max, mu) in &[(0, 0.1, 0.3, 0.5), (10, 0.1, 0.3, 0.), (10, 0.1, 0.3, 1.5)] {
+            match Deffuant::try_new(n, min, max, mu, 13) {
+                Err(HkError::InvalidParameter(_)) => {}
+                _ => panic!("invalid parameters {:?} accepted", (n, min, max, mu)),
+            }
+        }
+        for &(min, max) in &[(0.3, 0.1), (-0.1, 0.3), (0.1, Float::NAN)] {
+            match Deffuant::try_new(10, min, max, 0.5, 13) {
+                Err(HkError::InvalidConfidenceDistribution(_)) => {}
+                _ => panic!("invalid confidences {:?} accepted", (min, max)),
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+extern crate hk;
+use hk::{HegselmannKrause, OpinionSpace, WeightDistribution};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_weights_unchanged() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk2.set_weight_distribution(WeightDistribution::Unit)
+            .unwrap();
+        hk2.reset();
+        hk1.reset();
+        assert!(hk2.weights().iter().all(|&w| w == 1.));
+
+        for _ in 0..100 {
+            hk1.sweep_tree();
+            hk2.sweep_tree();
+            assert_eq!(hk1.opinions(), hk2.opinions());
+        }
+    }
+
+    #[test]
+    fn test_weighted_pair() {
+        let mut hk = HegselmannKrause::new(2, 0., 1., 13);
+        hk.set_weight_distribution(WeightDistribution::Uniform { low: 1., high: 3. })
+            .unwrap();
+        hk.reset();
+        hk.set_state(&[0.4, 0.6], &[0.5, 0.5]).unwrap();
+        let w = hk.weights();
+        hk.sweep_tree();
+
+        // both agents move to the weighted mean
+        let mean = (w[0] * 0.4 + w[1] * 0.6) / (w[0] + w[1]);
+        for x in hk.opinions() {
+            assert!((x - mean).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_cmp_weighted_naive_tree() {
+        let weights = WeightDistribution::PowerLaw {
+            exponent: 2.5,
+            min: 0.1,
+            max: 10.,
+        };
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        hk1.set_weight_distribution(weights.clone()).unwrap();
+        hk2.set_weight_distribution(weights).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_cmp_weighted_async_circle() {
+        let weights = WeightDistribution::Uniform { low: 0.5, high: 2. };
+        let mut hk1 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Circle);
+        let mut hk2 = HegselmannKrause::with_topology(100, 0., 0.3, 13, OpinionSpace::Circle);
+        hk1.set_weight_distribution(weights.clone()).unwrap();
+        hk2.set_weight_distribution(weights).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        // the tree is rebuilt regularly, which must not change the dynamics
+        for _ in 0..1500 {
+            hk1.sweep_async();
+            hk2.sweep_async_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_invalid_weights() {
+        let mut hk = HegselmannKrause::new(10, 0., 1., 13);
+        assert!(hk
+            .set_weight_distribution(WeightDistribution::Uniform { low: 0., high: 1. })
+            .is_err());
+        assert_eq!(
+            "uniform:1:2".parse(),
+            Ok(WeightDistribution::Uniform { low: 1., high: 2. })
+        );
+    }
+
+    #[test]
+    fn test_cmp_weighted_async_line() {
+        let weights = WeightDistribution::Uniform { low: 0.5, high: 2. };
+        let mut hk1 = HegselmannKrause::new(100, 0., 0.3, 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk1.set_weight_distribution(weights.clone()).unwrap();
+        hk2.set_weight_distribution(weights).unwrap();
+        hk1.reset();
+        hk2.reset();
+
+        for _ in 0..300 {
+            hk1.sweep_async();
+            hk2.sweep_async_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+extern crate hk;
+use hk::{Diagnostics, HegselmannKrause};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zealots_unchanged() {
+        let zealots = [0, 17, 42, 99];
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk.set_zealots(&zealots).unwrap();
+        let initial = hk.opinions();
+
+        for _ in 0..100 {
+            hk.sweep_tree();
+        }
+
+        let opinions = hk.opinions();
+        for &i in &zealots {
+            assert_eq!(opinions[i], initial[i]);
+        }
+        // but everybody else did move
+        assert!(opinions
+            .iter()
+            .zip(initial.iter())
+            .enumerate()
+            .any(|(i, (a, b))| !zealots.contains(&i) && a != b));
+    }
+
+    #[test]
+    fn test_zealots_survive_reset() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk.set_zealots(&[3]).unwrap();
+        hk.reset();
+        let initial = hk.opinions();
+
+        for _ in 0..100 {
+            hk.sweep_naive();
+        }
+        assert_eq!(hk.opinions()[3], initial[3]);
+    }
+
+    #[test]
+    fn test_cmp_zealots_naive_tree() {
+        let mut hk1 = HegselmannKrause::new(100, 0., 1., 13);
+        let mut hk2 = HegselmannKrause::new(100, 0., 1., 13);
+        let zealots: Vec<usize> = (0..10).collect();
+        hk1.set_zealots(&zealots).unwrap();
+        hk2.set_zealots(&zealots).unwrap();
+
+        // clusters will form on top of zealots, so the tree has to stay
+        // consistent if keys are shared between zealots and moving agents
+        for _ in 0..100 {
+            hk1.sweep_naive();
+            hk2.sweep_tree();
+            assert!(hk1.approx_eq(&hk2, 1e-5));
+        }
+        assert_eq!(hk2.total_agents_in_clusters(), 100);
+    }
+
+    #[test]
+    fn test_zealot_out_of_range() {
+        let mut hk = HegselmannKrause::new(100, 0., 0.3, 13);
+        hk.set_zealots(&[3]).unwrap();
+        assert!(hk.set_zealots(&[5, 100]).is_err());
+        // the zealots of the failed call are not applied
+        let initial = hk.opinions();
+        hk.sweep();
+        assert_eq!(hk.opinions()[3], initial[3]);
+        assert_ne!(hk.opinions()[5], initial[5]);
+    }
+}